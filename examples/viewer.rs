@@ -0,0 +1,40 @@
+// the read-only viewer on its own: a small graph with its shortest path and spanning tree drawn
+// over it, as a game might show its pathfinding in a debug overlay
+use bevy::prelude::*;
+use djikstra::graph::{Graph, NodeId};
+use djikstra::viewer::{GraphView, GraphViewerPlugin, HighlightLayer};
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, GraphViewerPlugin))
+        .add_systems(Startup, setup)
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2d);
+
+    let graph = Graph::from_adjacency_matrix(vec![
+        vec![0, 4, 1, 0, 0],
+        vec![4, 0, 2, 5, 0],
+        vec![1, 2, 0, 8, 0],
+        vec![0, 5, 8, 0, 3],
+        vec![0, 0, 0, 3, 0],
+    ]);
+    let layout = vec![
+        Vec2::new(-300.0, 0.0),
+        Vec2::new(-100.0, 150.0),
+        Vec2::new(-100.0, -150.0),
+        Vec2::new(100.0, 0.0),
+        Vec2::new(300.0, 0.0),
+    ];
+    let mut view = GraphView::new(graph, layout);
+    if let Some((_, path)) = view.graph.shortest_path(NodeId(0), NodeId(4)) {
+        view.set_layer(HighlightLayer::path(
+            "Shortest path",
+            Color::srgb(0.0, 1.0, 1.0),
+            &path,
+        ));
+    }
+    commands.insert_resource(view);
+}
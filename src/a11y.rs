@@ -0,0 +1,197 @@
+use accesskit::{Node as AccessNode, Role};
+use bevy::a11y::AccessibilityNode;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+use crate::graph::NodeId;
+use crate::{
+    EdgeVisual, GoalNode, MainCamera, Node, ResultsHud, ResultsText, SelectedNode, StartNode,
+    WeightUnits, WrapperGraph,
+};
+
+pub struct A11yPlugin;
+
+impl Plugin for A11yPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputFocus>().add_systems(
+            Update,
+            (
+                describe_nodes,
+                describe_edges,
+                describe_results,
+                traverse_focus.run_if(crate::prompt::prompt_closed),
+                sync_focus_with_selection,
+            ),
+        );
+    }
+}
+
+// only touch the component when the text actually differs, so the AccessKit tree isn't resent every frame
+fn set_description(
+    commands: &mut Commands,
+    entity: Entity,
+    current: Option<Mut<AccessibilityNode>>,
+    role: Role,
+    label: String,
+    description: String,
+) {
+    match current {
+        Some(mut node) => {
+            if node.label() != Some(label.as_str()) {
+                node.set_label(label);
+            }
+            if node.description() != Some(description.as_str()) {
+                node.set_description(description);
+            }
+        }
+        None => {
+            let mut node = AccessNode::new(role);
+            node.set_label(label);
+            node.set_description(description);
+            commands.entity(entity).insert(AccessibilityNode(node));
+        }
+    }
+}
+
+fn describe_nodes(
+    mut commands: Commands,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    flags: Query<(&StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut q_nodes: Query<(Entity, &Node, Option<&mut AccessibilityNode>)>,
+) -> Result<()> {
+    let wg = wrapper_graph.single()?;
+    let graph = wg.0.read().unwrap();
+    let (start, goal) = flags.single()?;
+
+    for (entity, node, access) in &mut q_nodes {
+        let mut description = String::new();
+        if start.id == Some(node.id) {
+            description.push_str("Start node. ");
+        } else if goal.id == Some(node.id) {
+            description.push_str("Goal node. ");
+        }
+        if let Some(about) = graph.describe_node(node.id) {
+            description.push_str(&format!("{}. ", about));
+        }
+        let neighbours = &graph.nodes[node.id.index()];
+        if neighbours.is_empty() {
+            description.push_str("No connections.");
+        } else {
+            let list = neighbours
+                .iter()
+                .map(|e| format!("{} ({})", e.node, units.format(e.cost)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            description.push_str(&format!("Connected to {}.", list));
+        }
+
+        set_description(
+            &mut commands,
+            entity,
+            access,
+            Role::ListItem,
+            format!("Node {}", node.id),
+            description,
+        );
+    }
+
+    return Ok(());
+}
+
+fn describe_edges(
+    mut commands: Commands,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut q_edges: Query<(Entity, &EdgeVisual, Option<&mut AccessibilityNode>)>,
+) -> Result<()> {
+    let wg = wrapper_graph.single()?;
+    let graph = wg.0.read().unwrap();
+
+    for (entity, ev, access) in &mut q_edges {
+        let (fwd, bwd) = (graph.has_edge(ev.a, ev.b), graph.has_edge(ev.b, ev.a));
+        let Some(edge) = graph.edge_between(ev.a, ev.b) else {
+            continue;
+        };
+        let (from, to, kind) = match (fwd, bwd) {
+            (false, true) => (ev.b, ev.a, "One-way edge"),
+            (true, false) => (ev.a, ev.b, "One-way edge"),
+            _ => (ev.a, ev.b, "Edge"),
+        };
+        set_description(
+            &mut commands,
+            entity,
+            access,
+            Role::ListItem,
+            format!("{} {} to {}", kind, from, to),
+            format!("Cost {}", units.format(edge.cost)),
+        );
+    }
+
+    return Ok(());
+}
+
+fn describe_results(
+    mut commands: Commands,
+    hud: Res<ResultsHud>,
+    mut q_text: Query<(Entity, Option<&mut AccessibilityNode>), With<ResultsText>>,
+) {
+    if !hud.is_changed() {
+        return;
+    }
+    for (entity, access) in &mut q_text {
+        set_description(
+            &mut commands,
+            entity,
+            access,
+            Role::Status,
+            "Results".to_string(),
+            hud.0.clone(),
+        );
+    }
+}
+
+fn traverse_focus(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_nodes: Query<&Node>,
+    mut selected_node: Query<&mut SelectedNode, With<MainCamera>>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::Tab) {
+        return Ok(());
+    }
+    let mut ids: Vec<NodeId> = q_nodes.iter().map(|n| n.id).collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    ids.sort_unstable();
+
+    let backwards = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+    let mut selected = selected_node.single_mut()?;
+    let next = match selected.id.and_then(|id| ids.iter().position(|&i| i == id)) {
+        Some(pos) if backwards => ids[(pos + ids.len() - 1) % ids.len()],
+        Some(pos) => ids[(pos + 1) % ids.len()],
+        None if backwards => ids[ids.len() - 1],
+        None => ids[0],
+    };
+    selected.id = Some(next);
+
+    return Ok(());
+}
+
+fn sync_focus_with_selection(
+    q_sel: Query<&SelectedNode, (With<MainCamera>, Changed<SelectedNode>)>,
+    q_nodes: Query<(Entity, &Node)>,
+    mut focus: ResMut<InputFocus>,
+) {
+    let Ok(selected) = q_sel.single() else {
+        return;
+    };
+    let entity = selected
+        .id
+        .and_then(|id| q_nodes.iter().find(|(_, n)| n.id == id))
+        .map(|(e, _)| e);
+    match entity {
+        Some(e) => focus.set(e),
+        None => focus.clear(),
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{AlgoStats, Cost, Graph, Landmarks, NodeId, Trace, TraceEvent};
+use crate::{
+    GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, clicked_node_id,
+    cursor_world,
+};
+
+const LANDMARKS: usize = 4;
+const LANDMARK: Color = Color::srgb(1.0, 0.8, 0.2);
+const ALT_SETTLED: Color = Color::srgb(0.3, 0.75, 1.0);
+const DIJKSTRA_ONLY: Color = Color::srgb(0.55, 0.55, 0.6);
+
+// ALT next to plain dijkstra for the current start and goal: landmarks are preprocessed once
+// per set of weights, and every query reuses them
+#[derive(Resource, Default)]
+struct AltView {
+    shown: bool,
+    landmarks: Landmarks,
+    // landmarks alt+clicked by hand; None picks them farthest first
+    picked: Option<Vec<NodeId>>,
+    basis: Option<(Option<(NodeId, NodeId)>, Vec<(NodeId, NodeId, Cost)>)>,
+    // the nodes each search settled
+    alt: HashSet<NodeId>,
+    dijkstra: HashSet<NodeId>,
+}
+
+pub struct AltPlugin;
+
+impl Plugin for AltPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AltView>().add_systems(
+            Update,
+            (
+                handle_alt_keys.run_if(crate::prompt::prompt_closed),
+                handle_landmark_clicks,
+                refresh_alt,
+                draw_alt,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn settled(trace: &Trace) -> HashSet<NodeId> {
+    return trace
+        .events
+        .iter()
+        .filter_map(|e| match e.event {
+            TraceEvent::Pop { node, stale, .. } if !stale => Some(node),
+            _ => None,
+        })
+        .collect();
+}
+
+// ctrl+L compares ALT with dijkstra, or stops
+fn handle_alt_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut view: ResMut<AltView>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+    let shown = !view.shown;
+    *view = AltView { shown, ..default() };
+    if !shown {
+        hud.0 = "ALT comparison off".to_string();
+    }
+}
+
+// alt+clicking a node while the comparison is on makes it a landmark, or stops it being one; the
+// first click starts from the landmarks picked farthest first
+fn handle_landmark_clicks(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    ui: Query<&Interaction>,
+    mut view: ResMut<AltView>,
+) {
+    let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    if !view.shown || !alt || !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    if ui.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    let Some(id) = cursor_world(&windows, &cams).and_then(|w| clicked_node_id(&nodes, w)) else {
+        return;
+    };
+    let mut picked = view
+        .picked
+        .take()
+        .unwrap_or_else(|| view.landmarks.nodes.clone());
+    match picked.iter().position(|&l| l == id) {
+        Some(pos) => {
+            picked.remove(pos);
+        }
+        None => picked.push(id),
+    }
+    view.picked = Some(picked);
+    // forgets the weights, so the next refresh builds the tables for the new set
+    view.basis = None;
+}
+
+// new weights pick new landmarks; a new start or goal only reruns the two searches
+fn refresh_alt(
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut view: ResMut<AltView>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !view.shown {
+        return Ok(());
+    }
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let query = start.id.zip(goal.id);
+    let basis = Some((query, graph.arc_costs()));
+    if view.basis == basis {
+        return Ok(());
+    }
+    if view.basis.as_ref().map(|(_, c)| c) != basis.as_ref().map(|(_, c)| c) {
+        view.landmarks = match &view.picked {
+            Some(picked) => graph.landmarks_at(picked),
+            None => graph.landmarks(LANDMARKS),
+        };
+    }
+    view.basis = basis;
+    view.alt.clear();
+    view.dijkstra.clear();
+
+    let labels: Vec<String> = view.landmarks.nodes.iter().map(|l| l.to_string()).collect();
+    let mut lines = vec![format!(
+        "ALT with {} landmarks ({}), {} (Alt+click a node to add or drop one, Ctrl+L hides)",
+        labels.len(),
+        labels.join(", "),
+        if view.picked.is_some() {
+            "picked by hand"
+        } else {
+            "picked farthest first"
+        }
+    )];
+    if graph.has_negative_edges() || !graph.forbidden.is_empty() {
+        lines.push("ALT needs non-negative weights and no turn bans".to_string());
+        hud.0 = lines.join("\n");
+        return Ok(());
+    }
+    let Some((start, goal)) = query else {
+        lines.push("Set a start and goal to compare it with Dijkstra".to_string());
+        hud.0 = lines.join("\n");
+        return Ok(());
+    };
+
+    let alt = graph.shortest_path_alt_trace(start, goal, &view.landmarks);
+    let dijkstra = graph.shortest_path_trace(start, goal);
+    let stats = AlgoStats::from_events(alt.events.iter().map(|e| &e.event));
+    let baseline = AlgoStats::from_events(dijkstra.events.iter().map(|e| &e.event));
+    view.alt = settled(&alt);
+    view.dijkstra = settled(&dijkstra);
+    lines.push(match &alt.result {
+        Some((cost, path)) => format!(
+            "Path length: {}, Path: {}",
+            units.format(*cost),
+            Graph::fmt_path(path)
+        ),
+        None => "No current available path".to_string(),
+    });
+    // how much of the real distance the landmarks can see from the start
+    if let Some((cost, _)) = &alt.result {
+        let bound = view.landmarks.lower_bound(start, goal);
+        lines.push(format!(
+            "Lower bound at the start: {} of {} ({:.0}%)",
+            units.format(bound),
+            units.format(*cost),
+            if *cost > 0 {
+                bound as f32 / *cost as f32 * 100.0
+            } else {
+                100.0
+            }
+        ));
+    }
+    lines.push(format!(
+        "ALT settled {} of Dijkstra's {} ({:.0}% fewer), {} relaxations against {}",
+        stats.settled,
+        baseline.settled,
+        (1.0 - stats.settled as f32 / baseline.settled.max(1) as f32) * 100.0,
+        stats.relaxations,
+        baseline.relaxations
+    ));
+    hud.0 = lines.join("\n");
+
+    return Ok(());
+}
+
+// landmarks ringed in gold; settled nodes ringed blue, or grey where only dijkstra got to them
+fn draw_alt(view: Res<AltView>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    if !view.shown {
+        return;
+    }
+    for node in &nodes {
+        let at = Isometry2d::from_translation(node.position);
+        if view.landmarks.nodes.contains(&node.id) {
+            gizmos.circle_2d(at, node.r + 10.0, LANDMARK);
+            gizmos.circle_2d(at, node.r + 12.0, LANDMARK);
+        }
+        if view.alt.contains(&node.id) {
+            gizmos.circle_2d(at, node.r + 5.0, ALT_SETTLED);
+        } else if view.dijkstra.contains(&node.id) {
+            gizmos.circle_2d(at, node.r + 5.0, DIJKSTRA_ONLY);
+        }
+    }
+}
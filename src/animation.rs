@@ -0,0 +1,510 @@
+use std::time::{Duration, Instant};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId, Trace, TraceEvent};
+use crate::heuristic::HeuristicView;
+use crate::params::AlgorithmParams;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::tags::Tags;
+use crate::{
+    GoalNode, HighlightedEdges, Layer, MainCamera, Node, ResultsHud, StartNode, WeightUnits,
+    WrapperGraph, node_positions, position_heuristic,
+};
+
+const RUN_COLORS: [Color; 2] = [Color::srgb(0.3, 0.6, 1.0), Color::srgb(1.0, 0.45, 0.3)];
+const DEFAULT_SPEED: f32 = 4.0; // settles per second
+// the top of the `=` ladder, where the clock no longer sets the pace: each frame settles as many
+// nodes as fit in FRAME_BUDGET
+const MAX_SPEED: f32 = 512.0;
+const FRAME_BUDGET: Duration = Duration::from_millis(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Dijkstra,
+    Bidirectional,
+    Greedy,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [
+        Algorithm::Dijkstra,
+        Algorithm::Bidirectional,
+        Algorithm::Greedy,
+    ];
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            Algorithm::Dijkstra => "dijkstra",
+            Algorithm::Bidirectional => "bidirectional",
+            Algorithm::Greedy => "greedy",
+        };
+    }
+
+    pub fn parse(word: &str) -> Option<Algorithm> {
+        let word = word.to_lowercase();
+        return Algorithm::ALL.into_iter().find(|a| a.name() == word);
+    }
+
+    // only greedy search reads the heuristic
+    pub fn trace(
+        self,
+        graph: &Graph,
+        start: NodeId,
+        goal: NodeId,
+        heuristic: impl Fn(NodeId) -> Cost,
+    ) -> Trace {
+        return match self {
+            Algorithm::Dijkstra => graph.shortest_path_trace(start, goal),
+            Algorithm::Bidirectional => graph.bidirectional_trace(start, goal),
+            Algorithm::Greedy => graph.greedy_best_first_trace(start, goal, heuristic),
+        };
+    }
+}
+
+// what greedy search steers by: the A* heuristic as picked in the heuristic panel
+#[derive(SystemParam)]
+pub struct RaceHeuristic<'w, 's> {
+    nodes: Query<'w, 's, &'static Node>,
+    view: Res<'w, HeuristicView>,
+    params: Res<'w, AlgorithmParams>,
+}
+
+impl RaceHeuristic<'_, '_> {
+    pub fn toward(&self, graph: &Graph, goal: NodeId) -> impl Fn(NodeId) -> Cost + use<> {
+        let positions = node_positions(&self.nodes, graph.nodes.len());
+        return position_heuristic(
+            graph,
+            &positions,
+            goal,
+            &self.view,
+            self.params.astar_weight,
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Seen {
+    Frontier,
+    Settled,
+}
+
+// one algorithm's recorded trace, replayed a settle at a time
+pub struct Run {
+    pub algorithm: Algorithm,
+    pub settled: usize,
+    events: Vec<TraceEvent>,
+    cursor: usize,
+    seen: Vec<Option<Seen>>,
+    // the tentative distance and predecessor of each node as of the events played so far
+    dist: Vec<Option<Cost>>,
+    via: Vec<Option<NodeId>>,
+    result: Option<(Cost, Vec<NodeId>)>,
+}
+
+impl Run {
+    pub fn new(
+        algorithm: Algorithm,
+        graph: &Graph,
+        start: NodeId,
+        goal: NodeId,
+        heuristic: impl Fn(NodeId) -> Cost,
+    ) -> Self {
+        let trace = algorithm.trace(graph, start, goal, heuristic);
+        return Run {
+            algorithm,
+            settled: 0,
+            events: trace.events.into_iter().map(|e| e.event).collect(),
+            cursor: 0,
+            seen: vec![None; graph.nodes.len()],
+            dist: vec![None; graph.nodes.len()],
+            via: vec![None; graph.nodes.len()],
+            result: trace.result,
+        };
+    }
+
+    pub fn done(&self) -> bool {
+        return self.cursor >= self.events.len();
+    }
+
+    // plays events up to and including the next node being settled
+    fn step(&mut self) {
+        while let Some(&event) = self.events.get(self.cursor) {
+            self.cursor += 1;
+            match event {
+                TraceEvent::Push { node, dist } => {
+                    self.seen[node.index()].get_or_insert(Seen::Frontier);
+                    self.dist[node.index()].get_or_insert(dist);
+                }
+                TraceEvent::DistUpdate { node, dist, via } => {
+                    self.dist[node.index()] = Some(dist);
+                    self.via[node.index()] = Some(via);
+                }
+                TraceEvent::Pop {
+                    node, stale: false, ..
+                } => {
+                    self.seen[node.index()] = Some(Seen::Settled);
+                    self.settled += 1;
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Animation {
+    pub runs: Vec<Run>,
+    pub paused: bool,
+    pub speed: f32,
+    finished: bool,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        return Animation {
+            runs: Vec::new(),
+            paused: false,
+            speed: DEFAULT_SPEED,
+            finished: false,
+        };
+    }
+}
+
+impl Animation {
+    pub fn start(&mut self, runs: Vec<Run>) {
+        self.runs = runs;
+        self.paused = false;
+        self.finished = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.runs.clear();
+        self.finished = false;
+    }
+
+    pub fn is_running(&self) -> bool {
+        return !self.runs.is_empty() && !self.finished;
+    }
+
+    // settled by any run, or else on a run's frontier
+    pub fn seen(&self, id: NodeId) -> Option<Seen> {
+        let seen: Vec<Seen> = self
+            .runs
+            .iter()
+            .filter_map(|run| run.seen.get(id.index()).copied().flatten())
+            .collect();
+        if seen.contains(&Seen::Settled) {
+            return Some(Seen::Settled);
+        }
+        return seen.first().copied();
+    }
+
+    // settled nodes take their run's colour (blended when several runs settled them), frontier
+    // nodes a paler version of it
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        let mut settled = Vec::new();
+        let mut frontier = Vec::new();
+        for (i, run) in self.runs.iter().enumerate() {
+            match run.seen.get(id.index()).copied().flatten() {
+                Some(Seen::Settled) => settled.push(RUN_COLORS[i % RUN_COLORS.len()]),
+                Some(Seen::Frontier) => frontier.push(RUN_COLORS[i % RUN_COLORS.len()]),
+                None => {}
+            }
+        }
+        let blend = |colors: &[Color]| {
+            let first = *colors.first()?;
+            return Some(colors[1..].iter().fold(first, |acc, c| acc.mix(c, 0.5)));
+        };
+        if let Some(color) = blend(&settled) {
+            return Some(color);
+        }
+        return blend(&frontier).map(|c| c.mix(&Color::WHITE, 0.6));
+    }
+
+    // what a paused (or finished) frame knows about a node, a line per run: its tentative
+    // distance, the node it would be reached from and whether it is settled yet
+    pub fn inspect(&self, id: NodeId, units: &WeightUnits) -> Option<String> {
+        if !(self.paused || self.finished) || self.runs.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = self
+            .runs
+            .iter()
+            .map(|run| {
+                let state = match (
+                    run.seen.get(id.index()).copied().flatten(),
+                    run.dist.get(id.index()).copied().flatten(),
+                ) {
+                    (_, None) => "not reached yet".to_string(),
+                    (seen, Some(dist)) => format!(
+                        "{} {}{}",
+                        if seen == Some(Seen::Settled) {
+                            "settled at"
+                        } else {
+                            "tentatively"
+                        },
+                        units.format(dist),
+                        match run.via[id.index()] {
+                            Some(via) => format!(" via {}", via),
+                            None => ", no predecessor".to_string(),
+                        }
+                    ),
+                };
+                format!("{}: {}", run.algorithm.name(), state)
+            })
+            .collect();
+        return Some(lines.join("\n"));
+    }
+
+    fn counters(&self) -> String {
+        return self
+            .runs
+            .iter()
+            .map(|r| format!("{}: {} settled", r.algorithm.name(), r.settled))
+            .collect::<Vec<_>>()
+            .join("  |  ");
+    }
+}
+
+pub fn speed_label(speed: f32) -> String {
+    if speed >= MAX_SPEED {
+        return format!("max ({}ms of settles a frame)", FRAME_BUDGET.as_millis());
+    }
+    return format!("{} settles/s", speed);
+}
+
+// runs `step` until it returns false or this frame's share is used up: `speed` steps a second of
+// `delta`, or at max speed as many as fit in FRAME_BUDGET. no frame spends longer than the budget,
+// and steps that didn't fit are dropped rather than bunched into the next frame
+pub fn pace(speed: f32, budget: &mut f32, delta: f32, mut step: impl FnMut() -> bool) -> usize {
+    let began = Instant::now();
+    let flat_out = speed >= MAX_SPEED;
+    *budget += delta * speed;
+    let mut steps = 0;
+    while (flat_out || *budget >= 1.0) && began.elapsed() < FRAME_BUDGET {
+        *budget -= 1.0;
+        steps += 1;
+        if !step() {
+            break;
+        }
+    }
+    *budget = budget.clamp(0.0, 1.0);
+    return steps;
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Animation>().add_systems(
+            Update,
+            (
+                handle_animation_keys.run_if(crate::prompt::prompt_closed),
+                handle_race_prompt,
+                advance_animation,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn query_nodes(
+    q_camera: &Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    hud: &mut ResultsHud,
+) -> Option<(NodeId, NodeId)> {
+    let (_, start, goal) = q_camera.single().ok()?;
+    if let (Some(start), Some(goal)) = (start.id, goal.id) {
+        return Some((start, goal));
+    }
+    hud.0 = "Missing starting or goal node!".to_string();
+    return None;
+}
+
+fn handle_animation_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    mut prompt: ResMut<Prompt>,
+    mut animation: ResMut<Animation>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+    tags: Res<Tags>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if shift && keys.just_pressed(KeyCode::KeyP) {
+        let Some((start, goal)) = query_nodes(&q_camera, &mut hud) else {
+            return Ok(());
+        };
+        let whole = q_camera.single()?.0.0.read().unwrap();
+        let graph = tags.restricted(&whole);
+        highlights.layer_mut(Layer::Path).clear();
+        animation.start(vec![Run::new(
+            Algorithm::Dijkstra,
+            &graph,
+            start,
+            goal,
+            |_| 0,
+        )]);
+    }
+
+    if shift && keys.just_pressed(KeyCode::KeyR) {
+        let names: Vec<&str> = Algorithm::ALL.iter().map(|a| a.name()).collect();
+        prompt.open(
+            PromptKind::Race,
+            format!(
+                "Race two of: {} (empty races the first two)",
+                names.join(", ")
+            ),
+        );
+    }
+
+    if !animation.runs.is_empty() {
+        if keys.just_pressed(KeyCode::Space) {
+            if animation.finished {
+                animation.clear();
+            } else {
+                animation.paused = !animation.paused;
+            }
+        }
+        if keys.just_pressed(KeyCode::Period) && animation.paused {
+            for run in animation.runs.iter_mut().filter(|r| !r.done()) {
+                run.step();
+            }
+            hud.0 = animation.counters();
+        }
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        animation.speed = (animation.speed * 0.5).max(0.5);
+        hud.0 = format!("Animation speed: {}", speed_label(animation.speed));
+    }
+    if keys.just_pressed(KeyCode::Equal) {
+        animation.speed = (animation.speed * 2.0).min(MAX_SPEED);
+        hud.0 = format!("Animation speed: {}", speed_label(animation.speed));
+    }
+
+    return Ok(());
+}
+
+fn handle_race_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    heuristic: RaceHeuristic,
+    tags: Res<Tags>,
+    mut animation: ResMut<Animation>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Race {
+            continue;
+        }
+        let words: Vec<&str> = event.text.split_whitespace().collect();
+        let picked: Option<Vec<Algorithm>> = words.iter().map(|w| Algorithm::parse(w)).collect();
+        let contenders = match picked {
+            Some(picked) if picked.is_empty() => vec![Algorithm::ALL[0], Algorithm::ALL[1]],
+            Some(picked) if picked.len() == 2 => picked,
+            _ => {
+                hud.0 = "Name exactly two algorithms to race".to_string();
+                continue;
+            }
+        };
+        let Some((start, goal)) = query_nodes(&q_camera, &mut hud) else {
+            continue;
+        };
+
+        let whole = q_camera.single()?.0.0.read().unwrap();
+        let graph = tags.restricted(&whole);
+        let h = heuristic.toward(&graph, goal);
+        highlights.layer_mut(Layer::Path).clear();
+        animation.start(
+            contenders
+                .into_iter()
+                .map(|a| Run::new(a, &graph, start, goal, &h))
+                .collect(),
+        );
+    }
+
+    return Ok(());
+}
+
+fn advance_animation(
+    time: Res<Time>,
+    mut budget: Local<f32>,
+    units: Res<WeightUnits>,
+    mut animation: ResMut<Animation>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if animation.runs.is_empty() || animation.paused || animation.finished {
+        *budget = 0.0;
+        return Ok(());
+    }
+    let speed = animation.speed;
+    let runs = &mut animation.runs;
+    let steps = pace(speed, &mut budget, time.delta_secs(), || {
+        for run in runs.iter_mut().filter(|r| !r.done()) {
+            run.step();
+        }
+        return !runs.iter().all(|r| r.done());
+    });
+    if steps == 0 {
+        return Ok(());
+    }
+    hud.0 = animation.counters();
+    if !animation.runs.iter().all(|r| r.done()) {
+        return Ok(());
+    }
+
+    animation.finished = true;
+    let least = animation.runs.iter().map(|r| r.settled).min().unwrap_or(0);
+    let winners: Vec<&Run> = animation
+        .runs
+        .iter()
+        .filter(|r| r.settled == least)
+        .collect();
+    let verdict = if animation.runs.len() == 1 {
+        format!("{} finished", winners[0].algorithm.name())
+    } else if winners.len() == animation.runs.len() {
+        format!("Tie: both settled {} nodes", least)
+    } else {
+        format!("{} explored less", winners[0].algorithm.name())
+    };
+    let path = match &winners[0].result {
+        Some((cost, path)) => {
+            highlights.set_path(Layer::Path, path);
+            format!(
+                "Path length: {}, Path: {}",
+                units.format(*cost),
+                Graph::fmt_path(path)
+            )
+        }
+        None => "No current available path".to_string(),
+    };
+    // exploring less is no win when the route it found costs more
+    let costs: Vec<(&str, Cost)> = animation
+        .runs
+        .iter()
+        .filter_map(|r| Some((r.algorithm.name(), r.result.as_ref()?.0)))
+        .collect();
+    let cheapest = costs.iter().map(|&(_, c)| c).min();
+    let mut lines = vec![animation.counters(), verdict, path];
+    lines.extend(
+        costs
+            .iter()
+            .filter(|&&(_, c)| Some(c) != cheapest)
+            .map(|&(name, c)| {
+                format!(
+                    "{} found a worse route: {} against the best {}",
+                    name,
+                    units.format(c),
+                    units.format(cheapest.unwrap())
+                )
+            }),
+    );
+    hud.0 = lines.join("\n");
+
+    return Ok(());
+}
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{MainCamera, ResultsHud, cursor_world};
+
+const ANNOTATION_COLOR: Color = Color::srgb(0.95, 0.9, 0.55);
+const STROKE_STEP: f32 = 4.0;
+
+// canvas-only notes: they are saved with the graph file but never touch the Graph itself
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    Text { position: Vec2, text: String },
+    Arrow { from: Vec2, to: Vec2 },
+    Stroke { points: Vec<Vec2> },
+}
+
+#[derive(Resource, Default)]
+struct AnnotationDraft {
+    // where the text prompt was opened, so the note lands where the user pointed
+    text_at: Option<Vec2>,
+    points: Vec<Vec2>,
+    arrow: bool,
+}
+
+pub struct AnnotationsPlugin;
+
+impl Plugin for AnnotationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnnotationDraft>().add_systems(
+            Update,
+            (
+                handle_annotation_keys,
+                handle_annotation_drag,
+                draw_annotations,
+            ),
+        );
+    }
+}
+
+pub fn spawn_annotation(commands: &mut Commands, annotation: Annotation) {
+    match &annotation {
+        Annotation::Text { position, text } => {
+            commands.spawn((
+                Text2d::new(text.clone()),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(ANNOTATION_COLOR),
+                Transform::from_translation(position.extend(5.0)),
+                annotation,
+            ));
+        }
+        Annotation::Arrow { .. } | Annotation::Stroke { .. } => {
+            commands.spawn(annotation);
+        }
+    }
+}
+
+fn handle_annotation_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    q_annotations: Query<Entity, With<Annotation>>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut draft: ResMut<AnnotationDraft>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    // ctrl+T is the TSP tour
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if keys.just_pressed(KeyCode::KeyT) && !prompt.is_open() && !ctrl {
+        if shift {
+            let count = q_annotations.iter().count();
+            for e in &q_annotations {
+                commands.entity(e).despawn();
+            }
+            hud.0 = format!("Cleared {} annotations", count);
+        } else if let Some(world) = cursor_world(&windows, &cams) {
+            draft.text_at = Some(world);
+            prompt.open(PromptKind::AnnotationText, "Annotation text");
+        }
+    }
+
+    for event in submitted.read() {
+        if event.kind != PromptKind::AnnotationText {
+            continue;
+        }
+        let Some(position) = draft.text_at.take() else {
+            continue;
+        };
+        if event.text.is_empty() {
+            continue;
+        }
+        spawn_annotation(
+            &mut commands,
+            Annotation::Text {
+                position,
+                text: event.text.clone(),
+            },
+        );
+    }
+}
+
+// right-drag draws freehand, shift+right-drag draws a straight arrow
+fn handle_annotation_drag(
+    mut commands: Commands,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut draft: ResMut<AnnotationDraft>,
+    mut gizmos: Gizmos,
+) {
+    let Some(world) = cursor_world(&windows, &cams) else {
+        return;
+    };
+    if buttons.just_pressed(MouseButton::Right) {
+        draft.points = vec![world];
+        draft.arrow = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    }
+    let Some(&first) = draft.points.first() else {
+        return;
+    };
+
+    if buttons.pressed(MouseButton::Right) {
+        if draft.arrow {
+            gizmos.arrow_2d(first, world, ANNOTATION_COLOR);
+        } else {
+            if draft.points.last().unwrap().distance(world) >= STROKE_STEP {
+                draft.points.push(world);
+            }
+            gizmos.linestrip_2d(draft.points.iter().copied(), ANNOTATION_COLOR);
+        }
+        return;
+    }
+
+    let points = std::mem::take(&mut draft.points);
+    if draft.arrow {
+        if first.distance(world) >= STROKE_STEP {
+            spawn_annotation(
+                &mut commands,
+                Annotation::Arrow {
+                    from: first,
+                    to: world,
+                },
+            );
+        }
+    } else if points.len() > 1 {
+        spawn_annotation(&mut commands, Annotation::Stroke { points });
+    }
+}
+
+fn draw_annotations(q_annotations: Query<&Annotation>, mut gizmos: Gizmos) {
+    for annotation in &q_annotations {
+        match annotation {
+            Annotation::Arrow { from, to } => {
+                gizmos.arrow_2d(*from, *to, ANNOTATION_COLOR);
+            }
+            Annotation::Stroke { points } => {
+                gizmos.linestrip_2d(points.iter().copied(), ANNOTATION_COLOR);
+            }
+            Annotation::Text { .. } => {}
+        }
+    }
+}
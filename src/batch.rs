@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::export;
+use crate::graph::{Cost, Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    GoalNode, HighlightedEdges, MainCamera, PathMode, ResultsHud, StartNode, WeightUnits,
+    WrapperGraph, show_shortest_path,
+};
+
+const QUERIES_FILE: &str = "queries.txt";
+const RESULTS_FILE: &str = "batch.csv";
+// queries times nodes above which the batch is split across threads
+const PARALLEL_WORK: usize = 20_000;
+// the rest are only in the csv
+const MAX_ROWS: usize = 15;
+const ROW: Color = Color::srgba(0.15, 0.15, 0.15, 0.9);
+const ROW_HOVERED: Color = Color::srgb(0.35, 0.35, 0.35);
+
+#[derive(Resource, Default)]
+struct Batch {
+    queries: Vec<(NodeId, NodeId)>,
+    results: Vec<Option<(Cost, Vec<NodeId>)>>,
+}
+
+#[derive(Component)]
+struct BatchPanel;
+
+#[derive(Component)]
+struct BatchRow(usize);
+
+pub struct BatchPlugin;
+
+impl Plugin for BatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Batch>()
+            .add_systems(Startup, spawn_batch_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_batch_keys.run_if(crate::prompt::prompt_closed),
+                    run_batch,
+                    update_batch_panel,
+                    handle_row_clicks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// below the heuristic panel on the right
+fn spawn_batch_panel(mut commands: Commands) {
+    commands.spawn((
+        UiNode {
+            position_type: PositionType::Absolute,
+            top: Val::Px(350.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        BatchPanel,
+    ));
+}
+
+// start and goal labels in pairs: `A-F B-D`, `A F, B D`, or one pair per line
+fn parse_queries(text: &str, n: usize) -> Result<Vec<(NodeId, NodeId)>, String> {
+    let labels: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || matches!(c, '-' | '>' | ',' | ';'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    if !labels.len().is_multiple_of(2) {
+        return Err(format!("`{}` has no goal", labels[labels.len() - 1]));
+    }
+    let id = |label: &str| {
+        Graph::parse_label(label)
+            .filter(|id| id.index() < n)
+            .ok_or_else(|| format!("node `{}` does not exist", label))
+    };
+    return labels
+        .chunks(2)
+        .map(|pair| Ok((id(pair[0])?, id(pair[1])?)))
+        .collect();
+}
+
+// shift+J asks for queries, or hides the results on screen
+fn handle_batch_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut prompt: ResMut<Prompt>,
+    mut batch: ResMut<Batch>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyJ) {
+        return;
+    }
+    if !batch.queries.is_empty() {
+        *batch = Batch::default();
+        hud.0 = "Batch results hidden".to_string();
+        return;
+    }
+    prompt.open(
+        PromptKind::Batch,
+        format!("Queries, e.g. `A-F B-D` (empty loads {})", QUERIES_FILE),
+    );
+}
+
+fn run_batch(
+    mut submitted: MessageReader<PromptSubmitted>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut batch: ResMut<Batch>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Batch {
+            continue;
+        }
+        let text = if event.text.is_empty() {
+            match std::fs::read_to_string(QUERIES_FILE) {
+                Ok(text) => text,
+                Err(e) => {
+                    hud.0 = format!("Could not load {}: {}", QUERIES_FILE, e);
+                    continue;
+                }
+            }
+        } else {
+            event.text.clone()
+        };
+
+        let graph = wrapper_graph.single()?.0.read().unwrap();
+        let queries = match parse_queries(&text, graph.nodes.len()) {
+            Ok(queries) if !queries.is_empty() => queries,
+            Ok(_) => {
+                hud.0 = "No queries to run".to_string();
+                continue;
+            }
+            Err(e) => {
+                hud.0 = format!("Bad query list: {}", e);
+                continue;
+            }
+        };
+        let threads = if queries.len() * graph.nodes.len() >= PARALLEL_WORK {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        } else {
+            1
+        };
+        let results = graph.shortest_paths_for(&queries, threads);
+
+        let csv = export::batch_csv(&graph, &queries, &results, |c| units.format(c));
+        let reached = results.iter().filter(|r| r.is_some()).count();
+        hud.0 = format!(
+            "Ran {} queries{}: {} reachable. {}",
+            queries.len(),
+            if threads > 1 {
+                format!(" on {} threads", threads)
+            } else {
+                String::new()
+            },
+            reached,
+            match std::fs::write(RESULTS_FILE, csv) {
+                Ok(()) => format!("Exported {}", RESULTS_FILE),
+                Err(e) => format!("Export failed: {}", e),
+            }
+        );
+        batch.queries = queries;
+        batch.results = results;
+    }
+
+    return Ok(());
+}
+
+fn update_batch_panel(
+    mut commands: Commands,
+    batch: Res<Batch>,
+    units: Res<WeightUnits>,
+    q_panel: Query<Entity, With<BatchPanel>>,
+) -> Result<()> {
+    if !batch.is_changed() && !units.is_changed() {
+        return Ok(());
+    }
+    let panel = q_panel.single()?;
+    commands.entity(panel).despawn_related::<Children>();
+    if batch.queries.is_empty() {
+        return Ok(());
+    }
+
+    let text = |content: String| {
+        (
+            Text::new(content),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )
+    };
+    let rows: Vec<String> = batch
+        .queries
+        .iter()
+        .zip(&batch.results)
+        .map(|(&(start, goal), result)| {
+            let query = format!("{} -> {}", start, goal);
+            match result {
+                Some((cost, path)) => format!(
+                    "{}: {} via {}",
+                    query,
+                    units.format(*cost),
+                    Graph::fmt_path(path)
+                ),
+                None => format!("{}: unreachable", query),
+            }
+        })
+        .collect();
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn(text(format!(
+            "Batch (Shift+J hides, click a row for its path, all in {})",
+            RESULTS_FILE
+        )));
+        for (i, row) in rows.into_iter().enumerate().take(MAX_ROWS) {
+            parent
+                .spawn((Button, UiNode::default(), BackgroundColor(ROW), BatchRow(i)))
+                .with_child(text(row));
+        }
+        if batch.queries.len() > MAX_ROWS {
+            parent.spawn(text(format!(
+                "... and {} more",
+                batch.queries.len() - MAX_ROWS
+            )));
+        }
+    });
+
+    return Ok(());
+}
+
+// clicking a row makes its query the start and goal and shows its path
+fn handle_row_clicks(
+    mut q_rows: Query<(&Interaction, &BatchRow, &mut BackgroundColor), Changed<Interaction>>,
+    mut q_camera: Query<(&WrapperGraph, &mut StartNode, &mut GoalNode), With<MainCamera>>,
+    batch: Res<Batch>,
+    mode: Res<PathMode>,
+    units: Res<WeightUnits>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for (interaction, row, mut background) in &mut q_rows {
+        background.0 = match interaction {
+            Interaction::None => ROW,
+            _ => ROW_HOVERED,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let (wg, mut start, mut goal) = q_camera.single_mut()?;
+        let graph = wg.0.read().unwrap();
+        let (from, to) = batch.queries[row.0];
+        if from.max(to).index() >= graph.nodes.len() {
+            continue;
+        }
+        start.id = Some(from);
+        goal.id = Some(to);
+        show_shortest_path(&graph, from, to, &mode, &units, &mut highlights, &mut hud);
+    }
+
+    return Ok(());
+}
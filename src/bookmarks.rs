@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::graph::NodeId;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    GoalNode, MainCamera, Node, ResultsHud, SelectedNode, StartNode, WrapperGraph, position_lookup,
+};
+
+const ROW: Color = Color::srgba(0.15, 0.15, 0.15, 0.9);
+const ROW_HOVERED: Color = Color::srgb(0.35, 0.35, 0.35);
+
+// named nodes in the order they were bookmarked, saved with the graph
+#[derive(Resource, Default)]
+pub struct Bookmarks(pub Vec<(NodeId, String)>);
+
+impl Bookmarks {
+    // follows renumbered nodes; bookmarks on removed nodes go
+    pub fn remap(&mut self, remap: &[Option<NodeId>]) {
+        self.0 = self
+            .0
+            .drain(..)
+            .filter_map(|(old, name)| Some((remap[old.index()]?, name)))
+            .collect();
+    }
+
+    fn position(&self, id: NodeId) -> Option<usize> {
+        return self.0.iter().position(|&(b, _)| b == id);
+    }
+}
+
+// node the open bookmark prompt will name
+#[derive(Resource, Default)]
+struct BookmarkDraft(Option<NodeId>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Jump,
+    Start,
+    Goal,
+}
+
+#[derive(Component)]
+struct BookmarkPanel;
+
+#[derive(Component)]
+struct BookmarkButton(usize, Action);
+
+pub struct BookmarksPlugin;
+
+impl Plugin for BookmarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bookmarks>()
+            .init_resource::<BookmarkDraft>()
+            .add_systems(Startup, spawn_bookmark_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_bookmark_keys.run_if(crate::prompt::prompt_closed),
+                    handle_bookmark_prompt,
+                    update_bookmark_panel,
+                    handle_bookmark_clicks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// down the left side, below the results
+fn spawn_bookmark_panel(mut commands: Commands) {
+    commands.spawn((
+        UiNode {
+            position_type: PositionType::Absolute,
+            top: Val::Px(200.0),
+            left: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        BookmarkPanel,
+    ));
+}
+
+// F6 with a node selected asks for a bookmark name; for a bookmarked node an empty name removes it
+fn handle_bookmark_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_selected: Query<&SelectedNode, With<MainCamera>>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    bookmarks: Res<Bookmarks>,
+    mut draft: ResMut<BookmarkDraft>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::F6) {
+        return Ok(());
+    }
+    let Some(id) = q_selected.single()?.id else {
+        hud.0 = "Select a node, then press F6 to bookmark it".to_string();
+        return Ok(());
+    };
+    let graph = q_graph.single()?.0.read().unwrap();
+    draft.0 = Some(id);
+    let label = match bookmarks.position(id) {
+        Some(i) => format!(
+            "Rename the bookmark \"{}\" on {} (empty removes it)",
+            bookmarks.0[i].1,
+            graph.node_name(id)
+        ),
+        None => format!("Bookmark {} as (empty uses its label)", graph.node_name(id)),
+    };
+    prompt.open(PromptKind::Bookmark, label);
+
+    return Ok(());
+}
+
+fn handle_bookmark_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut draft: ResMut<BookmarkDraft>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let graph = q_graph.single()?.0.read().unwrap();
+    for event in submitted.read() {
+        if event.kind != PromptKind::Bookmark {
+            continue;
+        }
+        let Some(id) = draft.0.take() else {
+            continue;
+        };
+        let name = event.text.trim();
+        match (bookmarks.position(id), name.is_empty()) {
+            (Some(i), true) => {
+                let (_, name) = bookmarks.0.remove(i);
+                hud.0 = format!("Removed the bookmark \"{}\"", name);
+            }
+            (Some(i), false) => {
+                bookmarks.0[i].1 = name.to_string();
+                hud.0 = format!("Renamed the bookmark on {}", graph.node_name(id));
+            }
+            (None, _) => {
+                let name = if name.is_empty() {
+                    graph.node_name(id)
+                } else {
+                    name.to_string()
+                };
+                hud.0 = format!("Bookmarked {} as \"{}\"", graph.node_name(id), name);
+                bookmarks.0.push((id, name));
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+fn update_bookmark_panel(
+    mut commands: Commands,
+    bookmarks: Res<Bookmarks>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    q_panel: Query<Entity, With<BookmarkPanel>>,
+) -> Result<()> {
+    if !bookmarks.is_changed() {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    let panel = q_panel.single()?;
+    commands.entity(panel).despawn_related::<Children>();
+    if bookmarks.0.is_empty() {
+        return Ok(());
+    }
+
+    let text = |content: String| {
+        (
+            Text::new(content),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )
+    };
+    let button = |index: usize, action: Action| {
+        (
+            Button,
+            UiNode {
+                padding: UiRect::horizontal(Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(ROW),
+            BookmarkButton(index, action),
+        )
+    };
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn(text(
+            "Bookmarks (click to jump, S/G to route from or to)".to_string(),
+        ));
+        for (i, (id, name)) in bookmarks.0.iter().enumerate() {
+            parent
+                .spawn(UiNode {
+                    column_gap: Val::Px(2.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(button(i, Action::Jump)).with_child(text(format!(
+                        "{} ({})",
+                        name,
+                        graph.node_name(*id)
+                    )));
+                    row.spawn(button(i, Action::Start))
+                        .with_child(text("S".to_string()));
+                    row.spawn(button(i, Action::Goal))
+                        .with_child(text("G".to_string()));
+                });
+        }
+    });
+
+    return Ok(());
+}
+
+// jumping selects the node and centres the view on it
+fn handle_bookmark_clicks(
+    mut q_buttons: Query<
+        (&Interaction, &BookmarkButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut q_camera: Query<
+        (
+            &mut Transform,
+            &mut SelectedNode,
+            &mut StartNode,
+            &mut GoalNode,
+        ),
+        With<MainCamera>,
+    >,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    bookmarks: Res<Bookmarks>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let graph = q_graph.single()?.0.read().unwrap();
+    let position = position_lookup(&nodes);
+    for (interaction, button, mut background) in &mut q_buttons {
+        background.0 = match interaction {
+            Interaction::None => ROW,
+            _ => ROW_HOVERED,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(&(id, ref name)) = bookmarks.0.get(button.0) else {
+            continue;
+        };
+        let Some(at) = position(id) else {
+            continue;
+        };
+        let (mut transform, mut selected, mut start, mut goal) = q_camera.single_mut()?;
+        match button.1 {
+            Action::Jump => {
+                selected.id = Some(id);
+                transform.translation.x = at.x;
+                transform.translation.y = at.y;
+                hud.0 = format!("Jumped to \"{}\" ({})", name, graph.node_name(id));
+            }
+            Action::Start => {
+                start.id = Some(id);
+                if goal.id == Some(id) {
+                    goal.id = None;
+                }
+                hud.0 = format!("Start is \"{}\" ({})", name, graph.node_name(id));
+            }
+            Action::Goal => {
+                goal.id = Some(id);
+                if start.id == Some(id) {
+                    start.id = None;
+                }
+                hud.0 = format!("Goal is \"{}\" ({})", name, graph.node_name(id));
+            }
+        }
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+
+use crate::graph::NodeId;
+use crate::{GraphSpawner, MainCamera, MultiSelection, Node, ResultsHud, WrapperGraph, ord};
+
+pub struct BulkEdgesPlugin;
+
+impl Plugin for BulkEdgesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            handle_bulk_edges.run_if(crate::prompt::prompt_closed),
+        );
+    }
+}
+
+fn clique(ids: &[NodeId]) -> Vec<(NodeId, NodeId)> {
+    let mut pairs = Vec::new();
+    for (i, &a) in ids.iter().enumerate() {
+        for &b in &ids[i + 1..] {
+            pairs.push((a, b));
+        }
+    }
+    return pairs;
+}
+
+fn chain(ids: &[NodeId]) -> Vec<(NodeId, NodeId)> {
+    return ids.windows(2).map(|w| (w[0], w[1])).collect();
+}
+
+fn nearest_neighbours(ids: &[NodeId], position: impl Fn(NodeId) -> Vec2) -> Vec<(NodeId, NodeId)> {
+    let mut pairs: Vec<(NodeId, NodeId)> = ids
+        .iter()
+        .filter_map(|&a| {
+            ids.iter()
+                .filter(|&&b| b != a)
+                .min_by(|&&b, &&c| {
+                    let (db, dc) = (
+                        position(a).distance(position(b)),
+                        position(a).distance(position(c)),
+                    );
+                    db.total_cmp(&dc)
+                })
+                .map(|&b| ord(a, b))
+        })
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    return pairs;
+}
+
+fn handle_bulk_edges(
+    keys: Res<ButtonInput<KeyCode>>,
+    nodes: Query<&Node>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    multi_selection: Query<&MultiSelection, With<MainCamera>>,
+    mut spawner: GraphSpawner,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let (all, path, nearest) = (
+        // ctrl+C writes the canonical form
+        keys.just_pressed(KeyCode::KeyC)
+            && !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+        // shift+H toggles minimum-hop mode, ctrl+H is the contraction hierarchy
+        keys.just_pressed(KeyCode::KeyH)
+            && !keys.any_pressed([
+                KeyCode::ShiftLeft,
+                KeyCode::ShiftRight,
+                KeyCode::ControlLeft,
+                KeyCode::ControlRight,
+            ]),
+        // ctrl+N tags
+        keys.just_pressed(KeyCode::KeyN)
+            && !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+    );
+    if !all && !path && !nearest {
+        return Ok(());
+    }
+    let ids = &multi_selection.single()?.ids;
+    if ids.len() < 2 {
+        hud.0 = "Shift+click at least two nodes to connect them in bulk".to_string();
+        return Ok(());
+    }
+
+    let node = |id: NodeId| nodes.iter().find(|n| n.id == id).unwrap();
+    let pairs = if all {
+        clique(ids)
+    } else if path {
+        chain(ids)
+    } else {
+        nearest_neighbours(ids, |id| node(id).position)
+    };
+
+    let wg = wrapper_graph.single()?;
+    let mut graph = wg.0.write().unwrap();
+    let mut added = 0;
+    for (a, b) in pairs {
+        if graph.has_edge(a, b) {
+            continue;
+        }
+        if spawner.connect(&mut graph, node(a), node(b)) {
+            added += 1;
+        }
+    }
+    hud.0 = format!("Added {} edges", added);
+
+    return Ok(());
+}
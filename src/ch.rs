@@ -0,0 +1,702 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::rng::Rng;
+use crate::{
+    GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, position_lookup,
+};
+
+// a witness search gives up after settling this many nodes, adding a shortcut it may not need
+const WITNESS_SETTLES: usize = 64;
+const FRAME_BUDGET: Duration = Duration::from_millis(8);
+const BENCH_QUERIES: usize = 200;
+const BENCH_NEIGHBOURS: usize = 3;
+const MAX_BENCH_NODES: usize = 5000;
+const PATH: Color = Color::srgb(0.95, 0.45, 0.85);
+const SHORTCUT: Color = Color::srgb(0.6, 0.5, 0.7);
+
+// every node's rank, and the edges each search may take, both toward higher ranks: `up[v]` out of
+// v, `down[v]` into v, read backwards by the search from the goal
+#[derive(Debug, Clone, Default)]
+pub struct ContractionHierarchy {
+    rank: Vec<usize>,
+    up: Vec<Vec<(usize, Cost)>>,
+    down: Vec<Vec<(usize, Cost)>>,
+    // the node each shortcut (from, to) skips
+    middle: HashMap<(usize, usize), usize>,
+    pub shortcuts: usize,
+}
+
+impl ContractionHierarchy {
+    // a bidirectional search that only ever climbs; each side stops once its nearest open node
+    // is no closer than the best meeting found. also returns how many nodes both sides settled
+    pub fn query(&self, start: NodeId, goal: NodeId) -> (Option<(Cost, Vec<NodeId>)>, usize) {
+        let (start, goal) = (start.index(), goal.index());
+        let n = self.rank.len();
+        let mut dist: [Vec<Option<Cost>>; 2] = [vec![None; n], vec![None; n]];
+        let mut prev: [Vec<Option<usize>>; 2] = [vec![None; n], vec![None; n]];
+        let mut open = [BinaryHeap::new(), BinaryHeap::new()];
+        for (side, from) in [start, goal].into_iter().enumerate() {
+            dist[side][from] = Some(0);
+            open[side].push(Reverse((0, from)));
+        }
+        let mut best: Option<(Cost, usize)> = None;
+        let mut settled = 0;
+
+        loop {
+            let top = |side: usize| open[side].peek().map(|&Reverse((cost, _))| cost);
+            let side = match (top(0), top(1)) {
+                (None, None) => break,
+                (Some(_), None) => 0,
+                (None, Some(_)) => 1,
+                (Some(a), Some(b)) => (b < a) as usize,
+            };
+            let Reverse((cost, v)) = open[side].pop().unwrap();
+            if best.is_some_and(|(b, _)| cost >= b) {
+                break;
+            }
+            if dist[side][v].is_some_and(|d| cost > d) {
+                continue;
+            }
+            settled += 1;
+            if let Some(other) = dist[1 - side][v]
+                && best.is_none_or(|(b, _)| cost + other < b)
+            {
+                best = Some((cost + other, v));
+            }
+            let edges = if side == 0 {
+                &self.up[v]
+            } else {
+                &self.down[v]
+            };
+            for &(w, c) in edges {
+                let next = cost + c;
+                if dist[side][w].is_none_or(|d| next < d) {
+                    dist[side][w] = Some(next);
+                    prev[side][w] = Some(v);
+                    open[side].push(Reverse((next, w)));
+                }
+            }
+        }
+
+        let Some((cost, meet)) = best else {
+            return (None, settled);
+        };
+        let mut hops = vec![meet];
+        while let Some(p) = prev[0][*hops.last().unwrap()] {
+            hops.push(p);
+        }
+        hops.reverse();
+        while let Some(p) = prev[1][*hops.last().unwrap()] {
+            hops.push(p);
+        }
+        let mut path = vec![start];
+        for hop in hops.windows(2) {
+            self.unpack(hop[0], hop[1], &mut path);
+        }
+        return (
+            Some((cost, path.into_iter().map(NodeId).collect())),
+            settled,
+        );
+    }
+
+    // pushes the original edges a shortcut stands for, all but its first node
+    fn unpack(&self, from: usize, to: usize, path: &mut Vec<usize>) {
+        match self.middle.get(&(from, to)) {
+            Some(&skipped) => {
+                self.unpack(from, skipped, path);
+                self.unpack(skipped, to, path);
+            }
+            None => path.push(to),
+        }
+    }
+
+    // the shortcuts as (from, to) pairs, to draw them
+    fn shortcut_pairs(&self) -> impl Iterator<Item = &(usize, usize)> {
+        return self.middle.keys();
+    }
+}
+
+// contracts one node at a time, so progress can be shown between frames. nodes go in the order of
+// their edge difference, the shortcuts one would need less the edges it takes away, plus how many
+// neighbours already went; a node's place is only checked again when it comes up
+pub struct Contractor {
+    // edges among the nodes not contracted yet, each with the node it skips if it is a shortcut
+    out: Vec<BTreeMap<usize, (Cost, Option<usize>)>>,
+    inc: Vec<BTreeMap<usize, (Cost, Option<usize>)>>,
+    gone_neighbours: Vec<i64>,
+    queue: BinaryHeap<Reverse<(i64, usize)>>,
+    done: usize,
+    hierarchy: ContractionHierarchy,
+}
+
+impl Contractor {
+    // None with negative weights or turn bans, which the hierarchy can't keep
+    pub fn new(graph: &Graph) -> Option<Contractor> {
+        if graph.has_negative_edges() || !graph.forbidden.is_empty() {
+            return None;
+        }
+        let n = graph.nodes.len();
+        let mut out: Vec<BTreeMap<usize, (Cost, Option<usize>)>> = vec![BTreeMap::new(); n];
+        let mut inc = out.clone();
+        // self loops never shorten a path, and of parallel edges only the cheapest counts
+        for (from, edge) in graph.arcs().filter(|(from, e)| e.node != *from) {
+            let (from, to) = (from.index(), edge.node.index());
+            let cost = match out[from].get(&to) {
+                Some(&(c, _)) => c.min(edge.cost),
+                None => edge.cost,
+            };
+            out[from].insert(to, (cost, None));
+            inc[to].insert(from, (cost, None));
+        }
+        let mut contractor = Contractor {
+            out,
+            inc,
+            gone_neighbours: vec![0; n],
+            queue: BinaryHeap::new(),
+            done: 0,
+            hierarchy: ContractionHierarchy {
+                rank: vec![0; n],
+                up: vec![Vec::new(); n],
+                down: vec![Vec::new(); n],
+                ..default()
+            },
+        };
+        for v in 0..n {
+            let priority = contractor.priority(v, &contractor.needed(v));
+            contractor.queue.push(Reverse((priority, v)));
+        }
+        return Some(contractor);
+    }
+
+    pub fn progress(&self) -> (usize, usize) {
+        return (self.done, self.hierarchy.rank.len());
+    }
+
+    pub fn shortcuts(&self) -> usize {
+        return self.hierarchy.shortcuts;
+    }
+
+    pub fn finish(self) -> ContractionHierarchy {
+        return self.hierarchy;
+    }
+
+    // contracts the next node, or returns false when none is left
+    pub fn step(&mut self) -> bool {
+        while let Some(Reverse((_, v))) = self.queue.pop() {
+            if self.hierarchy.rank[v] != 0 {
+                continue;
+            }
+            let shortcuts = self.needed(v);
+            let priority = self.priority(v, &shortcuts);
+            if let Some(&Reverse((next, _))) = self.queue.peek()
+                && priority > next
+            {
+                self.queue.push(Reverse((priority, v)));
+                continue;
+            }
+            self.contract(v, shortcuts);
+            return true;
+        }
+        return false;
+    }
+
+    fn priority(&self, v: usize, shortcuts: &[(usize, usize, Cost)]) -> i64 {
+        let removed = self.out[v].len() + self.inc[v].len();
+        return shortcuts.len() as i64 - removed as i64 + self.gone_neighbours[v];
+    }
+
+    // the shortcuts (from, to, cost) contracting v needs: every path u -> v -> w with no
+    // witness, a path at most as cheap that avoids v
+    fn needed(&self, v: usize) -> Vec<(usize, usize, Cost)> {
+        let mut needed = Vec::new();
+        for (&u, &(to_v, _)) in &self.inc[v] {
+            let targets: Vec<(usize, Cost)> = self.out[v]
+                .iter()
+                .filter(|&(&w, _)| w != u)
+                .map(|(&w, &(from_v, _))| (w, to_v + from_v))
+                .collect();
+            let Some(limit) = targets.iter().map(|&(_, c)| c).max() else {
+                continue;
+            };
+            let witness = self.witness(u, v, limit);
+            for (w, via) in targets {
+                if witness.get(&w).is_none_or(|&d| d > via) {
+                    needed.push((u, w, via));
+                }
+            }
+        }
+        return needed;
+    }
+
+    // distances from `from` among the remaining nodes without `skip`, up to `limit`
+    fn witness(&self, from: usize, skip: usize, limit: Cost) -> HashMap<usize, Cost> {
+        let mut dist: HashMap<usize, Cost> = HashMap::from([(from, 0)]);
+        let mut open = BinaryHeap::from([Reverse((0, from))]);
+        let mut settled = 0;
+        while let Some(Reverse((cost, v))) = open.pop() {
+            if cost > limit || settled == WITNESS_SETTLES {
+                break;
+            }
+            if dist[&v] < cost {
+                continue;
+            }
+            settled += 1;
+            for (&w, &(c, _)) in &self.out[v] {
+                let next = cost + c;
+                if w != skip && dist.get(&w).is_none_or(|&d| next < d) {
+                    dist.insert(w, next);
+                    open.push(Reverse((next, w)));
+                }
+            }
+        }
+        return dist;
+    }
+
+    // v takes the next rank; its remaining edges all lead to higher ranks, so they become its
+    // upward edges, and the shortcuts take over the paths through it
+    fn contract(&mut self, v: usize, shortcuts: Vec<(usize, usize, Cost)>) {
+        let hierarchy = &mut self.hierarchy;
+        self.done += 1;
+        hierarchy.rank[v] = self.done;
+        let (out, inc) = (
+            std::mem::take(&mut self.out[v]),
+            std::mem::take(&mut self.inc[v]),
+        );
+        for (&w, &(cost, skipped)) in &out {
+            hierarchy.up[v].push((w, cost));
+            if let Some(skipped) = skipped {
+                hierarchy.middle.insert((v, w), skipped);
+            }
+            self.inc[w].remove(&v);
+            self.gone_neighbours[w] += 1;
+        }
+        for (&u, &(cost, skipped)) in &inc {
+            hierarchy.down[v].push((u, cost));
+            if let Some(skipped) = skipped {
+                hierarchy.middle.insert((u, v), skipped);
+            }
+            self.out[u].remove(&v);
+            self.gone_neighbours[u] += 1;
+        }
+        for (u, w, cost) in shortcuts {
+            if self.out[u].get(&w).is_some_and(|&(c, _)| c <= cost) {
+                continue;
+            }
+            self.out[u].insert(w, (cost, Some(v)));
+            self.inc[w].insert(u, (cost, Some(v)));
+            hierarchy.shortcuts += 1;
+        }
+    }
+}
+
+// random points in a square, each joined both ways to its nearest few, weighted by distance
+fn generated(nodes: usize, rng: &mut Rng) -> Graph {
+    let side = (nodes as f32).sqrt() * 40.0;
+    let points: Vec<Vec2> = (0..nodes)
+        .map(|_| Vec2::new(rng.range_f32(0.0, side), rng.range_f32(0.0, side)))
+        .collect();
+    let mut graph = Graph {
+        nodes: vec![Vec::new(); nodes],
+        ..Default::default()
+    };
+    let k = BENCH_NEIGHBOURS.min(nodes.saturating_sub(1));
+    if k == 0 {
+        return graph;
+    }
+    // sorted by x, so the search for each point's nearest walks out to either side and stops once
+    // the gap in x alone is farther than the k-th nearest so far
+    let mut order: Vec<usize> = (0..nodes).collect();
+    order.sort_by(|&a, &b| points[a].x.total_cmp(&points[b].x));
+    for (i, &a) in order.iter().enumerate() {
+        let p = points[a];
+        let mut nearest: Vec<(f32, usize)> = Vec::with_capacity(k + 1);
+        let farthest = |nearest: &[(f32, usize)]| match nearest.len() < k {
+            true => f32::INFINITY,
+            false => nearest[k - 1].0,
+        };
+        let offer = |nearest: &mut Vec<(f32, usize)>, b: usize| {
+            nearest.push((p.distance(points[b]), b));
+            nearest.sort_by(|x, y| x.0.total_cmp(&y.0));
+            nearest.truncate(k);
+        };
+        for &b in order[..i].iter().rev() {
+            if p.x - points[b].x > farthest(&nearest) {
+                break;
+            }
+            offer(&mut nearest, b);
+        }
+        for &b in &order[i + 1..] {
+            if points[b].x - p.x > farthest(&nearest) {
+                break;
+            }
+            offer(&mut nearest, b);
+        }
+        for (d, b) in nearest {
+            let (a, b) = (NodeId(a), NodeId(b));
+            if !graph.has_edge(a, b) {
+                graph.add_lane(a, b, (d.round() as Cost).max(1), true);
+            }
+        }
+    }
+    return graph;
+}
+
+// the benchmark's baseline: dijkstra stopping at the goal like shortest_path does, but without
+// recording a trace, so only the searches are timed
+fn dijkstra(graph: &Graph, start: NodeId, goal: NodeId) -> Option<Cost> {
+    let mut dist = vec![Cost::MAX; graph.nodes.len()];
+    let mut open = BinaryHeap::from([Reverse((0, start))]);
+    dist[start.index()] = 0;
+    while let Some(Reverse((cost, v))) = open.pop() {
+        if v == goal {
+            return Some(cost);
+        }
+        if cost > dist[v.index()] {
+            continue;
+        }
+        for edge in graph.edges_from(v) {
+            let next = cost + edge.cost;
+            if next < dist[edge.node.index()] {
+                dist[edge.node.index()] = next;
+                open.push(Reverse((next, edge.node)));
+            }
+        }
+    }
+    return None;
+}
+
+// what the preprocessing is working on: the graph on screen, or one generated to time queries on
+enum Target {
+    Canvas,
+    Bench { seed: u64 },
+}
+
+struct Job {
+    target: Target,
+    graph: Graph,
+    contractor: Contractor,
+    spent: Duration,
+}
+
+#[derive(Resource, Default)]
+struct ChView {
+    job: Option<Job>,
+    // the finished hierarchy for the graph on screen, with the weights it was built for
+    hierarchy: Option<(ContractionHierarchy, Vec<(NodeId, NodeId, Cost)>)>,
+    query: Option<(NodeId, NodeId)>,
+    path: Vec<NodeId>,
+}
+
+pub struct ChPlugin;
+
+impl Plugin for ChPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChView>().add_systems(
+            Update,
+            (
+                handle_ch_keys.run_if(crate::prompt::prompt_closed),
+                start_ch,
+                contract_ch,
+                query_ch,
+                draw_ch,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+H asks what to contract, or drops the hierarchy
+fn handle_ch_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut view: ResMut<ChView>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+    if view.job.is_some() || view.hierarchy.is_some() {
+        *view = ChView::default();
+        hud.0 = "Contraction hierarchy off".to_string();
+        return;
+    }
+    prompt.open(
+        PromptKind::Ch,
+        "Contraction hierarchy: empty for this graph, or `bench 2000` to time queries on a generated graph",
+    );
+}
+
+fn start_ch(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut app_rng: ResMut<Rng>,
+    mut view: ResMut<ChView>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Ch {
+            continue;
+        }
+        let words: Vec<&str> = event.text.split_whitespace().collect();
+        let (target, graph) = match words.as_slice() {
+            [] => (Target::Canvas, q_graph.single()?.0.read().unwrap().clone()),
+            [bench, nodes] if bench.eq_ignore_ascii_case("bench") => {
+                let Some(nodes) = nodes
+                    .parse()
+                    .ok()
+                    .filter(|n| (2..=MAX_BENCH_NODES).contains(n))
+                else {
+                    hud.0 = format!("Benchmark between 2 and {} nodes", MAX_BENCH_NODES);
+                    continue;
+                };
+                let seed = app_rng.next_u64() >> 32;
+                (
+                    Target::Bench { seed },
+                    generated(nodes, &mut Rng::new(seed)),
+                )
+            }
+            _ => {
+                hud.0 = format!(
+                    "Could not read `{}`: leave it empty, or try `bench 2000`",
+                    event.text
+                );
+                continue;
+            }
+        };
+        if graph.nodes.is_empty() {
+            hud.0 = "Add some nodes to contract".to_string();
+            continue;
+        }
+        let Some(contractor) = Contractor::new(&graph) else {
+            hud.0 =
+                "Contraction hierarchies need non-negative weights and no turn bans".to_string();
+            continue;
+        };
+        *view = ChView {
+            job: Some(Job {
+                target,
+                graph,
+                contractor,
+                spent: Duration::ZERO,
+            }),
+            ..default()
+        };
+    }
+
+    return Ok(());
+}
+
+// contracts for FRAME_BUDGET each frame; a benchmark then times its queries against dijkstra
+fn contract_ch(mut view: ResMut<ChView>, mut hud: ResMut<ResultsHud>) -> Result<()> {
+    let Some(job) = &mut view.job else {
+        return Ok(());
+    };
+    let started = Instant::now();
+    let mut more = true;
+    while more && started.elapsed() < FRAME_BUDGET {
+        more = job.contractor.step();
+    }
+    job.spent += started.elapsed();
+    let (done, total) = job.contractor.progress();
+    if more {
+        hud.0 = format!(
+            "Contracting: {} of {} nodes ({:.0}%), {} shortcuts so far (Ctrl+H stops)",
+            done,
+            total,
+            done as f32 / total as f32 * 100.0,
+            job.contractor.shortcuts()
+        );
+        return Ok(());
+    }
+
+    let job = view.job.take().unwrap();
+    let edges = job.graph.nodes.iter().map(|e| e.len()).sum::<usize>();
+    let preprocessing = format!(
+        "Contracted {} nodes in {:.0} ms: {} shortcuts on top of {} edges",
+        total,
+        job.spent.as_secs_f64() * 1000.0,
+        job.contractor.shortcuts(),
+        edges
+    );
+    let hierarchy = job.contractor.finish();
+    let Target::Bench { seed } = job.target else {
+        view.hierarchy = Some((hierarchy, job.graph.arc_costs()));
+        hud.0 = format!(
+            "{}\nSet a start and goal to query it (Ctrl+H drops it)",
+            preprocessing
+        );
+        return Ok(());
+    };
+
+    let mut rng = Rng::new(seed);
+    let pairs: Vec<(NodeId, NodeId)> = (0..BENCH_QUERIES)
+        .map(|_| {
+            let pick = |rng: &mut Rng| NodeId((rng.next_u64() % total as u64) as usize);
+            (pick(&mut rng), pick(&mut rng))
+        })
+        .collect();
+    let started = Instant::now();
+    let expected: Vec<Option<Cost>> = pairs
+        .iter()
+        .map(|&(s, g)| dijkstra(&job.graph, s, g))
+        .collect();
+    let dijkstra = started.elapsed();
+    let started = Instant::now();
+    let found: Vec<Option<Cost>> = pairs
+        .iter()
+        .map(|&(s, g)| hierarchy.query(s, g).0.map(|(c, _)| c))
+        .collect();
+    let ch = started.elapsed();
+    let wrong = expected.iter().zip(&found).filter(|(a, b)| a != b).count();
+    let per_query = |d: Duration| d.as_secs_f64() * 1e6 / BENCH_QUERIES as f64;
+    hud.0 = format!(
+        "Benchmark on a generated graph (seed {})\n{}\n{} random queries: Dijkstra {:.0} us each, CH {:.0} us each, {:.1}x faster{}",
+        seed,
+        preprocessing,
+        BENCH_QUERIES,
+        per_query(dijkstra),
+        per_query(ch),
+        dijkstra.as_secs_f64() / ch.as_secs_f64().max(1e-9),
+        if wrong == 0 {
+            ", every distance agreeing".to_string()
+        } else {
+            format!(", {} distances disagreeing", wrong)
+        }
+    );
+
+    return Ok(());
+}
+
+// reruns for each new start and goal; editing the graph drops the hierarchy
+fn query_ch(
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut view: ResMut<ChView>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some((_, built)) = &view.hierarchy else {
+        return Ok(());
+    };
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    if graph.arc_costs() != *built {
+        *view = ChView::default();
+        hud.0 = "The graph changed, so the contraction hierarchy was dropped".to_string();
+        return Ok(());
+    }
+    let query = start.id.zip(goal.id);
+    if view.query == query {
+        return Ok(());
+    }
+    view.query = query;
+    view.path.clear();
+    let Some((start, goal)) = query else {
+        return Ok(());
+    };
+
+    let (hierarchy, _) = view.hierarchy.as_ref().unwrap();
+    let (result, settled) = hierarchy.query(start, goal);
+    let baseline = graph.shortest_path_with_stats(start, goal).1;
+    let Some((cost, path)) = result else {
+        hud.0 = format!(
+            "CH: no current available path, settled {} (Dijkstra {})",
+            settled, baseline.settled
+        );
+        return Ok(());
+    };
+    hud.0 = format!(
+        "CH path length: {}, Path: {}\nCH settled {} going up from both ends, Dijkstra {}",
+        units.format(cost),
+        Graph::fmt_path(&path),
+        settled,
+        baseline.settled
+    );
+    view.path = path;
+
+    return Ok(());
+}
+
+// shortcuts as faint straight lines, the unpacked path thick
+fn draw_ch(view: Res<ChView>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let Some((hierarchy, _)) = &view.hierarchy else {
+        return;
+    };
+    let position = position_lookup(&nodes);
+    for &(a, b) in hierarchy.shortcut_pairs() {
+        if let (Some(a), Some(b)) = (position(NodeId(a)), position(NodeId(b))) {
+            gizmos.line_2d(a, b, SHORTCUT.with_alpha(0.5));
+        }
+    }
+    for hop in view.path.windows(2) {
+        let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) else {
+            continue;
+        };
+        let side = (b - a).normalize_or_zero().perp() * 2.0;
+        for offset in [-side, Vec2::ZERO, side] {
+            gizmos.line_2d(a + offset, b + offset, PATH);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contracted(graph: &Graph) -> ContractionHierarchy {
+        let mut contractor = Contractor::new(graph).unwrap();
+        while contractor.step() {}
+        return contractor.finish();
+    }
+
+    #[test]
+    fn test_queries_match_dijkstra() {
+        let mut graph = generated(60, &mut Rng::new(3));
+        // a few one-way edges too
+        for a in (0..60).step_by(7) {
+            graph.nodes[a].retain(|e| e.node.index() % 2 == 0);
+        }
+        let hierarchy = contracted(&graph);
+        for start in graph.ids() {
+            for goal in graph.ids() {
+                let (result, _) = hierarchy.query(start, goal);
+                let expected = graph.shortest_path(start, goal).map(|(c, _)| c);
+                assert_eq!(result.as_ref().map(|(c, _)| *c), expected);
+                if let Some((cost, path)) = result {
+                    assert_eq!((path[0], path[path.len() - 1]), (start, goal));
+                    assert_eq!(graph.path_cost(&path), Some(cost));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_graph() {
+        let graph = generated(300, &mut Rng::new(5));
+        for (from, edges) in graph.nodes.iter().enumerate() {
+            // every node has its own nearest few, once each, and edges only come in pairs
+            assert!(edges.len() >= BENCH_NEIGHBOURS);
+            for edge in edges {
+                assert_eq!(edges.iter().filter(|e| e.node == edge.node).count(), 1);
+                assert!(graph.has_edge(edge.node, NodeId(from)));
+            }
+        }
+        assert!(generated(1, &mut Rng::new(5)).nodes[0].is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_graphs() {
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0, -1], vec![1, 0]]);
+        assert!(Contractor::new(&graph).is_none());
+        graph.nodes[0][0].cost = 1;
+        assert!(Contractor::new(&graph).is_some());
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(0)));
+        assert!(Contractor::new(&graph).is_none());
+    }
+}
@@ -0,0 +1,231 @@
+use serde::Serialize;
+
+use crate::document;
+use crate::graph::{Cost, Graph, NodeId};
+
+// what `--check` prints: whether the submitted path is a walk the graph allows from the start to
+// the goal, and whether it is as cheap as the shortest one. errors say what made it invalid
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct Verdict {
+    pub valid: bool,
+    pub optimal: bool,
+    pub cost: Option<Cost>,
+    pub optimal_cost: Option<Cost>,
+    pub optimal_path: Option<Vec<String>>,
+    pub errors: Vec<String>,
+}
+
+// the edges and turn bans of a saved graph.json; positions and everything else are ignored
+pub fn load_graph(json: &str) -> Result<Graph, String> {
+    document::read_version(json)?;
+    let graph: Graph = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let nodes = graph.nodes.len();
+    if let Some(e) = graph
+        .nodes
+        .iter()
+        .flatten()
+        .find(|e| e.node.index() >= nodes)
+    {
+        return Err(format!(
+            "an edge leads to node {}, past the last one",
+            e.node.index()
+        ));
+    }
+    return Ok(graph);
+}
+
+// a letter label like C or AB in either case, or a node number
+pub fn parse_node(token: &str) -> Option<NodeId> {
+    return token
+        .parse()
+        .ok()
+        .map(NodeId)
+        .or_else(|| Graph::parse_label(token));
+}
+
+// nodes separated by spaces, commas or arrows, e.g. `A -> C -> D`; an empty file or `none`
+// claims there is no route
+pub fn parse_path(text: &str) -> Result<Vec<NodeId>, String> {
+    let tokens: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '-' || c == '>')
+        .filter(|t| !t.is_empty())
+        .collect();
+    if let [none] = tokens.as_slice()
+        && none.eq_ignore_ascii_case("none")
+    {
+        return Ok(Vec::new());
+    }
+    return tokens
+        .iter()
+        .map(|t| parse_node(t).ok_or_else(|| format!("`{}` is not a node", t)))
+        .collect();
+}
+
+pub fn check_path(graph: &Graph, start: NodeId, goal: NodeId, path: &[NodeId]) -> Verdict {
+    let mut verdict = Verdict::default();
+    if graph.has_negative_edges() && graph.bellman_ford(start).is_err() {
+        verdict.errors.push(
+            "a negative cycle is reachable from the start, so no path is shortest".to_string(),
+        );
+    }
+    let best = graph.shortest_path(start, goal);
+    verdict.optimal_cost = best.as_ref().map(|(cost, _)| *cost);
+    verdict.optimal_path = best
+        .as_ref()
+        .map(|(_, route)| route.iter().map(|&i| graph.node_name(i)).collect());
+
+    // an empty answer is right exactly when the goal can't be reached
+    if path.is_empty() {
+        verdict.valid = best.is_none() && verdict.errors.is_empty();
+        verdict.optimal = verdict.valid;
+        if best.is_some() {
+            verdict
+                .errors
+                .push("no path was given, but the goal is reachable".to_string());
+        }
+        return verdict;
+    }
+
+    let errors = &mut verdict.errors;
+    if let Some(far) = path.iter().find(|i| i.index() >= graph.nodes.len()) {
+        errors.push(format!("node {} is not in the graph", far.index()));
+        return verdict;
+    }
+    let name = |id: NodeId| graph.node_name(id);
+    if path[0] != start {
+        errors.push(format!(
+            "the path starts at {}, not at {}",
+            name(path[0]),
+            name(start)
+        ));
+    }
+    if path[path.len() - 1] != goal {
+        errors.push(format!(
+            "the path ends at {}, not at {}",
+            name(path[path.len() - 1]),
+            name(goal)
+        ));
+    }
+    for hop in path.windows(2) {
+        if !graph.has_edge(hop[0], hop[1]) {
+            errors.push(format!(
+                "there is no edge from {} to {}",
+                name(hop[0]),
+                name(hop[1])
+            ));
+        }
+    }
+    for turn in path.windows(3) {
+        if graph.is_forbidden(turn[0], turn[1], turn[2]) {
+            let turn: Vec<String> = turn.iter().map(|&id| name(id)).collect();
+            errors.push(format!("the turn {} is banned", turn.join(" -> ")));
+        }
+    }
+    if !errors.is_empty() {
+        return verdict;
+    }
+
+    verdict.valid = true;
+    verdict.cost = graph.path_cost(path);
+    verdict.optimal = verdict.cost.is_some() && verdict.cost == verdict.optimal_cost;
+    return verdict;
+}
+
+// `--check <graph.json> <from> <to> <path file>`: the JSON to print and the exit code, 0 for an
+// optimal path, 1 for any other answer and 2 when the inputs can't be read
+pub fn run(args: &[String]) -> (String, i32) {
+    let fail = |error: String| (serde_json::json!({ "error": error }).to_string(), 2);
+    let [graph_file, from, to, path_file] = args else {
+        return fail("usage: --check <graph.json> <from> <to> <path file>".to_string());
+    };
+    let graph = match std::fs::read_to_string(graph_file)
+        .map_err(|e| e.to_string())
+        .and_then(|json| load_graph(&json))
+    {
+        Ok(graph) => graph,
+        Err(e) => return fail(format!("could not load {}: {}", graph_file, e)),
+    };
+    // the start and goal may also be given by a node's label
+    let node = |token: &str| {
+        parse_node(token)
+            .filter(|i| i.index() < graph.nodes.len())
+            .or_else(|| graph.find_node_label(token))
+    };
+    let (Some(start), Some(goal)) = (node(from), node(to)) else {
+        return fail(format!("`{}` or `{}` is not a node of the graph", from, to));
+    };
+    let path = match std::fs::read_to_string(path_file)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse_path(&text))
+    {
+        Ok(path) => path,
+        Err(e) => return fail(format!("could not read {}: {}", path_file, e)),
+    };
+
+    let verdict = check_path(&graph, start, goal, &path);
+    let code = if verdict.optimal { 0 } else { 1 };
+    return (serde_json::to_string(&verdict).unwrap(), code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Graph {
+        // A - B - D costs 2, A - C - D costs 5
+        let mut graph = Graph {
+            nodes: vec![Vec::new(); 5],
+            ..Default::default()
+        };
+        for (a, b, cost) in [(0, 1, 1), (1, 3, 1), (0, 2, 2), (2, 3, 3)] {
+            graph.add_lane(NodeId(a), NodeId(b), cost, true);
+        }
+        return graph;
+    }
+
+    #[test]
+    fn test_check_path() {
+        let mut graph = square();
+        let path = parse_path("A -> B -> D").unwrap();
+        let verdict = check_path(&graph, NodeId(0), NodeId(3), &path);
+        assert!(verdict.valid && verdict.optimal);
+        assert_eq!(verdict.cost, Some(2));
+
+        let verdict = check_path(
+            &graph,
+            NodeId(0),
+            NodeId(3),
+            &parse_path("a, c, d").unwrap(),
+        );
+        assert!(verdict.valid && !verdict.optimal);
+        assert_eq!((verdict.cost, verdict.optimal_cost), (Some(5), Some(2)));
+
+        assert_eq!(
+            parse_path("Z aa AB").unwrap(),
+            vec![NodeId(25), NodeId(26), NodeId(27)]
+        );
+        let verdict = check_path(&graph, NodeId(0), NodeId(3), &parse_path("0 3").unwrap());
+        assert!(!verdict.valid);
+        assert_eq!(verdict.errors, vec!["there is no edge from A to D"]);
+
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(3)));
+        let verdict = check_path(&graph, NodeId(0), NodeId(3), &path);
+        assert!(!verdict.valid);
+        let verdict = check_path(&graph, NodeId(0), NodeId(3), &parse_path("A C D").unwrap());
+        assert!(verdict.optimal);
+
+        // E is cut off, so only `none` is right
+        assert!(check_path(&graph, NodeId(0), NodeId(4), &parse_path("none").unwrap()).optimal);
+        assert!(!check_path(&graph, NodeId(0), NodeId(4), &parse_path("A E").unwrap()).valid);
+        assert!(parse_path("A ? D").is_err());
+
+        // a node with a label is reported by it
+        graph.node_labels.insert(NodeId(3), "Home".to_string());
+        let verdict = check_path(&graph, NodeId(0), NodeId(3), &parse_path("B D").unwrap());
+        assert_eq!(verdict.errors, vec!["the path starts at B, not at A"]);
+        assert_eq!(
+            verdict.optimal_path,
+            Some(vec!["A".to_string(), "C".to_string(), "Home".to_string()])
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::color::Srgba;
+use bevy::color::palettes::css::{
+    CORNFLOWER_BLUE, GOLD, LIGHT_GREEN, ORCHID, SALMON, SANDY_BROWN, TURQUOISE,
+};
+use bevy::prelude::*;
+
+use crate::graph::NodeId;
+use crate::{MainCamera, ResultsHud, WrapperGraph};
+
+const PALETTE: [Srgba; 7] = [
+    SALMON,
+    CORNFLOWER_BLUE,
+    LIGHT_GREEN,
+    GOLD,
+    ORCHID,
+    TURQUOISE,
+    SANDY_BROWN,
+];
+
+// every node filled with its greedy color class while shown; neighbours never share one
+#[derive(Resource, Default)]
+pub struct Coloring {
+    shown: bool,
+    classes: Vec<usize>,
+}
+
+impl Coloring {
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        if !self.shown {
+            return None;
+        }
+        let class = *self.classes.get(id.index())?;
+        return Some(match PALETTE.get(class) {
+            Some(color) => Color::from(*color),
+            // past the palette, hues a golden angle apart stay apart
+            None => Color::hsl((class as f32 * 137.5) % 360.0, 0.6, 0.6),
+        });
+    }
+
+    fn count(&self) -> usize {
+        return self.classes.iter().max().map_or(0, |&c| c + 1);
+    }
+
+    fn summary(&self) -> String {
+        let mut sizes = vec![0; self.count()];
+        for &c in &self.classes {
+            sizes[c] += 1;
+        }
+        let sizes: Vec<String> = sizes.iter().map(|s| s.to_string()).collect();
+        return format!(
+            "Greedy coloring: {} colors, classes of {} nodes (Ctrl+G hides)",
+            self.count(),
+            sizes.join(", ")
+        );
+    }
+}
+
+pub struct ColoringPlugin;
+
+impl Plugin for ColoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Coloring>().add_systems(
+            Update,
+            (
+                handle_coloring_keys.run_if(crate::prompt::prompt_closed),
+                refresh_coloring,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+G shows or hides the coloring
+fn handle_coloring_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut coloring: ResMut<Coloring>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyG) {
+        return Ok(());
+    }
+    coloring.shown = !coloring.shown;
+    if !coloring.shown {
+        hud.0 = "Coloring off".to_string();
+        return Ok(());
+    }
+    coloring.classes = q_graph.single()?.0.read().unwrap().greedy_coloring();
+    hud.0 = coloring.summary();
+
+    return Ok(());
+}
+
+// follows edits while shown, without touching change detection when nothing moved
+fn refresh_coloring(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut coloring: ResMut<Coloring>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !coloring.shown {
+        return Ok(());
+    }
+    let classes = q_graph.single()?.0.read().unwrap().greedy_coloring();
+    if coloring.classes != classes {
+        coloring.classes = classes;
+        hud.0 = coloring.summary();
+    }
+
+    return Ok(());
+}
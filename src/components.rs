@@ -0,0 +1,108 @@
+use bevy::color::Srgba;
+use bevy::color::palettes::css::{
+    CORNFLOWER_BLUE, GOLD, LIGHT_GREEN, ORCHID, SALMON, SANDY_BROWN, TURQUOISE,
+};
+use bevy::prelude::*;
+
+use crate::graph::{Graph, NodeId};
+use crate::{GoalNode, MainCamera, ResultsHud, StartNode, WrapperGraph};
+
+const PALETTE: [Srgba; 7] = [
+    CORNFLOWER_BLUE,
+    SALMON,
+    LIGHT_GREEN,
+    ORCHID,
+    GOLD,
+    TURQUOISE,
+    SANDY_BROWN,
+];
+
+// every node tinted by its connected component (edges taken as undirected) while shown
+#[derive(Resource, Default)]
+pub struct Components {
+    shown: bool,
+    ids: Vec<usize>,
+}
+
+impl Components {
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        if !self.shown {
+            return None;
+        }
+        let component = *self.ids.get(id.index())?;
+        return Some(Color::from(PALETTE[component % PALETTE.len()]));
+    }
+
+    fn count(&self) -> usize {
+        return self.ids.iter().max().map_or(0, |&c| c + 1);
+    }
+}
+
+pub struct ComponentsPlugin;
+
+impl Plugin for ComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Components>().add_systems(
+            Update,
+            (
+                handle_component_keys.run_if(crate::prompt::prompt_closed),
+                refresh_components,
+            )
+                .chain(),
+        );
+    }
+}
+
+// why a query may have no path at all: its ends are in different components
+pub fn separated(graph: &Graph, start: NodeId, goal: NodeId) -> bool {
+    let components = graph.connected_components();
+    return components.get(start.index()) != components.get(goal.index());
+}
+
+// shift+I shows or hides the components
+fn handle_component_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    mut components: ResMut<Components>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyI) {
+        return Ok(());
+    }
+    components.shown = !components.shown;
+    if !components.shown {
+        hud.0 = "Component view off".to_string();
+        return Ok(());
+    }
+
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    components.ids = graph.connected_components();
+    hud.0 = format!("{} connected components", components.count());
+    if let (Some(start), Some(goal)) = (start.id, goal.id) {
+        hud.0 += if separated(&graph, start, goal) {
+            "; the start and goal are in different ones, so no path joins them"
+        } else {
+            "; the start and goal share one"
+        };
+    }
+
+    return Ok(());
+}
+
+// follows edits while shown, without touching change detection when nothing moved
+fn refresh_components(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut components: ResMut<Components>,
+) -> Result<()> {
+    if !components.shown {
+        return Ok(());
+    }
+    let ids = q_graph.single()?.0.read().unwrap().connected_components();
+    if components.ids != ids {
+        components.ids = ids;
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::{GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph};
+
+const OPTIMAL: Color = Color::srgb(1.0, 0.85, 0.4);
+const IGNORED: Color = Color::srgb(0.45, 0.45, 0.45);
+const UNREACHED: Color = Color::srgb(0.2, 0.2, 0.2);
+const LABEL: Color = Color::srgb(0.85, 0.85, 0.85);
+
+// distance from the start plus distance to the goal at every node, against the best route's
+// cost: a node whose sum is over it lies on no optimal route, so an optimal search can skip it
+#[derive(Resource, Default)]
+pub struct Corridor {
+    shown: bool,
+    query: Option<(NodeId, NodeId)>,
+    costs: Vec<(NodeId, NodeId, Cost)>,
+    best: Option<Cost>,
+    through: Vec<Option<Cost>>,
+}
+
+impl Corridor {
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        let best = self.best?;
+        return Some(match self.through.get(id.index())? {
+            Some(through) if *through == best => OPTIMAL,
+            Some(_) => IGNORED,
+            None => UNREACHED,
+        });
+    }
+}
+
+#[derive(Component)]
+struct CorridorLabel;
+
+pub struct CorridorPlugin;
+
+impl Plugin for CorridorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Corridor>().add_systems(
+            Update,
+            (
+                handle_corridor_keys.run_if(crate::prompt::prompt_closed),
+                refresh_corridor,
+                update_corridor_labels,
+            )
+                .chain(),
+        );
+    }
+}
+
+// F11 shades the nodes an optimal search can ignore, or stops
+fn handle_corridor_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut corridor: ResMut<Corridor>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    if !keys.just_pressed(KeyCode::F11) {
+        return;
+    }
+    let shown = !corridor.shown;
+    *corridor = Corridor { shown, ..default() };
+    if !shown {
+        hud.0 = "Pruning shading off".to_string();
+    }
+}
+
+// redone whenever the start, the goal or a weight moves
+fn refresh_corridor(
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut corridor: ResMut<Corridor>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !corridor.shown {
+        return Ok(());
+    }
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let costs = graph.arc_costs();
+    let query = start.id.zip(goal.id);
+    if corridor.query == query && corridor.costs == costs {
+        return Ok(());
+    }
+    *corridor = Corridor {
+        shown: true,
+        query,
+        costs,
+        ..default()
+    };
+
+    let Some((start, goal)) = query else {
+        hud.0 = "Set a start (S) and goal (G) to see which nodes a search can skip".to_string();
+        return Ok(());
+    };
+    // the distances to the goal are the distances from it on the reversed graph
+    let (from_start, to_goal) = match (
+        graph.shortest_path_tree(start),
+        graph.reverse().shortest_path_tree(goal),
+    ) {
+        (Ok(from_start), Ok(to_goal)) => (from_start.dist, to_goal.dist),
+        (Err(cycle), _) | (_, Err(cycle)) => {
+            hud.0 = format!(
+                "No distances with the negative cycle {}",
+                Graph::fmt_path(&cycle)
+            );
+            return Ok(());
+        }
+    };
+    let Some(best) = from_start[goal.index()] else {
+        hud.0 = format!(
+            "{} can't reach {}, so there is nothing to prune against",
+            start, goal
+        );
+        return Ok(());
+    };
+    corridor.best = Some(best);
+    corridor.through = from_start
+        .iter()
+        .zip(&to_goal)
+        .map(|(a, b)| Some((*a)? + (*b)?))
+        .collect();
+
+    let optimal = corridor
+        .through
+        .iter()
+        .filter(|t| **t == Some(best))
+        .count();
+    let mut lines = vec![format!(
+        "Best route {}: {} of {} nodes lie on an optimal route, the other {} can be ignored (F11 stops)",
+        units.format(best),
+        optimal,
+        graph.nodes.len(),
+        graph.nodes.len() - optimal
+    )];
+    if !graph.forbidden.is_empty() {
+        lines.push("Turn bans are left out of these distances".to_string());
+    }
+    hud.0 = lines.join("\n");
+
+    return Ok(());
+}
+
+// start distance + goal distance above each node that has both
+fn update_corridor_labels(
+    mut commands: Commands,
+    corridor: Res<Corridor>,
+    units: Res<WeightUnits>,
+    nodes: Query<&Node>,
+    q_labels: Query<Entity, With<CorridorLabel>>,
+) {
+    if !corridor.is_changed() && !units.is_changed() {
+        return;
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    let Some(best) = corridor.best else {
+        return;
+    };
+    for node in &nodes {
+        let Some(Some(through)) = corridor.through.get(node.id.index()) else {
+            continue;
+        };
+        let text = if *through == best {
+            units.format(*through)
+        } else {
+            format!(
+                "{} (+{})",
+                units.format(*through),
+                units.format(through - best)
+            )
+        };
+        commands.spawn((
+            Text2d::new(text),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(LABEL),
+            Transform::from_translation(node.position.extend(5.0) + Vec3::Y * (node.r + 10.0)),
+            CorridorLabel,
+        ));
+    }
+}
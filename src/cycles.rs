@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::graph::Graph;
+use crate::{EdgeMode, HighlightedEdges, Layer, MainCamera, ResultsHud, WrapperGraph};
+
+pub struct CyclesPlugin;
+
+impl Plugin for CyclesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            handle_cycle_keys.run_if(crate::prompt::prompt_closed),
+        );
+    }
+}
+
+// shift+K highlights a cycle: a directed one in directed mode, otherwise one through three or
+// more nodes, since every two-way edge would count as a cycle by itself
+fn handle_cycle_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mode: Res<EdgeMode>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyK) {
+        return Ok(());
+    }
+    let graph = wrapper_graph.single()?.0.read().unwrap();
+    let kind = if mode.directed {
+        "directed"
+    } else {
+        "undirected"
+    };
+
+    match graph.find_cycle(mode.directed) {
+        Some(cycle) => {
+            highlights.set_path(Layer::Cycle, &cycle);
+            hud.0 = format!(
+                "Found a {} cycle: {} ({} nodes)",
+                kind,
+                Graph::fmt_path(&cycle),
+                cycle.len() - 1
+            );
+        }
+        None => {
+            highlights.layer_mut(Layer::Cycle).clear();
+            hud.0 = format!("The graph has no {} cycle", kind);
+        }
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,363 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::directed::EdgeMode;
+use crate::graph::{Graph, NodeId};
+use crate::params::AlgorithmParams;
+use crate::{MainCamera, Node, ResultsHud, WrapperGraph};
+
+const MIN_SCALE: f32 = 0.6;
+const MAX_SCALE: f32 = 1.8;
+const LOW: Color = Color::srgb(1.0, 0.95, 0.8);
+const HIGH: Color = Color::srgb(0.9, 0.25, 0.1);
+const LEGEND_STEPS: usize = 5;
+// plenty for the ranks to settle on graphs this size
+const PAGERANK_ITERATIONS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Size,
+    Tint,
+}
+
+// which edges count towards a node's degree; in and out only differ in directed mode. pagerank
+// is not a degree, but sizes and tints nodes the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Measure {
+    Total,
+    In,
+    Out,
+    PageRank,
+}
+
+impl Measure {
+    fn name(self) -> &'static str {
+        return match self {
+            Measure::Total => "degree",
+            Measure::In => "in-degree",
+            Measure::Out => "out-degree",
+            Measure::PageRank => "PageRank",
+        };
+    }
+
+    // undirected graphs skip in and out
+    fn next(self, directed: bool) -> Measure {
+        return match self {
+            Measure::Total if directed => Measure::In,
+            Measure::Total => Measure::PageRank,
+            Measure::In => Measure::Out,
+            Measure::Out => Measure::PageRank,
+            Measure::PageRank => Measure::Total,
+        };
+    }
+
+    fn format(self, score: f32) -> String {
+        return match self {
+            Measure::PageRank => format!("{:.3}", score),
+            _ => format!("{}", score.round()),
+        };
+    }
+
+    fn scores(self, graph: &Graph, damping: f32) -> Vec<f32> {
+        if self == Measure::PageRank {
+            return graph.pagerank(damping, PAGERANK_ITERATIONS);
+        }
+        return self.degrees(graph).into_iter().map(|d| d as f32).collect();
+    }
+
+    fn degrees(self, graph: &Graph) -> Vec<usize> {
+        let n = graph.nodes.len();
+        let mut out = vec![0; n];
+        let mut inc = vec![0; n];
+        let mut total = vec![0; n];
+        for (from, edge) in graph.arcs() {
+            out[from.index()] += 1;
+            inc[edge.node.index()] += 1;
+            // a two-way pair is one neighbour, counted from its lower end
+            if !graph.has_edge(edge.node, from) || from < edge.node {
+                total[from.index()] += 1;
+                total[edge.node.index()] += 1;
+            }
+        }
+        return match self {
+            Measure::In => inc,
+            Measure::Out => out,
+            _ => total,
+        };
+    }
+}
+
+#[derive(Resource)]
+pub struct DegreeView {
+    style: Option<Style>,
+    measure: Measure,
+    scores: Vec<f32>,
+}
+
+impl Default for DegreeView {
+    fn default() -> Self {
+        return DegreeView {
+            style: None,
+            measure: Measure::Total,
+            scores: Vec::new(),
+        };
+    }
+}
+
+impl DegreeView {
+    fn max(&self) -> f32 {
+        return self.scores.iter().copied().fold(0.0, f32::max);
+    }
+
+    fn fraction(&self, score: f32) -> f32 {
+        let max = self.max();
+        if max == 0.0 {
+            return 0.0;
+        }
+        return score / max;
+    }
+
+    fn scale(&self, score: f32) -> f32 {
+        return MIN_SCALE + (MAX_SCALE - MIN_SCALE) * self.fraction(score);
+    }
+
+    fn tint(&self, score: f32) -> Color {
+        return LOW.mix(&HIGH, self.fraction(score));
+    }
+
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        if self.style != Some(Style::Tint) {
+            return None;
+        }
+        return Some(self.tint(*self.scores.get(id.index())?));
+    }
+}
+
+#[derive(Component)]
+struct DegreeLegend;
+
+pub struct DegreePlugin;
+
+impl Plugin for DegreePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DegreeView>().add_systems(
+            Update,
+            (
+                handle_degree_keys.run_if(crate::prompt::prompt_closed),
+                refresh_degrees,
+                scale_nodes,
+                update_degree_legend,
+            )
+                .chain(),
+        );
+    }
+}
+
+// the three nodes that score highest; total degree is given as degree centrality, the share of
+// the other nodes a node is joined to
+fn leaders(graph: &Graph, measure: Measure, damping: f32) -> String {
+    let (what, scores, format): (&str, Vec<f32>, fn(f32) -> String) = match measure {
+        Measure::Total => ("degree centrality", graph.degree_centrality(), |s| {
+            format!("{:.2}", s)
+        }),
+        _ => (measure.name(), measure.scores(graph, damping), |s| {
+            if s.fract() == 0.0 {
+                format!("{}", s)
+            } else {
+                format!("{:.3}", s)
+            }
+        }),
+    };
+    let mut ids: Vec<usize> = (0..scores.len()).collect();
+    ids.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+    let top: Vec<String> = ids
+        .iter()
+        .take(3)
+        .map(|&id| format!("{} {}", Graph::node_label(id), format(scores[id])))
+        .collect();
+    return format!("Highest {}: {}", what, top.join(", "));
+}
+
+// L cycles off -> size -> tint; Shift+L switches between total, in and out degree and PageRank
+fn handle_degree_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mode: Res<EdgeMode>,
+    params: Res<AlgorithmParams>,
+    mut view: ResMut<DegreeView>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // ctrl+L is the ALT comparison
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if ctrl || !keys.just_pressed(KeyCode::KeyL) {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if shift {
+        view.measure = view.measure.next(mode.directed);
+        hud.0 = format!("Degree view measures {}", view.measure.name());
+        if !mode.directed && view.measure == Measure::PageRank {
+            hud.0
+                .push_str(" (in- and out-degree only differ in directed mode, Shift+D)");
+        }
+        if !graph.nodes.is_empty() {
+            hud.0 = format!(
+                "{}\n{}",
+                hud.0,
+                leaders(&graph, view.measure, params.pagerank_damping)
+            );
+        }
+        return Ok(());
+    }
+
+    view.style = match view.style {
+        None => Some(Style::Size),
+        Some(Style::Size) => Some(Style::Tint),
+        Some(Style::Tint) => None,
+    };
+    hud.0 = match view.style {
+        Some(Style::Size) => format!("Node size shows {}", view.measure.name()),
+        Some(Style::Tint) => format!("Node tint shows {}", view.measure.name()),
+        None => "Degree view off".to_string(),
+    };
+    if view.style.is_some() && !graph.nodes.is_empty() {
+        hud.0 = format!(
+            "{}\n{}",
+            hud.0,
+            leaders(&graph, view.measure, params.pagerank_damping)
+        );
+    }
+
+    return Ok(());
+}
+
+fn refresh_degrees(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mode: Res<EdgeMode>,
+    params: Res<AlgorithmParams>,
+    mut view: ResMut<DegreeView>,
+) {
+    if view.style.is_none() {
+        return;
+    }
+    let Ok(wg) = q_graph.single() else {
+        return;
+    };
+    if !mode.directed && matches!(view.measure, Measure::In | Measure::Out) {
+        view.measure = Measure::Total;
+    }
+    let scores = view
+        .measure
+        .scores(&wg.0.read().unwrap(), params.pagerank_damping);
+    if view.scores != scores {
+        view.scores = scores;
+    }
+}
+
+fn scale_nodes(view: Res<DegreeView>, mut q_nodes: Query<(&Node, &mut Transform)>) {
+    for (node, mut transform) in &mut q_nodes {
+        if !view.is_changed() && !transform.is_added() {
+            continue;
+        }
+        let scale = match (view.style, view.scores.get(node.id.index())) {
+            (Some(Style::Size), Some(&score)) => view.scale(score),
+            _ => 1.0,
+        };
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
+
+// a row of samples from 0 up to the current maximum
+fn update_degree_legend(
+    mut commands: Commands,
+    view: Res<DegreeView>,
+    q_old: Query<Entity, With<DegreeLegend>>,
+) {
+    if !view.is_changed() {
+        return;
+    }
+    for e in &q_old {
+        commands.entity(e).despawn();
+    }
+    let Some(style) = view.style else {
+        return;
+    };
+
+    // degrees are whole, so their samples are rounded and may repeat
+    let max = view.max();
+    let mut samples: Vec<f32> = (0..LEGEND_STEPS)
+        .map(|i| max * i as f32 / (LEGEND_STEPS - 1) as f32)
+        .map(|s| match view.measure {
+            Measure::PageRank => s,
+            _ => s.round(),
+        })
+        .collect();
+    samples.dedup();
+    let title = match style {
+        Style::Size => format!("Node size = {}", view.measure.name()),
+        Style::Tint => format!("Node tint = {}", view.measure.name()),
+    };
+
+    commands
+        .spawn((
+            UiNode {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            DegreeLegend,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+            parent
+                .spawn(UiNode {
+                    align_items: AlignItems::End,
+                    column_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for score in samples {
+                        let (size, color) = match style {
+                            Style::Size => (crate::NODE_RADIUS * view.scale(score), Color::WHITE),
+                            Style::Tint => (crate::NODE_RADIUS, view.tint(score)),
+                        };
+                        row.spawn(UiNode {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            row_gap: Val::Px(2.0),
+                            ..default()
+                        })
+                        .with_children(|sample| {
+                            sample.spawn((
+                                UiNode {
+                                    width: Val::Px(size),
+                                    height: Val::Px(size),
+                                    ..default()
+                                },
+                                BorderRadius::MAX,
+                                BackgroundColor(color),
+                            ));
+                            sample.spawn((
+                                Text::new(view.measure.format(score)),
+                                TextFont {
+                                    font_size: 12.0,
+                                    ..default()
+                                },
+                                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                            ));
+                        });
+                    }
+                });
+        });
+}
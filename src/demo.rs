@@ -0,0 +1,241 @@
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::{MouseButtonInput, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::animation::{Algorithm, Animation, RaceHeuristic, Run};
+use crate::bookmarks::Bookmarks;
+use crate::graph::{Graph, NodeId};
+use crate::history::QueryHistory;
+use crate::isochrone::Isochrone;
+use crate::node_info::NodeInfo;
+use crate::notes::NodeNotes;
+use crate::rng::Rng;
+use crate::routing::Routing;
+use crate::scatter::{connect_nearest, poisson_disk};
+use crate::sources::Sources;
+use crate::tags::Tags;
+use crate::traversal::Traversal;
+use crate::{
+    EdgeVisual, GoalNode, GraphSpawner, HighlightedEdges, Layer, MainCamera, MultiSelection, Node,
+    ResultsHud, SelectedNode, StartNode, WrapperGraph,
+};
+
+const IDLE_SECS: f32 = 30.0;
+const PAUSE_SECS: f32 = 3.0;
+const RUNS_PER_GRAPH: usize = 5;
+const DEMO_NODES: usize = 14;
+const DEMO_NEIGHBOURS: usize = 3;
+
+// armed with F2; after IDLE_SECS without input it plays random queries until the next input
+#[derive(Resource, Default)]
+struct Demo {
+    armed: bool,
+    running: bool,
+    idle: f32,
+    // time left before the next run starts
+    pause: f32,
+    runs: usize,
+    // only graphs the demo generated itself are ever replaced: (nodes, edges) as generated
+    generated: Option<(usize, usize)>,
+    owns_graph: bool,
+    regenerate: bool,
+}
+
+fn size(graph: &Graph) -> (usize, usize) {
+    return (graph.nodes.len(), graph.nodes.iter().map(|e| e.len()).sum());
+}
+
+pub struct DemoPlugin;
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Demo>().add_systems(
+            Update,
+            (track_idle, regenerate_demo_graph, run_demo).chain(),
+        );
+    }
+}
+
+fn track_idle(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut key_events: MessageReader<KeyboardInput>,
+    mut button_events: MessageReader<MouseButtonInput>,
+    mut wheel_events: MessageReader<MouseWheel>,
+    mut cursor_events: MessageReader<CursorMoved>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut demo: ResMut<Demo>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let input = key_events.read().count()
+        + button_events.read().count()
+        + wheel_events.read().count()
+        + cursor_events.read().count()
+        > 0;
+
+    if keys.just_pressed(KeyCode::F2) {
+        demo.armed = !demo.armed;
+        hud.0 = if demo.armed {
+            format!("Demo mode armed: starts after {}s without input", IDLE_SECS)
+        } else {
+            "Demo mode off".to_string()
+        };
+    }
+    if input {
+        if demo.running {
+            hud.0 = "Demo stopped".to_string();
+        }
+        demo.running = false;
+        demo.idle = 0.0;
+        return Ok(());
+    }
+    if !demo.armed || demo.running {
+        return Ok(());
+    }
+
+    demo.idle += time.delta_secs();
+    if demo.idle < IDLE_SECS {
+        return Ok(());
+    }
+    let current = size(&q_graph.single()?.0.read().unwrap());
+    demo.running = true;
+    demo.runs = 0;
+    demo.pause = 0.0;
+    // a graph the user built or edited is demoed as it is; an empty canvas or an untouched demo
+    // graph gets a freshly generated one
+    demo.owns_graph = current.0 == 0 || demo.generated == Some(current);
+    demo.regenerate = demo.owns_graph;
+
+    return Ok(());
+}
+
+fn regenerate_demo_graph(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<&GlobalTransform, With<MainCamera>>,
+    mut q_camera: Query<
+        (
+            &WrapperGraph,
+            &mut SelectedNode,
+            &mut MultiSelection,
+            &mut StartNode,
+            &mut GoalNode,
+        ),
+        With<MainCamera>,
+    >,
+    q_old: Query<Entity, Or<(With<Node>, With<EdgeVisual>)>>,
+    mut spawner: GraphSpawner,
+    mut rng: ResMut<Rng>,
+    mut demo: ResMut<Demo>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut overlays: (
+        ResMut<Sources>,
+        ResMut<Isochrone>,
+        ResMut<Traversal>,
+        ResMut<QueryHistory>,
+        ResMut<NodeNotes>,
+        ResMut<Routing>,
+        ResMut<Bookmarks>,
+        ResMut<Tags>,
+        ResMut<NodeInfo>,
+    ),
+) -> Result<()> {
+    if !demo.regenerate {
+        return Ok(());
+    }
+    demo.regenerate = false;
+
+    for e in &q_old {
+        spawner.commands.entity(e).despawn();
+    }
+    let (wg, mut selected, mut multi, mut start, mut goal) = q_camera.single_mut()?;
+    let mut graph = wg.0.write().unwrap();
+    *graph = Graph::default();
+    selected.id = None;
+    multi.ids.clear();
+    start.id = None;
+    goal.id = None;
+    highlights.clear_all();
+    let (sources, isochrone, traversal, history, notes, routing, bookmarks, tags, info) =
+        &mut overlays;
+    sources.clear();
+    isochrone.clear();
+    traversal.clear();
+    history.clear();
+    notes.0.clear();
+    routing.categories.clear();
+    bookmarks.0.clear();
+    tags.clear();
+    info.clear();
+
+    // fill most of the visible area around the camera
+    let center = cams.single()?.translation().truncate();
+    let half = windows.single()?.size() * 0.4;
+    let points = poisson_disk(&mut rng, center - half, center + half, DEMO_NODES);
+    let placed: Vec<Node> = points
+        .iter()
+        .map(|&p| spawner.spawn_node(&mut graph, p))
+        .collect();
+    connect_nearest(&mut spawner, &mut graph, &placed, DEMO_NEIGHBOURS);
+    demo.generated = Some(size(&graph));
+
+    return Ok(());
+}
+
+// races two random algorithms between a random reachable pair, pausing between runs
+fn run_demo(
+    time: Res<Time>,
+    mut q_camera: Query<(&WrapperGraph, &mut StartNode, &mut GoalNode), With<MainCamera>>,
+    mut rng: ResMut<Rng>,
+    mut demo: ResMut<Demo>,
+    mut animation: ResMut<Animation>,
+    mut highlights: ResMut<HighlightedEdges>,
+    heuristic: RaceHeuristic,
+) -> Result<()> {
+    if !demo.running || demo.regenerate || animation.is_running() {
+        return Ok(());
+    }
+    demo.pause -= time.delta_secs();
+    if demo.pause > 0.0 {
+        return Ok(());
+    }
+    demo.pause = PAUSE_SECS;
+    if demo.owns_graph && demo.runs >= RUNS_PER_GRAPH {
+        demo.runs = 0;
+        demo.regenerate = true;
+        return Ok(());
+    }
+
+    let (wg, mut start, mut goal) = q_camera.single_mut()?;
+    let graph = wg.0.read().unwrap();
+    let n = graph.nodes.len();
+    if n < 2 {
+        return Ok(());
+    }
+    let mut pick = || (rng.next_u64() % n as u64) as usize;
+    let Some((from, to)) = (0..20)
+        .map(|_| (NodeId(pick()), NodeId(pick())))
+        .find(|&(a, b)| a != b && graph.shortest_path(a, b).is_some())
+    else {
+        return Ok(());
+    };
+    let first = pick() % Algorithm::ALL.len();
+    let contenders = [
+        Algorithm::ALL[first],
+        Algorithm::ALL[(first + 1) % Algorithm::ALL.len()],
+    ];
+
+    start.id = Some(from);
+    goal.id = Some(to);
+    let h = heuristic.toward(&graph, to);
+    highlights.layer_mut(Layer::Path).clear();
+    animation.start(
+        contenders
+            .into_iter()
+            .map(|a| Run::new(a, &graph, from, to, &h))
+            .collect(),
+    );
+    demo.runs += 1;
+
+    return Ok(());
+}
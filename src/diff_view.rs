@@ -0,0 +1,149 @@
+use bevy::color::palettes::css::{LIME, ORANGE, RED};
+use bevy::prelude::*;
+
+use crate::graph::{Graph, GraphDiff, NodeId};
+use crate::{MainCamera, Node, ResultsHud, WrapperGraph, ord, position_lookup};
+
+#[derive(Resource, Default)]
+pub struct DiffView {
+    snapshot: Option<Graph>,
+    diff: Option<GraphDiff>,
+    announce: bool,
+}
+
+impl DiffView {
+    pub fn edge_color(&self, a: NodeId, b: NodeId) -> Option<Color> {
+        let diff = self.diff.as_ref()?;
+        let key = ord(a, b);
+        if diff.added.iter().any(|&(x, y, _)| ord(x, y) == key) {
+            return Some(Color::from(LIME));
+        }
+        if diff.changed.iter().any(|&(x, y, _, _)| ord(x, y) == key) {
+            return Some(Color::from(ORANGE));
+        }
+        return None;
+    }
+}
+
+pub struct DiffViewPlugin;
+
+impl Plugin for DiffViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DiffView>().add_systems(
+            Update,
+            (
+                handle_diff_keys.run_if(crate::prompt::prompt_closed),
+                refresh_diff,
+                draw_removed_edges,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn handle_diff_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut view: ResMut<DiffView>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // shift+K finds a cycle, ctrl+K is the layout morph
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if keys.just_pressed(KeyCode::KeyK) && !shift && !ctrl {
+        let wg = wrapper_graph.single()?;
+        view.snapshot = Some(wg.0.read().unwrap().clone());
+        hud.0 = "Snapshot taken".to_string();
+    }
+
+    // shift+I is the component view, ctrl+I names a node
+    if keys.just_pressed(KeyCode::KeyI) && !shift && !ctrl {
+        let Some(reference) = &view.snapshot else {
+            hud.0 = "Take a snapshot (K) of the reference graph first".to_string();
+            return Ok(());
+        };
+        let wg = wrapper_graph.single()?;
+        let graph = wg.0.read().unwrap();
+        hud.0 = match (
+            reference.isomorphism(&graph, true),
+            reference.is_isomorphic(&graph),
+        ) {
+            (Some(mapping), _) => {
+                let pairs = mapping
+                    .iter()
+                    .enumerate()
+                    .map(|(r, &g)| format!("{}={}", NodeId(r), g))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("Matches the reference, weights included ({})", pairs)
+            }
+            (None, true) => "Same shape as the reference, but the weights differ".to_string(),
+            (None, false) => "Does not match the reference graph".to_string(),
+        };
+    }
+
+    // shift+D is the directed-mode toggle, ctrl+D the D* Lite walk
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if keys.just_pressed(KeyCode::KeyD) && !shift && !ctrl {
+        if view.diff.take().is_some() {
+            hud.0 = "Diff view off".to_string();
+        } else if view.snapshot.is_none() {
+            hud.0 = "Take a snapshot (K) before opening the diff view".to_string();
+        } else {
+            view.diff = Some(GraphDiff::default());
+            view.announce = true;
+        }
+    }
+
+    return Ok(());
+}
+
+fn refresh_diff(
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut view: ResMut<DiffView>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if view.diff.is_none() {
+        return Ok(());
+    }
+    let Some(snapshot) = &view.snapshot else {
+        return Ok(());
+    };
+    let wg = wrapper_graph.single()?;
+    let diff = snapshot.diff(&wg.0.read().unwrap());
+
+    if view.announce || view.diff.as_ref() != Some(&diff) {
+        // undirected edges are stored in both directions, so count each pair once
+        let pairs = |edges: Vec<(NodeId, NodeId)>| {
+            let mut pairs: Vec<_> = edges.into_iter().map(|(a, b)| ord(a, b)).collect();
+            pairs.sort_unstable();
+            pairs.dedup();
+            pairs.len()
+        };
+        hud.0 = format!(
+            "Diff vs snapshot: +{} nodes, +{} edges, -{} edges, {} reweighted",
+            diff.added_nodes.len(),
+            pairs(diff.added.iter().map(|&(a, b, _)| (a, b)).collect()),
+            pairs(diff.removed.iter().map(|&(a, b, _)| (a, b)).collect()),
+            pairs(diff.changed.iter().map(|&(a, b, _, _)| (a, b)).collect()),
+        );
+        view.diff = Some(diff);
+        view.announce = false;
+    }
+
+    return Ok(());
+}
+
+// removed edges no longer have a visual of their own, so draw them as an overlay
+fn draw_removed_edges(view: Res<DiffView>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let Some(diff) = &view.diff else {
+        return;
+    };
+    for &(a, b, _) in &diff.removed {
+        let position = position_lookup(&nodes);
+        if let (Some(p), Some(q)) = (position(a), position(b)) {
+            gizmos.line_2d(p, q, Color::from(RED));
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::{
+    EdgeMat, EdgeVisual, HighlightedEdges, MainCamera, Node, ResultsHud, WrapperGraph,
+    cursor_world, edge_at, lane_offset,
+};
+
+const ARROW_LENGTH: f32 = 12.0;
+const ARROW_SPREAD: f32 = 0.45;
+
+// in directed mode new edges only go from the first clicked node to the second
+#[derive(Resource, Default)]
+pub struct EdgeMode {
+    pub directed: bool,
+}
+
+pub struct DirectedPlugin;
+
+impl Plugin for DirectedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EdgeMode>().add_systems(
+            Update,
+            (
+                handle_direction_keys.run_if(crate::prompt::prompt_closed),
+                draw_arrowheads,
+            ),
+        );
+    }
+}
+
+fn handle_direction_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut mode: ResMut<EdgeMode>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if shift && keys.just_pressed(KeyCode::KeyD) {
+        mode.directed = !mode.directed;
+        hud.0 = if mode.directed {
+            "Directed mode: new edges point from the first node to the second".to_string()
+        } else {
+            "Undirected mode: new edges go both ways".to_string()
+        };
+    }
+
+    // shift+Y makes the hovered edge one-way (in the direction it was drawn) or two-way again
+    let one_way = shift && keys.just_pressed(KeyCode::KeyY);
+    if !keys.just_pressed(KeyCode::KeyV) && !one_way {
+        return Ok(());
+    }
+    let wg = wrapper_graph.single()?;
+    let mut graph = wg.0.write().unwrap();
+    // the old path may run against the new directions
+    highlights.clear_results();
+
+    if one_way {
+        let Some((a, b)) = cursor_world(&windows, &cams).and_then(|w| edge_at(&nodes, &edges, w))
+        else {
+            hud.0 = "Hover an edge and press Shift+Y to make it one-way".to_string();
+            return Ok(());
+        };
+        hud.0 = match graph.toggle_one_way(a, b) {
+            Some(true) => format!("{} -> {} is one-way now (V reverses it)", a, b),
+            Some(false) => format!("{} - {} goes both ways again", a, b),
+            None => format!(
+                "{} - {} has {} parallel edges; only a single edge can be made one-way",
+                a,
+                b,
+                graph.lane_count(a, b)
+            ),
+        };
+        return Ok(());
+    }
+
+    if shift {
+        graph.transpose();
+        hud.0 = "Reversed every edge".to_string();
+        return Ok(());
+    }
+
+    let Some((a, b)) = cursor_world(&windows, &cams).and_then(|w| edge_at(&nodes, &edges, w))
+    else {
+        hud.0 = "Hover an edge and press V to reverse it".to_string();
+        return Ok(());
+    };
+    let (from, to) = if graph.has_edge(a, b) { (a, b) } else { (b, a) };
+    hud.0 = if graph.reverse_edge(from, to) {
+        format!("Reversed {} -> {} to {} -> {}", from, to, to, from)
+    } else {
+        format!("{} - {} goes both ways; nothing to reverse", a, b)
+    };
+
+    return Ok(());
+}
+
+// drawn from the graph every frame, so reversals show up immediately
+fn draw_arrowheads(
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<(&EdgeVisual, &EdgeMat)>,
+    materials: Res<Assets<ColorMaterial>>,
+    mut gizmos: Gizmos,
+) -> Result<()> {
+    let wg = wrapper_graph.single()?;
+    let graph = wg.0.read().unwrap();
+
+    for (ev, EdgeMat(handle)) in &edges {
+        let (from, to) = match (
+            graph.has_lane(ev.a, ev.b, ev.lane),
+            graph.has_lane(ev.b, ev.a, ev.lane),
+        ) {
+            (true, false) => (ev.a, ev.b),
+            (false, true) => (ev.b, ev.a),
+            _ => continue,
+        };
+        let (Some(from), Some(to)) = (
+            nodes.iter().find(|n| n.id == from),
+            nodes.iter().find(|n| n.id == to),
+        ) else {
+            continue;
+        };
+        let color = materials.get(handle).map_or(Color::WHITE, |m| m.color);
+
+        let dir = (to.position - from.position).normalize_or_zero();
+        let offset = if from.id == ev.a {
+            lane_offset(ev, from.position, to.position)
+        } else {
+            lane_offset(ev, to.position, from.position)
+        };
+        let tip = to.position - dir * to.r + offset;
+        for side in [ARROW_SPREAD, -ARROW_SPREAD] {
+            let back = Vec2::from_angle(side).rotate(-dir) * ARROW_LENGTH;
+            gizmos.line_2d(tip, tip + back, color);
+        }
+    }
+
+    return Ok(());
+}
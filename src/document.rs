@@ -0,0 +1,128 @@
+use bevy::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::Graph;
+
+// the version files are written with; bump it when the layout changes and teach from_json to
+// read the old one. files from before versions were recorded read as version 0, which has the
+// same layout as 1
+pub const FORMAT_VERSION: u32 = 1;
+
+// a graph and where each node sits, as graph.json stores them; the editor saves its own state
+// (regions, notes, tags and so on) alongside in the same object
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphDocument {
+    #[serde(default)]
+    pub version: u32,
+    pub positions: Vec<Vec2>,
+    #[serde(flatten)]
+    pub graph: Graph,
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    version: u32,
+}
+
+// the version a file was written with, or an error if it is newer than this build reads
+pub fn read_version(json: &str) -> Result<u32, String> {
+    let envelope: Envelope = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if envelope.version > FORMAT_VERSION {
+        return Err(format!(
+            "the file is format version {}, newer than the {} this build reads",
+            envelope.version, FORMAT_VERSION
+        ));
+    }
+    return Ok(envelope.version);
+}
+
+impl GraphDocument {
+    pub fn new(graph: Graph, positions: Vec<Vec2>) -> Self {
+        return GraphDocument {
+            version: FORMAT_VERSION,
+            positions,
+            graph,
+        };
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        return serde_json::to_string_pretty(self).map_err(|e| e.to_string());
+    }
+
+    // any version this build knows, brought up to FORMAT_VERSION; fields it doesn't know are
+    // ignored
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        read_version(json)?;
+        let mut document: GraphDocument = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        document.validate()?;
+        document.version = FORMAT_VERSION;
+        return Ok(document);
+    }
+
+    // a position for every node, and every edge, turn ban, label and detail on nodes that exist
+    pub fn validate(&self) -> Result<(), String> {
+        let n = self.positions.len();
+        if self.graph.nodes.len() != n {
+            return Err(format!(
+                "{} positions but {} adjacency lists",
+                n,
+                self.graph.nodes.len()
+            ));
+        }
+        let ids = self
+            .graph
+            .nodes
+            .iter()
+            .flatten()
+            .map(|e| e.node)
+            .chain(self.graph.forbidden.iter().flat_map(|&(a, v, b)| [a, v, b]))
+            .chain(self.graph.node_labels.keys().copied())
+            .chain(self.graph.node_metadata.keys().copied());
+        for id in ids {
+            if id.index() >= n {
+                return Err(format!("node {} does not exist", id.index()));
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::NodeId;
+
+    #[test]
+    fn test_document_round_trip() {
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0, 4], vec![4, 0]]);
+        graph.set_lane_label(NodeId(0), NodeId(1), 0, Some("I-95".to_string()));
+        graph.node_labels.insert(NodeId(1), "Home".to_string());
+        let positions = vec![Vec2::new(-50.0, 0.0), Vec2::new(50.0, 10.0)];
+        let json = GraphDocument::new(graph, positions.clone())
+            .to_json()
+            .unwrap();
+        let document = GraphDocument::from_json(&json).unwrap();
+        assert_eq!(document.version, FORMAT_VERSION);
+        assert_eq!(document.positions, positions);
+        assert_eq!(document.graph.path_cost(&[NodeId(0), NodeId(1)]), Some(4));
+        assert_eq!(
+            document.graph.lane_label(NodeId(1), NodeId(0), 0),
+            Some("I-95")
+        );
+        assert_eq!(document.graph.node_name(NodeId(1)), "Home");
+
+        // files from before versions were written still load; newer ones and broken ones don't
+        let old = r#"{"positions": [[0, 0]], "adjacency": [[]], "start": null}"#;
+        assert_eq!(
+            GraphDocument::from_json(old).unwrap().version,
+            FORMAT_VERSION
+        );
+        let newer = r#"{"version": 99, "positions": [], "adjacency": []}"#;
+        assert!(GraphDocument::from_json(newer).is_err());
+        let dangling = r#"{"positions": [[0, 0]], "adjacency": [[{"node": 3, "cost": 1}]]}"#;
+        assert!(GraphDocument::from_json(dangling).is_err());
+        let unknown = r#"{"positions": [[0, 0]], "adjacency": [[]], "node_labels": [[4, "Home"]]}"#;
+        assert!(GraphDocument::from_json(unknown).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{Cost, DStarLite, Graph, NodeId};
+use crate::{
+    EdgeVisual, GoalNode, HighlightedEdges, MainCamera, Node, ResultsHud, StartNode, WeightUnits,
+    WrapperGraph, cursor_world, edge_at, position_lookup,
+};
+
+const HOP_SECS: f32 = 0.8;
+const ROUTE: Color = Color::srgb(0.4, 0.9, 0.6);
+const WALKED: Color = Color::srgb(0.5, 0.6, 0.55);
+const TOKEN: Color = Color::WHITE;
+
+// a token walking from the start to the goal on a D* Lite plan; any edit to the weights under it
+// is repaired in place rather than searched again from scratch
+#[derive(Resource, Default)]
+struct Replanner {
+    plan: Option<DStarLite>,
+    route: Vec<NodeId>,
+    cost: Cost,
+    // how far along route[0] -> route[1] the token is, in [0, 1)
+    progress: f32,
+    walked: Vec<NodeId>,
+    repairs: usize,
+    last_repair: Option<String>,
+    // the weights the plan last saw, and how many nodes there were
+    costs: Vec<(NodeId, NodeId, Cost)>,
+    nodes: usize,
+}
+
+impl Replanner {
+    fn summary(&self, units: &WeightUnits) -> String {
+        let Some(plan) = &self.plan else {
+            return String::new();
+        };
+        let mut lines = vec![if self.route.len() < 2 {
+            format!(
+                "D* Lite: arrived at {} after {} repairs (Ctrl+D stops)",
+                plan.goal, self.repairs
+            )
+        } else {
+            format!(
+                "D* Lite from {} to {}: {} left along {} (Ctrl+D stops, Backspace cuts the hovered edge)",
+                plan.start,
+                plan.goal,
+                units.format(self.cost),
+                Graph::fmt_path(&self.route)
+            )
+        }];
+        lines.extend(self.last_repair.clone());
+        return lines.join("\n");
+    }
+}
+
+pub struct DStarPlugin;
+
+impl Plugin for DStarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Replanner>().add_systems(
+            Update,
+            (
+                handle_dstar_keys.run_if(crate::prompt::prompt_closed),
+                repair_dstar,
+                walk_dstar,
+                draw_dstar,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+D sets a token walking from the start to the goal, or stops it; Backspace over an edge
+// cuts it both ways while it walks
+fn handle_dstar_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_edges: Query<(Entity, &EdgeVisual)>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut replanner: ResMut<Replanner>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let (wg, start, goal) = q_camera.single()?;
+    if replanner.plan.is_some() && keys.just_pressed(KeyCode::Backspace) {
+        let Some((a, b)) = cursor_world(&windows, &cams).and_then(|w| edge_at(&nodes, &edges, w))
+        else {
+            hud.0 = "Hover an edge and press Backspace to cut it".to_string();
+            return Ok(());
+        };
+        wg.0.write().unwrap().remove_edge(a, b);
+        // results drawn over the old graph are stale
+        highlights.clear_results();
+        for (entity, visual) in &q_edges {
+            if (visual.a, visual.b) == (a, b) || (visual.a, visual.b) == (b, a) {
+                commands.entity(entity).despawn();
+            }
+        }
+        return Ok(());
+    }
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyD) {
+        return Ok(());
+    }
+    if replanner.plan.is_some() {
+        *replanner = Replanner::default();
+        hud.0 = "D* Lite replanning off".to_string();
+        return Ok(());
+    }
+
+    let (Some(start), Some(goal)) = (start.id, goal.id) else {
+        hud.0 = "Missing starting or goal node!".to_string();
+        return Ok(());
+    };
+    let graph = wg.0.read().unwrap();
+    if graph.has_negative_edges() {
+        hud.0 = "D* Lite needs non-negative weights".to_string();
+        return Ok(());
+    }
+    let plan = graph.dstar_lite(start, goal);
+    let Some((cost, route)) = plan.path(&graph) else {
+        hud.0 = "No current available path".to_string();
+        return Ok(());
+    };
+    *replanner = Replanner {
+        last_repair: Some(format!(
+            "Planned backwards from the goal, expanding {} nodes",
+            plan.expanded
+        )),
+        plan: Some(plan),
+        walked: vec![start],
+        route,
+        cost,
+        costs: graph.arc_costs(),
+        nodes: graph.nodes.len(),
+        ..default()
+    };
+    hud.0 = replanner.summary(&units);
+
+    return Ok(());
+}
+
+// every edge added, removed or reweighted since the last frame goes to the plan as a repair;
+// adding or deleting nodes ends the walk
+fn repair_dstar(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut replanner: ResMut<Replanner>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if replanner.plan.is_none() {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    let now = graph.arc_costs();
+    if now == replanner.costs {
+        return Ok(());
+    }
+    if graph.nodes.len() != replanner.nodes || graph.has_negative_edges() {
+        *replanner = Replanner::default();
+        hud.0 =
+            "The nodes or signs of the weights changed, so D* Lite replanning stopped".to_string();
+        return Ok(());
+    }
+    let (before, after): (BTreeSet<_>, BTreeSet<_>) = (
+        replanner.costs.iter().copied().collect(),
+        now.iter().copied().collect(),
+    );
+    let changed: Vec<(NodeId, NodeId)> = before
+        .symmetric_difference(&after)
+        .map(|&(from, to, _)| (from, to))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    replanner.costs = now;
+
+    let state = &mut *replanner;
+    let plan = state.plan.as_mut().unwrap();
+    plan.repair(&graph, &changed);
+    let fresh = graph.dstar_lite(plan.start, plan.goal).expanded;
+    state.repairs += 1;
+    let repaired = format!(
+        "Repair {} after {} changed edges: expanded {} nodes, a search from scratch would expand {}",
+        state.repairs,
+        changed.len(),
+        plan.expanded,
+        fresh
+    );
+    match plan.path(&graph) {
+        Some((cost, route)) => {
+            // a cut under the token sends it back to the node it left
+            if route.get(1) != state.route.get(1) {
+                state.progress = 0.0;
+            }
+            state.route = route;
+            state.cost = cost;
+            state.last_repair = Some(repaired);
+            hud.0 = state.summary(&units);
+        }
+        None => {
+            let at = plan.start;
+            *state = Replanner::default();
+            hud.0 = format!(
+                "{}\nNo route is left from {}; D* Lite replanning stopped",
+                repaired, at
+            );
+        }
+    }
+
+    return Ok(());
+}
+
+// one hop every HOP_SECS; each arrival moves the plan's start along
+fn walk_dstar(
+    time: Res<Time>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut replanner: ResMut<Replanner>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if replanner.plan.is_none() || replanner.route.len() < 2 {
+        return Ok(());
+    }
+    replanner.progress += time.delta_secs() / HOP_SECS;
+    if replanner.progress < 1.0 {
+        return Ok(());
+    }
+    replanner.progress = 0.0;
+
+    let graph = q_graph.single()?.0.read().unwrap();
+    let state = &mut *replanner;
+    let plan = state.plan.as_mut().unwrap();
+    let next = state.route[1];
+    plan.move_start(&graph, next);
+    state.walked.push(next);
+    if let Some((cost, route)) = plan.path(&graph) {
+        state.route = route;
+        state.cost = cost;
+    }
+    hud.0 = state.summary(&units);
+
+    return Ok(());
+}
+
+// the way walked faint, the route ahead thick and the token on its current hop
+fn draw_dstar(replanner: Res<Replanner>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    if replanner.plan.is_none() {
+        return;
+    }
+    let position = position_lookup(&nodes);
+    for hop in replanner.walked.windows(2) {
+        if let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) {
+            gizmos.line_2d(a, b, WALKED);
+        }
+    }
+    for hop in replanner.route.windows(2) {
+        let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) else {
+            continue;
+        };
+        let side = (b - a).normalize_or_zero().perp() * 2.0;
+        for offset in [-side, Vec2::ZERO, side] {
+            gizmos.line_2d(a + offset, b + offset, ROUTE);
+        }
+    }
+    let token = match replanner.route.as_slice() {
+        [at, next, ..] => position(*at)
+            .zip(position(*next))
+            .map(|(a, b)| a.lerp(b, replanner.progress)),
+        [at] => position(*at),
+        [] => None,
+    };
+    if let Some(token) = token {
+        gizmos.circle_2d(Isometry2d::from_translation(token), 7.0, TOKEN);
+    }
+}
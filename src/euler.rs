@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NoEuler, NodeId};
+use crate::{EdgeMode, MainCamera, Node, ResultsHud, StartNode, WrapperGraph, position_lookup};
+
+const EDGE_SECS: f32 = 0.6;
+const TRACED: Color = Color::srgb(1.0, 0.75, 0.2);
+const TRACING: Color = Color::srgb(1.0, 0.95, 0.6);
+
+// an euler trail being traced edge by edge; `traced` counts the edges drawn so far, with the
+// fraction of the one in progress
+#[derive(Resource, Default)]
+struct EulerTour {
+    trail: Vec<NodeId>,
+    traced: f32,
+    directed: bool,
+    // the weights the trail was found for
+    costs: Vec<(NodeId, NodeId, Cost)>,
+}
+
+impl EulerTour {
+    fn edges(&self) -> usize {
+        return self.trail.len().saturating_sub(1);
+    }
+
+    fn done(&self) -> usize {
+        return (self.traced as usize).min(self.edges());
+    }
+
+    fn summary(&self) -> String {
+        let (first, last) = (self.trail[0], self.trail[self.trail.len() - 1]);
+        let kind = match (self.directed, first == last) {
+            (true, true) => "Directed Euler circuit",
+            (true, false) => "Directed Euler path",
+            (false, true) => "Euler circuit",
+            (false, false) => "Euler path",
+        };
+        let ends = if first == last {
+            format!("from {}", first)
+        } else {
+            format!("from {} to {}", first, last)
+        };
+        return format!(
+            "{} {}: {} of {} edges traced (Ctrl+E stops)\n{}",
+            kind,
+            ends,
+            self.done(),
+            self.edges(),
+            Graph::fmt_path(&self.trail)
+        );
+    }
+}
+
+#[derive(Component)]
+struct EulerLabel(usize);
+
+pub struct EulerPlugin;
+
+impl Plugin for EulerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EulerTour>().add_systems(
+            Update,
+            (
+                handle_euler_keys.run_if(crate::prompt::prompt_closed),
+                step_euler,
+                draw_euler,
+                update_euler_labels,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn labels(ids: &[NodeId]) -> String {
+    let labels: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    return labels.join(", ");
+}
+
+// ctrl+E looks for a walk over every edge exactly once and traces it, or stops tracing
+fn handle_euler_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    mode: Res<EdgeMode>,
+    mut tour: ResMut<EulerTour>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyE) {
+        return Ok(());
+    }
+    if !tour.trail.is_empty() {
+        *tour = EulerTour::default();
+        hud.0 = "Euler tracing off".to_string();
+        return Ok(());
+    }
+    let (wg, start) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let trail = match graph.euler_path(mode.directed, start.id) {
+        Ok(trail) => trail,
+        Err(NoEuler::NoEdges) => {
+            hud.0 = "There are no edges to trace".to_string();
+            return Ok(());
+        }
+        Err(NoEuler::Disconnected) => {
+            hud.0 = "No Euler path: the edges are in more than one piece".to_string();
+            return Ok(());
+        }
+        Err(NoEuler::OddDegrees(odd)) => {
+            hud.0 = format!(
+                "No Euler path: {} nodes have an odd degree ({}), and at most two may",
+                odd.len(),
+                labels(&odd)
+            );
+            return Ok(());
+        }
+        Err(NoEuler::Unbalanced(unbalanced)) => {
+            hud.0 = format!(
+                "No directed Euler path: {} have in and out degrees that differ; only one start with an extra edge out and one end with an extra edge in may",
+                labels(&unbalanced)
+            );
+            return Ok(());
+        }
+    };
+    *tour = EulerTour {
+        trail,
+        traced: 0.0,
+        directed: mode.directed,
+        costs: graph.arc_costs(),
+    };
+    hud.0 = tour.summary();
+
+    return Ok(());
+}
+
+// one edge every EDGE_SECS; editing the graph stops the trace, or clears a finished one
+fn step_euler(
+    time: Res<Time>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut tour: ResMut<EulerTour>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if tour.trail.is_empty() {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    if graph.arc_costs() != tour.costs {
+        *tour = EulerTour::default();
+        hud.0 = "The graph changed, so the Euler trace stopped".to_string();
+        return Ok(());
+    }
+    if tour.done() == tour.edges() {
+        return Ok(());
+    }
+    let done = tour.done();
+    tour.traced = (tour.traced + time.delta_secs() / EDGE_SECS).min(tour.edges() as f32);
+    if tour.done() != done {
+        hud.0 = tour.summary();
+    }
+
+    return Ok(());
+}
+
+// traced edges thick, the one in progress growing from its tail
+fn draw_euler(tour: Res<EulerTour>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let position = position_lookup(&nodes);
+    for (i, hop) in tour.trail.windows(2).enumerate() {
+        let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) else {
+            continue;
+        };
+        let side = (b - a).normalize_or_zero().perp() * 2.0;
+        if i < tour.done() {
+            for offset in [-side, Vec2::ZERO, side] {
+                gizmos.line_2d(a + offset, b + offset, TRACED);
+            }
+        } else if i == tour.done() {
+            let tip = a.lerp(b, tour.traced.fract());
+            gizmos.line_2d(a, tip, TRACING);
+            gizmos.circle_2d(Isometry2d::from_translation(tip), 5.0, TRACING);
+            return;
+        }
+    }
+}
+
+// the order each edge was traced in, at its middle
+fn update_euler_labels(
+    mut commands: Commands,
+    tour: Res<EulerTour>,
+    nodes: Query<&Node>,
+    mut q_labels: Query<(Entity, &EulerLabel, &mut Transform)>,
+) {
+    let position = position_lookup(&nodes);
+    let at = |i: usize| -> Option<Vec3> {
+        let (a, b) = (position(tour.trail[i])?, position(tour.trail[i + 1])?);
+        return Some(
+            a.midpoint(b).extend(6.0) + (b - a).normalize_or_zero().perp().extend(0.0) * 12.0,
+        );
+    };
+    let mut labelled = 0;
+    for (e, label, mut transform) in &mut q_labels {
+        match (label.0 < tour.done()).then(|| at(label.0)).flatten() {
+            Some(translation) => {
+                transform.translation = translation;
+                labelled = labelled.max(label.0 + 1);
+            }
+            None => commands.entity(e).despawn(),
+        }
+    }
+    for i in labelled..tour.done() {
+        let Some(translation) = at(i) else {
+            continue;
+        };
+        commands.spawn((
+            Text2d::new((i + 1).to_string()),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(TRACED),
+            Transform::from_translation(translation),
+            EulerLabel(i),
+        ));
+    }
+}
@@ -0,0 +1,564 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use bevy::math::Vec2;
+use serde::Serialize;
+
+use crate::graph::{Cost, Graph, NodeId, TableRow, TimedEvent, Trace};
+use crate::notes::NodeNotes;
+
+const MARGIN: f32 = 40.0;
+const NODE_R: f32 = 18.0;
+const CELL_W: f32 = 48.0;
+const CELL_H: f32 = 26.0;
+const NOTE_H: f32 = 20.0;
+const STAMP_H: f32 = 20.0;
+const CAPTION_H: f32 = 76.0;
+// the app's start, goal and path colours, darkened enough to read on white
+const START_FILL: &str = "limegreen";
+const GOAL_FILL: &str = "gold";
+const PATH_STROKE: &str = "darkturquoise";
+
+// a title and date for the caption block across the top of an export
+pub struct Caption {
+    pub title: String,
+    pub date: String,
+}
+
+// today's date from the system clock (utc), as yyyy-mm-dd
+pub fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    // days since 1970 to a civil date, in 400-year eras starting each march
+    let z = secs.div_euclid(86_400) + 719_468;
+    let (era, doe) = (z.div_euclid(146_097), z.rem_euclid(146_097));
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    return format!("{:04}-{:02}-{:02}", year, month, day);
+}
+
+struct Layout {
+    min: Vec2,
+    size: Vec2,
+    // room left above the graph for a caption
+    top: f32,
+}
+
+impl Layout {
+    fn new(positions: &[Vec2]) -> Self {
+        if positions.is_empty() {
+            return Layout {
+                min: Vec2::ZERO,
+                size: Vec2::ZERO,
+                top: 0.0,
+            };
+        }
+        let (min, max) = positions.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(lo, hi), &p| (lo.min(p), hi.max(p)),
+        );
+        return Layout {
+            min,
+            size: max - min,
+            top: 0.0,
+        };
+    }
+
+    // world space is y-up, svg is y-down
+    fn to_svg(&self, p: Vec2) -> Vec2 {
+        return Vec2::new(
+            p.x - self.min.x + MARGIN,
+            self.size.y - (p.y - self.min.y) + MARGIN + self.top,
+        );
+    }
+}
+
+// `marks` colours the start, the goal and the edges along a path, for a captioned export
+fn write_graph(
+    out: &mut String,
+    graph: &Graph,
+    positions: &[Vec2],
+    layout: &Layout,
+    marks: Option<(NodeId, NodeId, &[NodeId])>,
+) {
+    let on_path: HashSet<(NodeId, NodeId)> = marks
+        .map(|(_, _, path)| path.windows(2).map(|w| (w[0].min(w[1]), w[0].max(w[1]))))
+        .into_iter()
+        .flatten()
+        .collect();
+    let mut drawn = HashSet::new();
+    for (a, edge) in graph.arcs() {
+        let key = if a < edge.node {
+            (a, edge.node)
+        } else {
+            (edge.node, a)
+        };
+        if !drawn.insert(key) {
+            continue;
+        }
+        let (p, q) = (
+            layout.to_svg(positions[a.index()]),
+            layout.to_svg(positions[edge.node.index()]),
+        );
+        let mid = (p + q) * 0.5;
+        let (stroke, width) = if on_path.contains(&key) {
+            (PATH_STROKE, 4)
+        } else {
+            ("black", 2)
+        };
+        let _ = writeln!(
+            out,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="{}"/>"#,
+            p.x, p.y, q.x, q.y, stroke, width
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" font-size="13" text-anchor="middle" fill="black" stroke="white" stroke-width="3" paint-order="stroke">{}</text>"#,
+            mid.x,
+            mid.y - 4.0,
+            edge.cost
+        );
+        // the name runs along the line below it, turned so it never reads upside down
+        if let Some(name) = graph.lane_label(key.0, key.1, 0) {
+            let d = q - p;
+            let mut angle = d.y.atan2(d.x).to_degrees();
+            if angle.abs() > 90.0 {
+                angle += 180.0;
+            }
+            let _ = writeln!(
+                out,
+                r#"<text x="{:.1}" y="{:.1}" font-size="11" text-anchor="middle" dominant-baseline="hanging" fill="steelblue" stroke="white" stroke-width="3" paint-order="stroke" transform="rotate({:.1} {:.1} {:.1})">{}</text>"#,
+                mid.x,
+                mid.y + 4.0,
+                angle,
+                mid.x,
+                mid.y,
+                escape(name)
+            );
+        }
+    }
+
+    for (i, &pos) in positions.iter().enumerate() {
+        let p = layout.to_svg(pos);
+        let fill = match marks {
+            Some((start, _, _)) if i == start.index() => START_FILL,
+            Some((_, goal, _)) if i == goal.index() => GOAL_FILL,
+            _ => "white",
+        };
+        let _ = writeln!(
+            out,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{}" fill="{}" stroke="black" stroke-width="2"/>"#,
+            p.x, p.y, NODE_R, fill
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" font-size="16" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            p.x,
+            p.y,
+            escape(&graph.node_name(NodeId(i)))
+        );
+    }
+}
+
+fn write_table(out: &mut String, n: usize, rows: &[TableRow], filled: bool, top: f32) {
+    let cell = |out: &mut String, col: usize, row: usize, text: &str, bold: bool| {
+        let (x, y) = (MARGIN + col as f32 * CELL_W, top + row as f32 * CELL_H);
+        let _ = writeln!(
+            out,
+            r#"<rect x="{:.1}" y="{:.1}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            x, y, CELL_W, CELL_H
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" font-size="13" text-anchor="middle" dominant-baseline="central" font-weight="{}">{}</text>"#,
+            x + CELL_W * 0.5,
+            y + CELL_H * 0.5,
+            if bold { "bold" } else { "normal" },
+            text
+        );
+    };
+
+    cell(out, 0, 0, "Settled", true);
+    for i in 0..n {
+        cell(out, i + 1, 0, &Graph::node_label(i), true);
+    }
+
+    if !filled {
+        // as many rows as nodes, so the blank sheet doesn't leak how many are reachable
+        for r in 1..=n {
+            for col in 0..=n {
+                cell(out, col, r, "", false);
+            }
+        }
+        return;
+    }
+
+    let mut done = vec![false; n];
+    for (r, row) in rows.iter().enumerate() {
+        done[row.settled.index()] = true;
+        cell(out, 0, r + 1, &row.settled.to_string(), false);
+        for (i, d) in row.dist.iter().enumerate() {
+            let text = match d {
+                Some(d) => d.to_string(),
+                None => "∞".to_string(),
+            };
+            cell(
+                out,
+                i + 1,
+                r + 1,
+                &text,
+                i == row.settled.index() || !done[i],
+            );
+        }
+    }
+}
+
+// title, query and date, then a legend for the colours the graph is drawn in
+fn write_caption(out: &mut String, caption: &Caption, summary: &str, path_shown: bool) {
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="{}" font-size="18" font-weight="bold">{}</text>"#,
+        MARGIN,
+        MARGIN,
+        escape(&caption.title)
+    );
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="{}" font-size="13">{}, exported {}</text>"#,
+        MARGIN,
+        MARGIN + 22.0,
+        summary,
+        caption.date
+    );
+
+    let y = MARGIN + 44.0;
+    let mut x = MARGIN;
+    for (fill, label) in [(START_FILL, "Start"), (GOAL_FILL, "Goal")] {
+        let _ = writeln!(
+            out,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="7" fill="{}" stroke="black"/>"#,
+            x + 7.0,
+            y,
+            fill
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" font-size="13" dominant-baseline="central">{}</text>"#,
+            x + 20.0,
+            y,
+            label
+        );
+        x += 80.0;
+    }
+    if path_shown {
+        let _ = writeln!(
+            out,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="4"/>"#,
+            x,
+            y,
+            x + 24.0,
+            y,
+            PATH_STROKE
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}" font-size="13" dominant-baseline="central">Shortest path</text>"#,
+            x + 32.0,
+            y
+        );
+    }
+}
+
+fn escape(text: &str) -> String {
+    return text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+}
+
+// `notes` is only read for the answer key, which lists the notes of the nodes on the path. the
+// exercise's caption leaves the cost and path out, so it gives nothing away
+fn document(
+    graph: &Graph,
+    positions: &[Vec2],
+    start: NodeId,
+    goal: NodeId,
+    filled: bool,
+    notes: &NodeNotes,
+    caption: Option<&Caption>,
+) -> String {
+    let n = graph.nodes.len();
+    let mut layout = Layout::new(positions);
+    if caption.is_some() {
+        layout.top = CAPTION_H;
+    }
+    let rows = graph.dijkstra_table(start);
+    let answer = graph.shortest_path(start, goal);
+    let path_notes = match (&answer, filled) {
+        (Some((_, path)), true) => notes.along(path),
+        _ => Vec::new(),
+    };
+    let table_top = layout.top + layout.size.y + MARGIN * 2.0 + 40.0;
+    let width = (layout.size.x + MARGIN * 2.0).max((n + 1) as f32 * CELL_W + MARGIN * 2.0);
+    let height = table_top
+        + (n + 1) as f32 * CELL_H
+        + MARGIN * 2.0
+        + path_notes.len() as f32 * NOTE_H
+        + STAMP_H;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" font-family="sans-serif">"#,
+        width, height
+    );
+    let _ = writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    match caption {
+        Some(caption) => {
+            let query = format!("Dijkstra from {} to {}", start, goal);
+            let summary = match (&answer, filled) {
+                (Some((cost, _)), true) => format!("{}, cost {}", query, cost),
+                (None, true) => format!("{}, unreachable", query),
+                (_, false) => query,
+            };
+            let path = match (&answer, filled) {
+                (Some((_, path)), true) => path.as_slice(),
+                _ => &[],
+            };
+            write_caption(&mut out, caption, &summary, !path.is_empty());
+            write_graph(
+                &mut out,
+                graph,
+                positions,
+                &layout,
+                Some((start, goal, path)),
+            );
+        }
+        None => write_graph(&mut out, graph, positions, &layout, None),
+    }
+
+    let prompt = format!(
+        "Run Dijkstra from {} and find the shortest path to {}.",
+        start, goal
+    );
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="{:.1}" font-size="15">{}</text>"#,
+        MARGIN,
+        table_top - 24.0,
+        prompt
+    );
+    write_table(&mut out, n, &rows, filled, table_top);
+
+    if filled {
+        let answer = match answer {
+            Some((cost, path)) => format!("Path: {}, cost: {}", Graph::fmt_path(&path), cost),
+            None => format!("{} is unreachable from {}", goal, start),
+        };
+        let answer_y = table_top + (rows.len() + 1) as f32 * CELL_H + 28.0;
+        let _ = writeln!(
+            out,
+            r#"<text x="{}" y="{:.1}" font-size="15" font-weight="bold">{}</text>"#,
+            MARGIN, answer_y, answer
+        );
+        for (i, (id, note)) in path_notes.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                r#"<text x="{}" y="{:.1}" font-size="13">{}: {}</text>"#,
+                MARGIN,
+                answer_y + (i + 1) as f32 * NOTE_H,
+                id,
+                escape(note)
+            );
+        }
+    }
+
+    // the graph's content hash, so two copies can be checked against each other
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="{:.1}" font-size="11" fill="gray">graph {}</text>"#,
+        MARGIN,
+        height - MARGIN,
+        graph.fmt_hash()
+    );
+
+    out.push_str("</svg>\n");
+    return out;
+}
+
+pub fn exercise_svg(
+    graph: &Graph,
+    positions: &[Vec2],
+    start: NodeId,
+    goal: NodeId,
+    caption: Option<&Caption>,
+) -> String {
+    return document(
+        graph,
+        positions,
+        start,
+        goal,
+        false,
+        &NodeNotes::default(),
+        caption,
+    );
+}
+
+pub fn answer_key_svg(
+    graph: &Graph,
+    positions: &[Vec2],
+    start: NodeId,
+    goal: NodeId,
+    notes: &NodeNotes,
+    caption: Option<&Caption>,
+) -> String {
+    return document(graph, positions, start, goal, true, notes, caption);
+}
+
+#[derive(Serialize)]
+struct TraceFile<'a> {
+    algorithm: &'static str,
+    graph_hash: String,
+    nodes: Vec<String>,
+    start: NodeId,
+    goal: NodeId,
+    cost: Option<Cost>,
+    path: Option<&'a [NodeId]>,
+    notes: Vec<PathNote<'a>>,
+    events: &'a [TimedEvent],
+}
+
+#[derive(Serialize)]
+struct PathNote<'a> {
+    node: String,
+    note: &'a str,
+}
+
+pub fn trace_json(
+    graph: &Graph,
+    trace: &Trace,
+    start: NodeId,
+    goal: NodeId,
+    notes: &NodeNotes,
+) -> String {
+    let path_notes = match &trace.result {
+        Some((_, path)) => notes
+            .along(path)
+            .into_iter()
+            .map(|(id, note)| PathNote {
+                node: id.to_string(),
+                note,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let file = TraceFile {
+        algorithm: "dijkstra",
+        graph_hash: graph.fmt_hash(),
+        nodes: graph.ids().map(|id| graph.node_name(id)).collect(),
+        start,
+        goal,
+        cost: trace.result.as_ref().map(|(cost, _)| *cost),
+        path: trace.result.as_ref().map(|(_, path)| path.as_slice()),
+        notes: path_notes,
+        events: &trace.events,
+    };
+    return serde_json::to_string_pretty(&file).unwrap();
+}
+
+// one row per query; unreachable goals get an empty cost and path. every row carries the graph's
+// hash so rows from different graphs can't be mixed up
+pub fn batch_csv(
+    graph: &Graph,
+    queries: &[(NodeId, NodeId)],
+    results: &[Option<(Cost, Vec<NodeId>)>],
+    format: impl Fn(Cost) -> String,
+) -> String {
+    let hash = graph.fmt_hash();
+    let mut out = String::from("start,goal,cost,hops,path,graph\n");
+    for (&(start, goal), result) in queries.iter().zip(results) {
+        let (cost, hops, path) = match result {
+            Some((cost, path)) => (
+                format(*cost),
+                (path.len() - 1).to_string(),
+                Graph::fmt_path(path),
+            ),
+            None => (String::new(), String::new(), String::new()),
+        };
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            start, goal, cost, hops, path, hash
+        );
+    }
+    return out;
+}
+
+// a mermaid flowchart to paste into markdown: one link per connected pair, `---` when both
+// directions have the same weight and an arrow each way otherwise. the start and goal are filled
+// and the path's links drawn thick, in the app's colours
+pub fn mermaid(
+    graph: &Graph,
+    query: Option<(NodeId, NodeId)>,
+    path: &[NodeId],
+    format: impl Fn(Cost) -> String,
+) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for id in graph.ids() {
+        let name = graph.node_name(id).replace('"', "#quot;");
+        let _ = writeln!(out, "    n{}((\"{}\"))", id.index(), name);
+    }
+    let on_path: HashSet<(NodeId, NodeId)> = path.windows(2).map(|hop| (hop[0], hop[1])).collect();
+    let mut links = 0;
+    let mut styled = Vec::new();
+    for (from, edge) in graph.arcs() {
+        let back = graph
+            .edges_from(edge.node)
+            .iter()
+            .find(|e| e.node == from && edge.node != from);
+        let same = back.is_some_and(|b| b.cost == edge.cost);
+        // a pair with one weight both ways is a single link, written from its lower end
+        if same && from > edge.node {
+            continue;
+        }
+        let arrow = if same { "---" } else { "-->" };
+        let text = match &edge.label {
+            Some(name) => format!("{} ({})", name.replace('"', "#quot;"), format(edge.cost)),
+            None => format(edge.cost),
+        };
+        let _ = writeln!(
+            out,
+            "    n{} {}|\"{}\"| n{}",
+            from.index(),
+            arrow,
+            text,
+            edge.node.index()
+        );
+        if on_path.contains(&(from, edge.node)) || (same && on_path.contains(&(edge.node, from))) {
+            styled.push(links.to_string());
+        }
+        links += 1;
+    }
+    let Some((start, goal)) = query else {
+        return out;
+    };
+    let _ = writeln!(out, "    classDef start fill:{}", START_FILL);
+    let _ = writeln!(out, "    classDef goal fill:{}", GOAL_FILL);
+    let _ = writeln!(out, "    class n{} start", start.index());
+    let _ = writeln!(out, "    class n{} goal", goal.index());
+    if !styled.is_empty() {
+        let _ = writeln!(
+            out,
+            "    linkStyle {} stroke:{},stroke-width:4px",
+            styled.join(","),
+            PATH_STROKE
+        );
+    }
+    return out;
+}
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::rng::Rng;
+use crate::{
+    GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, position_lookup,
+};
+
+const DEFAULT_EVERY_SECS: f32 = 1.0;
+const LOG_LINES: usize = 6;
+const FAILED: Color = Color::srgb(0.9, 0.2, 0.2);
+const ROUTE: Color = Color::srgb(0.3, 0.85, 1.0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Settings {
+    seed: Option<u64>,
+    every: f32,
+}
+
+impl Settings {
+    // optionally `seed <n>` and `every <seconds>`, in either order
+    fn parse(text: &str) -> Option<Settings> {
+        let mut settings = Settings {
+            seed: None,
+            every: DEFAULT_EVERY_SECS,
+        };
+        let mut words = text.split_whitespace();
+        while let Some(word) = words.next() {
+            match word.to_ascii_lowercase().as_str() {
+                "seed" => settings.seed = Some(words.next()?.parse().ok()?),
+                "every" => {
+                    settings.every = words.next()?.parse().ok().filter(|s: &f32| *s > 0.0)?;
+                }
+                _ => return None,
+            }
+        }
+        return Some(settings);
+    }
+}
+
+// edges knocked out of a copy of the graph one at a time, with the route and the number of
+// pieces after each; the real graph is never touched
+#[derive(Resource, Default)]
+struct Failures {
+    graph: Option<Graph>,
+    rng: Rng,
+    seed: u64,
+    every: f32,
+    timer: f32,
+    failed: Vec<(NodeId, NodeId)>,
+    query: Option<(NodeId, NodeId)>,
+    route: Option<(Cost, Vec<NodeId>)>,
+    pieces: usize,
+    // the failures that changed the route or split the graph
+    log: Vec<String>,
+    // the weights the copy was taken with
+    costs: Vec<(NodeId, NodeId, Cost)>,
+}
+
+impl Failures {
+    fn summary(&self, units: &WeightUnits) -> String {
+        let Some(graph) = &self.graph else {
+            return String::new();
+        };
+        let left = graph.connection_count();
+        let mut lines = vec![format!(
+            "Edge failures (seed {}): {} down, {} left, {} pieces (Ctrl+F stops)",
+            self.seed,
+            self.failed.len(),
+            left,
+            self.pieces
+        )];
+        lines.push(match (self.query, &self.route) {
+            (None, _) => "Set a start (S) and goal (G) to follow a route too".to_string(),
+            (Some(_), Some((cost, path))) => format!(
+                "Route {} costs {}",
+                Graph::fmt_path(path),
+                units.format(*cost)
+            ),
+            (Some((start, goal)), None) => format!("{} can no longer reach {}", start, goal),
+        });
+        let skip = self.log.len().saturating_sub(LOG_LINES);
+        lines.extend(self.log.iter().skip(skip).cloned());
+        if left == 0 {
+            lines.push("Every edge has failed".to_string());
+        }
+        return lines.join("\n");
+    }
+}
+
+fn pieces(graph: &Graph) -> usize {
+    return graph
+        .connected_components()
+        .iter()
+        .max()
+        .map_or(0, |c| c + 1);
+}
+
+pub struct FailuresPlugin;
+
+impl Plugin for FailuresPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Failures>().add_systems(
+            Update,
+            (
+                handle_failure_keys.run_if(crate::prompt::prompt_closed),
+                start_failures,
+                step_failures,
+                draw_failures,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+F asks for a seed and a pace, or ends a running simulation
+fn handle_failure_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut failures: ResMut<Failures>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+    if failures.graph.is_some() {
+        *failures = Failures::default();
+        hud.0 = "Failure simulation off; the graph is as it was".to_string();
+        return;
+    }
+    prompt.open(
+        PromptKind::Failures,
+        "Fail random edges: empty for a random seed, or `seed 7`, `every 0.5` (seconds)",
+    );
+}
+
+fn start_failures(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut app_rng: ResMut<Rng>,
+    mut failures: ResMut<Failures>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Failures {
+            continue;
+        }
+        let Some(settings) = Settings::parse(&event.text) else {
+            hud.0 = format!(
+                "Could not read `{}`: try `seed 7`, `every 0.5` or both",
+                event.text
+            );
+            continue;
+        };
+        let (wg, start, goal) = q_camera.single()?;
+        let graph = wg.0.read().unwrap();
+        if graph.connection_count() == 0 {
+            hud.0 = "There are no edges to fail".to_string();
+            continue;
+        }
+        // an unseeded run still gets a seed, reported so it can be repeated
+        let seed = settings.seed.unwrap_or_else(|| app_rng.next_u64() >> 32);
+        let query = start.id.zip(goal.id);
+        *failures = Failures {
+            graph: Some(graph.clone()),
+            rng: Rng::new(seed),
+            seed,
+            every: settings.every,
+            query,
+            route: query.and_then(|(start, goal)| graph.shortest_path(start, goal)),
+            pieces: pieces(&graph),
+            costs: graph.arc_costs(),
+            ..default()
+        };
+        hud.0 = failures.summary(&units);
+    }
+
+    return Ok(());
+}
+
+// one failure every `every` seconds until no edge is left; editing the real graph ends the run
+fn step_failures(
+    time: Res<Time>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut failures: ResMut<Failures>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if failures.graph.is_none() {
+        return Ok(());
+    }
+    if q_graph.single()?.0.read().unwrap().arc_costs() != failures.costs {
+        *failures = Failures::default();
+        hud.0 = "The graph changed, so the failure simulation stopped".to_string();
+        return Ok(());
+    }
+    failures.timer += time.delta_secs();
+    if failures.timer < failures.every {
+        return Ok(());
+    }
+    failures.timer = 0.0;
+
+    let state = &mut *failures;
+    let graph = state.graph.as_mut().unwrap();
+    let Some((a, b)) = graph.fail_random_edge(&mut state.rng) else {
+        return Ok(());
+    };
+    state.failed.push((a, b));
+    let route = state
+        .query
+        .and_then(|(start, goal)| graph.shortest_path(start, goal));
+    let pieces = pieces(graph);
+
+    let failure = format!("#{} {} - {}", state.failed.len(), a, b);
+    if pieces > state.pieces {
+        state.log.push(format!(
+            "{}: the graph splits into {} pieces",
+            failure, pieces
+        ));
+    }
+    match (&state.route, &route, state.query) {
+        (Some(_), None, Some((start, goal))) => state
+            .log
+            .push(format!("{}: {} is cut off from {}", failure, goal, start)),
+        (Some((_, old)), Some((cost, new)), _) if old != new => state.log.push(format!(
+            "{}: the route moves to {} at {}",
+            failure,
+            Graph::fmt_path(new),
+            units.format(*cost)
+        )),
+        _ => {}
+    }
+    state.route = route;
+    state.pieces = pieces;
+    hud.0 = failures.summary(&units);
+
+    return Ok(());
+}
+
+// failed edges crossed out in red, the surviving route thick on top
+fn draw_failures(failures: Res<Failures>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    if failures.graph.is_none() {
+        return;
+    }
+    let position = position_lookup(&nodes);
+    for &(a, b) in &failures.failed {
+        let (Some(a), Some(b)) = (position(a), position(b)) else {
+            continue;
+        };
+        gizmos.line_2d(a, b, FAILED.with_alpha(0.35));
+        let mid = a.midpoint(b);
+        let dir = (b - a).normalize_or_zero() * 6.0;
+        gizmos.line_2d(mid - dir - dir.perp(), mid + dir + dir.perp(), FAILED);
+        gizmos.line_2d(mid - dir + dir.perp(), mid + dir - dir.perp(), FAILED);
+    }
+    let Some((_, path)) = &failures.route else {
+        return;
+    };
+    for hop in path.windows(2) {
+        let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) else {
+            continue;
+        };
+        let side = (b - a).normalize_or_zero().perp() * 2.0;
+        for offset in [-side, Vec2::ZERO, side] {
+            gizmos.line_2d(a + offset, b + offset, ROUTE);
+        }
+    }
+}
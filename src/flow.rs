@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use bevy::color::palettes::css::{AQUA, CRIMSON};
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, MaxFlow, NodeId};
+use crate::node_info::NodeInfo;
+use crate::params::AlgorithmParams;
+use crate::{
+    EdgeVisual, GoalNode, HighlightedEdges, Layer, MainCamera, NODE_RADIUS, Node, ResultsHud,
+    StartNode, WeightUnits, WrapperGraph, ord, position_lookup,
+};
+
+const STEP_SECS: f32 = 0.8; // per augmenting path
+const IDLE: Color = Color::srgb(0.6, 0.6, 0.6);
+const MAX_RINGS: usize = 4; // around the node with the largest capacity
+const RING_GAP: f32 = 1.5;
+
+// a max flow from the start to the goal, its augmenting paths played back one at a time
+#[derive(Resource, Default)]
+struct FlowView {
+    query: Option<(NodeId, NodeId)>,
+    result: MaxFlow,
+    shown: usize,
+    elapsed: f32,
+    // every arc and node capacity when the flow was found, and the path cap it ran under
+    arcs: Vec<(NodeId, NodeId, Cost)>,
+    node_caps: Vec<(NodeId, Cost)>,
+    max_paths: usize,
+}
+
+impl FlowView {
+    // net flow after the paths shown so far, keyed by ord pairs and positive from the lower id
+    fn net_flow(&self) -> HashMap<(NodeId, NodeId), Cost> {
+        let mut net = HashMap::new();
+        for (amount, path) in self.result.augmenting.iter().take(self.shown) {
+            for w in path.windows(2) {
+                let sign = if w[0] < w[1] { 1 } else { -1 };
+                *net.entry(ord(w[0], w[1])).or_insert(0) += sign * amount;
+            }
+        }
+        return net;
+    }
+
+    // what passes through each node so far: the larger of what comes in and what goes out, so the
+    // source and the sink count too
+    fn through(&self) -> HashMap<NodeId, Cost> {
+        let mut through: HashMap<NodeId, (Cost, Cost)> = HashMap::new();
+        for ((lo, hi), flow) in self.net_flow() {
+            let (from, to) = if flow < 0 { (hi, lo) } else { (lo, hi) };
+            through.entry(from).or_default().1 += flow.abs();
+            through.entry(to).or_default().0 += flow.abs();
+        }
+        return through
+            .into_iter()
+            .map(|(id, (inflow, outflow))| (id, inflow.max(outflow)))
+            .collect();
+    }
+}
+
+#[derive(Component)]
+struct FlowLabel;
+
+pub struct FlowPlugin;
+
+impl Plugin for FlowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlowView>().add_systems(
+            Update,
+            (
+                handle_flow_keys.run_if(crate::prompt::prompt_closed),
+                drop_stale_flow,
+                step_flow,
+                update_flow_labels,
+                draw_augmenting_path,
+                draw_node_capacities,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn arcs(graph: &Graph) -> Vec<(NodeId, NodeId, Cost)> {
+    return graph
+        .arcs()
+        .map(|(from, e)| (from, e.node, e.flow_capacity()))
+        .collect();
+}
+
+fn node_caps(info: &NodeInfo) -> Vec<(NodeId, Cost)> {
+    let mut caps: Vec<(NodeId, Cost)> = info.capacities.iter().map(|(&id, &c)| (id, c)).collect();
+    caps.sort();
+    return caps;
+}
+
+// shift+G pushes as much flow as fits from the start to the goal, or hides it again
+fn handle_flow_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    params: Res<AlgorithmParams>,
+    info: Res<NodeInfo>,
+    mut view: ResMut<FlowView>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyG) {
+        return Ok(());
+    }
+    highlights.layer_mut(Layer::MinCut).clear();
+    if view.query.is_some() {
+        *view = FlowView::default();
+        hud.0 = "Flow view off".to_string();
+        return Ok(());
+    }
+    let (wg, start, goal) = q_camera.single()?;
+    let (Some(source), Some(sink)) = (start.id, goal.id) else {
+        hud.0 = "Set a start (source) and goal (sink) first".to_string();
+        return Ok(());
+    };
+    let graph = wg.0.read().unwrap();
+
+    let result = graph.max_flow_through(source, sink, params.flow_paths, &info.capacities);
+    hud.0 = format!(
+        "{} from {} to {}: {} over {} augmenting paths (Shift+G hides)",
+        if result.capped {
+            "Flow so far"
+        } else {
+            "Max flow"
+        },
+        source,
+        sink,
+        units.format(result.value),
+        result.augmenting.len()
+    );
+    *view = FlowView {
+        query: Some((source, sink)),
+        result,
+        arcs: arcs(&graph),
+        node_caps: node_caps(&info),
+        max_paths: params.flow_paths,
+        ..default()
+    };
+
+    return Ok(());
+}
+
+// the flow no longer holds once the start, goal, path cap or any capacity changes
+fn drop_stale_flow(
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    params: Res<AlgorithmParams>,
+    info: Res<NodeInfo>,
+    mut view: ResMut<FlowView>,
+    mut highlights: ResMut<HighlightedEdges>,
+) -> Result<()> {
+    let Some(query) = view.query else {
+        return Ok(());
+    };
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    if (start.id, goal.id) != (Some(query.0), Some(query.1))
+        || arcs(&graph) != view.arcs
+        || node_caps(&info) != view.node_caps
+        || params.flow_paths != view.max_paths
+    {
+        *view = FlowView::default();
+        highlights.layer_mut(Layer::MinCut).clear();
+    }
+
+    return Ok(());
+}
+
+// once the last path is in, the saturated edges it left behind go on the minimum cut layer
+fn step_flow(
+    time: Res<Time>,
+    units: Res<WeightUnits>,
+    mut view: ResMut<FlowView>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    if view.query.is_none() || view.shown > view.result.augmenting.len() {
+        return;
+    }
+    // the timer alone should not respawn the labels
+    let timer = &mut view.bypass_change_detection().elapsed;
+    *timer += time.delta_secs();
+    if *timer < STEP_SECS {
+        return;
+    }
+    *timer = 0.0;
+    view.shown += 1;
+    if view.shown <= view.result.augmenting.len() {
+        return;
+    }
+
+    if view.result.capped {
+        hud.0 = format!(
+            "Stopped after {} augmenting paths with {} flowing; raise the cap with F7",
+            view.result.augmenting.len(),
+            units.format(view.result.value)
+        );
+        return;
+    }
+    let cut = highlights.layer_mut(Layer::MinCut);
+    cut.clear();
+    cut.extend(view.result.cut.iter().map(|&(a, b)| ord(a, b)));
+    let edges: Vec<String> = view
+        .result
+        .cut
+        .iter()
+        .map(|&(a, b)| format!("{} -> {}", a, b))
+        .chain(
+            view.result
+                .cut_nodes
+                .iter()
+                .map(|&id| format!("node {}", id)),
+        )
+        .collect();
+    hud.0 = if edges.is_empty() {
+        "Nothing can flow from the start to the goal".to_string()
+    } else {
+        format!(
+            "Max flow {}; the minimum cut is {}",
+            units.format(view.result.value),
+            edges.join(", ")
+        )
+    };
+}
+
+fn fullness(flow: Cost, cap: Cost) -> Color {
+    return match flow {
+        0 => IDLE,
+        f if f >= cap => Color::from(CRIMSON),
+        _ => Color::from(AQUA),
+    };
+}
+
+// flow/capacity beside every edge and under every capped node, brighter the fuller it is
+fn update_flow_labels(
+    mut commands: Commands,
+    view: Res<FlowView>,
+    units: Res<WeightUnits>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_labels: Query<Entity, With<FlowLabel>>,
+) {
+    if !view.is_changed() && !units.is_changed() {
+        return;
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    if view.query.is_none() {
+        return;
+    }
+    let net = view.net_flow();
+    let capacity = |from: NodeId, to: NodeId| {
+        view.arcs
+            .iter()
+            .find(|&&(p, q, _)| (p, q) == (from, to))
+            .map(|&(_, _, c)| c)
+    };
+    let position = position_lookup(&nodes);
+    for ev in &edges {
+        let (lo, hi) = ord(ev.a, ev.b);
+        let flow = net.get(&(lo, hi)).copied().unwrap_or(0);
+        let (from, to) = if flow < 0 { (hi, lo) } else { (lo, hi) };
+        let Some(cap) = capacity(from, to).or_else(|| capacity(to, from)) else {
+            continue;
+        };
+        let (Some(a), Some(b)) = (position(ev.a), position(ev.b)) else {
+            continue;
+        };
+        let normal = (b - a).perp().normalize_or_zero();
+        let color = fullness(flow.abs(), cap);
+        commands.spawn((
+            Text2d::new(format!(
+                "{}/{}",
+                units.format(flow.abs()),
+                units.format(cap)
+            )),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(color),
+            Transform::from_translation(((a + b) * 0.5 + normal * 16.0).extend(6.0)),
+            FlowLabel,
+        ));
+    }
+    let through = view.through();
+    for &(id, cap) in &view.node_caps {
+        let Some(at) = position(id) else {
+            continue;
+        };
+        let flow = through.get(&id).copied().unwrap_or(0);
+        commands.spawn((
+            Text2d::new(format!("{}/{}", units.format(flow), units.format(cap))),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(fullness(flow, cap)),
+            Transform::from_translation((at - Vec2::Y * (NODE_RADIUS + 14.0)).extend(6.0)),
+            FlowLabel,
+        ));
+    }
+}
+
+// the path most recently augmented, with arrows for the way it pushed
+fn draw_augmenting_path(view: Res<FlowView>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let Some((_, path)) = view
+        .shown
+        .checked_sub(1)
+        .and_then(|i| view.result.augmenting.get(i))
+    else {
+        return;
+    };
+    let position = position_lookup(&nodes);
+    for w in path.windows(2) {
+        let (Some(a), Some(b)) = (position(w[0]), position(w[1])) else {
+            continue;
+        };
+        gizmos.arrow_2d(a, b, Color::from(AQUA));
+    }
+}
+
+// a ring around every capped node, thicker the more it can carry next to the others; while a flow
+// is shown it takes the colour of how full the node is
+fn draw_node_capacities(
+    info: Res<NodeInfo>,
+    view: Res<FlowView>,
+    nodes: Query<&Node>,
+    mut gizmos: Gizmos,
+) {
+    let Some(&most) = info.capacities.values().max() else {
+        return;
+    };
+    let through = view.through();
+    for node in &nodes {
+        let Some(&cap) = info.capacities.get(&node.id) else {
+            continue;
+        };
+        let rings = 1 + (cap as f32 / most.max(1) as f32 * (MAX_RINGS - 1) as f32).round() as usize;
+        let color = match view.query {
+            Some(_) => fullness(through.get(&node.id).copied().unwrap_or(0), cap),
+            None => IDLE,
+        };
+        for ring in 0..rings {
+            let radius = NODE_RADIUS + 4.0 + ring as f32 * RING_GAP;
+            gizmos.circle_2d(Isometry2d::from_translation(node.position), radius, color);
+        }
+    }
+}
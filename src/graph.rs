@@ -1,180 +1,5760 @@
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use std::{fmt, usize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt::{self, Write};
+use std::time::Instant;
 
-#[derive(Debug, Clone, Copy)]
-pub struct Edge {
-    pub node: usize,
-    pub cost: usize,
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::rng::Rng;
+use crate::union_find::UnionFind;
+
+// signed so edges can carry negative weights; searches that assume non-negative costs say so
+pub type Cost = i64;
+
+// what a graph can be weighted with: anything ordered that adds up from zero. the app uses Cost;
+// a pair such as (time, toll) compares the first part and breaks ties on the second, and Real
+// stands in for f64. only dijkstra and dijkstra_events take any Weight. every other search, from
+// A* and bellman-ford to flows and the k shortest paths, is written for Cost, since it needs
+// negative weights, subtraction or a heuristic in the same units
+pub trait Weight: Copy + Ord + fmt::Debug {
+    fn zero() -> Self;
+    fn plus(self, other: Self) -> Self;
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-struct State {
-    position: usize,
-    cost: usize,
+macro_rules! integer_weight {
+    ($($t:ty),*) => {
+        $(impl Weight for $t {
+            fn zero() -> Self {
+                return 0;
+            }
+
+            fn plus(self, other: Self) -> Self {
+                return self + other;
+            }
+        })*
+    };
 }
 
-pub type Node = Vec<Edge>;
+integer_weight!(i32, i64, u32, u64, usize);
 
-#[derive(Debug, Clone)]
-pub struct Graph {
-    pub nodes: Vec<Node>,
+impl<A: Weight, B: Weight> Weight for (A, B) {
+    fn zero() -> Self {
+        return (A::zero(), B::zero());
+    }
+
+    fn plus(self, other: Self) -> Self {
+        return (self.0.plus(other.0), self.1.plus(other.1));
+    }
+}
+
+// an f64 weight, ordered by f64::total_cmp so it can sit in a heap
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Real(pub f64);
+
+impl PartialEq for Real {
+    fn eq(&self, other: &Self) -> bool {
+        return self.cmp(other) == Ordering::Equal;
+    }
 }
 
-impl Ord for State {
+impl Eq for Real {}
+
+impl Ord for Real {
     fn cmp(&self, other: &Self) -> Ordering {
-        return other
-            .cost
-            .cmp(&self.cost)
-            .then_with(|| self.position.cmp(&other.position));
+        return self.0.total_cmp(&other.0);
     }
 }
 
-impl PartialOrd for State {
+impl PartialOrd for Real {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         return Some(self.cmp(other));
     }
 }
 
-impl Graph {
-    pub fn fmt_path(path: &Vec<usize>) -> String {
-        return path
-            .iter()
-            .map(|&i| ((b'A' + i as u8) as char).to_string())
-            .collect::<Vec<_>>()
-            .join(" -> ");
+impl Weight for Real {
+    fn zero() -> Self {
+        return Real(0.0);
     }
 
-    pub fn from_adjacency_matrix(adjacency_matrix: Vec<Vec<usize>>) -> Self {
-        let mut graph = Graph { nodes: vec![] };
+    fn plus(self, other: Self) -> Self {
+        return Real(self.0 + other.0);
+    }
+}
 
-        let size = adjacency_matrix.len();
+// a node's index in Graph::nodes, kept apart from edge indices, lanes and costs
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct NodeId(pub usize);
 
-        for i in 0..size {
-            let mut node: Node = vec![];
+impl NodeId {
+    pub fn index(self) -> usize {
+        return self.0;
+    }
+}
 
-            for j in 0..size {
-                if adjacency_matrix[i][j] != 0 {
-                    node.push(Edge {
-                        node: j,
-                        cost: adjacency_matrix[i][j],
-                    });
-                }
+impl From<usize> for NodeId {
+    fn from(index: usize) -> Self {
+        return NodeId(index);
+    }
+}
+
+impl From<NodeId> for usize {
+    fn from(id: NodeId) -> Self {
+        return id.0;
+    }
+}
+
+// the letter the app calls the node by
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", Graph::node_label(self.0));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "W: Deserialize<'de>"))]
+pub struct Edge<W = Cost> {
+    pub node: NodeId,
+    pub cost: W,
+    // how much can flow along the edge; without one, max flow uses the cost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<W>,
+    // a name shown along the edge besides its cost, e.g. a road number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl Edge {
+    pub fn flow_capacity(&self) -> Cost {
+        return self.capacity.unwrap_or(self.cost).max(0);
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct State<W = Cost> {
+    position: NodeId,
+    cost: W,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct AlgoStats {
+    pub settled: usize,
+    pub relaxations: usize,
+    pub heap_ops: usize,
+    pub max_frontier: usize,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceEvent<W = Cost> {
+    Push { node: NodeId, dist: W },
+    Pop { node: NodeId, dist: W, stale: bool },
+    Relax { from: NodeId, to: NodeId, cost: W },
+    DistUpdate { node: NodeId, dist: W, via: NodeId },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TimedEvent {
+    pub t_ns: u64,
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub events: Vec<TimedEvent>,
+    pub result: Option<(Cost, Vec<NodeId>)>,
+    started: Instant,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TableRow {
+    pub settled: NodeId,
+    pub dist: Vec<Option<Cost>>,
+}
+
+// one edge as kruskal's algorithm considered it, in the order it did
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct KruskalStep {
+    pub a: NodeId,
+    pub b: NodeId,
+    pub cost: Cost,
+    pub accepted: bool,
+}
+
+// one move of wilson's algorithm, in the order it made them
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum WilsonStep {
+    // the walk takes an edge
+    Hop(NodeId, NodeId),
+    // the walk came back to a node it had passed; the loop, from that node round to it again,
+    // is erased
+    Erase(Vec<NodeId>),
+    // the loop-erased walk reached the tree and joins it, from its start to where it met the tree
+    Join(Vec<NodeId>),
+}
+
+// distances and predecessors from one start; None where a node is unreachable
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ShortestPaths {
+    pub dist: Vec<Option<Cost>>,
+    pub prev: Vec<Option<NodeId>>,
+}
+
+// landmark nodes with every node's distance from and to each, for A* lower bounds (ALT); None
+// where there is no path
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Landmarks {
+    pub nodes: Vec<NodeId>,
+    from: Vec<Vec<Option<Cost>>>,
+    to: Vec<Vec<Option<Cost>>>,
+}
+
+impl Landmarks {
+    // by the triangle inequality through each landmark L, d(v, goal) is at least
+    // d(L, goal) - d(L, v) and d(v, L) - d(goal, L); the best of these, or 0
+    pub fn lower_bound(&self, v: NodeId, goal: NodeId) -> Cost {
+        let (v, goal) = (v.index(), goal.index());
+        let mut best = 0;
+        for (from, to) in self.from.iter().zip(&self.to) {
+            if let (Some(lv), Some(lg)) = (from[v], from[goal]) {
+                best = best.max(lg - lv);
             }
+            if let (Some(vl), Some(gl)) = (to[v], to[goal]) {
+                best = best.max(vl - gl);
+            }
+        }
+        return best;
+    }
+}
 
-            graph.nodes.push(node);
+// a D* Lite plan toward a fixed goal, searched backwards from it so the start can move along the
+// route and edges can change under it; a repair only searches the nodes whose distance to the
+// goal the changes touch. there is no heuristic, so no key modifier either. distances are
+// Cost::MAX where there is no path; weights must not be negative, and turn bans are ignored
+#[derive(Debug, Clone)]
+pub struct DStarLite {
+    pub start: NodeId,
+    pub goal: NodeId,
+    g: Vec<Cost>,
+    rhs: Vec<Cost>,
+    open: BinaryHeap<Reverse<(Cost, usize)>>,
+    // each queued node's key; heap entries with any other are stale
+    queued: Vec<Option<Cost>>,
+    // nodes expanded by the last search or repair
+    pub expanded: usize,
+    // the graph's edges turned round, for the predecessors a settled node updates; rebuilt by
+    // each repair
+    reversed: Graph,
+}
+
+impl DStarLite {
+    // the route from the start as the plan stands, taking the cheapest next hop each time
+    pub fn path(&self, graph: &Graph) -> Option<(Cost, Vec<NodeId>)> {
+        if self.g[self.start.index()] == Cost::MAX {
+            return None;
+        }
+        let mut path = vec![self.start];
+        while path[path.len() - 1] != self.goal && path.len() <= graph.nodes.len() {
+            let at = path[path.len() - 1];
+            let next = graph
+                .edges_from(at)
+                .iter()
+                .min_by_key(|e| (self.g[e.node.index()].saturating_add(e.cost), e.node))?;
+            path.push(next.node);
+        }
+        return (path[path.len() - 1] == self.goal).then(|| (self.g[self.start.index()], path));
+    }
+
+    // the start moved, usually one hop along the route
+    pub fn move_start(&mut self, graph: &Graph, start: NodeId) {
+        self.start = start;
+        self.search(graph);
+    }
+
+    // `graph` already has the changes: each (from, to) edge in `changed` was reweighted, added
+    // or removed
+    pub fn repair(&mut self, graph: &Graph, changed: &[(NodeId, NodeId)]) {
+        self.reversed = graph.reverse();
+        for &(from, _) in changed {
+            self.update(graph, from.index());
         }
+        self.search(graph);
+    }
 
-        return graph;
+    fn update(&mut self, graph: &Graph, v: usize) {
+        if v != self.goal.index() {
+            self.rhs[v] = graph.nodes[v]
+                .iter()
+                .map(|e| self.g[e.node.index()].saturating_add(e.cost))
+                .min()
+                .unwrap_or(Cost::MAX);
+        }
+        self.queued[v] = None;
+        if self.g[v] != self.rhs[v] {
+            let key = self.g[v].min(self.rhs[v]);
+            self.queued[v] = Some(key);
+            self.open.push(Reverse((key, v)));
+        }
     }
 
-    fn reconstruct_path(
-        possible_path: Vec<Option<usize>>,
-        start: usize,
-        goal: usize,
-    ) -> Option<Vec<usize>> {
-        let mut path = Vec::new();
-        let mut cur = goal;
-        path.push(cur);
-        while cur != start {
-            cur = possible_path[cur]?;
-            path.push(cur);
+    // expands inconsistent nodes nearest the goal first, until the start's distance is settled
+    fn search(&mut self, graph: &Graph) {
+        // taken out while the loop updates the plan, and put back after
+        let reversed = std::mem::take(&mut self.reversed);
+        self.expanded = 0;
+        while let Some(&Reverse((key, v))) = self.open.peek() {
+            let start = self.start.index();
+            if key >= self.g[start].min(self.rhs[start]) && self.g[start] == self.rhs[start] {
+                break;
+            }
+            self.open.pop();
+            if self.queued[v] != Some(key) {
+                continue;
+            }
+            self.queued[v] = None;
+            self.expanded += 1;
+            if self.g[v] > self.rhs[v] {
+                self.g[v] = self.rhs[v];
+            } else {
+                self.g[v] = Cost::MAX;
+                self.update(graph, v);
+            }
+            for edge in &reversed.nodes[v] {
+                self.update(graph, edge.node.index());
+            }
+        }
+        self.reversed = reversed;
+    }
+}
+
+// edmonds-karp's augmenting paths in the order it found them, each with the amount it pushed,
+// and the saturated edges that separate the source's side from the sink's at the end, along with
+// any capped nodes that are full. a run stopped by its path cap is `capped`, and has no cut
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MaxFlow {
+    pub value: Cost,
+    pub augmenting: Vec<(Cost, Vec<NodeId>)>,
+    pub cut: Vec<(NodeId, NodeId)>,
+    pub cut_nodes: Vec<NodeId>,
+    pub capped: bool,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<NodeId>,
+    pub removed_nodes: Vec<NodeId>,
+    pub added: Vec<(NodeId, NodeId, Cost)>,
+    pub removed: Vec<(NodeId, NodeId, Cost)>,
+    pub changed: Vec<(NodeId, NodeId, Cost, Cost)>,
+}
+
+// why a graph has no euler trail: the edges are in more than one piece, or too many nodes have
+// an odd degree (undirected) or more edges out than in or the other way round (directed)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NoEuler {
+    NoEdges,
+    Disconnected,
+    OddDegrees(Vec<NodeId>),
+    Unbalanced(Vec<NodeId>),
+}
+
+// something wrong with a graph that the editor never makes itself, as found by Graph::validate.
+// exact copies of an edge are only a warning, since a fresh lane starts as one
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Defect {
+    DanglingEdge { from: NodeId, to: NodeId },
+    SelfLoop(NodeId),
+    ZeroWeight { from: NodeId, to: NodeId },
+    NegativeCapacity { from: NodeId, to: NodeId },
+    DuplicateEdge { from: NodeId, to: NodeId },
+    // the two directions of a lane disagree on their cost, capacity or label, which the one edge
+    // drawn for it can't show
+    Asymmetric { a: NodeId, b: NodeId, lane: usize },
+    // a turn ban through a node or along an edge that isn't there
+    BadTurnBan(NodeId, NodeId, NodeId),
+    DuplicateTurnBan(NodeId, NodeId, NodeId),
+}
+
+impl Defect {
+    pub fn is_error(&self) -> bool {
+        return !matches!(self, Defect::DuplicateEdge { .. });
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Validation {
+    pub defects: Vec<Defect>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        return !self.defects.iter().any(Defect::is_error);
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Defect> {
+        return self.defects.iter().filter(|d| d.is_error());
+    }
+
+    // the first few defects on one line, errors first, e.g. for the HUD
+    pub fn summary(&self, shown: usize) -> String {
+        let mut defects: Vec<&Defect> = self.defects.iter().collect();
+        defects.sort_by_key(|d| !d.is_error());
+        let mut parts: Vec<String> = defects.iter().take(shown).map(|d| d.to_string()).collect();
+        if defects.len() > shown {
+            parts.push(format!("{} more", defects.len() - shown));
+        }
+        return parts.join("; ");
+    }
+}
+
+// which of several equally cheap routes a search returns: whichever it happened to find first,
+// the one with the fewest edges (then the lowest labels), or the lowest labels compared node by
+// node
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum TieBreak {
+    #[default]
+    Search,
+    FewestHops,
+    Labels,
+}
+
+// a weight matrix as from_adjacency_matrix takes it, printed as a table with node letters along
+// both sides and `-` where there is no edge
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AdjacencyMatrix(pub Vec<Vec<Cost>>);
+
+pub type Node<W = Cost> = Vec<Edge<W>>;
+
+// saved as `adjacency` and `forbidden`, the names graph.json has always used. the weights are
+// Cost unless said otherwise; the searches below that are written for any Weight come first, the
+// rest need Cost's signs and arithmetic
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Graph<W = Cost> {
+    #[serde(rename = "adjacency")]
+    pub nodes: Vec<Node<W>>,
+    // turn bans (from, via, to): after arriving at `via` from `from`, the search may not go on to `to`
+    #[serde(default)]
+    pub forbidden: Vec<(NodeId, NodeId, NodeId)>,
+    // a name shown in place of a node's letter, and key = value details, e.g. `pop = 5000`
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        serialize_with = "save_pairs",
+        deserialize_with = "load_pairs"
+    )]
+    pub node_labels: BTreeMap<NodeId, String>,
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        serialize_with = "save_pairs",
+        deserialize_with = "load_pairs"
+    )]
+    pub node_metadata: BTreeMap<NodeId, BTreeMap<String, String>>,
+}
+
+// per-node maps are saved as [id, value] pairs: json keys are strings, and the graph flattened
+// into graph.json can't read them back as numbers
+fn save_pairs<V: Serialize, S: Serializer>(
+    map: &BTreeMap<NodeId, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    return serializer.collect_seq(map);
+}
+
+fn load_pairs<'de, V: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<NodeId, V>, D::Error> {
+    let pairs: Vec<(NodeId, V)> = Vec::deserialize(deserializer)?;
+    return Ok(pairs.into_iter().collect());
+}
+
+struct Vf2<'a> {
+    a: &'a [Vec<Option<Cost>>],
+    b: &'a [Vec<Option<Cost>>],
+    weighted: bool,
+    order: Vec<usize>,
+    map: Vec<Option<usize>>,
+    used: Vec<bool>,
+}
+
+impl Vf2<'_> {
+    fn edge_matches(&self, x: Option<Cost>, y: Option<Cost>) -> bool {
+        return match (x, y) {
+            (Some(x), Some(y)) => !self.weighted || x == y,
+            (None, None) => true,
+            _ => false,
+        };
+    }
+
+    fn feasible(&self, u: usize, v: usize) -> bool {
+        if !self.edge_matches(self.a[u][u], self.b[v][v]) {
+            return false;
+        }
+        for (w, mapped) in self.map.iter().enumerate() {
+            let Some(x) = *mapped else {
+                continue;
+            };
+            if !self.edge_matches(self.a[u][w], self.b[v][x])
+                || !self.edge_matches(self.a[w][u], self.b[x][v])
+            {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    fn search(&mut self, depth: usize) -> bool {
+        if depth == self.order.len() {
+            return true;
+        }
+        let u = self.order[depth];
+        for v in 0..self.b.len() {
+            if self.used[v] || !self.feasible(u, v) {
+                continue;
+            }
+            self.map[u] = Some(v);
+            self.used[v] = true;
+            if self.search(depth + 1) {
+                return true;
+            }
+            self.map[u] = None;
+            self.used[v] = false;
+        }
+        return false;
+    }
+}
+
+impl Trace {
+    fn new() -> Self {
+        return Trace {
+            events: Vec::new(),
+            result: None,
+            started: Instant::now(),
+        };
+    }
+
+    fn record(&mut self, event: TraceEvent) {
+        let t_ns = self.started.elapsed().as_nanos() as u64;
+        self.events.push(TimedEvent { t_ns, event });
+    }
+}
+
+impl ShortestPaths {
+    pub fn path_to(&self, start: NodeId, goal: NodeId) -> Option<(Cost, Vec<NodeId>)> {
+        let cost = self.dist[goal.index()]?;
+        let path = reconstruct_path(self.prev.clone(), start, goal)?;
+        return Some((cost, path));
+    }
+
+    // the (predecessor, node) edge into every reached node but the start
+    pub fn tree_edges(&self) -> Vec<(NodeId, NodeId)> {
+        return self
+            .prev
+            .iter()
+            .enumerate()
+            .filter_map(|(node, prev)| Some(((*prev)?, NodeId(node))))
+            .collect();
+    }
+}
+
+impl AlgoStats {
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a TraceEvent>) -> Self {
+        let mut stats = AlgoStats::default();
+        let mut frontier = 0usize;
+        for event in events {
+            match event {
+                TraceEvent::Push { .. } => {
+                    stats.heap_ops += 1;
+                    frontier += 1;
+                    stats.max_frontier = stats.max_frontier.max(frontier);
+                }
+                TraceEvent::Pop { stale, .. } => {
+                    stats.heap_ops += 1;
+                    frontier -= 1;
+                    if !stale {
+                        stats.settled += 1;
+                    }
+                }
+                TraceEvent::Relax { .. } => stats.relaxations += 1,
+                TraceEvent::DistUpdate { .. } => {}
+            }
         }
+        return stats;
+    }
+}
+
+impl<W: Ord> Ord for State<W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        return other
+            .cost
+            .cmp(&self.cost)
+            .then_with(|| self.position.cmp(&other.position));
+    }
+}
+
+impl<W: Ord> PartialOrd for State<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
 
-        path.reverse();
+impl<W: Weight> Graph<W> {
+    pub fn has_edge(&self, from: NodeId, to: NodeId) -> bool {
+        return self.nodes[from.index()].iter().any(|e| e.node == to);
+    }
 
-        return Some(path);
+    pub fn add_lane(&mut self, a: NodeId, b: NodeId, cost: W, two_way: bool) {
+        let edge = |node: NodeId| Edge {
+            node,
+            cost,
+            capacity: None,
+            label: None,
+        };
+        self.nodes[a.index()].push(edge(b));
+        if two_way {
+            self.nodes[b.index()].push(edge(a));
+        }
     }
 
-    pub fn shortest_path(&self, start: usize, goal: usize) -> Option<(usize, Vec<usize>)> {
+    // dijkstra from start until the goal is settled, reporting every push, pop and relaxation to
+    // `record`. it trusts every weight to be at least zero and ignores turn bans; for Cost,
+    // shortest_path_trace picks the search that handles those
+    pub fn dijkstra_events(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        mut record: impl FnMut(TraceEvent<W>),
+    ) -> Option<(W, Vec<NodeId>)> {
         let n = self.nodes.len();
-        let mut dist = vec![usize::MAX; n];
+        let mut dist: Vec<Option<W>> = vec![None; n];
         let mut visited = BinaryHeap::new();
-        let mut prev: Vec<Option<usize>> = vec![None; n];
+        let mut prev: Vec<Option<NodeId>> = vec![None; n];
 
-        dist[start] = 0; // set the starting node to have 0 distance
+        dist[start.index()] = Some(W::zero()); // set the starting node to have 0 distance
         visited.push(State {
             position: start,
-            cost: 0,
+            cost: W::zero(),
+        });
+        record(TraceEvent::Push {
+            node: start,
+            dist: W::zero(),
         });
 
         while let Some(State { cost, position }) = visited.pop() {
-            if position == goal {
-                return Some((cost, Graph::reconstruct_path(prev, start, goal).unwrap()));
+            let stale = dist[position.index()].is_some_and(|d| cost > d);
+            record(TraceEvent::Pop {
+                node: position,
+                dist: cost,
+                stale,
+            });
+            if stale {
+                continue;
             }
 
-            if cost > dist[position] {
-                continue;
+            if position == goal {
+                let path = reconstruct_path(prev, start, goal).unwrap();
+                return Some((cost, path));
             }
 
-            for edge in &self.nodes[position] {
+            for edge in &self.nodes[position.index()] {
                 let next = State {
-                    cost: cost + edge.cost,
+                    cost: cost.plus(edge.cost),
                     position: edge.node,
                 };
+                record(TraceEvent::Relax {
+                    from: position,
+                    to: edge.node,
+                    cost: edge.cost,
+                });
 
-                if next.cost < dist[next.position] {
+                if dist[next.position.index()].is_none_or(|d| next.cost < d) {
                     visited.push(next);
-                    dist[next.position] = next.cost;
-                    prev[edge.node] = Some(position);
+                    dist[next.position.index()] = Some(next.cost);
+                    prev[edge.node.index()] = Some(position);
+                    record(TraceEvent::DistUpdate {
+                        node: edge.node,
+                        dist: next.cost,
+                        via: position,
+                    });
+                    record(TraceEvent::Push {
+                        node: edge.node,
+                        dist: next.cost,
+                    });
                 }
             }
         }
         return None;
     }
+
+    pub fn dijkstra(&self, start: NodeId, goal: NodeId) -> Option<(W, Vec<NodeId>)> {
+        return self.dijkstra_events(start, goal, |_| {});
+    }
 }
 
-impl fmt::Display for Edge {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let out = format!(
-            "Edge {{ node: {}, cost {} }}",
-            (b'A' + self.node as u8) as char,
-            self.cost
-        );
-        return write!(f, "{}", out);
+fn reconstruct_path(
+    possible_path: Vec<Option<NodeId>>,
+    start: NodeId,
+    goal: NodeId,
+) -> Option<Vec<NodeId>> {
+    let mut path = Vec::new();
+    let mut cur = goal;
+    path.push(cur);
+    while cur != start {
+        cur = possible_path[cur.index()]?;
+        path.push(cur);
     }
+
+    path.reverse();
+
+    return Some(path);
 }
 
-impl fmt::Display for Graph {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut out = String::new();
+fn ids(indices: Vec<usize>) -> Vec<NodeId> {
+    return indices.into_iter().map(NodeId).collect();
+}
 
-        for (i, node) in self.nodes.iter().enumerate() {
-            let label = (b'A' + i as u8) as char;
-            out.push_str(&format!("{}: [ ", label));
-            for edge in node {
-                out.push_str(&format!("{}, ", edge));
+impl Graph {
+    // spreadsheet column style: A to Z, then AA, AB and on, so every node has a label
+    pub fn node_label(i: usize) -> String {
+        let mut label = Vec::new();
+        let mut rest = i + 1;
+        while rest > 0 {
+            rest -= 1;
+            label.push(b'A' + (rest % 26) as u8);
+            rest /= 26;
+        }
+        label.reverse();
+        return String::from_utf8(label).unwrap();
+    }
+
+    // the inverse of node_label, in either case
+    pub fn parse_label(label: &str) -> Option<NodeId> {
+        if label.is_empty() || !label.bytes().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let mut i: usize = 0;
+        for c in label.bytes() {
+            let digit = (c.to_ascii_uppercase() - b'A') as usize + 1;
+            i = i.checked_mul(26)?.checked_add(digit)?;
+        }
+        return Some(NodeId(i - 1));
+    }
+
+    pub fn fmt_path(path: &[NodeId]) -> String {
+        return path
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+    }
+
+    pub fn from_adjacency_matrix(adjacency_matrix: Vec<Vec<Cost>>) -> Self {
+        let mut graph = Graph::default();
+
+        for row in &adjacency_matrix {
+            let mut node: Node = vec![];
+
+            for (j, &cost) in row.iter().enumerate() {
+                if cost != 0 {
+                    node.push(Edge {
+                        node: NodeId(j),
+                        cost,
+                        capacity: None,
+                        label: None,
+                    });
+                }
             }
-            out.push_str("]\n");
+
+            graph.nodes.push(node);
         }
 
-        write!(f, "{}", out)
+        return graph;
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // the ids of every node, in order
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> + use<> {
+        return (0..self.nodes.len()).map(NodeId);
+    }
 
-    #[test]
-    fn test_dijkstra() {
-        let graph = Graph {
-            nodes: vec![
-                vec![
-                    Edge { node: 1, cost: 6 },
-                    Edge { node: 2, cost: 4 },
-                    Edge { node: 3, cost: 1 },
-                ],
-                vec![Edge { node: 0, cost: 6 }, Edge { node: 2, cost: 3 }],
-                vec![
-                    Edge { node: 0, cost: 4 },
-                    Edge { node: 1, cost: 3 },
-                    Edge { node: 3, cost: 1 },
-                ],
-                vec![Edge { node: 0, cost: 1 }, Edge { node: 2, cost: 1 }],
-            ],
-        };
+    // every edge with the node it leaves, in adjacency order
+    pub fn arcs(&self) -> impl Iterator<Item = (NodeId, &Edge)> {
+        return self
+            .nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(from, edges)| edges.iter().map(move |e| (NodeId(from), e)));
+    }
+
+    // every edge's ends and weight, in adjacency order; views keep one to notice when the graph
+    // they were worked out for has changed
+    pub fn arc_costs(&self) -> Vec<(NodeId, NodeId, Cost)> {
+        return self
+            .arcs()
+            .map(|(from, e)| (from, e.node, e.cost))
+            .collect();
+    }
+
+    pub fn edges_from(&self, id: NodeId) -> &[Edge] {
+        return &self.nodes[id.index()];
+    }
+
+    // the inverse of from_adjacency_matrix: row `from`, column `to` holds the weight of the edge
+    // between them, 0 where there is none. parallel edges keep only their cheapest, and an edge
+    // weighing 0 reads as missing, so graphs with either don't come back the same
+    pub fn to_adjacency_matrix(&self) -> Vec<Vec<Cost>> {
+        let mut matrix = vec![vec![0; self.nodes.len()]; self.nodes.len()];
+        for (from, edge) in self.arcs() {
+            let cell = &mut matrix[from.index()][edge.node.index()];
+            if *cell == 0 || edge.cost < *cell {
+                *cell = edge.cost;
+            }
+        }
+        return matrix;
+    }
+
+    // a stable text form for diffing: every node, then every arc sorted by its ends, weight,
+    // capacity and name, then the banned turns. graphs built in a different order, or with
+    // parallel edges in different lanes, print the same
+    pub fn canonical_text(&self) -> String {
+        let mut out = format!("nodes {}\n", self.nodes.len());
+        for id in self.ids() {
+            write!(out, "{}", id).unwrap();
+            if let Some(label) = self.node_labels.get(&id) {
+                write!(out, " {:?}", label).unwrap();
+            }
+            for (key, value) in self.node_metadata.get(&id).into_iter().flatten() {
+                write!(out, " {}={:?}", key, value).unwrap();
+            }
+            out.push('\n');
+        }
+        let mut arcs: Vec<(NodeId, NodeId, Cost, Option<Cost>, Option<&str>)> = self
+            .arcs()
+            .map(|(from, e)| (from, e.node, e.cost, e.capacity, e.label.as_deref()))
+            .collect();
+        arcs.sort();
+        writeln!(out, "edges {}", arcs.len()).unwrap();
+        for (from, to, cost, capacity, label) in arcs {
+            write!(out, "{} -> {} {}", from, to, cost).unwrap();
+            if let Some(capacity) = capacity {
+                write!(out, " cap {}", capacity).unwrap();
+            }
+            if let Some(label) = label {
+                write!(out, " {:?}", label).unwrap();
+            }
+            out.push('\n');
+        }
+        let mut forbidden = self.forbidden.clone();
+        forbidden.sort();
+        forbidden.dedup();
+        writeln!(out, "forbidden {}", forbidden.len()).unwrap();
+        for (a, b, c) in forbidden {
+            writeln!(out, "{}", Graph::fmt_path(&[a, b, c])).unwrap();
+        }
+        return out;
+    }
+
+    // the edge drawn between a and b, whichever way it points; of parallel edges the cheapest,
+    // the one a shortest path takes
+    pub fn edge_between(&self, a: NodeId, b: NodeId) -> Option<&Edge> {
+        let cheapest = |from: NodeId, to: NodeId| {
+            self.nodes[from.index()]
+                .iter()
+                .filter(|e| e.node == to)
+                .min_by_key(|e| e.cost)
+        };
+        return cheapest(a, b).or_else(|| cheapest(b, a));
+    }
+
+    fn count_edges(&self, from: NodeId, to: NodeId) -> usize {
+        return self.nodes[from.index()]
+            .iter()
+            .filter(|e| e.node == to)
+            .count();
+    }
+
+    // parallel edges between two nodes sit in lanes: lane k of a - b is the k-th a -> b edge with
+    // the k-th b -> a one, so a pair has as many lanes as its busier direction has edges
+    pub fn lane_count(&self, a: NodeId, b: NodeId) -> usize {
+        return self.count_edges(a, b).max(self.count_edges(b, a));
+    }
+
+    fn nth_edge(&self, from: NodeId, to: NodeId, lane: usize) -> Option<&Edge> {
+        return self.nodes[from.index()]
+            .iter()
+            .filter(|e| e.node == to)
+            .nth(lane);
+    }
+
+    // the edge drawn in a lane, a -> b if there is one
+    pub fn lane(&self, a: NodeId, b: NodeId, lane: usize) -> Option<&Edge> {
+        return self
+            .nth_edge(a, b, lane)
+            .or_else(|| self.nth_edge(b, a, lane));
+    }
+
+    pub fn has_lane(&self, from: NodeId, to: NodeId, lane: usize) -> bool {
+        return self.count_edges(from, to) > lane;
+    }
+
+    pub fn lane_mut(&mut self, from: NodeId, to: NodeId, lane: usize) -> Option<&mut Edge> {
+        return self.nodes[from.index()]
+            .iter_mut()
+            .filter(|e| e.node == to)
+            .nth(lane);
+    }
+
+    // the name on a lane, from whichever of its edges has one
+    pub fn lane_label(&self, a: NodeId, b: NodeId, lane: usize) -> Option<&str> {
+        return [self.nth_edge(a, b, lane), self.nth_edge(b, a, lane)]
+            .into_iter()
+            .flatten()
+            .find_map(|e| e.label.as_deref());
+    }
+
+    // the node's label, or its letter when it has none
+    pub fn node_name(&self, id: NodeId) -> String {
+        return match self.node_labels.get(&id) {
+            Some(label) => label.clone(),
+            None => id.to_string(),
+        };
+    }
+
+    // the node whose label this is, ignoring case
+    pub fn find_node_label(&self, label: &str) -> Option<NodeId> {
+        return self
+            .node_labels
+            .iter()
+            .find(|(_, l)| l.eq_ignore_ascii_case(label))
+            .map(|(&id, _)| id);
+    }
+
+    // e.g. "Home; pop = 5000, zone = north", or None for a node without a label or details
+    pub fn describe_node(&self, id: NodeId) -> Option<String> {
+        let details: Vec<String> = self
+            .node_metadata
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| format!("{} = {}", key, value))
+            .collect();
+        return match (self.node_labels.get(&id), details.is_empty()) {
+            (None, true) => None,
+            (Some(label), true) => Some(label.clone()),
+            (None, false) => Some(details.join(", ")),
+            (Some(label), false) => Some(format!("{}; {}", label, details.join(", "))),
+        };
+    }
+
+    // names both edges of a lane, or with None clears them; false if there is no such lane
+    pub fn set_lane_label(
+        &mut self,
+        a: NodeId,
+        b: NodeId,
+        lane: usize,
+        label: Option<String>,
+    ) -> bool {
+        let mut found = false;
+        for (from, to) in [(a, b), (b, a)] {
+            if let Some(edge) = self.lane_mut(from, to, lane) {
+                edge.label = label.clone();
+                found = true;
+            }
+        }
+        return found;
+    }
+
+    // every lane to draw as (a, b, lane), pointing the way its edge was found: the lower end
+    // first for two-way ones
+    pub fn lanes(&self) -> Vec<(NodeId, NodeId, usize)> {
+        let mut lanes = Vec::new();
+        for a in self.ids() {
+            let mut seen: HashMap<NodeId, usize> = HashMap::new();
+            for edge in self.edges_from(a) {
+                let b = edge.node;
+                let lane = seen.entry(b).or_default();
+                if a < b || *lane >= self.count_edges(b, a) {
+                    lanes.push((a, b, *lane));
+                }
+                *lane += 1;
+            }
+        }
+        return lanes;
+    }
+
+    // both directions of one lane, with the turn bans through them once no edge is left; false if
+    // the lane was empty. the lanes after it move down one
+    pub fn remove_lane(&mut self, a: NodeId, b: NodeId, lane: usize) -> bool {
+        let mut removed = false;
+        for (from, to) in [(a, b), (b, a)] {
+            let index = self.nodes[from.index()]
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.node == to)
+                .nth(lane)
+                .map(|(i, _)| i);
+            if let Some(index) = index {
+                self.nodes[from.index()].remove(index);
+                removed = true;
+            }
+        }
+        self.drop_stale_bans();
+        return removed;
+    }
+
+    // a two-way a - b keeps only a -> b, and the turn bans over b -> a go; a one-way edge gets its
+    // reverse back at the same cost. Some(true) when the edge is now one-way, None without an edge
+    // or with parallel lanes, which pair up by order and so cannot lose one direction of just one
+    pub fn toggle_one_way(&mut self, a: NodeId, b: NodeId) -> Option<bool> {
+        if self.lane_count(a, b) > 1 {
+            return None;
+        }
+        if self.has_edge(a, b) && self.has_edge(b, a) {
+            self.nodes[b.index()].retain(|e| e.node != a);
+            self.forbidden
+                .retain(|&(p, v, q)| (p, v) != (b, a) && (v, q) != (b, a));
+            return Some(true);
+        }
+        let edge = self.edge_between(a, b)?.clone();
+        let from = if edge.node == b { a } else { b };
+        self.nodes[edge.node.index()].push(Edge {
+            node: from,
+            cost: edge.cost,
+            capacity: edge.capacity,
+            label: edge.label.clone(),
+        });
+        return Some(false);
+    }
+
+    // turns from -> to into to -> from; false when there is no such edge or it already goes both ways
+    pub fn reverse_edge(&mut self, from: NodeId, to: NodeId) -> bool {
+        if self.has_edge(to, from) {
+            return false;
+        }
+        let Some(pos) = self.nodes[from.index()].iter().position(|e| e.node == to) else {
+            return false;
+        };
+        let edge = self.nodes[from.index()].remove(pos);
+        self.nodes[to.index()].push(Edge {
+            node: from,
+            cost: edge.cost,
+            capacity: edge.capacity,
+            label: edge.label,
+        });
+        return true;
+    }
+
+    // replaces a - b with a - mid - b for a new node mid, splitting each direction's cost so the
+    // part next to a gets `t` of it; turn bans through the old edge move onto the new halves.
+    // None when there is no such edge or a cost is too small to split into two non-zero parts
+    pub fn split_edge(&mut self, a: NodeId, b: NodeId, t: f32) -> Option<NodeId> {
+        let mut halves = Vec::new();
+        for (from, to, share) in [(a, b, t), (b, a, 1.0 - t)] {
+            let Some(edge) = self.edges_from(from).iter().find(|e| e.node == to) else {
+                continue;
+            };
+            if edge.cost.abs() < 2 {
+                return None;
+            }
+            // each part keeps the sign of the whole and stays non-zero
+            let first = (edge.cost as f32 * share).round() as Cost;
+            let first = if edge.cost > 0 {
+                first.clamp(1, edge.cost - 1)
+            } else {
+                first.clamp(edge.cost + 1, -1)
+            };
+            halves.push((from, to, first, edge.cost - first));
+        }
+        if halves.is_empty() {
+            return None;
+        }
+
+        let mid = NodeId(self.nodes.len());
+        self.nodes.push(Vec::new());
+        for &(from, to, first, second) in &halves {
+            // both halves carry the whole edge's capacity and label
+            let edge = self.nodes[from.index()]
+                .iter_mut()
+                .find(|e| e.node == to)
+                .unwrap();
+            let (capacity, label) = (edge.capacity, edge.label.clone());
+            *edge = Edge {
+                node: mid,
+                cost: first,
+                capacity,
+                label: label.clone(),
+            };
+            self.nodes[mid.index()].push(Edge {
+                node: to,
+                cost: second,
+                capacity,
+                label,
+            });
+        }
+        let split =
+            |p: NodeId, q: NodeId| halves.iter().any(|&(from, to, _, _)| (from, to) == (p, q));
+        for ban in &mut self.forbidden {
+            let (p, v, q) = *ban;
+            *ban = (
+                if split(p, v) { mid } else { p },
+                v,
+                if split(v, q) { mid } else { q },
+            );
+        }
+
+        return Some(mid);
+    }
+
+    // drops every edge `doomed` picks, and the turn bans over them
+    pub fn remove_edges(&mut self, doomed: impl Fn(NodeId, &Edge) -> bool) {
+        for (from, edges) in self.nodes.iter_mut().enumerate() {
+            edges.retain(|e| !doomed(NodeId(from), e));
+        }
+        self.drop_stale_bans();
+    }
+
+    // turn bans over an edge that is gone
+    fn drop_stale_bans(&mut self) {
+        let nodes = &self.nodes;
+        let has_edge = |from: NodeId, to: NodeId| nodes[from.index()].iter().any(|e| e.node == to);
+        self.forbidden
+            .retain(|&(p, v, q)| has_edge(p, v) && has_edge(v, q));
+    }
+
+    // both directions of a - b, with the turn bans through them; false if there was no edge
+    pub fn remove_edge(&mut self, a: NodeId, b: NodeId) -> bool {
+        if !self.has_edge(a, b) && !self.has_edge(b, a) {
+            return false;
+        }
+        self.remove_edges(|from, e| (from, e.node) == (a, b) || (from, e.node) == (b, a));
+        return true;
+    }
+
+    // connected pairs, whichever directions they have, and each extra lane of them; one visual each
+    pub fn connection_count(&self) -> usize {
+        return self.lanes().len();
+    }
+
+    // connected pairs in up to `buckets` equal weight ranges from the lightest to the heaviest, each
+    // with its inclusive bounds; a two-way pair counts once, at its lower end's weight
+    pub fn weight_buckets(&self, buckets: usize) -> Vec<(Cost, Cost, Vec<(NodeId, NodeId)>)> {
+        let pairs: Vec<(NodeId, NodeId, Cost)> = self
+            .arcs()
+            .map(|(a, e)| (a, e.node, e.cost))
+            .filter(|&(a, b, _)| a < b || !self.has_edge(b, a))
+            .collect();
+        let (Some(min), Some(max)) = (
+            pairs.iter().map(|p| p.2).min(),
+            pairs.iter().map(|p| p.2).max(),
+        ) else {
+            return Vec::new();
+        };
+        // wide enough that no bucket is narrower than one weight
+        let span = max as i128 - min as i128 + 1;
+        let k = (buckets as i128).clamp(1, span);
+        let mut out: Vec<(Cost, Cost, Vec<(NodeId, NodeId)>)> = (0..k)
+            .map(|i| {
+                let low = min as i128 + span * i / k;
+                let high = min as i128 + span * (i + 1) / k - 1;
+                (low as Cost, high as Cost, Vec::new())
+            })
+            .collect();
+        for (a, b, cost) in pairs {
+            let i = (cost as i128 - min as i128) * k / span;
+            out[i as usize].2.push((a.min(b), a.max(b)));
+        }
+        return out;
+    }
+
+    // keeps the nodes marked in `keep`, renumbered in their old order, and the edges and turn
+    // bans among them; returns each old node's new id
+    pub fn retain_nodes(&mut self, keep: &[bool]) -> Vec<Option<NodeId>> {
+        let mut next = 0;
+        let remap: Vec<Option<NodeId>> = keep
+            .iter()
+            .map(|&k| {
+                k.then(|| {
+                    next += 1;
+                    NodeId(next - 1)
+                })
+            })
+            .collect();
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .zip(keep)
+            .filter(|&(_, &k)| k)
+            .map(|(edges, _)| {
+                edges
+                    .into_iter()
+                    .filter_map(|e| {
+                        Some(Edge {
+                            node: remap[e.node.index()]?,
+                            cost: e.cost,
+                            capacity: e.capacity,
+                            label: e.label,
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        self.forbidden = self
+            .forbidden
+            .iter()
+            .filter_map(|&(p, v, q)| {
+                Some((remap[p.index()]?, remap[v.index()]?, remap[q.index()]?))
+            })
+            .collect();
+        self.node_labels = std::mem::take(&mut self.node_labels)
+            .into_iter()
+            .filter_map(|(id, label)| Some((remap[id.index()]?, label)))
+            .collect();
+        self.node_metadata = std::mem::take(&mut self.node_metadata)
+            .into_iter()
+            .filter_map(|(id, details)| Some((remap[id.index()]?, details)))
+            .collect();
+        return remap;
+    }
+
+    // folds `gone` into `keep`: every edge of `gone` moves over to `keep`, edges between the two
+    // disappear, and where both had an edge to the same node only the cheaper one stays. turn bans
+    // follow too, minus any that would turn through a node and back on itself. returns each old
+    // node's new id, with `gone` sharing the new id of `keep`. the merged node has the label of
+    // whichever came first, and the details of both, the first's winning on a clash
+    pub fn merge_nodes(&mut self, keep: NodeId, gone: NodeId) -> Vec<Option<NodeId>> {
+        let to = |n: NodeId| if n == gone { keep } else { n };
+        if let Some(label) = self.node_labels.remove(&gone)
+            && (gone < keep || !self.node_labels.contains_key(&keep))
+        {
+            self.node_labels.insert(keep, label);
+        }
+        if let Some(details) = self.node_metadata.remove(&gone) {
+            let merged = self.node_metadata.entry(keep).or_default();
+            for (key, value) in details {
+                if gone < keep {
+                    merged.insert(key, value);
+                } else {
+                    merged.entry(key).or_insert(value);
+                }
+            }
+        }
+        let moved = std::mem::take(&mut self.nodes[gone.index()]);
+        self.nodes[keep.index()].extend(moved);
+        for (from, edges) in self.nodes.iter_mut().enumerate() {
+            for edge in edges.iter_mut() {
+                edge.node = to(edge.node);
+            }
+            edges.retain(|e| e.node != NodeId(from));
+            // the cheapest of each neighbour's edges, where it was first in the list
+            let mut best: HashMap<NodeId, usize> = HashMap::new();
+            for (i, edge) in edges.iter().enumerate() {
+                match best.entry(edge.node) {
+                    Entry::Occupied(mut b) if edge.cost < edges[*b.get()].cost => *b.get_mut() = i,
+                    Entry::Occupied(_) => {}
+                    Entry::Vacant(b) => {
+                        b.insert(i);
+                    }
+                }
+            }
+            let mut keep_edge: HashMap<NodeId, Edge> = best
+                .into_iter()
+                .map(|(node, i)| (node, edges[i].clone()))
+                .collect();
+            let mut merged = Vec::new();
+            for edge in edges.iter() {
+                if let Some(e) = keep_edge.remove(&edge.node) {
+                    merged.push(e);
+                }
+            }
+            *edges = merged;
+        }
+        let mut bans: Vec<(NodeId, NodeId, NodeId)> = self
+            .forbidden
+            .iter()
+            .map(|&(a, v, b)| (to(a), to(v), to(b)))
+            .filter(|&(a, v, b)| a != v && v != b)
+            .collect();
+        bans.sort_unstable();
+        bans.dedup();
+        self.forbidden = bans;
+
+        let mut alive = vec![true; self.nodes.len()];
+        alive[gone.index()] = false;
+        let mut remap = self.retain_nodes(&alive);
+        remap[gone.index()] = remap[keep.index()];
+        return remap;
+    }
+
+    // true for every node with at least one edge, in either direction
+    pub fn connected_nodes(&self) -> Vec<bool> {
+        let mut connected = vec![false; self.nodes.len()];
+        for (from, edge) in self.arcs() {
+            connected[from.index()] = true;
+            connected[edge.node.index()] = true;
+        }
+        return connected;
+    }
+
+    // kahn's algorithm, taking ready nodes lowest first; when the graph is not a DAG, Err holds one
+    // of its directed cycles with the first node repeated at the end. a two-way edge is a cycle too
+    pub fn topological_sort(&self) -> Result<Vec<NodeId>, Vec<NodeId>> {
+        let n = self.nodes.len();
+        let mut indegree = vec![0; n];
+        for (_, edge) in self.arcs() {
+            indegree[edge.node.index()] += 1;
+        }
+        let mut ready: BinaryHeap<Reverse<NodeId>> = self
+            .ids()
+            .filter(|v| indegree[v.index()] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(n);
+        while let Some(Reverse(v)) = ready.pop() {
+            order.push(v);
+            for edge in self.edges_from(v) {
+                indegree[edge.node.index()] -= 1;
+                if indegree[edge.node.index()] == 0 {
+                    ready.push(Reverse(edge.node));
+                }
+            }
+        }
+        if order.len() == n {
+            return Ok(order);
+        }
+
+        // every node left has a predecessor that is left too, so walking back from one must loop
+        let mut pred: Vec<Option<NodeId>> = vec![None; n];
+        for (from, edge) in self.arcs() {
+            if indegree[from.index()] > 0 && indegree[edge.node.index()] > 0 {
+                pred[edge.node.index()].get_or_insert(from);
+            }
+        }
+        let mut seen = vec![false; n];
+        let mut v = self.ids().find(|v| indegree[v.index()] > 0).unwrap();
+        while !seen[v.index()] {
+            seen[v.index()] = true;
+            v = pred[v.index()].unwrap();
+        }
+        let mut cycle = vec![v];
+        let mut u = pred[v.index()].unwrap();
+        while u != v {
+            cycle.push(u);
+            u = pred[u.index()].unwrap();
+        }
+        cycle.push(v);
+        cycle.reverse();
+        return Err(cycle);
+    }
+
+    // any cycle, first node repeated at the end. directed cycles follow edge directions, so a
+    // two-way edge counts as one; undirected ones need three distinct nodes or more
+    pub fn find_cycle(&self, directed: bool) -> Option<Vec<NodeId>> {
+        if directed {
+            return self.topological_sort().err();
+        }
+        let n = self.nodes.len();
+        let mut neighbours: Vec<Vec<NodeId>> = vec![Vec::new(); n];
+        for (from, edge) in self.arcs().filter(|(from, e)| e.node != *from) {
+            neighbours[from.index()].push(edge.node);
+            neighbours[edge.node.index()].push(from);
+        }
+        for list in &mut neighbours {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        // an undirected depth-first search has no cross edges, so any visited neighbour other
+        // than the parent is an ancestor still on the stack
+        let mut parent: Vec<Option<NodeId>> = vec![None; n];
+        let mut visited = vec![false; n];
+        for root in self.ids() {
+            if visited[root.index()] {
+                continue;
+            }
+            visited[root.index()] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some((v, next)) = stack.last_mut() {
+                let v = *v;
+                let Some(&u) = neighbours[v.index()].get(*next) else {
+                    stack.pop();
+                    continue;
+                };
+                *next += 1;
+                if parent[v.index()] == Some(u) {
+                    continue;
+                }
+                if visited[u.index()] {
+                    let mut cycle = vec![u];
+                    let mut w = v;
+                    while w != u {
+                        cycle.push(w);
+                        w = parent[w.index()].unwrap();
+                    }
+                    cycle.push(u);
+                    return Some(cycle);
+                }
+                visited[u.index()] = true;
+                parent[u.index()] = Some(v);
+                stack.push((u, 0));
+            }
+        }
+        return None;
+    }
+
+    // a walk using every edge exactly once, as the nodes it passes, by hierholzer's algorithm.
+    // undirected, each lane between a pair of nodes is one edge whichever way it was drawn;
+    // directed, a two-way edge is two arcs, one each way. a closed circuit starts at `from` when it touches an edge,
+    // an open trail at `from` when it is one of the two ends; otherwise the lowest candidate
+    pub fn euler_path(&self, directed: bool, from: Option<NodeId>) -> Result<Vec<NodeId>, NoEuler> {
+        let n = self.nodes.len();
+        let mut arcs: Vec<(usize, usize)> = self
+            .arcs()
+            .map(|(a, e)| (a.index(), e.node.index()))
+            .filter(|(a, b)| a != b)
+            .map(|(a, b)| {
+                if directed {
+                    (a, b)
+                } else {
+                    (a.min(b), a.max(b))
+                }
+            })
+            .collect();
+        arcs.sort_unstable();
+        if !directed {
+            // the two arcs of a lane are one edge, but parallel lanes stay apart
+            arcs.dedup();
+            arcs = arcs
+                .into_iter()
+                .flat_map(|(a, b)| {
+                    let lanes = self.lane_count(NodeId(a), NodeId(b));
+                    return std::iter::repeat_n((a, b), lanes);
+                })
+                .collect();
+        }
+        if arcs.is_empty() {
+            return Err(NoEuler::NoEdges);
+        }
+
+        // out[v] - into[v] in a directed graph, the degree in an undirected one
+        let mut adjacent: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+        let mut balance = vec![0i64; n];
+        let mut sets = UnionFind::new(n);
+        for (i, &(a, b)) in arcs.iter().enumerate() {
+            adjacent[a].push((b, i));
+            sets.union(a, b);
+            if directed {
+                balance[a] += 1;
+                balance[b] -= 1;
+            } else {
+                adjacent[b].push((a, i));
+                balance[a] += 1;
+                balance[b] += 1;
+            }
+        }
+        let root = sets.find(arcs[0].0);
+        if arcs.iter().any(|&(a, _)| sets.find(a) != root) {
+            return Err(NoEuler::Disconnected);
+        }
+        let starts: Vec<usize> = if directed {
+            let unbalanced: Vec<usize> = (0..n).filter(|&v| balance[v] != 0).collect();
+            let heads: Vec<usize> = (0..n).filter(|&v| balance[v] == 1).collect();
+            let tails = (0..n).filter(|&v| balance[v] == -1).count();
+            match (unbalanced.len(), heads.len(), tails) {
+                (0, ..) => (0..n).filter(|&v| !adjacent[v].is_empty()).collect(),
+                (2, 1, 1) => heads,
+                _ => return Err(NoEuler::Unbalanced(ids(unbalanced))),
+            }
+        } else {
+            let odd: Vec<usize> = (0..n).filter(|&v| balance[v] % 2 == 1).collect();
+            match odd.len() {
+                0 => (0..n).filter(|&v| balance[v] > 0).collect(),
+                2 => odd,
+                _ => return Err(NoEuler::OddDegrees(ids(odd))),
+            }
+        };
+        let start = from
+            .map(NodeId::index)
+            .filter(|v| starts.contains(v))
+            .unwrap_or(starts[0]);
+
+        for list in &mut adjacent {
+            list.sort_unstable();
+        }
+        let mut used = vec![false; arcs.len()];
+        let mut next = vec![0; n];
+        let mut stack = vec![start];
+        let mut trail = Vec::with_capacity(arcs.len() + 1);
+        while let Some(&v) = stack.last() {
+            while next[v] < adjacent[v].len() && used[adjacent[v][next[v]].1] {
+                next[v] += 1;
+            }
+            match adjacent[v].get(next[v]) {
+                Some(&(u, i)) => {
+                    used[i] = true;
+                    stack.push(u);
+                }
+                None => trail.push(stack.pop().unwrap()),
+            }
+        }
+        trail.reverse();
+        return Ok(ids(trail));
+    }
+
+    // edmonds-karp: augment along the fewest-hop path with capacity to spare until none is left,
+    // or until `max_paths` paths were pushed. a two-way edge is two arcs, each with the full
+    // capacity
+    pub fn max_flow(&self, source: NodeId, sink: NodeId, max_paths: usize) -> MaxFlow {
+        let n = self.nodes.len();
+        let mut residual: HashMap<(NodeId, NodeId), Cost> = HashMap::new();
+        let mut neighbours: Vec<Vec<NodeId>> = vec![Vec::new(); n];
+        for (from, edge) in self.arcs() {
+            for (p, q) in [(from, edge.node), (edge.node, from)] {
+                if let Entry::Vacant(slot) = residual.entry((p, q)) {
+                    slot.insert(0);
+                    neighbours[p.index()].push(q);
+                }
+            }
+            *residual.get_mut(&(from, edge.node)).unwrap() += edge.flow_capacity();
+        }
+
+        let mut flow = MaxFlow::default();
+        if source == sink {
+            return flow;
+        }
+        loop {
+            let mut prev: Vec<Option<NodeId>> = vec![None; n];
+            prev[source.index()] = Some(source);
+            let mut queue = VecDeque::from([source]);
+            while let Some(u) = queue.pop_front() {
+                if u == sink {
+                    break;
+                }
+                for &v in &neighbours[u.index()] {
+                    if prev[v.index()].is_none() && residual[&(u, v)] > 0 {
+                        prev[v.index()] = Some(u);
+                        queue.push_back(v);
+                    }
+                }
+            }
+
+            if prev[sink.index()].is_none() {
+                // whatever the source still reaches is its side of a minimum cut
+                for (from, edge) in self.arcs() {
+                    if prev[from.index()].is_some()
+                        && prev[edge.node.index()].is_none()
+                        && edge.flow_capacity() > 0
+                    {
+                        flow.cut.push((from, edge.node));
+                    }
+                }
+                return flow;
+            }
+            if flow.augmenting.len() >= max_paths {
+                flow.capped = true;
+                return flow;
+            }
+            let mut path = vec![sink];
+            while *path.last().unwrap() != source {
+                path.push(prev[path.last().unwrap().index()].unwrap());
+            }
+            path.reverse();
+            let amount = path
+                .windows(2)
+                .map(|w| residual[&(w[0], w[1])])
+                .min()
+                .unwrap();
+            for w in path.windows(2) {
+                *residual.get_mut(&(w[0], w[1])).unwrap() -= amount;
+                *residual.get_mut(&(w[1], w[0])).unwrap() += amount;
+            }
+            flow.value += amount;
+            flow.augmenting.push((amount, path));
+        }
+    }
+
+    // max_flow where the nodes in `caps` pass at most their capacity. each of them is split in
+    // two, every edge in arriving at the first half and every edge out leaving the second, with
+    // one arc of the capacity between; paths and the cut come back in this graph's ids
+    pub fn max_flow_through(
+        &self,
+        source: NodeId,
+        sink: NodeId,
+        max_paths: usize,
+        caps: &HashMap<NodeId, Cost>,
+    ) -> MaxFlow {
+        let n = self.nodes.len();
+        let mut capped: Vec<(NodeId, Cost)> = caps
+            .iter()
+            .filter(|&(&v, _)| v.index() < n)
+            .map(|(&v, &cap)| (v, cap))
+            .collect();
+        if capped.is_empty() || source == sink {
+            return self.max_flow(source, sink, max_paths);
+        }
+        capped.sort();
+        // owner[i] is the node split node i stands for; out[v] is the half v's edges leave from
+        let mut owner: Vec<NodeId> = self.ids().collect();
+        let mut out: Vec<NodeId> = self.ids().collect();
+        for &(v, _) in &capped {
+            out[v.index()] = NodeId(owner.len());
+            owner.push(v);
+        }
+        let mut split = Graph {
+            nodes: vec![Vec::new(); owner.len()],
+            ..Default::default()
+        };
+        for (from, edges) in self.nodes.iter().enumerate() {
+            split.nodes[out[from].index()].extend(edges.iter().cloned());
+        }
+        for &(v, cap) in &capped {
+            split.nodes[v.index()].push(Edge {
+                node: out[v.index()],
+                cost: 1,
+                capacity: Some(cap),
+                label: None,
+            });
+        }
+
+        // the sink's own cap applies too, so the flow has to get through to its second half
+        let mut flow = split.max_flow(source, out[sink.index()], max_paths);
+        for (_, path) in &mut flow.augmenting {
+            let mut walked: Vec<NodeId> = path.iter().map(|i| owner[i.index()]).collect();
+            walked.dedup();
+            *path = walked;
+        }
+        for (p, q) in std::mem::take(&mut flow.cut) {
+            if q.index() >= n {
+                flow.cut_nodes.push(p);
+            } else {
+                flow.cut.push((owner[p.index()], q));
+            }
+        }
+        return flow;
+    }
+
+    // each node's component, edges taken as undirected; components are numbered in the order of
+    // their lowest node, so node 0 is always in component 0
+    pub fn connected_components(&self) -> Vec<usize> {
+        let n = self.nodes.len();
+        let mut sets = UnionFind::new(n);
+        for (from, edge) in self.arcs() {
+            sets.union(from.index(), edge.node.index());
+        }
+        let mut index: HashMap<usize, usize> = HashMap::new();
+        return (0..n)
+            .map(|v| {
+                let root = sets.find(v);
+                let next = index.len();
+                *index.entry(root).or_insert(next)
+            })
+            .collect();
+    }
+
+    // true for the members of the largest component; on a tie the one holding the lowest node wins
+    pub fn largest_component(&self) -> Vec<bool> {
+        let components = self.connected_components();
+        let mut sizes = vec![0; components.len()];
+        for &c in &components {
+            sizes[c] += 1;
+        }
+        let best = sizes.iter().copied().max().unwrap_or(0);
+        let Some(largest) = sizes.iter().position(|&s| s == best) else {
+            return Vec::new();
+        };
+        return components.iter().map(|&c| c == largest).collect();
+    }
+
+    // the transpose: every edge u -> v becomes v -> u, so searches from a goal give distances *to* it
+    pub fn reverse(&self) -> Graph {
+        let mut reversed = Graph {
+            nodes: vec![vec![]; self.nodes.len()],
+            forbidden: self.forbidden.iter().map(|&(a, v, b)| (b, v, a)).collect(),
+            ..Default::default()
+        };
+        for (from, edge) in self.arcs() {
+            reversed.nodes[edge.node.index()].push(Edge {
+                node: from,
+                cost: edge.cost,
+                capacity: edge.capacity,
+                label: edge.label.clone(),
+            });
+        }
+        return reversed;
+    }
+
+    // a copy with every weight scaled by its own random factor in [1 - spread, 1 + spread] and
+    // rounded; both directions of a two-way edge share a factor, and capacities are untouched
+    pub fn jittered(&self, spread: f32, rng: &mut Rng) -> Graph {
+        let mut factors: HashMap<(NodeId, NodeId), f32> = HashMap::new();
+        let mut graph = self.clone();
+        for (from, edges) in graph.nodes.iter_mut().enumerate() {
+            let from = NodeId(from);
+            for edge in edges {
+                let key = (from.min(edge.node), from.max(edge.node));
+                let factor = *factors
+                    .entry(key)
+                    .or_insert_with(|| rng.range_f32(1.0 - spread, 1.0 + spread));
+                edge.cost = (edge.cost as f32 * factor).round() as Cost;
+            }
+        }
+        return graph;
+    }
+
+    // a copy where busy edges cost more: each of the usage[(from, to)] runs over an edge adds
+    // `per_use` of its weight, a linear congestion function. each direction has its own traffic,
+    // and capacities are untouched
+    pub fn congested(&self, usage: &HashMap<(NodeId, NodeId), usize>, per_use: f32) -> Graph {
+        let mut graph = self.clone();
+        for (from, edges) in graph.nodes.iter_mut().enumerate() {
+            for edge in edges {
+                let runs = usage.get(&(NodeId(from), edge.node)).copied().unwrap_or(0);
+                edge.cost = (edge.cost as f32 * (1.0 + per_use * runs as f32)).round() as Cost;
+            }
+        }
+        return graph;
+    }
+
+    // knocks out one connection picked uniformly, both ways for a two-way edge, and returns its
+    // (from, to) pair, or None when no edge is left. bans over it go too
+    pub fn fail_random_edge(&mut self, rng: &mut Rng) -> Option<(NodeId, NodeId)> {
+        let pairs: Vec<(NodeId, NodeId)> = self
+            .arcs()
+            .map(|(a, e)| (a, e.node))
+            .filter(|&(a, b)| a < b || !self.has_edge(b, a))
+            .collect();
+        if pairs.is_empty() {
+            return None;
+        }
+        let (a, b) = pairs[(rng.next_u64() % pairs.len() as u64) as usize];
+        self.remove_edge(a, b);
+        return Some((a, b));
+    }
+
+    // wilson's algorithm: from each node outside the tree in turn, a random walk until it meets
+    // the tree, erasing every loop as it closes; what is left of the walk joins the tree. hops go
+    // by random_step, so a tree comes out with probability proportional to the product of its
+    // weights, with weights under one counted as one. only nodes that can reach `root` join, and
+    // every tree edge points toward it
+    pub fn wilson_spanning_tree(&self, root: NodeId, rng: &mut Rng) -> Vec<WilsonStep> {
+        let n = self.nodes.len();
+        let reversed = self.reverse();
+        let mut reaches = vec![false; n];
+        reaches[root.index()] = true;
+        let mut stack = vec![root];
+        while let Some(v) = stack.pop() {
+            for edge in reversed.edges_from(v) {
+                if !reaches[edge.node.index()] {
+                    reaches[edge.node.index()] = true;
+                    stack.push(edge.node);
+                }
+            }
+        }
+        // walks that can't leave the nodes reaching the root always get there
+        let mut within = self.clone();
+        within.remove_edges(|_, e| !reaches[e.node.index()]);
+        for edges in &mut within.nodes {
+            for edge in edges {
+                edge.cost = edge.cost.max(1);
+            }
+        }
+
+        let mut in_tree = vec![false; n];
+        in_tree[root.index()] = true;
+        let mut steps = Vec::new();
+        for start in self.ids() {
+            if in_tree[start.index()] || !reaches[start.index()] {
+                continue;
+            }
+            let mut walk = vec![start];
+            let mut at = start;
+            while !in_tree[at.index()] {
+                // every node here has an edge toward the root
+                let next = within.random_step(at, rng).unwrap();
+                steps.push(WilsonStep::Hop(at, next));
+                match walk.iter().position(|&v| v == next) {
+                    Some(i) => {
+                        let mut erased = walk.split_off(i);
+                        erased.push(next);
+                        walk.push(next);
+                        steps.push(WilsonStep::Erase(erased));
+                    }
+                    None => walk.push(next),
+                }
+                at = next;
+            }
+            for &v in &walk {
+                in_tree[v.index()] = true;
+            }
+            steps.push(WilsonStep::Join(walk));
+        }
+        return steps;
+    }
+
+    // one step of a random walk: an edge out of `from` picked with probability proportional to
+    // its weight, or uniformly when no weight is positive. a dead end has no step
+    pub fn random_step(&self, from: NodeId, rng: &mut Rng) -> Option<NodeId> {
+        let edges = self.edges_from(from);
+        if edges.is_empty() {
+            return None;
+        }
+        let total: Cost = edges.iter().map(|e| e.cost.max(0)).sum();
+        if total == 0 {
+            let i = (rng.next_f32() * edges.len() as f32) as usize;
+            return Some(edges[i.min(edges.len() - 1)].node);
+        }
+        let mut pick = rng.next_f32() * total as f32;
+        for edge in edges {
+            pick -= edge.cost.max(0) as f32;
+            if pick < 0.0 {
+                return Some(edge.node);
+            }
+        }
+        // rounding can leave `pick` a hair above zero; the last weighted edge takes it
+        return edges.iter().rev().find(|e| e.cost > 0).map(|e| e.node);
+    }
+
+    // FNV-1a over each node's name and details, every edge's end, weight, capacity and name, and
+    // the turn bans. edges and bans are sorted first, so the order they were added in doesn't
+    // matter; positions are left out, so moving nodes around keeps the hash. the same graph
+    // hashes the same on any machine
+    pub fn content_hash(&self) -> u64 {
+        let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &b in bytes {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+            }
+        };
+        // texts go in with their length first, so "ab" + "c" and "a" + "bc" differ
+        let text = |feed: &mut dyn FnMut(&[u8]), text: &str| {
+            feed(&(text.len() as u64).to_le_bytes());
+            feed(text.as_bytes());
+        };
+        feed(&(self.nodes.len() as u64).to_le_bytes());
+        for (id, edges) in self.ids().zip(&self.nodes) {
+            text(&mut feed, &self.node_name(id));
+            let details: Vec<(&String, &String)> =
+                self.node_metadata.get(&id).into_iter().flatten().collect();
+            feed(&(details.len() as u64).to_le_bytes());
+            for (key, value) in details {
+                text(&mut feed, key);
+                text(&mut feed, value);
+            }
+            let mut sorted: Vec<(usize, Cost, Option<Cost>, Option<&str>)> = edges
+                .iter()
+                .map(|e| (e.node.index(), e.cost, e.capacity, e.label.as_deref()))
+                .collect();
+            sorted.sort_unstable();
+            feed(&(sorted.len() as u64).to_le_bytes());
+            for (to, cost, capacity, label) in sorted {
+                feed(&(to as u64).to_le_bytes());
+                feed(&cost.to_le_bytes());
+                match capacity {
+                    Some(c) => {
+                        feed(&[1]);
+                        feed(&c.to_le_bytes());
+                    }
+                    None => feed(&[0]),
+                }
+                match label {
+                    Some(label) => {
+                        feed(&[1]);
+                        text(&mut feed, label);
+                    }
+                    None => feed(&[0]),
+                }
+            }
+        }
+        let mut bans = self.forbidden.clone();
+        bans.sort_unstable();
+        bans.dedup();
+        for (a, v, b) in bans {
+            for id in [a, v, b] {
+                feed(&(id.index() as u64).to_le_bytes());
+            }
+        }
+        return hash;
+    }
+
+    pub fn fmt_hash(&self) -> String {
+        return format!("{:016x}", self.content_hash());
+    }
+
+    // the same transpose without building a second graph
+    pub fn transpose(&mut self) {
+        let n = self.nodes.len();
+        let old = std::mem::replace(&mut self.nodes, vec![vec![]; n]);
+        for (from, edges) in old.into_iter().enumerate() {
+            for edge in edges {
+                self.nodes[edge.node.index()].push(Edge {
+                    node: NodeId(from),
+                    cost: edge.cost,
+                    capacity: edge.capacity,
+                    label: edge.label,
+                });
+            }
+        }
+        for ban in &mut self.forbidden {
+            *ban = (ban.2, ban.1, ban.0);
+        }
+    }
+
+    pub fn shortest_path(&self, start: NodeId, goal: NodeId) -> Option<(Cost, Vec<NodeId>)> {
+        return self.shortest_path_with_stats(start, goal).0;
+    }
+
+    // one shortest path per (start, goal) query, in order; split across `threads` scoped threads
+    // when there is more than one
+    pub fn shortest_paths_for(
+        &self,
+        queries: &[(NodeId, NodeId)],
+        threads: usize,
+    ) -> Vec<Option<(Cost, Vec<NodeId>)>> {
+        let run = |chunk: &[(NodeId, NodeId)]| -> Vec<Option<(Cost, Vec<NodeId>)>> {
+            return chunk
+                .iter()
+                .map(|&(start, goal)| self.shortest_path(start, goal))
+                .collect();
+        };
+        if threads <= 1 || queries.len() < 2 {
+            return run(queries);
+        }
+        let chunk = queries.len().div_ceil(threads);
+        return std::thread::scope(|scope| {
+            let workers: Vec<_> = queries
+                .chunks(chunk)
+                .map(|c| scope.spawn(move || run(c)))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().unwrap())
+                .collect()
+        });
+    }
+
+    // what walking `path` costs under the current weights, taking the cheapest of parallel edges;
+    // None if one of its edges is gone
+    pub fn path_cost(&self, path: &[NodeId]) -> Option<Cost> {
+        return path
+            .windows(2)
+            .map(|w| {
+                self.edges_from(w[0])
+                    .iter()
+                    .filter(|e| e.node == w[1])
+                    .map(|e| e.cost)
+                    .min()
+            })
+            .sum();
+    }
+
+    // the k cheapest loopless routes from start to goal, cheapest first (Yen's algorithm): each new
+    // route leaves an earlier one at some spur node, avoiding the edges the routes found so far take
+    // on from there and the nodes before it
+    pub fn k_shortest_paths(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        k: usize,
+    ) -> Vec<(Cost, Vec<NodeId>)> {
+        let Some(first) = self.shortest_path(start, goal).filter(|_| k > 0) else {
+            return Vec::new();
+        };
+        let mut found = vec![first];
+        let mut candidates: Vec<(Cost, Vec<NodeId>)> = Vec::new();
+        while found.len() < k {
+            let last = found.last().unwrap().1.clone();
+            for i in 0..last.len() - 1 {
+                let (spur, root) = (last[i], &last[..=i]);
+                let mut pruned = self.clone();
+                for (_, path) in &found {
+                    if path.len() > i + 1 && path[..=i] == *root {
+                        pruned.nodes[spur.index()].retain(|e| e.node != path[i + 1]);
+                    }
+                }
+                // the spur search starts fresh, so a ban on turning at the spur node is applied here
+                if i > 0 {
+                    pruned.nodes[spur.index()]
+                        .retain(|e| !self.is_forbidden(last[i - 1], spur, e.node));
+                }
+                for &node in &root[..i] {
+                    pruned.nodes[node.index()].clear();
+                    for edges in &mut pruned.nodes {
+                        edges.retain(|e| e.node != node);
+                    }
+                }
+
+                let Some((_, spur_path)) = pruned.shortest_path(spur, goal) else {
+                    continue;
+                };
+                let mut path = root[..i].to_vec();
+                path.extend(spur_path);
+                let Some(cost) = self.path_cost(&path) else {
+                    continue;
+                };
+                if !found.iter().chain(&candidates).any(|(_, p)| *p == path) {
+                    candidates.push((cost, path));
+                }
+            }
+            // ties go to the route with fewer hops
+            let Some(best) =
+                (0..candidates.len()).min_by_key(|&i| (candidates[i].0, candidates[i].1.len()))
+            else {
+                break;
+            };
+            found.push(candidates.swap_remove(best));
+        }
+
+        return found;
+    }
+
+    // fewest edges from start to goal, ignoring costs; the returned cost is that route's weight.
+    // searches over (node, came from) so turn bans still hold
+    pub fn bfs_shortest_path(&self, start: NodeId, goal: NodeId) -> Option<(Cost, Vec<NodeId>)> {
+        type Arrival = (NodeId, Option<NodeId>);
+        let mut prev: HashMap<Arrival, Arrival> = HashMap::new();
+        let mut seen: HashSet<Arrival> = HashSet::from([(start, None)]);
+        let mut queue = VecDeque::from([(start, None)]);
+
+        while let Some((position, from)) = queue.pop_front() {
+            if position == goal {
+                let mut path = vec![position];
+                let mut arrival = (position, from);
+                while let Some(&before) = prev.get(&arrival) {
+                    path.push(before.0);
+                    arrival = before;
+                }
+                path.reverse();
+                return Some((self.path_cost(&path)?, path));
+            }
+
+            for edge in &self.nodes[position.index()] {
+                if from.is_some_and(|f| self.is_forbidden(f, position, edge.node)) {
+                    continue;
+                }
+                let arrival = (edge.node, Some(position));
+                if seen.insert(arrival) {
+                    prev.insert(arrival, (position, from));
+                    queue.push_back(arrival);
+                }
+            }
+        }
+
+        return None;
+    }
+
+    pub fn shortest_path_with_stats(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+    ) -> (Option<(Cost, Vec<NodeId>)>, AlgoStats) {
+        let trace = self.shortest_path_trace(start, goal);
+        let stats = AlgoStats::from_events(trace.events.iter().map(|e| &e.event));
+        return (trace.result, stats);
+    }
+
+    pub fn is_forbidden(&self, from: NodeId, via: NodeId, to: NodeId) -> bool {
+        return self.forbidden.contains(&(from, via, to));
+    }
+
+    // everything Defect lists, edge by edge and then the turn bans
+    pub fn validate(&self) -> Validation {
+        let n = self.nodes.len();
+        let same = |x: &Edge, y: &Edge| {
+            (x.node, x.cost, x.capacity, &x.label) == (y.node, y.cost, y.capacity, &y.label)
+        };
+        let mut defects = Vec::new();
+        for (from, edges) in self.nodes.iter().enumerate() {
+            let from = NodeId(from);
+            for (i, e) in edges.iter().enumerate() {
+                let to = e.node;
+                if to.index() >= n {
+                    defects.push(Defect::DanglingEdge { from, to });
+                    continue;
+                }
+                if to == from {
+                    defects.push(Defect::SelfLoop(from));
+                }
+                if e.cost == 0 {
+                    defects.push(Defect::ZeroWeight { from, to });
+                }
+                if e.capacity.is_some_and(|c| c < 0) {
+                    defects.push(Defect::NegativeCapacity { from, to });
+                }
+                // once per set of copies, at the second one
+                if edges[..i].iter().filter(|d| same(d, e)).count() == 1 {
+                    defects.push(Defect::DuplicateEdge { from, to });
+                }
+            }
+            let higher: BTreeSet<NodeId> = edges
+                .iter()
+                .map(|e| e.node)
+                .filter(|&to| to > from && to.index() < n)
+                .collect();
+            for to in higher {
+                let there = edges.iter().filter(|e| e.node == to);
+                let back = self.edges_from(to).iter().filter(|e| e.node == from);
+                for (lane, (x, y)) in there.zip(back).enumerate() {
+                    if (x.cost, x.capacity, &x.label) != (y.cost, y.capacity, &y.label) {
+                        defects.push(Defect::Asymmetric {
+                            a: from,
+                            b: to,
+                            lane,
+                        });
+                    }
+                }
+            }
+        }
+        for (i, &(p, v, q)) in self.forbidden.iter().enumerate() {
+            let along = [p, v, q].iter().all(|id| id.index() < n)
+                && self.has_edge(p, v)
+                && self.has_edge(v, q);
+            if !along {
+                defects.push(Defect::BadTurnBan(p, v, q));
+            } else if self.forbidden[..i].contains(&(p, v, q)) {
+                defects.push(Defect::DuplicateTurnBan(p, v, q));
+            }
+        }
+        return Validation { defects };
+    }
+
+    pub fn has_negative_edges(&self) -> bool {
+        return self.nodes.iter().flatten().any(|e| e.cost < 0);
+    }
+
+    // dijkstra, or bellman-ford once any weight is negative; turn bans need the edge-based search
+    pub fn shortest_path_trace(&self, start: NodeId, goal: NodeId) -> Trace {
+        if !self.forbidden.is_empty() {
+            return self.restricted_trace(start, goal);
+        }
+        if self.has_negative_edges() {
+            let mut trace = Trace::new();
+            if let Ok(paths) = self.bellman_ford_recorded(start, &mut trace) {
+                trace.result = paths.path_to(start, goal);
+            }
+            return trace;
+        }
+        let mut trace = Trace::new();
+        let result = self.dijkstra_events(start, goal, |event| trace.record(event));
+        trace.result = result;
+        return trace;
+    }
+
+    pub fn astar(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        heuristic: impl Fn(NodeId) -> Cost,
+    ) -> Option<(Cost, Vec<NodeId>)> {
+        return self.astar_trace(start, goal, heuristic).result;
+    }
+
+    // dijkstra ordered by cost so far + heuristic(node). the result is only shortest when the
+    // heuristic never overestimates the remaining cost. turn bans and negative weights fall back
+    // to shortest_path_trace
+    pub fn astar_trace(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        heuristic: impl Fn(NodeId) -> Cost,
+    ) -> Trace {
+        if !self.forbidden.is_empty() || self.has_negative_edges() {
+            return self.shortest_path_trace(start, goal);
+        }
+        let n = self.nodes.len();
+        let mut dist = vec![Cost::MAX; n];
+        let mut prev: Vec<Option<NodeId>> = vec![None; n];
+        // (estimated total, cost so far, node)
+        let mut open = BinaryHeap::new();
+        let mut trace = Trace::new();
+
+        dist[start.index()] = 0;
+        open.push(Reverse((heuristic(start), 0, start)));
+        trace.record(TraceEvent::Push {
+            node: start,
+            dist: 0,
+        });
+
+        while let Some(Reverse((_, cost, position))) = open.pop() {
+            let stale = cost > dist[position.index()];
+            trace.record(TraceEvent::Pop {
+                node: position,
+                dist: cost,
+                stale,
+            });
+            if stale {
+                continue;
+            }
+
+            if position == goal {
+                let path = reconstruct_path(prev, start, goal).unwrap();
+                trace.result = Some((cost, path));
+                return trace;
+            }
+
+            for edge in &self.nodes[position.index()] {
+                let next = cost + edge.cost;
+                trace.record(TraceEvent::Relax {
+                    from: position,
+                    to: edge.node,
+                    cost: edge.cost,
+                });
+
+                if next < dist[edge.node.index()] {
+                    dist[edge.node.index()] = next;
+                    prev[edge.node.index()] = Some(position);
+                    open.push(Reverse((next + heuristic(edge.node), next, edge.node)));
+                    trace.record(TraceEvent::DistUpdate {
+                        node: edge.node,
+                        dist: next,
+                        via: position,
+                    });
+                    trace.record(TraceEvent::Push {
+                        node: edge.node,
+                        dist: next,
+                    });
+                }
+            }
+        }
+        return trace;
+    }
+
+    // up to `count` landmarks picked farthest first: each is the node farthest from the ones
+    // already picked, one none of them reaches before any other, lowest on a tie. the first is
+    // the node farthest from A. two searches per landmark; weights must not be negative
+    pub fn landmarks(&self, count: usize) -> Landmarks {
+        let mut picked: Vec<NodeId> = Vec::new();
+        let mut seeds = vec![NodeId(0)];
+        while picked.len() < count.min(self.nodes.len()) {
+            let dist = self.multi_source_shortest_paths(&seeds);
+            let far = self.ids().filter(|v| !picked.contains(v)).max_by_key(|&v| {
+                let d = dist[v.index()];
+                return (d.is_none(), d.map(|(d, _)| d), Reverse(v));
+            });
+            let Some(far) = far else {
+                break;
+            };
+            picked.push(far);
+            seeds = picked.clone();
+        }
+        return self.landmarks_at(&picked);
+    }
+
+    // the distance tables for landmarks chosen by hand; any set of nodes gives valid lower
+    // bounds, though not all of them help. ids past the last node are skipped
+    pub fn landmarks_at(&self, nodes: &[NodeId]) -> Landmarks {
+        let mut landmarks = Landmarks::default();
+        let reversed = self.reverse();
+        let distances = |dist: Vec<Option<(Cost, NodeId)>>| -> Vec<Option<Cost>> {
+            return dist.into_iter().map(|d| d.map(|(d, _)| d)).collect();
+        };
+        for &node in nodes.iter().filter(|v| v.index() < self.nodes.len()) {
+            if landmarks.nodes.contains(&node) {
+                continue;
+            }
+            landmarks.nodes.push(node);
+            landmarks
+                .from
+                .push(distances(self.multi_source_shortest_paths(&[node])));
+            landmarks
+                .to
+                .push(distances(reversed.multi_source_shortest_paths(&[node])));
+        }
+        return landmarks;
+    }
+
+    pub fn shortest_path_alt(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        landmarks: &Landmarks,
+    ) -> Option<(Cost, Vec<NodeId>)> {
+        return self.shortest_path_alt_trace(start, goal, landmarks).result;
+    }
+
+    // A* steered by the landmarks' lower bounds, which never overestimate, so the path is a
+    // shortest one. the landmarks must have been picked on this graph's weights
+    pub fn shortest_path_alt_trace(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        landmarks: &Landmarks,
+    ) -> Trace {
+        return self.astar_trace(start, goal, |v| landmarks.lower_bound(v, goal));
+    }
+
+    pub fn dstar_lite(&self, start: NodeId, goal: NodeId) -> DStarLite {
+        let n = self.nodes.len();
+        let mut plan = DStarLite {
+            start,
+            goal,
+            g: vec![Cost::MAX; n],
+            rhs: vec![Cost::MAX; n],
+            open: BinaryHeap::new(),
+            queued: vec![None; n],
+            expanded: 0,
+            reversed: self.reverse(),
+        };
+        plan.rhs[goal.index()] = 0;
+        plan.update(self, goal.index());
+        plan.search(self);
+        return plan;
+    }
+
+    pub fn greedy_best_first(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        heuristic: impl Fn(NodeId) -> Cost,
+    ) -> Option<(Cost, Vec<NodeId>)> {
+        return self.greedy_best_first_trace(start, goal, heuristic).result;
+    }
+
+    // always expands the open node that looks closest to the goal by heuristic alone, ignoring
+    // the cost so far, and keeps the first way it reached each node. it tends to settle few nodes
+    // but its route need not be the cheapest. turn bans are respected along that first way
+    pub fn greedy_best_first_trace(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        heuristic: impl Fn(NodeId) -> Cost,
+    ) -> Trace {
+        let n = self.nodes.len();
+        let mut dist: Vec<Option<Cost>> = vec![None; n];
+        let mut prev: Vec<Option<NodeId>> = vec![None; n];
+        // (heuristic, node)
+        let mut open = BinaryHeap::new();
+        let mut trace = Trace::new();
+
+        dist[start.index()] = Some(0);
+        open.push(Reverse((heuristic(start), start)));
+        trace.record(TraceEvent::Push {
+            node: start,
+            dist: 0,
+        });
+
+        while let Some(Reverse((_, position))) = open.pop() {
+            let cost = dist[position.index()].unwrap();
+            trace.record(TraceEvent::Pop {
+                node: position,
+                dist: cost,
+                stale: false,
+            });
+            if position == goal {
+                let path = reconstruct_path(prev, start, goal).unwrap();
+                trace.result = Some((cost, path));
+                return trace;
+            }
+
+            for edge in &self.nodes[position.index()] {
+                trace.record(TraceEvent::Relax {
+                    from: position,
+                    to: edge.node,
+                    cost: edge.cost,
+                });
+                let banned = prev[position.index()]
+                    .is_some_and(|p| self.is_forbidden(p, position, edge.node));
+                if dist[edge.node.index()].is_some() || banned {
+                    continue;
+                }
+                let next = cost + edge.cost;
+                dist[edge.node.index()] = Some(next);
+                prev[edge.node.index()] = Some(position);
+                open.push(Reverse((heuristic(edge.node), edge.node)));
+                trace.record(TraceEvent::DistUpdate {
+                    node: edge.node,
+                    dist: next,
+                    via: position,
+                });
+                trace.record(TraceEvent::Push {
+                    node: edge.node,
+                    dist: next,
+                });
+            }
+        }
+        return trace;
+    }
+
+    // searches forward from start and backward (on the transpose) from goal, stopping once the two
+    // frontiers can no longer improve on the best meeting point. like astar_trace, turn bans and
+    // negative weights fall back to shortest_path_trace
+    pub fn bidirectional_trace(&self, start: NodeId, goal: NodeId) -> Trace {
+        if !self.forbidden.is_empty() || self.has_negative_edges() {
+            return self.shortest_path_trace(start, goal);
+        }
+        let n = self.nodes.len();
+        let reversed = self.reverse();
+        let graphs = [self, &reversed];
+        let mut dist = [vec![Cost::MAX; n], vec![Cost::MAX; n]];
+        let mut prev: [Vec<Option<NodeId>>; 2] = [vec![None; n], vec![None; n]];
+        let mut settled = [vec![false; n], vec![false; n]];
+        let mut heaps = [BinaryHeap::new(), BinaryHeap::new()];
+        // (cost, a, b): the best start -> a -> b -> goal seen, a in the forward tree, b in the backward one
+        let mut best: Option<(Cost, NodeId, NodeId)> = None;
+        let mut trace = Trace::new();
+
+        for (side, node) in [(0, start), (1, goal)] {
+            dist[side][node.index()] = 0;
+            heaps[side].push(State {
+                position: node,
+                cost: 0,
+            });
+            trace.record(TraceEvent::Push { node, dist: 0 });
+        }
+        if start == goal {
+            best = Some((0, start, start));
+        }
+
+        loop {
+            let tops = [
+                heaps[0].peek().map(|s| s.cost),
+                heaps[1].peek().map(|s| s.cost),
+            ];
+            let (Some(f), Some(b)) = (tops[0], tops[1]) else {
+                break;
+            };
+            if best.is_some_and(|(cost, _, _)| f + b >= cost) {
+                break;
+            }
+            let side = if f <= b { 0 } else { 1 };
+            let State { cost, position } = heaps[side].pop().unwrap();
+            let stale = settled[side][position.index()] || cost > dist[side][position.index()];
+            trace.record(TraceEvent::Pop {
+                node: position,
+                dist: cost,
+                stale,
+            });
+            if stale {
+                continue;
+            }
+            settled[side][position.index()] = true;
+
+            for edge in &graphs[side].nodes[position.index()] {
+                trace.record(TraceEvent::Relax {
+                    from: position,
+                    to: edge.node,
+                    cost: edge.cost,
+                });
+                let next = cost + edge.cost;
+                if next < dist[side][edge.node.index()] {
+                    dist[side][edge.node.index()] = next;
+                    prev[side][edge.node.index()] = Some(position);
+                    heaps[side].push(State {
+                        position: edge.node,
+                        cost: next,
+                    });
+                    trace.record(TraceEvent::DistUpdate {
+                        node: edge.node,
+                        dist: next,
+                        via: position,
+                    });
+                    trace.record(TraceEvent::Push {
+                        node: edge.node,
+                        dist: next,
+                    });
+                }
+                let other = dist[1 - side][edge.node.index()];
+                if other != Cost::MAX && best.is_none_or(|(c, _, _)| next + other < c) {
+                    // backward edges are reversed, so the forward-tree end is edge.node
+                    let (a, b) = if side == 0 {
+                        (position, edge.node)
+                    } else {
+                        (edge.node, position)
+                    };
+                    best = Some((next + other, a, b));
+                }
+            }
+        }
+
+        if let Some((cost, a, b)) = best {
+            let mut path = reconstruct_path(prev[0].clone(), start, a).unwrap();
+            if a != b {
+                path.push(b);
+            }
+            let mut cur = b;
+            while let Some(next) = prev[1][cur.index()] {
+                path.push(next);
+                cur = next;
+            }
+            trace.result = Some((cost, path));
+        }
+        return trace;
+    }
+
+    // edge-based search: a state is (node, node we arrived from), since a ban depends on the way in.
+    // paths may pass a node twice, e.g. looping round a block instead of turning left
+    fn restricted_trace(&self, start: NodeId, goal: NodeId) -> Trace {
+        type Arrival = (NodeId, Option<NodeId>);
+        let mut dist: HashMap<Arrival, Cost> = HashMap::new();
+        let mut prev: HashMap<Arrival, Arrival> = HashMap::new();
+        // with negative weights a settled state could improve again and loop forever
+        let mut settled: HashSet<Arrival> = HashSet::new();
+        let mut visited = BinaryHeap::new();
+        let mut trace = Trace::new();
+
+        dist.insert((start, None), 0);
+        visited.push(Reverse((0, start, None)));
+        trace.record(TraceEvent::Push {
+            node: start,
+            dist: 0,
+        });
+
+        while let Some(Reverse((cost, position, from))) = visited.pop() {
+            let stale = cost > dist[&(position, from)] || !settled.insert((position, from));
+            trace.record(TraceEvent::Pop {
+                node: position,
+                dist: cost,
+                stale,
+            });
+            if stale {
+                continue;
+            }
+
+            if position == goal {
+                let mut path = vec![position];
+                let mut arrival = (position, from);
+                while let Some(&before) = prev.get(&arrival) {
+                    path.push(before.0);
+                    arrival = before;
+                }
+                path.reverse();
+                trace.result = Some((cost, path));
+                return trace;
+            }
+
+            for edge in &self.nodes[position.index()] {
+                if from.is_some_and(|f| self.is_forbidden(f, position, edge.node)) {
+                    continue;
+                }
+                trace.record(TraceEvent::Relax {
+                    from: position,
+                    to: edge.node,
+                    cost: edge.cost,
+                });
+
+                let next = cost + edge.cost;
+                let arrival = (edge.node, Some(position));
+                if dist.get(&arrival).is_none_or(|&d| next < d) {
+                    dist.insert(arrival, next);
+                    prev.insert(arrival, (position, from));
+                    visited.push(Reverse((next, edge.node, Some(position))));
+                    trace.record(TraceEvent::DistUpdate {
+                        node: edge.node,
+                        dist: next,
+                        via: position,
+                    });
+                    trace.record(TraceEvent::Push {
+                        node: edge.node,
+                        dist: next,
+                    });
+                }
+            }
+        }
+        return trace;
+    }
+
+    // distances from start under any weights, or Err with a negative cycle reachable from start,
+    // listed in travel order (the first node is not repeated at the end)
+    pub fn bellman_ford(&self, start: NodeId) -> Result<ShortestPaths, Vec<NodeId>> {
+        return self.bellman_ford_recorded(start, &mut Trace::new());
+    }
+
+    fn bellman_ford_recorded(
+        &self,
+        start: NodeId,
+        trace: &mut Trace,
+    ) -> Result<ShortestPaths, Vec<NodeId>> {
+        let n = self.nodes.len();
+        let mut dist: Vec<Option<Cost>> = vec![None; n];
+        let mut prev: Vec<Option<NodeId>> = vec![None; n];
+        dist[start.index()] = Some(0);
+
+        // after n - 1 rounds every shortest path is found; anything that still improves in round n
+        // is downstream of a negative cycle
+        for round in 0..n {
+            let mut improved = None;
+            for (from, edges) in self.nodes.iter().enumerate() {
+                let from = NodeId(from);
+                let Some(d) = dist[from.index()] else {
+                    continue;
+                };
+                for edge in edges {
+                    trace.record(TraceEvent::Relax {
+                        from,
+                        to: edge.node,
+                        cost: edge.cost,
+                    });
+                    let next = d + edge.cost;
+                    if dist[edge.node.index()].is_none_or(|old| next < old) {
+                        dist[edge.node.index()] = Some(next);
+                        prev[edge.node.index()] = Some(from);
+                        improved = Some(edge.node);
+                        trace.record(TraceEvent::DistUpdate {
+                            node: edge.node,
+                            dist: next,
+                            via: from,
+                        });
+                    }
+                }
+            }
+            match improved {
+                None => return Ok(ShortestPaths { dist, prev }),
+                Some(node) if round == n - 1 => {
+                    // walking back n steps from an improved node is sure to end up on the cycle
+                    let mut on_cycle = node;
+                    for _ in 0..n {
+                        on_cycle = prev[on_cycle.index()].unwrap();
+                    }
+                    let mut cycle = vec![on_cycle];
+                    let mut cur = prev[on_cycle.index()].unwrap();
+                    while cur != on_cycle {
+                        cycle.push(cur);
+                        cur = prev[cur.index()].unwrap();
+                    }
+                    cycle.reverse();
+                    return Err(cycle);
+                }
+                Some(_) => {}
+            }
+        }
+        return Ok(ShortestPaths { dist, prev });
+    }
+
+    // one search seeded with every source at distance 0; each node gets (distance, nearest source)
+    pub fn multi_source_shortest_paths(&self, starts: &[NodeId]) -> Vec<Option<(Cost, NodeId)>> {
+        let mut best: Vec<Option<(Cost, NodeId)>> = vec![None; self.nodes.len()];
+        let mut settled = vec![false; self.nodes.len()];
+        let mut visited = BinaryHeap::new();
+
+        for &start in starts {
+            if best[start.index()].is_none() {
+                best[start.index()] = Some((0, start));
+                visited.push(State {
+                    position: start,
+                    cost: 0,
+                });
+            }
+        }
+
+        while let Some(State { cost, position }) = visited.pop() {
+            let Some((dist, owner)) = best[position.index()] else {
+                continue;
+            };
+            if cost > dist || settled[position.index()] {
+                continue;
+            }
+            settled[position.index()] = true;
+            for edge in &self.nodes[position.index()] {
+                let next = cost + edge.cost;
+                if best[edge.node.index()].is_none_or(|(d, _)| next < d) {
+                    best[edge.node.index()] = Some((next, owner));
+                    visited.push(State {
+                        position: edge.node,
+                        cost: next,
+                    });
+                }
+            }
+        }
+        return best;
+    }
+
+    // every node's distance from start and the edge it is reached by: dijkstra, or bellman-ford
+    // as soon as a weight is negative, with Err naming a negative cycle reachable from start. turn
+    // bans are not considered, since a tree has one way into each node
+    pub fn shortest_path_tree(&self, start: NodeId) -> Result<ShortestPaths, Vec<NodeId>> {
+        if self.has_negative_edges() {
+            return self.bellman_ford(start);
+        }
+        let n = self.nodes.len();
+        let mut dist: Vec<Option<Cost>> = vec![None; n];
+        let mut prev: Vec<Option<NodeId>> = vec![None; n];
+        let mut settled = vec![false; n];
+        let mut visited = BinaryHeap::new();
+
+        dist[start.index()] = Some(0);
+        visited.push(State {
+            position: start,
+            cost: 0,
+        });
+        while let Some(State { cost, position }) = visited.pop() {
+            if settled[position.index()] {
+                continue;
+            }
+            settled[position.index()] = true;
+            for edge in &self.nodes[position.index()] {
+                let next = cost + edge.cost;
+                if !settled[edge.node.index()] && dist[edge.node.index()].is_none_or(|d| next < d) {
+                    dist[edge.node.index()] = Some(next);
+                    prev[edge.node.index()] = Some(position);
+                    visited.push(State {
+                        position: edge.node,
+                        cost: next,
+                    });
+                }
+            }
+        }
+        return Ok(ShortestPaths { dist, prev });
+    }
+
+    // patches a tree from shortest_path_tree(start) after the edges in `changed` (from, to) were
+    // reweighted, added or removed, instead of growing it again: the nodes hanging below a changed
+    // tree edge lose their distances and take the best offer from the rest of the tree, and any
+    // node a changed edge now reaches more cheaply is pushed on from there. only the nodes whose
+    // distance moved are settled again; their count comes back, or None when a patch can't be
+    // trusted (a negative weight, or nodes added or removed) and the tree should be grown afresh
+    pub fn repair_shortest_path_tree(
+        &self,
+        start: NodeId,
+        tree: &mut ShortestPaths,
+        changed: &[(NodeId, NodeId)],
+    ) -> Option<usize> {
+        let n = self.nodes.len();
+        if tree.dist.len() != n || start.index() >= n || self.has_negative_edges() {
+            return None;
+        }
+        let mut children = vec![Vec::new(); n];
+        for (node, prev) in tree.prev.iter().enumerate() {
+            if let Some(p) = *prev {
+                children[p.index()].push(node);
+            }
+        }
+        let mut cut_off = vec![false; n];
+        let mut stack: Vec<usize> = changed
+            .iter()
+            .filter(|&&(from, to)| to.index() < n && tree.prev[to.index()] == Some(from))
+            .map(|&(_, to)| to.index())
+            .collect();
+        while let Some(node) = stack.pop() {
+            if !cut_off[node] {
+                cut_off[node] = true;
+                tree.dist[node] = None;
+                tree.prev[node] = None;
+                stack.extend(&children[node]);
+            }
+        }
+
+        // one pass over the edges for the offers; no heap work is done for the rest of the tree
+        let changed: HashSet<(NodeId, NodeId)> = changed.iter().copied().collect();
+        let mut heap = BinaryHeap::new();
+        for (from, edges) in self.nodes.iter().enumerate() {
+            let from = NodeId(from);
+            let Some(d) = tree.dist[from.index()] else {
+                continue;
+            };
+            for edge in edges {
+                let next = d + edge.cost;
+                let offer = cut_off[edge.node.index()] || changed.contains(&(from, edge.node));
+                if offer && tree.dist[edge.node.index()].is_none_or(|old| next < old) {
+                    tree.dist[edge.node.index()] = Some(next);
+                    tree.prev[edge.node.index()] = Some(from);
+                    heap.push(State {
+                        position: edge.node,
+                        cost: next,
+                    });
+                }
+            }
+        }
+        let mut settled = 0;
+        while let Some(State { cost, position }) = heap.pop() {
+            // a cheaper offer came in after this one
+            if tree.dist[position.index()] != Some(cost) {
+                continue;
+            }
+            settled += 1;
+            for edge in &self.nodes[position.index()] {
+                let next = cost + edge.cost;
+                if tree.dist[edge.node.index()].is_none_or(|old| next < old) {
+                    tree.dist[edge.node.index()] = Some(next);
+                    tree.prev[edge.node.index()] = Some(position);
+                    heap.push(State {
+                        position: edge.node,
+                        cost: next,
+                    });
+                }
+            }
+        }
+        return Some(settled);
+    }
+
+    // every simple path from start to goal that matches the shortest cost, in node order and at
+    // most `limit` of them; ties can multiply with every diamond in the graph. an edge is on one
+    // of these paths exactly when it is tight (dist[from] + cost == dist[to]) and the goal can
+    // still be reached from its far end over tight edges. turn bans fall back to the one
+    // shortest_path, and a negative cycle gives none
+    pub fn all_shortest_paths(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        limit: usize,
+    ) -> Option<(Cost, Vec<Vec<NodeId>>)> {
+        if !self.forbidden.is_empty() {
+            return self.shortest_path(start, goal).map(|(c, p)| (c, vec![p]));
+        }
+        let dist = self.shortest_path_tree(start).ok()?.dist;
+        let cost = dist[goal.index()]?;
+        if start == goal {
+            return Some((cost, vec![vec![start]]));
+        }
+        let tight = |from: NodeId, to: NodeId, cost: Cost| {
+            return dist[from.index()].is_some_and(|d| dist[to.index()] == Some(d + cost));
+        };
+
+        let leads: Vec<bool> = self
+            .tight_hops(goal, &tight)
+            .iter()
+            .map(|h| h.is_some())
+            .collect();
+
+        // then enumerate forward; zero weight cycles are tight too, so paths stay simple
+        let mut paths = Vec::new();
+        let mut path = vec![start];
+        let mut next: Vec<Vec<NodeId>> = vec![self.tight_successors(start, &leads, &tight)];
+        while let Some(options) = next.last_mut() {
+            if paths.len() >= limit {
+                break;
+            }
+            let Some(node) = options.pop() else {
+                next.pop();
+                path.pop();
+                continue;
+            };
+            if path.contains(&node) {
+                continue;
+            }
+            path.push(node);
+            if node == goal {
+                paths.push(path.clone());
+                path.pop();
+                continue;
+            }
+            next.push(self.tight_successors(node, &leads, &tight));
+        }
+        return Some((cost, paths));
+    }
+
+    // the cheapest route from start to goal, picked out of the ties by `rule`. fewest hops walks
+    // the tight edges with one hop fewer to go each time, lowest label first; labels takes the
+    // first of all_shortest_paths, which come in node order
+    pub fn shortest_path_tie_break(
+        &self,
+        start: NodeId,
+        goal: NodeId,
+        rule: TieBreak,
+    ) -> Option<(Cost, Vec<NodeId>)> {
+        if rule == TieBreak::Search || !self.forbidden.is_empty() {
+            return self.shortest_path(start, goal);
+        }
+        if rule == TieBreak::Labels {
+            let (cost, mut paths) = self.all_shortest_paths(start, goal, 1)?;
+            return paths.pop().map(|path| (cost, path));
+        }
+        let dist = self.shortest_path_tree(start).ok()?.dist;
+        let cost = dist[goal.index()]?;
+        let tight = |from: NodeId, to: NodeId, cost: Cost| {
+            return dist[from.index()].is_some_and(|d| dist[to.index()] == Some(d + cost));
+        };
+        let hops = self.tight_hops(goal, &tight);
+        let mut path = vec![start];
+        while let Some(&at) = path.last().filter(|&&at| at != goal) {
+            let left = hops[at.index()]?;
+            let next = self
+                .edges_from(at)
+                .iter()
+                .filter(|e| tight(at, e.node, e.cost) && hops[e.node.index()] == Some(left - 1))
+                .map(|e| e.node)
+                .min()?;
+            path.push(next);
+        }
+        return Some((cost, path));
+    }
+
+    // how many tight edges each node is from the goal, for the nodes that reach it over them
+    fn tight_hops(
+        &self,
+        goal: NodeId,
+        tight: &impl Fn(NodeId, NodeId, Cost) -> bool,
+    ) -> Vec<Option<usize>> {
+        let mut hops = vec![None; self.nodes.len()];
+        hops[goal.index()] = Some(0);
+        let mut queue = VecDeque::from([goal]);
+        let reversed = self.reverse();
+        while let Some(node) = queue.pop_front() {
+            for edge in reversed.edges_from(node) {
+                if hops[edge.node.index()].is_none() && tight(edge.node, node, edge.cost) {
+                    hops[edge.node.index()] = hops[node.index()].map(|h| h + 1);
+                    queue.push_back(edge.node);
+                }
+            }
+        }
+        return hops;
+    }
+
+    // reversed so popping visits them in node order
+    fn tight_successors(
+        &self,
+        node: NodeId,
+        leads: &[bool],
+        tight: &impl Fn(NodeId, NodeId, Cost) -> bool,
+    ) -> Vec<NodeId> {
+        let mut out: Vec<NodeId> = self
+            .edges_from(node)
+            .iter()
+            .filter(|e| leads[e.node.index()] && tight(node, e.node, e.cost))
+            .map(|e| e.node)
+            .collect();
+        out.sort_unstable_by(|a, b| b.cmp(a));
+        out.dedup();
+        return out;
+    }
+
+    // kruskal's algorithm over the edges taken as undirected, cheapest first with ties broken in
+    // node order. every edge comes back in the order it was considered; rejected ones would have
+    // closed a cycle
+    pub fn mst_kruskal(&self) -> Vec<KruskalStep> {
+        let mut cheapest: HashMap<(NodeId, NodeId), Cost> = HashMap::new();
+        for (from, edge) in self.arcs() {
+            let key = (from.min(edge.node), from.max(edge.node));
+            let cost = cheapest.entry(key).or_insert(edge.cost);
+            *cost = (*cost).min(edge.cost);
+        }
+        let mut sorted: Vec<((NodeId, NodeId), Cost)> = cheapest.into_iter().collect();
+        sorted.sort_by_key(|&((a, b), cost)| (cost, a, b));
+
+        let mut sets = UnionFind::new(self.nodes.len());
+        return sorted
+            .into_iter()
+            .map(|((a, b), cost)| KruskalStep {
+                a,
+                b,
+                cost,
+                accepted: sets.union(a.index(), b.index()),
+            })
+            .collect();
+    }
+
+    // prim's algorithm with every edge taken as undirected, growing one tree per component, so a
+    // disconnected graph gets a spanning forest. the edges come as (tree node, new node) pairs
+    pub fn mst_prim(&self) -> (Cost, Vec<(NodeId, NodeId)>) {
+        let n = self.nodes.len();
+        let mut undirected = vec![vec![]; n];
+        for (from, edges) in self.nodes.iter().enumerate() {
+            for edge in edges {
+                undirected[from].push((edge.node.index(), edge.cost));
+                undirected[edge.node.index()].push((from, edge.cost));
+            }
+        }
+
+        let mut in_tree = vec![false; n];
+        let mut total = 0;
+        let mut tree = Vec::new();
+        for root in 0..n {
+            if in_tree[root] {
+                continue;
+            }
+            in_tree[root] = true;
+            let mut frontier: BinaryHeap<_> = undirected[root]
+                .iter()
+                .map(|&(to, cost)| Reverse((cost, root, to)))
+                .collect();
+            while let Some(Reverse((cost, from, to))) = frontier.pop() {
+                if in_tree[to] {
+                    continue;
+                }
+                in_tree[to] = true;
+                total += cost;
+                tree.push((NodeId(from), NodeId(to)));
+                for &(next, cost) in &undirected[to] {
+                    if !in_tree[next] {
+                        frontier.push(Reverse((cost, to, next)));
+                    }
+                }
+            }
+        }
+
+        return (total, tree);
+    }
+
+    // floyd-warshall: dist[i][j] is the cost from i to j, None when j can't be reached. turn bans
+    // are not considered, and a reachable negative cycle shows up as a negative diagonal entry
+    pub fn all_pairs_shortest_paths(&self) -> Vec<Vec<Option<Cost>>> {
+        let n = self.nodes.len();
+        let mut dist = vec![vec![None; n]; n];
+        for (from, edges) in self.nodes.iter().enumerate() {
+            dist[from][from] = Some(0);
+            for edge in edges {
+                let d = &mut dist[from][edge.node.index()];
+                *d = Some(d.map_or(edge.cost, |d: Cost| d.min(edge.cost)));
+            }
+        }
+
+        for k in 0..n {
+            let through = dist[k].clone();
+            for row in &mut dist {
+                let Some(ik) = row[k] else {
+                    continue;
+                };
+                for (d, kj) in row.iter_mut().zip(&through) {
+                    if let Some(kj) = *kj
+                        && d.is_none_or(|d| ik + kj < d)
+                    {
+                        *d = Some(ik + kj);
+                    }
+                }
+            }
+        }
+
+        return dist;
+    }
+
+    // a closed tour by the nearest neighbour heuristic: from `start`, always go on to the closest
+    // node not visited yet, by shortest-path distance, then back; `start` is repeated at the end.
+    // only the nodes start can reach and get back from are visited
+    pub fn tsp_nearest_neighbor(&self, start: NodeId) -> (Cost, Vec<NodeId>) {
+        let dist = self.all_pairs_shortest_paths();
+        let (s, dist_from) = (start.index(), &dist[start.index()]);
+        let mut left: Vec<NodeId> = self
+            .ids()
+            .filter(|&v| {
+                v != start && dist_from[v.index()].is_some() && dist[v.index()][s].is_some()
+            })
+            .collect();
+        let mut tour = vec![start];
+        // every node left is reachable from the last one, through start if nothing shorter
+        while let Some(i) =
+            (0..left.len()).min_by_key(|&i| dist[tour.last().unwrap().index()][left[i].index()])
+        {
+            tour.push(left.remove(i));
+        }
+        tour.push(start);
+        return (Graph::tour_cost(&dist, &tour), tour);
+    }
+
+    // 2-opt on a closed tour: reverse any stretch of it that makes the tour shorter, until no
+    // reversal helps. the ends stay put, and in a directed graph a reversed stretch is paid for
+    // in its new direction
+    pub fn tsp_two_opt(&self, tour: &[NodeId]) -> (Cost, Vec<NodeId>) {
+        let dist = self.all_pairs_shortest_paths();
+        let mut tour = tour.to_vec();
+        let mut cost = Graph::tour_cost(&dist, &tour);
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 1..tour.len().saturating_sub(2) {
+                for j in i + 1..tour.len() - 1 {
+                    tour[i..=j].reverse();
+                    let shorter = Graph::tour_cost(&dist, &tour);
+                    if shorter < cost {
+                        cost = shorter;
+                        improved = true;
+                    } else {
+                        tour[i..=j].reverse();
+                    }
+                }
+            }
+        }
+        return (cost, tour);
+    }
+
+    // the legs of a tour added up by shortest-path distance
+    fn tour_cost(dist: &[Vec<Option<Cost>>], tour: &[NodeId]) -> Cost {
+        return tour
+            .windows(2)
+            .map(|leg| dist[leg[0].index()][leg[1].index()].unwrap_or(0))
+            .sum();
+    }
+
+    // johnson: one bellman-ford from a virtual node tied to every node at 0 gives potentials that
+    // make every edge non-negative, then a dijkstra from each node runs over those weights and is
+    // shifted back. same matrix as floyd-warshall, or Err with a negative cycle as bellman_ford
+    // reports it. turn bans are not considered
+    pub fn johnson(&self) -> Result<Vec<Vec<Option<Cost>>>, Vec<NodeId>> {
+        let n = self.nodes.len();
+        let mut extended = Graph {
+            nodes: self.nodes.clone(),
+            ..Default::default()
+        };
+        extended.nodes.push(
+            (0..n)
+                .map(|node| Edge {
+                    node: NodeId(node),
+                    cost: 0,
+                    capacity: None,
+                    label: None,
+                })
+                .collect(),
+        );
+        let potential: Vec<Cost> = extended
+            .bellman_ford(NodeId(n))?
+            .dist
+            .iter()
+            .take(n)
+            .map(|d| d.unwrap())
+            .collect();
+
+        extended.nodes.pop();
+        for (from, edges) in extended.nodes.iter_mut().enumerate() {
+            for edge in edges {
+                edge.cost += potential[from] - potential[edge.node.index()];
+            }
+        }
+        return Ok((0..n)
+            .map(|from| {
+                extended
+                    .multi_source_shortest_paths(&[NodeId(from)])
+                    .iter()
+                    .enumerate()
+                    .map(|(to, d)| d.map(|(d, _)| d - potential[from] + potential[to]))
+                    .collect()
+            })
+            .collect());
+    }
+
+    // the share of the other nodes each node is joined to, by an edge either way; weights play
+    // no part
+    pub fn degree_centrality(&self) -> Vec<f32> {
+        let n = self.nodes.len();
+        if n < 2 {
+            return vec![0.0; n];
+        }
+        return self
+            .neighbour_sets()
+            .iter()
+            .map(|s| s.len() as f32 / (n - 1) as f32)
+            .collect();
+    }
+
+    // welsh-powell: the nodes in order of falling degree, ties to the lower id, each take the
+    // lowest class no neighbour has, edges taken as undirected. never more classes than the
+    // highest degree plus one, though not always the fewest
+    pub fn greedy_coloring(&self) -> Vec<usize> {
+        let neighbours = self.neighbour_sets();
+        let mut order: Vec<usize> = (0..self.nodes.len()).collect();
+        order.sort_by_key(|&v| (Reverse(neighbours[v].len()), v));
+        let mut classes: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        for v in order {
+            let taken: HashSet<usize> = neighbours[v].iter().filter_map(|&u| classes[u]).collect();
+            classes[v] = (0..).find(|c| !taken.contains(c));
+        }
+        return classes.into_iter().flatten().collect();
+    }
+
+    // every other node each node shares an edge with, either way
+    fn neighbour_sets(&self) -> Vec<HashSet<usize>> {
+        let mut neighbours: Vec<HashSet<usize>> = vec![HashSet::new(); self.nodes.len()];
+        for (from, edges) in self.nodes.iter().enumerate() {
+            for edge in edges.iter().filter(|e| e.node.index() != from) {
+                neighbours[from].insert(edge.node.index());
+                neighbours[edge.node.index()].insert(from);
+            }
+        }
+        return neighbours;
+    }
+
+    // the unconnected (low, high) pairs, nearest first by `distance` and then by id
+    fn missing_pairs(&self, distance: impl Fn(NodeId, NodeId) -> f32) -> Vec<(NodeId, NodeId)> {
+        let n = self.nodes.len();
+        let neighbours = self.neighbour_sets();
+        let mut pairs: Vec<(NodeId, NodeId)> = (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .filter(|(a, b)| !neighbours[*a].contains(b))
+            .map(|(a, b)| (NodeId(a), NodeId(b)))
+            .collect();
+        pairs.sort_by(|&(a, b), &(c, d)| distance(a, b).total_cmp(&distance(c, d)));
+        return pairs;
+    }
+
+    // the fewest new edges that join every component, as Kruskal picks them: the nearest pair
+    // bridging two components that nothing joins yet, over and over
+    pub fn connecting_edges(
+        &self,
+        distance: impl Fn(NodeId, NodeId) -> f32,
+    ) -> Vec<(NodeId, NodeId)> {
+        let mut sets = UnionFind::new(self.nodes.len());
+        for (from, edges) in self.nodes.iter().enumerate() {
+            for edge in edges {
+                sets.union(from, edge.node.index());
+            }
+        }
+        return self
+            .missing_pairs(distance)
+            .into_iter()
+            .filter(|&(a, b)| sets.union(a.index(), b.index()))
+            .collect();
+    }
+
+    // new edges until every node has at least `min_degree` neighbours, or no unconnected pair is
+    // left for it; nearest first, pairing two nodes that are both short wherever it can
+    pub fn degree_edges(
+        &self,
+        min_degree: usize,
+        distance: impl Fn(NodeId, NodeId) -> f32,
+    ) -> Vec<(NodeId, NodeId)> {
+        let mut degree: Vec<usize> = self.neighbour_sets().iter().map(|s| s.len()).collect();
+        let mut pairs = self.missing_pairs(distance);
+        let mut added = Vec::new();
+        for both in [true, false] {
+            pairs.retain(|&(a, b)| {
+                let (a_i, b_i) = (a.index(), b.index());
+                let (short_a, short_b) = (degree[a_i] < min_degree, degree[b_i] < min_degree);
+                let wanted = if both {
+                    short_a && short_b
+                } else {
+                    short_a || short_b
+                };
+                if wanted {
+                    degree[a_i] += 1;
+                    degree[b_i] += 1;
+                    added.push((a, b));
+                }
+                !wanted
+            });
+        }
+        return added;
+    }
+
+    // power iteration from a uniform start: each round a node keeps 1 - damping spread evenly over
+    // all nodes, and passes `damping` of its rank along its out-edges in equal shares. a node
+    // with no out-edges hands its share to every node, so the ranks always sum to 1. edges are
+    // followed as links and weights are ignored
+    pub fn pagerank(&self, damping: f32, iterations: usize) -> Vec<f32> {
+        let n = self.nodes.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut rank = vec![1.0 / n as f32; n];
+        for _ in 0..iterations {
+            let dangling: f32 = (0..n)
+                .filter(|&i| self.nodes[i].is_empty())
+                .map(|i| rank[i])
+                .sum();
+            let mut next = vec![(1.0 - damping + damping * dangling) / n as f32; n];
+            for (from, edges) in self.nodes.iter().enumerate() {
+                let share = damping * rank[from] / edges.len().max(1) as f32;
+                for edge in edges {
+                    next[edge.node.index()] += share;
+                }
+            }
+            rank = next;
+        }
+        return rank;
+    }
+
+    // each node's greatest distance to another node, and that farthest node (the lowest one on a
+    // tie); None where some node can't be reached, making the eccentricity infinite
+    pub fn eccentricities(&self) -> Vec<Option<(Cost, NodeId)>> {
+        return self
+            .all_pairs_shortest_paths()
+            .iter()
+            .enumerate()
+            .map(|(from, row)| {
+                let mut farthest = (0, NodeId(from));
+                for (to, d) in row.iter().enumerate() {
+                    let d = (*d)?;
+                    if d > farthest.0 {
+                        farthest = (d, NodeId(to));
+                    }
+                }
+                Some(farthest)
+            })
+            .collect();
+    }
+
+    // the largest eccentricity and the pair of nodes it spans; None for an empty graph or when
+    // some pair has no path
+    pub fn diameter(&self) -> Option<(Cost, NodeId, NodeId)> {
+        let mut widest: Option<(Cost, NodeId, NodeId)> = None;
+        for (from, eccentricity) in self.ids().zip(self.eccentricities()) {
+            let (d, to) = eccentricity?;
+            if widest.is_none_or(|(w, _, _)| d > w) {
+                widest = Some((d, from, to));
+            }
+        }
+        return widest;
+    }
+
+    // depth-first preorder from start, taking each node's edges in adjacency order
+    pub fn dfs_order(&self, start: NodeId) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![start];
+
+        while let Some(node) = stack.pop() {
+            if seen[node.index()] {
+                continue;
+            }
+            seen[node.index()] = true;
+            order.push(node);
+            // reversed so the first edge is the first one followed
+            for edge in self.edges_from(node).iter().rev() {
+                if !seen[edge.node.index()] {
+                    stack.push(edge.node);
+                }
+            }
+        }
+
+        return order;
+    }
+
+    // distances from start for every node no more than `budget` away; the search stops at the budget
+    pub fn reachable_within(&self, start: NodeId, budget: Cost) -> Vec<Option<Cost>> {
+        let mut dist: Vec<Option<Cost>> = vec![None; self.nodes.len()];
+        let mut settled = vec![false; self.nodes.len()];
+        let mut visited = BinaryHeap::new();
+
+        dist[start.index()] = Some(0);
+        visited.push(State {
+            position: start,
+            cost: 0,
+        });
+
+        while let Some(State { cost, position }) = visited.pop() {
+            if dist[position.index()].is_some_and(|d| cost > d) || settled[position.index()] {
+                continue;
+            }
+            settled[position.index()] = true;
+            for edge in &self.nodes[position.index()] {
+                let next = cost + edge.cost;
+                if next <= budget && dist[edge.node.index()].is_none_or(|d| next < d) {
+                    dist[edge.node.index()] = Some(next);
+                    visited.push(State {
+                        position: edge.node,
+                        cost: next,
+                    });
+                }
+            }
+        }
+        return dist;
+    }
+
+    // edges are compared per (from, to) pair; `changed` holds (from, to, old cost, new cost)
+    pub fn diff(&self, other: &Graph) -> GraphDiff {
+        let mut diff = GraphDiff::default();
+        let (n, m) = (self.nodes.len(), other.nodes.len());
+        diff.added_nodes = ids((n..m).collect());
+        diff.removed_nodes = ids((m..n).collect());
+
+        for (a, node) in self.nodes.iter().enumerate() {
+            let a_id = NodeId(a);
+            for edge in node {
+                let theirs = other
+                    .nodes
+                    .get(a)
+                    .and_then(|o| o.iter().find(|e| e.node == edge.node));
+                match theirs {
+                    None => diff.removed.push((a_id, edge.node, edge.cost)),
+                    Some(e) if e.cost != edge.cost => {
+                        diff.changed.push((a_id, edge.node, edge.cost, e.cost))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        for (a, node) in other.nodes.iter().enumerate() {
+            for edge in node {
+                let ours = self
+                    .nodes
+                    .get(a)
+                    .is_some_and(|o| o.iter().any(|e| e.node == edge.node));
+                if !ours {
+                    diff.added.push((NodeId(a), edge.node, edge.cost));
+                }
+            }
+        }
+
+        return diff;
+    }
+
+    fn cost_matrix(&self) -> Vec<Vec<Option<Cost>>> {
+        let n = self.nodes.len();
+        let mut matrix = vec![vec![None; n]; n];
+        for (a, node) in self.nodes.iter().enumerate() {
+            for edge in node {
+                let cell: &mut Option<Cost> = &mut matrix[a][edge.node.index()];
+                *cell = Some(cell.map_or(edge.cost, |c| c.min(edge.cost)));
+            }
+        }
+        return matrix;
+    }
+
+    fn degrees(matrix: &[Vec<Option<Cost>>]) -> Vec<(usize, usize)> {
+        return (0..matrix.len())
+            .map(|i| {
+                let out = matrix[i].iter().filter(|c| c.is_some()).count();
+                let inc = matrix.iter().filter(|row| row[i].is_some()).count();
+                (out, inc)
+            })
+            .collect();
+    }
+
+    // returns a mapping from our node ids to `other`'s, matching costs too when `weighted`
+    pub fn isomorphism(&self, other: &Graph, weighted: bool) -> Option<Vec<NodeId>> {
+        let n = self.nodes.len();
+        if n != other.nodes.len() {
+            return None;
+        }
+        let (a, b) = (self.cost_matrix(), other.cost_matrix());
+        let (deg_a, deg_b) = (Graph::degrees(&a), Graph::degrees(&b));
+        let (mut sorted_a, mut sorted_b) = (deg_a.clone(), deg_b);
+        sorted_a.sort_unstable();
+        sorted_b.sort_unstable();
+        if sorted_a != sorted_b {
+            return None;
+        }
+
+        // VF2 extends the mapping along connections, so visit nodes in BFS order from the best-connected ones
+        let mut order = Vec::with_capacity(n);
+        let mut seen = vec![false; n];
+        let mut roots: Vec<usize> = (0..n).collect();
+        roots.sort_by_key(|&i| std::cmp::Reverse(deg_a[i].0 + deg_a[i].1));
+        for root in roots {
+            if seen[root] {
+                continue;
+            }
+            seen[root] = true;
+            let mut queue = std::collections::VecDeque::from([root]);
+            while let Some(u) = queue.pop_front() {
+                order.push(u);
+                for w in 0..n {
+                    if !seen[w] && (a[u][w].is_some() || a[w][u].is_some()) {
+                        seen[w] = true;
+                        queue.push_back(w);
+                    }
+                }
+            }
+        }
+
+        let mut vf2 = Vf2 {
+            a: &a,
+            b: &b,
+            weighted,
+            order,
+            map: vec![None; n],
+            used: vec![false; n],
+        };
+        if !vf2.search(0) {
+            return None;
+        }
+        return vf2.map.into_iter().map(|m| m.map(NodeId)).collect();
+    }
+
+    pub fn is_isomorphic(&self, other: &Graph) -> bool {
+        return self.isomorphism(other, false).is_some();
+    }
+
+    pub fn is_weighted_isomorphic(&self, other: &Graph) -> bool {
+        return self.isomorphism(other, true).is_some();
+    }
+
+    pub fn dijkstra_table(&self, start: NodeId) -> Vec<TableRow> {
+        let n = self.nodes.len();
+        let mut dist = vec![Cost::MAX; n];
+        let mut settled = vec![false; n];
+        let mut visited = BinaryHeap::new();
+        let mut rows = Vec::new();
+
+        dist[start.index()] = 0;
+        visited.push(State {
+            position: start,
+            cost: 0,
+        });
+
+        while let Some(State { cost, position }) = visited.pop() {
+            if settled[position.index()] {
+                continue;
+            }
+            settled[position.index()] = true;
+
+            for edge in &self.nodes[position.index()] {
+                let next = cost + edge.cost;
+                if !settled[edge.node.index()] && next < dist[edge.node.index()] {
+                    dist[edge.node.index()] = next;
+                    visited.push(State {
+                        position: edge.node,
+                        cost: next,
+                    });
+                }
+            }
+
+            rows.push(TableRow {
+                settled: position,
+                dist: dist
+                    .iter()
+                    .map(|&d| (d != Cost::MAX).then_some(d))
+                    .collect(),
+            });
+        }
+        return rows;
+    }
+}
+
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match &self.label {
+            Some(label) => format!(", label {}", label),
+            None => String::new(),
+        };
+        let out = format!(
+            "Edge {{ node: {}, cost {}{} }}",
+            self.node, self.cost, label
+        );
+        return write!(f, "{}", out);
+    }
+}
+
+impl fmt::Display for Defect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match *self {
+            Defect::DanglingEdge { from, to } => {
+                write!(
+                    f,
+                    "{} has an edge to {}, which doesn't exist",
+                    from,
+                    to.index()
+                )
+            }
+            Defect::SelfLoop(node) => write!(f, "{} has an edge to itself", node),
+            Defect::ZeroWeight { from, to } => {
+                write!(f, "{} -> {} weighs zero", from, to)
+            }
+            Defect::NegativeCapacity { from, to } => {
+                write!(f, "{} -> {} has a negative capacity", from, to)
+            }
+            Defect::DuplicateEdge { from, to } => {
+                write!(f, "{} -> {} is there twice, exactly the same", from, to)
+            }
+            Defect::Asymmetric { a, b, lane } => write!(
+                f,
+                "lane {} of {} - {} differs one way from the other",
+                lane + 1,
+                a,
+                b
+            ),
+            Defect::BadTurnBan(p, v, q) => write!(
+                f,
+                "the turn ban {} is not along edges of the graph",
+                Graph::fmt_path(&[p, v, q])
+            ),
+            Defect::DuplicateTurnBan(p, v, q) => write!(
+                f,
+                "the turn ban {} is listed twice",
+                Graph::fmt_path(&[p, v, q])
+            ),
+        };
+    }
+}
+
+impl fmt::Display for AlgoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "settled: {}, relaxations: {}, heap ops: {}, max frontier: {}",
+            self.settled, self.relaxations, self.heap_ops, self.max_frontier
+        );
+    }
+}
+
+impl fmt::Display for AdjacencyMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cell = |cost: Cost| match cost {
+            0 => "-".to_string(),
+            cost => cost.to_string(),
+        };
+        let width = self
+            .0
+            .iter()
+            .flatten()
+            .map(|&c| cell(c).len())
+            .chain((0..self.0.len()).map(|i| Graph::node_label(i).len()))
+            .max()
+            .unwrap_or(1);
+        let mut out = format!("{:>width$}", "", width = width);
+        for i in 0..self.0.len() {
+            out.push_str(&format!(" {:>width$}", Graph::node_label(i), width = width));
+        }
+        for (i, row) in self.0.iter().enumerate() {
+            out.push_str(&format!(
+                "\n{:>width$}",
+                Graph::node_label(i),
+                width = width
+            ));
+            for &cost in row {
+                out.push_str(&format!(" {:>width$}", cell(cost), width = width));
+            }
+        }
+        return write!(f, "{}", out);
+    }
+}
+
+impl fmt::Display for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            out.push_str(&format!("{}: [ ", self.node_name(NodeId(i))));
+            for edge in node {
+                out.push_str(&format!("{}, ", edge));
+            }
+            out.push_str("]\n");
+        }
+
+        write!(f, "{}", out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id_pairs(pairs: &[(usize, usize)]) -> Vec<(NodeId, NodeId)> {
+        return pairs.iter().map(|&(a, b)| (NodeId(a), NodeId(b))).collect();
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let graph = Graph {
+            nodes: vec![
+                vec![
+                    Edge {
+                        node: NodeId(1),
+                        cost: 6,
+                        capacity: None,
+                        label: None,
+                    },
+                    Edge {
+                        node: NodeId(2),
+                        cost: 4,
+                        capacity: None,
+                        label: None,
+                    },
+                    Edge {
+                        node: NodeId(3),
+                        cost: 1,
+                        capacity: None,
+                        label: None,
+                    },
+                ],
+                vec![
+                    Edge {
+                        node: NodeId(0),
+                        cost: 6,
+                        capacity: None,
+                        label: None,
+                    },
+                    Edge {
+                        node: NodeId(2),
+                        cost: 3,
+                        capacity: None,
+                        label: None,
+                    },
+                ],
+                vec![
+                    Edge {
+                        node: NodeId(0),
+                        cost: 4,
+                        capacity: None,
+                        label: None,
+                    },
+                    Edge {
+                        node: NodeId(1),
+                        cost: 3,
+                        capacity: None,
+                        label: None,
+                    },
+                    Edge {
+                        node: NodeId(3),
+                        cost: 1,
+                        capacity: None,
+                        label: None,
+                    },
+                ],
+                vec![
+                    Edge {
+                        node: NodeId(0),
+                        cost: 1,
+                        capacity: None,
+                        label: None,
+                    },
+                    Edge {
+                        node: NodeId(2),
+                        cost: 1,
+                        capacity: None,
+                        label: None,
+                    },
+                ],
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(1)),
+            Some((5, ids(vec![0, 3, 2, 1])))
+        );
+
+        let (_, stats) = graph.shortest_path_with_stats(NodeId(0), NodeId(1));
+        assert_eq!(stats.settled, 4);
+        assert_eq!(stats.relaxations, 8);
+
+        let trace = graph.shortest_path_trace(NodeId(0), NodeId(1));
+        let pops: Vec<NodeId> = trace
+            .events
+            .iter()
+            .filter_map(|e| match e.event {
+                TraceEvent::Pop { node, stale, .. } if !stale => Some(node),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(pops, ids(vec![0, 3, 2, 1]));
+        assert!(trace.events.windows(2).all(|w| w[0].t_ns <= w[1].t_ns));
+
+        let bidirectional = graph.bidirectional_trace(NodeId(0), NodeId(1));
+        assert_eq!(bidirectional.result, Some((5, ids(vec![0, 3, 2, 1]))));
+        assert!(bidirectional.events.len() <= trace.events.len());
+        for a in 0..4 {
+            for b in 0..4 {
+                let cost = graph
+                    .bidirectional_trace(NodeId(a), NodeId(b))
+                    .result
+                    .map(|(c, _)| c);
+                assert_eq!(
+                    cost,
+                    graph.shortest_path(NodeId(a), NodeId(b)).map(|(c, _)| c)
+                );
+            }
+        }
+
+        let table = graph.dijkstra_table(NodeId(0));
+        let order: Vec<NodeId> = table.iter().map(|row| row.settled).collect();
+        assert_eq!(order, ids(vec![0, 3, 2, 1]));
+        assert_eq!(table[0].dist, vec![Some(0), Some(6), Some(4), Some(1)]);
+        assert_eq!(table[3].dist, vec![Some(0), Some(5), Some(2), Some(1)]);
+    }
+
+    #[test]
+    fn test_all_pairs() {
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 6, 4, 1],
+            vec![6, 0, 3, 0],
+            vec![4, 3, 0, 1],
+            vec![1, 0, 1, 0],
+        ]);
+        graph.nodes.push(vec![]);
+        let dist = graph.all_pairs_shortest_paths();
+        for (from, row) in dist.iter().enumerate().take(4) {
+            for (to, &d) in row.iter().enumerate().take(4) {
+                assert_eq!(
+                    d,
+                    graph
+                        .shortest_path(NodeId(from), NodeId(to))
+                        .map(|(cost, _)| cost)
+                );
+            }
+            assert_eq!(row[4], None);
+            assert_eq!(dist[4][from], None);
+        }
+        assert_eq!(dist[4][4], Some(0));
+
+        // a directed negative cycle B -> C -> B
+        let mut negative =
+            Graph::from_adjacency_matrix(vec![vec![0, 1, 0], vec![0, 0, -3], vec![0, 1, 0]]);
+        assert!(negative.all_pairs_shortest_paths()[1][1].is_some_and(|d| d < 0));
+        negative.nodes[1][0].cost = 3;
+        assert_eq!(negative.all_pairs_shortest_paths()[0][2], Some(4));
+    }
+
+    #[test]
+    fn test_shortest_path_tree() {
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 6, 4, 1],
+            vec![6, 0, 3, 0],
+            vec![4, 3, 0, 1],
+            vec![1, 0, 1, 0],
+        ]);
+        graph.nodes.push(vec![]);
+        let tree = graph.shortest_path_tree(NodeId(0)).unwrap();
+        assert_eq!(tree.dist, vec![Some(0), Some(5), Some(2), Some(1), None]);
+        assert_eq!(tree.tree_edges(), id_pairs(&[(2, 1), (3, 2), (0, 3)]));
+        for goal in 0..4 {
+            assert_eq!(
+                tree.path_to(NodeId(0), NodeId(goal)),
+                graph.shortest_path(NodeId(0), NodeId(goal))
+            );
+        }
+
+        // a negative weight goes through bellman-ford
+        let negative = Graph::from_adjacency_matrix(vec![
+            vec![0, 3, 0, 1],
+            vec![0, 0, 1, 0],
+            vec![0, 0, 0, 0],
+            vec![0, -2, 0, 0],
+        ]);
+        let tree = negative.shortest_path_tree(NodeId(0)).unwrap();
+        assert_eq!(tree.dist, vec![Some(0), Some(-1), Some(0), Some(1)]);
+        assert_eq!(tree.tree_edges(), id_pairs(&[(3, 1), (1, 2), (0, 3)]));
+    }
+
+    #[test]
+    fn test_repair_shortest_path_tree() {
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 6, 4, 1],
+            vec![6, 0, 3, 0],
+            vec![4, 3, 0, 1],
+            vec![1, 0, 1, 0],
+        ]);
+        graph.nodes.push(vec![]);
+        let mut tree = graph.shortest_path_tree(NodeId(0)).unwrap();
+
+        // D - C was on the tree: C and B below it are re-settled, through A - C
+        graph.lane_mut(NodeId(3), NodeId(2), 0).unwrap().cost = 9;
+        graph.lane_mut(NodeId(2), NodeId(3), 0).unwrap().cost = 9;
+        let settled = graph.repair_shortest_path_tree(
+            NodeId(0),
+            &mut tree,
+            &[(NodeId(3), NodeId(2)), (NodeId(2), NodeId(3))],
+        );
+        assert_eq!(settled, Some(2));
+        assert_eq!(tree.dist, graph.shortest_path_tree(NodeId(0)).unwrap().dist);
+        assert_eq!(tree.prev[2], Some(NodeId(0)));
+
+        // a cheaper new edge pulls the unreached E in
+        graph.add_lane(NodeId(1), NodeId(4), 2, true);
+        graph.repair_shortest_path_tree(
+            NodeId(0),
+            &mut tree,
+            &[(NodeId(1), NodeId(4)), (NodeId(4), NodeId(1))],
+        );
+        assert_eq!(tree.dist, graph.shortest_path_tree(NodeId(0)).unwrap().dist);
+        assert_eq!(
+            tree.path_to(NodeId(0), NodeId(4)),
+            graph.shortest_path(NodeId(0), NodeId(4))
+        );
+
+        // a removed tree edge leaves what is only reached through it unreachable
+        graph.remove_edge(NodeId(1), NodeId(4));
+        graph.repair_shortest_path_tree(
+            NodeId(0),
+            &mut tree,
+            &[(NodeId(1), NodeId(4)), (NodeId(4), NodeId(1))],
+        );
+        assert_eq!(tree.dist[4], None);
+        assert_eq!(tree.dist, graph.shortest_path_tree(NodeId(0)).unwrap().dist);
+
+        graph.lane_mut(NodeId(0), NodeId(1), 0).unwrap().cost = -1;
+        assert_eq!(
+            graph.repair_shortest_path_tree(NodeId(0), &mut tree, &[(NodeId(0), NodeId(1))]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_all_shortest_paths() {
+        // two equal diamonds in a row make four ties; the direct A -> E edge costs more
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 0, 5],
+            vec![0, 0, 0, 1, 0],
+            vec![0, 0, 0, 1, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+        ]);
+        assert_eq!(
+            graph.all_shortest_paths(NodeId(0), NodeId(3), 10),
+            Some((2, vec![ids(vec![0, 1, 3]), ids(vec![0, 2, 3])]))
+        );
+        assert_eq!(
+            graph.all_shortest_paths(NodeId(0), NodeId(3), 1),
+            Some((2, vec![ids(vec![0, 1, 3])]))
+        );
+        assert_eq!(
+            graph.all_shortest_paths(NodeId(0), NodeId(4), 10),
+            Some((5, vec![ids(vec![0, 4])]))
+        );
+        assert_eq!(graph.all_shortest_paths(NodeId(3), NodeId(0), 10), None);
+        assert_eq!(
+            graph.all_shortest_paths(NodeId(2), NodeId(2), 10),
+            Some((0, vec![ids(vec![2])]))
+        );
+
+        let mut doubled = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 0, 0, 0],
+            vec![0, 0, 0, 1, 0, 0],
+            vec![0, 0, 0, 1, 0, 0],
+            vec![0, 0, 0, 0, 2, 2],
+            vec![0, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 0],
+        ]);
+        doubled.nodes[4].push(Edge {
+            node: NodeId(5),
+            cost: 0,
+            capacity: None,
+            label: None,
+        });
+        doubled.nodes[5].push(Edge {
+            node: NodeId(4),
+            cost: 0,
+            capacity: None,
+            label: None,
+        });
+        // a zero weight loop between E and F is tight both ways but never walked twice
+        let (cost, paths) = doubled
+            .all_shortest_paths(NodeId(0), NodeId(5), 10)
+            .unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(
+            paths,
+            vec![
+                ids(vec![0, 1, 3, 4, 5]),
+                ids(vec![0, 1, 3, 5]),
+                ids(vec![0, 2, 3, 4, 5]),
+                ids(vec![0, 2, 3, 5]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_johnson() {
+        // the negative detour A -> D -> B from test_bellman_ford, and an unreachable E
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 3, 0, 1],
+            vec![0, 0, 1, 0],
+            vec![0, 0, 0, 0],
+            vec![0, -2, 0, 0],
+        ]);
+        graph.nodes.push(vec![]);
+        let dist = graph.johnson().unwrap();
+        assert_eq!(dist, graph.all_pairs_shortest_paths());
+        assert_eq!(dist[0], vec![Some(0), Some(-1), Some(0), Some(1), None]);
+
+        // C -> D closes a negative cycle, which johnson refuses
+        graph.nodes[2].push(Edge {
+            node: NodeId(3),
+            cost: 0,
+            capacity: None,
+            label: None,
+        });
+        let mut cycle = graph.johnson().unwrap_err();
+        let at = cycle.iter().position(|&n| n == NodeId(1)).unwrap();
+        cycle.rotate_left(at);
+        assert_eq!(cycle, ids(vec![1, 2, 3]));
+        assert_eq!(Graph::default().johnson(), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_mst_prim() {
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 6, 4, 1],
+            vec![6, 0, 3, 0],
+            vec![4, 3, 0, 1],
+            vec![1, 0, 1, 0],
+        ]);
+        assert_eq!(graph.mst_prim(), (5, id_pairs(&[(0, 3), (3, 2), (2, 1)])));
+
+        // a one-way edge still joins the tree, and an isolated pair gets its own
+        graph.nodes.push(vec![Edge {
+            node: NodeId(5),
+            cost: 2,
+            capacity: None,
+            label: None,
+        }]);
+        graph.nodes.push(vec![]);
+        graph.nodes[1].push(Edge {
+            node: NodeId(3),
+            cost: -1,
+            capacity: None,
+            label: None,
+        });
+        assert_eq!(
+            graph.mst_prim(),
+            (3, id_pairs(&[(0, 3), (3, 1), (3, 2), (4, 5)]))
+        );
+    }
+
+    #[test]
+    fn test_mst_kruskal() {
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 6, 4, 1],
+            vec![6, 0, 3, 0],
+            vec![4, 3, 0, 1],
+            vec![1, 0, 1, 0],
+        ]);
+        graph.nodes.push(vec![]);
+        let steps = graph.mst_kruskal();
+        let considered: Vec<(usize, usize, bool)> = steps
+            .iter()
+            .map(|s| (s.a.index(), s.b.index(), s.accepted))
+            .collect();
+        // the two cost-1 edges tie and go in node order; A - C would close A - D - C
+        assert_eq!(
+            considered,
+            vec![
+                (0, 3, true),
+                (2, 3, true),
+                (1, 2, true),
+                (0, 2, false),
+                (0, 1, false)
+            ]
+        );
+        let total: Cost = steps.iter().filter(|s| s.accepted).map(|s| s.cost).sum();
+        assert_eq!(total, graph.mst_prim().0);
+    }
+
+    #[test]
+    fn test_dfs() {
+        // A - B - D, A - C, C - D, and E on its own
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 0, 0],
+            vec![1, 0, 0, 1, 0],
+            vec![1, 0, 0, 1, 0],
+            vec![0, 1, 1, 0, 0],
+            vec![0, 0, 0, 0, 0],
+        ]);
+        // D is reached through B, so C comes last, from D rather than from A
+        assert_eq!(graph.dfs_order(NodeId(0)), ids(vec![0, 1, 3, 2]));
+        assert_eq!(graph.dfs_order(NodeId(2)), ids(vec![2, 0, 1, 3]));
+        assert_eq!(graph.dfs_order(NodeId(4)), ids(vec![4]));
+    }
+
+    #[test]
+    fn test_multi_source() {
+        // A - B - C - D - E with unit weights except a heavy C-D edge
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 0, 0],
+            vec![1, 0, 1, 0, 0],
+            vec![0, 1, 0, 5, 0],
+            vec![0, 0, 5, 0, 1],
+            vec![0, 0, 0, 1, 0],
+        ]);
+        let field = graph.multi_source_shortest_paths(&[NodeId(0), NodeId(4)]);
+        assert_eq!(
+            field,
+            vec![
+                Some((0, NodeId(0))),
+                Some((1, NodeId(0))),
+                Some((2, NodeId(0))),
+                Some((1, NodeId(4))),
+                Some((0, NodeId(4))),
+            ]
+        );
+
+        let mut split = graph.clone();
+        split.nodes[2].clear();
+        split.nodes[3].retain(|e| e.node != NodeId(2));
+        split.nodes[1].retain(|e| e.node != NodeId(2));
+        assert_eq!(split.multi_source_shortest_paths(&[NodeId(0)])[2], None);
+        assert_eq!(graph.multi_source_shortest_paths(&[]), vec![None; 5]);
+
+        assert_eq!(
+            graph.reachable_within(NodeId(0), 2),
+            vec![Some(0), Some(1), Some(2), None, None]
+        );
+        assert_eq!(graph.reachable_within(NodeId(4), 6)[2], Some(6));
+        assert_eq!(
+            graph.reachable_within(NodeId(4), 0),
+            vec![None, None, None, None, Some(0)]
+        );
+    }
+
+    #[test]
+    fn test_random_step() {
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 3, 0],
+            vec![0, 0, 0, 0],
+            vec![0, 0, 0, 0],
+            vec![-1, 0, 0, 0],
+        ]);
+        let mut rng = Rng::new(7);
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            counts[graph.random_step(NodeId(0), &mut rng).unwrap().index()] += 1;
+        }
+        // three times as likely to take the weight 3 edge
+        assert_eq!(counts[0] + counts[3], 0);
+        assert!((2800..3200).contains(&counts[2]), "{:?}", counts);
+        assert_eq!(graph.random_step(NodeId(1), &mut rng), None);
+        // without a positive weight every edge is as likely
+        assert_eq!(graph.random_step(NodeId(3), &mut rng), Some(NodeId(0)));
+    }
+
+    #[test]
+    fn test_fail_random_edge() {
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 0],
+            vec![1, 0, 1, 0],
+            vec![1, 1, 0, 0],
+            vec![0, 0, 1, 0],
+        ]);
+        graph.forbidden = vec![(NodeId(3), NodeId(2), NodeId(0))];
+        let mut rng = Rng::new(3);
+        let mut failed = Vec::new();
+        while let Some(pair) = graph.fail_random_edge(&mut rng) {
+            failed.push(pair);
+            // a two-way edge goes both ways at once
+            assert!(!graph.has_edge(pair.0, pair.1) && !graph.has_edge(pair.1, pair.0));
+            assert_eq!(graph.connection_count(), 4 - failed.len());
+        }
+        failed.sort();
+        assert_eq!(failed, id_pairs(&[(0, 1), (0, 2), (1, 2), (3, 2)]));
+        assert!(graph.forbidden.is_empty());
+
+        // the same seed knocks out the same edges in the same order
+        let run = |seed: u64| -> Vec<(NodeId, NodeId)> {
+            let matrix = (0..5)
+                .map(|a| (0..5).map(|b| (a != b) as Cost).collect())
+                .collect();
+            let mut graph = Graph::from_adjacency_matrix(matrix);
+            let mut rng = Rng::new(seed);
+            return std::iter::from_fn(|| graph.fail_random_edge(&mut rng)).collect();
+        };
+        assert_eq!(run(11), run(11));
+        assert_ne!(run(11), run(12));
+    }
+
+    #[test]
+    fn test_congested() {
+        // two equally good ways from A to D, through B or through C
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 10, 10, 0],
+            vec![10, 0, 0, 10],
+            vec![10, 0, 0, 10],
+            vec![0, 10, 10, 0],
+        ]);
+        let mut usage: HashMap<(NodeId, NodeId), usize> = HashMap::new();
+        let mut routes = Vec::new();
+        for _ in 0..4 {
+            let (_, path) = graph
+                .congested(&usage, 0.1)
+                .shortest_path(NodeId(0), NodeId(3))
+                .unwrap();
+            for hop in path.windows(2) {
+                *usage.entry((hop[0], hop[1])).or_insert(0) += 1;
+            }
+            routes.push(path);
+        }
+        // each run makes the route it took dearer, so the next one takes the other
+        assert_ne!(routes[0], routes[1]);
+        assert_ne!(routes[2], routes[3]);
+        let pairs = id_pairs(&[(0, 1), (0, 2)]);
+        assert_eq!((usage[&pairs[0]], usage[&pairs[1]]), (2, 2));
+        let congested = graph.congested(&usage, 0.1);
+        assert_eq!(congested.nodes[0][0].cost, 12);
+        // the other direction saw no traffic
+        assert_eq!(congested.nodes[1][0].cost, 10);
+        assert_eq!(
+            graph.congested(&usage, 0.0).content_hash(),
+            graph.content_hash()
+        );
+    }
+
+    #[test]
+    fn test_wilson_spanning_tree() {
+        let graph = Graph::from_adjacency_matrix(
+            (0..6)
+                .map(|a| (0..6).map(|b| (a != b) as Cost).collect())
+                .collect(),
+        );
+        let tree = |steps: &[WilsonStep]| -> Vec<(NodeId, NodeId)> {
+            return steps
+                .iter()
+                .filter_map(|s| match s {
+                    WilsonStep::Join(walk) => Some(walk.windows(2).map(|h| (h[0], h[1]))),
+                    _ => None,
+                })
+                .flatten()
+                .collect();
+        };
+        let steps = graph.wilson_spanning_tree(NodeId(2), &mut Rng::new(7));
+        assert_eq!(
+            steps,
+            graph.wilson_spanning_tree(NodeId(2), &mut Rng::new(7))
+        );
+        let edges = tree(&steps);
+        assert_eq!(edges.len(), 5);
+        // one edge out of every node but the root, and following them always ends at the root
+        for v in 0..6 {
+            let mut at = v;
+            for _ in 0..6 {
+                if at == 2 {
+                    break;
+                }
+                let out: Vec<usize> = edges
+                    .iter()
+                    .filter(|e| e.0.index() == at)
+                    .map(|e| e.1.index())
+                    .collect();
+                assert_eq!(out.len(), 1);
+                at = out[0];
+            }
+            assert_eq!(at, 2);
+        }
+        // replaying the hops and erasures gives back each joining walk
+        let mut walk: Vec<NodeId> = Vec::new();
+        for step in &steps {
+            match step {
+                WilsonStep::Hop(from, to) => {
+                    if walk.is_empty() {
+                        walk.push(*from);
+                    }
+                    assert_eq!(walk.last(), Some(from));
+                    walk.push(*to);
+                }
+                WilsonStep::Erase(erased) => {
+                    assert_eq!(erased.first(), erased.last());
+                    walk.truncate(walk.len() - erased.len() + 1);
+                }
+                WilsonStep::Join(joined) => {
+                    assert_eq!(&walk, joined);
+                    walk.clear();
+                }
+            }
+        }
+        let seeds =
+            (0..20).map(|seed| tree(&graph.wilson_spanning_tree(NodeId(2), &mut Rng::new(seed))));
+        assert!(seeds.collect::<HashSet<_>>().len() > 1);
+
+        // B can't get back to A, and C is on its own
+        let mut graph =
+            Graph::from_adjacency_matrix(vec![vec![0, 1, 0], vec![0, 0, 0], vec![0, 0, 0]]);
+        assert_eq!(
+            graph.wilson_spanning_tree(NodeId(0), &mut Rng::new(1)),
+            vec![]
+        );
+        graph.nodes[1].push(Edge {
+            node: NodeId(0),
+            cost: 0,
+            capacity: None,
+            label: None,
+        });
+        assert_eq!(
+            graph.wilson_spanning_tree(NodeId(0), &mut Rng::new(1)),
+            vec![
+                WilsonStep::Hop(NodeId(1), NodeId(0)),
+                WilsonStep::Join(ids(vec![1, 0]))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let graph = Graph::from_adjacency_matrix(vec![vec![0, 4, 1], vec![4, 0, 2], vec![1, 2, 0]]);
+        // pinned, so anything that changes the hashing and breaks old stamps fails here
+        assert_eq!(graph.fmt_hash(), "0867f155f0bae771");
+
+        // the same edges added in another order
+        let mut shuffled = graph.clone();
+        for edges in &mut shuffled.nodes {
+            edges.reverse();
+        }
+        assert_eq!(shuffled.content_hash(), graph.content_hash());
+
+        let mut heavier = graph.clone();
+        heavier.nodes[0][0].cost += 1;
+        let mut capped = graph.clone();
+        capped.nodes[0][0].capacity = Some(4);
+        let mut banned = graph.clone();
+        banned.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        let mut bigger = graph.clone();
+        bigger.nodes.push(vec![]);
+        // only a name or a detail differs
+        let mut labelled = graph.clone();
+        labelled.node_labels.insert(NodeId(1), "Home".to_string());
+        let mut detailed = graph.clone();
+        detailed.node_metadata.insert(
+            NodeId(1),
+            BTreeMap::from([("pop".to_string(), "5000".to_string())]),
+        );
+        let mut named = graph.clone();
+        named.set_lane_label(NodeId(0), NodeId(1), 0, Some("I-95".to_string()));
+        let mut renamed = labelled.clone();
+        renamed.node_labels.insert(NodeId(1), "Work".to_string());
+        assert_ne!(renamed.content_hash(), labelled.content_hash());
+        for other in [heavier, capped, banned, bigger, labelled, detailed, named] {
+            assert_ne!(other.content_hash(), graph.content_hash());
+        }
+    }
+
+    #[test]
+    fn test_centrality() {
+        // a star around A, plus a one-way edge D -> B
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 1],
+            vec![1, 0, 0, 0],
+            vec![1, 0, 0, 0],
+            vec![1, 0, 0, 0],
+        ]);
+        graph.nodes[3].push(Edge {
+            node: NodeId(1),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        let centrality = graph.degree_centrality();
+        assert_eq!(centrality, vec![1.0, 2.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0]);
+
+        let rank = graph.pagerank(0.85, 100);
+        assert!((rank.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        // the hub ranks first, and B gets more than C from D's extra link
+        assert!(rank[0] > rank[1] && rank[1] > rank[2]);
+        assert!((rank[2] - rank[3]).abs() < 1e-4, "{:?}", rank);
+
+        // with no links at all every node ranks the same
+        let empty = Graph {
+            nodes: vec![vec![]; 4],
+            ..Default::default()
+        };
+        assert_eq!(empty.pagerank(0.85, 10), vec![0.25; 4]);
+        assert_eq!(empty.degree_centrality(), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn test_merge_nodes() {
+        // D duplicates B: A reaches both, both reach C, and a short edge joins them
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 5, 0, 3],
+            vec![5, 0, 2, 1],
+            vec![0, 2, 0, 4],
+            vec![3, 1, 4, 0],
+        ]);
+        graph.forbidden = vec![
+            (NodeId(0), NodeId(3), NodeId(2)),
+            (NodeId(1), NodeId(3), NodeId(0)),
+        ];
+        let detail = |key: &str, value: &str| (key.to_string(), value.to_string());
+        graph.node_labels = BTreeMap::from([
+            (NodeId(1), "Depot".to_string()),
+            (NodeId(2), "Home".to_string()),
+            (NodeId(3), "Depot 2".to_string()),
+        ]);
+        graph.node_metadata = BTreeMap::from([
+            (NodeId(1), BTreeMap::from([detail("zone", "north")])),
+            (
+                NodeId(3),
+                BTreeMap::from([detail("zone", "south"), detail("pop", "5")]),
+            ),
+        ]);
+        let remap = graph.merge_nodes(NodeId(1), NodeId(3));
+        assert_eq!(
+            remap,
+            [Some(0), Some(1), Some(2), Some(1)].map(|v| v.map(NodeId))
+        );
+        let edges: Vec<Vec<(usize, Cost)>> = graph
+            .nodes
+            .iter()
+            .map(|edges| edges.iter().map(|e| (e.node.index(), e.cost)).collect())
+            .collect();
+        assert_eq!(
+            edges,
+            vec![vec![(1, 3)], vec![(0, 3), (2, 2)], vec![(1, 2)]]
+        );
+        // the ban through D now runs through B; the one from B through D back to A is gone
+        assert_eq!(graph.forbidden, vec![(NodeId(0), NodeId(1), NodeId(2))]);
+        // B came first, so its label and zone win over D's
+        assert_eq!(graph.node_name(NodeId(1)), "Depot");
+        assert_eq!(graph.node_name(NodeId(0)), "A");
+        assert_eq!(
+            graph.describe_node(NodeId(1)).as_deref(),
+            Some("Depot; pop = 5, zone = north")
+        );
+        assert_eq!(graph.find_node_label("home"), Some(NodeId(2)));
+
+        // labels are only written when there are some
+        let json = serde_json::to_string(&graph).unwrap();
+        let back: Graph = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.node_labels, graph.node_labels);
+        graph.retain_nodes(&[true, false, true]);
+        assert_eq!(
+            graph.node_labels,
+            BTreeMap::from([(NodeId(1), "Home".to_string())])
+        );
+        assert!(graph.node_metadata.is_empty());
+        graph.node_labels.clear();
+        assert!(
+            !serde_json::to_string(&graph)
+                .unwrap()
+                .contains("node_labels")
+        );
+    }
+
+    #[test]
+    fn test_suggested_edges() {
+        // a path 0 - 1 and two loose nodes, spaced out along a line
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0; 4]; 4]);
+        graph.nodes[0].push(Edge {
+            node: NodeId(1),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        let x: [f32; 4] = [0.0, 1.0, 2.0, 5.0];
+        let distance = |a: NodeId, b: NodeId| -> f32 { (x[a.index()] - x[b.index()]).abs() };
+        assert_eq!(
+            graph.connecting_edges(distance),
+            id_pairs(&[(1, 2), (2, 3)])
+        );
+        // 1 - 2 and 0 - 2 pair up nodes that are both short; 3 then takes the nearest it can get
+        assert_eq!(
+            graph.degree_edges(2, distance),
+            id_pairs(&[(1, 2), (0, 2), (2, 3), (1, 3)])
+        );
+        assert!(graph.degree_edges(0, distance).is_empty());
+    }
+
+    #[test]
+    fn test_euler_path() {
+        // a square with one diagonal: B and D have degree 3, so the trail runs between them
+        let square = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 1],
+            vec![1, 0, 1, 1],
+            vec![0, 1, 0, 1],
+            vec![1, 1, 1, 0],
+        ]);
+        let trail = square.euler_path(false, None).unwrap();
+        assert_eq!(trail, ids(vec![1, 0, 3, 1, 2, 3]));
+        assert_eq!(
+            square.euler_path(false, Some(NodeId(3))).unwrap()[0],
+            NodeId(3)
+        );
+        // starting anywhere else still starts at an odd node
+        assert_eq!(
+            square.euler_path(false, Some(NodeId(0))).unwrap()[0],
+            NodeId(1)
+        );
+        // every two-way edge is two arcs, so directed it's a circuit
+        let circuit = square.euler_path(true, Some(NodeId(2))).unwrap();
+        assert_eq!(circuit.len(), 11);
+        assert_eq!((circuit[0], circuit[10]), (NodeId(2), NodeId(2)));
+
+        let mut star = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 1],
+            vec![1, 0, 0, 0],
+            vec![1, 0, 0, 0],
+            vec![1, 0, 0, 0],
+        ]);
+        assert_eq!(
+            star.euler_path(false, None),
+            Err(NoEuler::OddDegrees(ids(vec![0, 1, 2, 3])))
+        );
+        star.nodes.push(Vec::new());
+        star.nodes.push(Vec::new());
+        star.nodes[4].push(Edge {
+            node: NodeId(5),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        assert_eq!(star.euler_path(true, None), Err(NoEuler::Disconnected));
+        assert_eq!(
+            Graph::from_adjacency_matrix(vec![vec![0; 2]; 2]).euler_path(false, None),
+            Err(NoEuler::NoEdges)
+        );
+
+        // two parallel A - B edges and a B - C one: B and C are odd, so the trail runs from C over
+        // both lanes and ends at B
+        let mut parallel = Graph::from_adjacency_matrix(vec![vec![0; 3]; 3]);
+        parallel.add_lane(NodeId(0), NodeId(1), 1, true);
+        parallel.add_lane(NodeId(0), NodeId(1), 2, true);
+        parallel.add_lane(NodeId(1), NodeId(2), 1, true);
+        assert_eq!(
+            parallel.euler_path(false, Some(NodeId(2))).unwrap(),
+            ids(vec![2, 1, 0, 1])
+        );
+        // directed, each lane is two arcs, so four arcs at A - B and two at B - C
+        assert_eq!(parallel.euler_path(true, None).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_tsp() {
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 4, 5, 8, 9],
+            vec![4, 0, 7, 4, 8],
+            vec![5, 7, 0, 5, 6],
+            vec![8, 4, 5, 0, 8],
+            vec![9, 8, 6, 8, 0],
+        ]);
+        // an unreachable node is left out of the tour
+        graph.nodes.push(Vec::new());
+        let (cost, tour) = graph.tsp_nearest_neighbor(NodeId(0));
+        assert_eq!((cost, tour.clone()), (28, ids(vec![0, 1, 3, 2, 4, 0])));
+        // greed saves the long way back for last; swapping C and E fixes it
+        assert_eq!(graph.tsp_two_opt(&tour), (27, ids(vec![0, 1, 3, 4, 2, 0])));
+        assert_eq!(graph.tsp_nearest_neighbor(NodeId(5)), (0, ids(vec![5, 5])));
+        assert_eq!(
+            graph.tsp_two_opt(&[NodeId(5), NodeId(5)]),
+            (0, ids(vec![5, 5]))
+        );
+    }
+
+    #[test]
+    fn test_greedy_coloring() {
+        let ring = |n: usize| -> Graph {
+            let matrix = (0..n)
+                .map(|a| {
+                    (0..n)
+                        .map(|b| ((a + 1) % n == b || (b + 1) % n == a) as Cost)
+                        .collect()
+                })
+                .collect();
+            return Graph::from_adjacency_matrix(matrix);
+        };
+        let check = |graph: &Graph, colors: usize| {
+            let classes = graph.greedy_coloring();
+            for (a, edges) in graph.nodes.iter().enumerate() {
+                assert!(edges.iter().all(|e| classes[a] != classes[e.node.index()]));
+            }
+            assert_eq!(classes.iter().max().map_or(0, |c| c + 1), colors);
+        };
+        check(&ring(4), 2);
+        // an odd ring can't be done in two
+        check(&ring(5), 3);
+        let complete = (0..4)
+            .map(|a| (0..4).map(|b| (a != b) as Cost).collect())
+            .collect();
+        check(&Graph::from_adjacency_matrix(complete), 4);
+        check(&Graph::from_adjacency_matrix(vec![vec![0; 3]; 3]), 1);
+        // the busiest node goes first and takes the first class
+        let star = Graph::from_adjacency_matrix(vec![
+            vec![0, 0, 0, 1],
+            vec![0, 0, 0, 1],
+            vec![0, 0, 0, 1],
+            vec![1, 1, 1, 0],
+        ]);
+        assert_eq!(star.greedy_coloring(), vec![1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_alt() {
+        // a line of eight, A - B - ... - H
+        let mut matrix = vec![vec![0; 8]; 8];
+        for i in 0..7 {
+            matrix[i][i + 1] = 1;
+            matrix[i + 1][i] = 1;
+        }
+        let graph = Graph::from_adjacency_matrix(matrix);
+        let landmarks = graph.landmarks(2);
+        assert_eq!(landmarks.nodes, ids(vec![7, 0]));
+        assert_eq!(landmarks.lower_bound(NodeId(3), NodeId(5)), 2);
+        assert_eq!(landmarks.lower_bound(NodeId(5), NodeId(3)), 2);
+
+        let alt = graph.shortest_path_alt_trace(NodeId(3), NodeId(5), &landmarks);
+        assert_eq!(alt.result, Some((2, ids(vec![3, 4, 5]))));
+        let stats = AlgoStats::from_events(alt.events.iter().map(|e| &e.event));
+        assert_eq!(stats.settled, 3);
+        assert!(
+            stats.settled
+                < graph
+                    .shortest_path_with_stats(NodeId(3), NodeId(5))
+                    .1
+                    .settled
+        );
+        for a in 0..8 {
+            for b in 0..8 {
+                assert_eq!(
+                    graph
+                        .shortest_path_alt(NodeId(a), NodeId(b), &landmarks)
+                        .map(|(c, _)| c),
+                    graph.shortest_path(NodeId(a), NodeId(b)).map(|(c, _)| c)
+                );
+            }
+        }
+
+        // an unreachable node is picked before any reachable one
+        let mut graph =
+            Graph::from_adjacency_matrix(vec![vec![0, 1, 0], vec![1, 0, 0], vec![0, 0, 0]]);
+        assert_eq!(graph.landmarks(5).nodes, ids(vec![2, 0, 1]));
+        graph.nodes.clear();
+        assert_eq!(graph.landmarks(2), Landmarks::default());
+    }
+
+    #[test]
+    fn test_landmarks_at() {
+        let mut matrix = vec![vec![0; 8]; 8];
+        for i in 0..7 {
+            matrix[i][i + 1] = 1;
+            matrix[i + 1][i] = 1;
+        }
+        let graph = Graph::from_adjacency_matrix(matrix);
+        assert_eq!(
+            graph.landmarks_at(&[NodeId(7), NodeId(0)]),
+            graph.landmarks(2)
+        );
+
+        // a landmark in the middle of the line is exact for pairs on one side of it and loose for
+        // pairs across it, but the paths stay shortest ones; repeats and missing ids are dropped
+        let middle = graph.landmarks_at(&[NodeId(3), NodeId(3), NodeId(9)]);
+        assert_eq!(middle.nodes, ids(vec![3]));
+        assert_eq!(middle.lower_bound(NodeId(4), NodeId(6)), 2);
+        assert_eq!(middle.lower_bound(NodeId(1), NodeId(6)), 1);
+        for a in 0..8 {
+            for b in 0..8 {
+                assert_eq!(
+                    graph
+                        .shortest_path_alt(NodeId(a), NodeId(b), &middle)
+                        .map(|(c, _)| c),
+                    graph.shortest_path(NodeId(a), NodeId(b)).map(|(c, _)| c)
+                );
+            }
+        }
+        assert_eq!(graph.landmarks_at(&[]).lower_bound(NodeId(0), NodeId(7)), 0);
+    }
+
+    #[test]
+    fn test_dstar_lite() {
+        // A - B - C - D with a detour B - E - D, and F - G hanging off the goal D
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0; 7]; 7]);
+        for (a, b, cost) in [
+            (0, 1, 1),
+            (1, 2, 1),
+            (2, 3, 1),
+            (1, 4, 2),
+            (4, 3, 2),
+            (3, 5, 1),
+            (5, 6, 1),
+        ] {
+            graph.nodes[a].push(Edge {
+                node: NodeId(b),
+                cost,
+                capacity: None,
+                label: None,
+            });
+            graph.nodes[b].push(Edge {
+                node: NodeId(a),
+                cost,
+                capacity: None,
+                label: None,
+            });
+        }
+        let mut plan = graph.dstar_lite(NodeId(0), NodeId(3));
+        assert_eq!(plan.path(&graph), Some((3, ids(vec![0, 1, 2, 3]))));
+
+        // cutting B - C on the route sends it round the detour, without searching behind the goal
+        graph.remove_edges(|from, e| {
+            (from, e.node) == (NodeId(1), NodeId(2)) || (from, e.node) == (NodeId(2), NodeId(1))
+        });
+        plan.repair(&graph, &[(NodeId(1), NodeId(2)), (NodeId(2), NodeId(1))]);
+        assert_eq!(plan.path(&graph), Some((5, ids(vec![0, 1, 4, 3]))));
+        let fresh = graph.dstar_lite(NodeId(0), NodeId(3));
+        assert!(plan.expanded < fresh.expanded);
+
+        plan.move_start(&graph, NodeId(1));
+        assert_eq!(plan.path(&graph), Some((4, ids(vec![1, 4, 3]))));
+        graph.remove_edges(|from, e| (from, e.node) == (NodeId(4), NodeId(3)));
+        plan.repair(&graph, &[(NodeId(4), NodeId(3))]);
+        assert_eq!(plan.path(&graph), None);
+
+        // random reweighting, checked against dijkstra after every repair
+        let mut rng = Rng::new(5);
+        let mut graph = Graph::from_adjacency_matrix(
+            (0..8)
+                .map(|a| {
+                    (0..8)
+                        .map(|b| {
+                            if a != b && rng.next_f32() < 0.4 {
+                                1 + (rng.next_u64() % 9) as Cost
+                            } else {
+                                0
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+        );
+        let mut plan = graph.dstar_lite(NodeId(0), NodeId(7));
+        for _ in 0..50 {
+            let from = (rng.next_u64() % 8) as usize;
+            if graph.nodes[from].is_empty() {
+                continue;
+            }
+            let i = (rng.next_u64() % graph.nodes[from].len() as u64) as usize;
+            graph.nodes[from][i].cost = 1 + (rng.next_u64() % 9) as Cost;
+            let to = graph.nodes[from][i].node;
+            plan.repair(&graph, &[(NodeId(from), to)]);
+            assert_eq!(
+                plan.path(&graph).map(|(c, _)| c),
+                graph.shortest_path(NodeId(0), NodeId(7)).map(|(c, _)| c)
+            );
+        }
+    }
+
+    #[test]
+    fn test_shortest_path_tie_break() {
+        // A -> E costs 3 directly, through B, or through C and D
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 0, 3],
+            vec![0, 0, 0, 0, 2],
+            vec![0, 0, 0, 1, 2],
+            vec![0, 0, 0, 0, 1],
+            vec![0, 0, 0, 0, 0],
+        ]);
+        let tie = |graph: &Graph, rule| graph.shortest_path_tie_break(NodeId(0), NodeId(4), rule);
+        assert_eq!(
+            tie(&graph, TieBreak::FewestHops),
+            Some((3, ids(vec![0, 4])))
+        );
+        assert_eq!(tie(&graph, TieBreak::Labels), Some((3, ids(vec![0, 1, 4]))));
+        assert_eq!(tie(&graph, TieBreak::Search).map(|(c, _)| c), Some(3));
+
+        // without the direct edge, A - B - E and A - C - E both take two hops and B sorts first
+        graph.nodes[0].retain(|e| e.node != NodeId(4));
+        assert_eq!(
+            tie(&graph, TieBreak::FewestHops),
+            Some((3, ids(vec![0, 1, 4])))
+        );
+        assert_eq!(tie(&graph, TieBreak::Labels), Some((3, ids(vec![0, 1, 4]))));
+        assert_eq!(
+            graph.shortest_path_tie_break(NodeId(4), NodeId(0), TieBreak::FewestHops),
+            None
+        );
+        assert_eq!(
+            graph.shortest_path_tie_break(NodeId(2), NodeId(2), TieBreak::FewestHops),
+            Some((0, ids(vec![2])))
+        );
+    }
+
+    #[test]
+    fn test_remove_edge() {
+        let mut graph =
+            Graph::from_adjacency_matrix(vec![vec![0, 1, 0], vec![1, 0, 2], vec![0, 2, 0]]);
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        assert!(graph.remove_edge(NodeId(1), NodeId(0)));
+        assert!(!graph.has_edge(NodeId(0), NodeId(1)) && !graph.has_edge(NodeId(1), NodeId(0)));
+        assert!(graph.has_edge(NodeId(1), NodeId(2)) && graph.has_edge(NodeId(2), NodeId(1)));
+        assert!(graph.forbidden.is_empty());
+        assert!(!graph.remove_edge(NodeId(0), NodeId(1)));
+        assert!(!graph.remove_edge(NodeId(0), NodeId(2)));
+    }
+
+    #[test]
+    fn test_lanes() {
+        // a highway A - B - C next to a back road A - C, and a second, cheaper A - C road
+        let mut graph =
+            Graph::from_adjacency_matrix(vec![vec![0, 2, 9], vec![2, 0, 2], vec![9, 2, 0]]);
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((4, ids(vec![0, 1, 2])))
+        );
+        graph.add_lane(NodeId(0), NodeId(2), 3, true);
+        assert_eq!(graph.lane_count(NodeId(0), NodeId(2)), 2);
+        assert_eq!(graph.edge_between(NodeId(2), NodeId(0)).unwrap().cost, 3);
+        assert_eq!(graph.lane(NodeId(0), NodeId(2), 1).unwrap().cost, 3);
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((3, ids(vec![0, 2])))
+        );
+        assert_eq!(
+            graph.lanes(),
+            [(0, 1, 0), (0, 2, 0), (0, 2, 1), (1, 2, 0)].map(|(a, b, lane)| (
+                NodeId(a),
+                NodeId(b),
+                lane
+            ))
+        );
+        assert_eq!(graph.connection_count(), 4);
+
+        // a one-way lane back from C, and one more one-way lane from A, share lane 2
+        graph.add_lane(NodeId(2), NodeId(0), 5, false);
+        graph.add_lane(NodeId(0), NodeId(2), 6, false);
+        assert_eq!(graph.lane_count(NodeId(0), NodeId(2)), 3);
+        assert_eq!(graph.connection_count(), 5);
+        graph.add_lane(NodeId(2), NodeId(0), 7, false);
+        assert_eq!(graph.lanes().last(), Some(&(NodeId(2), NodeId(0), 3)));
+
+        // removing a lane moves the later ones down; a turn ban goes with the last road
+        graph.lane_mut(NodeId(0), NodeId(2), 0).unwrap().cost = 1;
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((1, ids(vec![0, 2])))
+        );
+        assert!(graph.remove_lane(NodeId(0), NodeId(2), 0));
+        assert!(!graph.remove_lane(NodeId(0), NodeId(2), 3));
+        assert_eq!(graph.lane(NodeId(0), NodeId(2), 0).unwrap().cost, 3);
+        assert_eq!(graph.lane_count(NodeId(0), NodeId(2)), 3);
+        graph.forbidden.push((NodeId(1), NodeId(2), NodeId(0)));
+        assert!(graph.remove_lane(NodeId(2), NodeId(0), 2));
+        assert!(graph.remove_lane(NodeId(0), NodeId(2), 1));
+        assert_eq!(graph.forbidden, vec![(NodeId(1), NodeId(2), NodeId(0))]);
+        assert!(graph.remove_lane(NodeId(0), NodeId(2), 0));
+        assert!(!graph.has_edge(NodeId(2), NodeId(0)));
+        assert!(graph.forbidden.is_empty());
+    }
+
+    #[test]
+    fn test_edge_labels() {
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0, 4], vec![4, 0]]);
+        graph.add_lane(NodeId(0), NodeId(1), 9, false);
+        assert!(graph.set_lane_label(NodeId(1), NodeId(0), 0, Some("I-95".to_string())));
+        assert!(!graph.set_lane_label(NodeId(0), NodeId(1), 2, Some("US-1".to_string())));
+        assert_eq!(graph.lane_label(NodeId(0), NodeId(1), 0), Some("I-95"));
+        assert_eq!(graph.lane_label(NodeId(0), NodeId(1), 1), None);
+        assert_eq!(
+            graph.nodes[0][0].to_string(),
+            "Edge { node: B, cost 4, label I-95 }"
+        );
+
+        // both halves of a split edge keep its name
+        let mid = graph.split_edge(NodeId(0), NodeId(1), 0.5).unwrap();
+        assert_eq!(graph.lane_label(NodeId(0), mid, 0), Some("I-95"));
+        assert_eq!(graph.lane_label(mid, NodeId(1), 0), Some("I-95"));
+        assert!(graph.set_lane_label(NodeId(0), mid, 0, None));
+        assert_eq!(graph.lane_label(NodeId(0), mid, 0), None);
+    }
+
+    #[test]
+    fn test_adjacency_matrix() {
+        let matrix = vec![vec![0, 4, 0], vec![4, 0, -12], vec![7, 0, 0]];
+        let mut graph = Graph::from_adjacency_matrix(matrix.clone());
+        assert_eq!(graph.to_adjacency_matrix(), matrix);
+        assert_eq!(
+            AdjacencyMatrix(matrix).to_string(),
+            "      A   B   C\n  A   -   4   -\n  B   4   - -12\n  C   7   -   -"
+        );
+
+        // of parallel edges the cheapest shows
+        graph.add_lane(NodeId(0), NodeId(1), 2, false);
+        assert_eq!(graph.to_adjacency_matrix()[0][1], 2);
+        assert_eq!(graph.to_adjacency_matrix()[1][0], 4);
+    }
+
+    #[test]
+    fn test_max_flow_through() {
+        // A -> B -> D and A -> C -> D, 5 each way
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0; 4]; 4]);
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            graph.add_lane(NodeId(from), NodeId(to), 5, false);
+        }
+        assert_eq!(
+            graph
+                .max_flow_through(NodeId(0), NodeId(3), usize::MAX, &HashMap::new())
+                .value,
+            10
+        );
+
+        let flow = graph.max_flow_through(
+            NodeId(0),
+            NodeId(3),
+            usize::MAX,
+            &HashMap::from([(NodeId(1), 2)]),
+        );
+        assert_eq!(flow.value, 7);
+        assert_eq!(
+            (flow.cut.clone(), flow.cut_nodes.clone()),
+            (id_pairs(&[(0, 2)]), ids(vec![1]))
+        );
+        assert!(
+            flow.augmenting
+                .iter()
+                .all(|(_, p)| p[0] == NodeId(0) && p[p.len() - 1] == NodeId(3))
+        );
+        assert!(
+            flow.augmenting
+                .iter()
+                .any(|(_, p)| *p == ids(vec![0, 1, 3]))
+        );
+
+        // the source and the sink are held to their caps as well
+        let flow = graph.max_flow_through(
+            NodeId(0),
+            NodeId(3),
+            usize::MAX,
+            &HashMap::from([(NodeId(0), 4)]),
+        );
+        assert_eq!((flow.value, flow.cut_nodes), (4, ids(vec![0])));
+        let flow = graph.max_flow_through(
+            NodeId(0),
+            NodeId(3),
+            usize::MAX,
+            &HashMap::from([(NodeId(3), 3), (NodeId(1), 9)]),
+        );
+        assert_eq!((flow.value, flow.cut_nodes), (3, ids(vec![3])));
+        assert!(flow.cut.is_empty());
+    }
+
+    #[test]
+    fn test_canonical_text() {
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0; 3]; 3]);
+        graph.add_lane(NodeId(1), NodeId(2), 4, false);
+        graph.add_lane(NodeId(0), NodeId(1), 3, true);
+        graph.add_lane(NodeId(0), NodeId(1), 2, true);
+        graph.nodes[1][0].capacity = Some(5);
+        graph.set_lane_label(NodeId(1), NodeId(2), 0, Some("I-95".to_string()));
+        graph.forbidden = vec![
+            (NodeId(0), NodeId(1), NodeId(2)),
+            (NodeId(0), NodeId(1), NodeId(2)),
+        ];
+        graph.node_labels.insert(NodeId(2), "Home".to_string());
+        graph.node_metadata.insert(
+            NodeId(2),
+            BTreeMap::from([("pop".to_string(), "5000".to_string())]),
+        );
+        assert_eq!(
+            graph.canonical_text(),
+            "nodes 3\nA\nB\nC \"Home\" pop=\"5000\"\nedges 5\nA -> B 2\nA -> B 3\nB -> A 2\nB -> A 3\n\
+             B -> C 4 cap 5 \"I-95\"\nforbidden 1\nA -> B -> C\n"
+        );
+
+        // the same graph built the other way round
+        let mut other = Graph::from_adjacency_matrix(vec![vec![0; 3]; 3]);
+        other.add_lane(NodeId(0), NodeId(1), 2, true);
+        other.add_lane(NodeId(0), NodeId(1), 3, true);
+        other.add_lane(NodeId(1), NodeId(2), 4, false);
+        other.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        other.nodes[1]
+            .iter_mut()
+            .find(|e| e.node == NodeId(2))
+            .unwrap()
+            .capacity = Some(5);
+        other.set_lane_label(NodeId(1), NodeId(2), 0, Some("I-95".to_string()));
+        other.node_metadata = graph.node_metadata.clone();
+        other.node_labels.insert(NodeId(2), "Home".to_string());
+        assert_eq!(other.canonical_text(), graph.canonical_text());
+    }
+
+    #[test]
+    fn test_generic_weights() {
+        // A - B - D and A - C - D take as long, but the second costs less in tolls
+        let mut pairs: Graph<(u32, u32)> = Graph {
+            nodes: vec![Vec::new(); 4],
+            ..Default::default()
+        };
+        for (a, b, weight) in [
+            (0, 1, (1, 3)),
+            (1, 3, (1, 2)),
+            (0, 2, (1, 0)),
+            (2, 3, (1, 1)),
+        ] {
+            pairs.add_lane(NodeId(a), NodeId(b), weight, true);
+        }
+        assert_eq!(
+            pairs.dijkstra(NodeId(0), NodeId(3)),
+            Some(((2, 1), ids(vec![0, 2, 3])))
+        );
+        pairs.add_lane(NodeId(0), NodeId(3), (3, 0), false);
+        assert_eq!(pairs.dijkstra(NodeId(0), NodeId(3)).unwrap().0, (2, 1));
+
+        let mut reals: Graph<Real> = Graph {
+            nodes: vec![Vec::new(); 3],
+            ..Default::default()
+        };
+        reals.add_lane(NodeId(0), NodeId(1), Real(0.25), false);
+        reals.add_lane(NodeId(1), NodeId(2), Real(0.5), false);
+        reals.add_lane(NodeId(0), NodeId(2), Real(0.8), false);
+        assert_eq!(
+            reals.dijkstra(NodeId(0), NodeId(2)),
+            Some((Real(0.75), ids(vec![0, 1, 2])))
+        );
+        assert_eq!(reals.dijkstra(NodeId(2), NodeId(0)), None);
+
+        // the traced search reports its distances in the graph's own weight
+        let mut settled = Vec::new();
+        let found = reals.dijkstra_events(NodeId(0), NodeId(2), |event| {
+            if let TraceEvent::Pop {
+                node,
+                dist,
+                stale: false,
+            } = event
+            {
+                settled.push((node, dist));
+            }
+        });
+        assert_eq!(found, reals.dijkstra(NodeId(0), NodeId(2)));
+        assert_eq!(
+            settled,
+            vec![
+                (NodeId(0), Real(0.0)),
+                (NodeId(1), Real(0.25)),
+                (NodeId(2), Real(0.75))
+            ]
+        );
+        let mut updates = 0;
+        pairs.dijkstra_events(NodeId(0), NodeId(3), |event| {
+            if let TraceEvent::DistUpdate { node, dist, .. } = event
+                && node == NodeId(3)
+            {
+                updates += 1;
+                assert!(dist >= (2, 1));
+            }
+        });
+        // reached straight from A at (3, 0), then more cheaply through C; B's way is no better
+        assert_eq!(updates, 2);
+
+        // the app's graphs run the same search
+        let graph = Graph::from_adjacency_matrix(vec![vec![0, 2, 9], vec![2, 0, 3], vec![9, 3, 0]]);
+        assert_eq!(
+            graph.dijkstra(NodeId(0), NodeId(2)),
+            graph.shortest_path(NodeId(0), NodeId(2))
+        );
+    }
+
+    #[test]
+    fn test_node_ids() {
+        let graph = Graph::from_adjacency_matrix(vec![vec![0, 4, 0], vec![4, 0, 2], vec![0, 2, 0]]);
+        assert_eq!(NodeId(27).to_string(), Graph::node_label(27));
+        assert_eq!(usize::from(NodeId::from(3)), 3);
+        assert_eq!(serde_json::to_string(&NodeId(2)).unwrap(), "2");
+
+        // A's one edge, then B's two, then C's
+        let arcs: Vec<(NodeId, NodeId)> = graph.arcs().map(|(from, e)| (from, e.node)).collect();
+        assert_eq!(arcs.len(), 4);
+        assert_eq!(arcs[2], (NodeId(1), NodeId(2)));
+        assert_eq!(graph.ids().last(), Some(NodeId(2)));
+        assert_eq!(graph.edges_from(NodeId(1)).len(), 2);
+    }
+
+    #[test]
+    fn test_node_labels() {
+        assert_eq!(Graph::node_label(0), "A");
+        assert_eq!(Graph::node_label(25), "Z");
+        assert_eq!(Graph::node_label(26), "AA");
+        assert_eq!(Graph::node_label(701), "ZZ");
+        assert_eq!(Graph::node_label(702), "AAA");
+        assert_eq!(Graph::parse_label("ab"), Some(NodeId(27)));
+        assert_eq!(Graph::parse_label("A1"), None);
+
+        // past the one-byte letters, every label is distinct and reads back as its node
+        let n = 300;
+        let graph = Graph::from_adjacency_matrix(vec![vec![0; n]; n]);
+        let labels: HashSet<String> = graph.ids().map(|id| id.to_string()).collect();
+        assert_eq!(labels.len(), n);
+        for id in graph.ids() {
+            assert_eq!(Graph::parse_label(&id.to_string()), Some(id));
+        }
+        assert!(graph.to_string().contains("\nKN: [ ]\n"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut graph =
+            Graph::from_adjacency_matrix(vec![vec![0, 4, 0], vec![4, 0, 2], vec![0, 2, 0]]);
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        assert_eq!(graph.validate(), Validation::default());
+
+        // a second lane copied from the first is only a warning
+        graph.add_lane(NodeId(1), NodeId(2), 2, true);
+        let report = graph.validate();
+        assert!(report.is_valid());
+        assert_eq!(
+            report.defects,
+            vec![
+                Defect::DuplicateEdge {
+                    from: NodeId(1),
+                    to: NodeId(2)
+                },
+                Defect::DuplicateEdge {
+                    from: NodeId(2),
+                    to: NodeId(1)
+                }
+            ]
+        );
+        // the letter names the far end of a real edge; a dangling one has only its index
+        assert_eq!(
+            report.defects[0].to_string(),
+            "B -> C is there twice, exactly the same"
+        );
+
+        graph.nodes[0][0].cost = 5;
+        graph.nodes[2].push(Edge {
+            node: NodeId(7),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        graph.forbidden.push((NodeId(2), NodeId(0), NodeId(1)));
+        let report = graph.validate();
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.errors().copied().collect::<Vec<_>>(),
+            vec![
+                Defect::Asymmetric {
+                    a: NodeId(0),
+                    b: NodeId(1),
+                    lane: 0
+                },
+                Defect::DanglingEdge {
+                    from: NodeId(2),
+                    to: NodeId(7)
+                },
+                Defect::DuplicateTurnBan(NodeId(0), NodeId(1), NodeId(2)),
+                Defect::BadTurnBan(NodeId(2), NodeId(0), NodeId(1)),
+            ]
+        );
+        assert!(report.summary(1).starts_with("lane 1 of A - B differs"));
+        assert_eq!(
+            report.errors().nth(1).unwrap().to_string(),
+            "C has an edge to 7, which doesn't exist"
+        );
+        assert!(report.summary(1).ends_with("; 5 more"));
+    }
+
+    #[test]
+    fn test_jittered() {
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 100, 0, 40],
+            vec![100, 0, 200, 0],
+            vec![0, 200, 0, 0],
+            vec![0, 0, 0, 0],
+        ]);
+        let jittered = graph.jittered(0.1, &mut Rng::new(7));
+        // the same seed gives the same weights
+        assert_eq!(
+            jittered.arc_costs(),
+            graph.jittered(0.1, &mut Rng::new(7)).arc_costs()
+        );
+        for (edges, original) in jittered.nodes.iter().zip(&graph.nodes) {
+            for (e, o) in edges.iter().zip(original) {
+                assert_eq!(e.node, o.node);
+                assert!((e.cost - o.cost).abs() <= o.cost / 10 + 1);
+            }
+        }
+        // a two-way edge stays symmetric, and no spread changes nothing
+        assert_eq!(jittered.nodes[0][0].cost, jittered.nodes[1][0].cost);
+        assert_eq!(
+            graph.jittered(0.0, &mut Rng::new(1)).arc_costs(),
+            graph.arc_costs()
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        // one-way chain A -> B -> C plus a two-way B - D
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 2, 0, 0],
+            vec![0, 0, 3, 1],
+            vec![0, 0, 0, 0],
+            vec![0, 1, 0, 0],
+        ]);
+        assert_eq!(graph.shortest_path(NodeId(2), NodeId(0)), None);
+
+        let reversed = graph.reverse();
+        assert_eq!(
+            reversed.shortest_path(NodeId(2), NodeId(0)),
+            Some((5, ids(vec![2, 1, 0])))
+        );
+        assert_eq!(
+            graph.bidirectional_trace(NodeId(0), NodeId(2)).result,
+            Some((5, ids(vec![0, 1, 2])))
+        );
+        assert_eq!(graph.bidirectional_trace(NodeId(2), NodeId(0)).result, None);
+        assert!(reversed.has_edge(NodeId(1), NodeId(3)) && reversed.has_edge(NodeId(3), NodeId(1)));
+        assert_eq!(reversed.reverse().diff(&graph), GraphDiff::default());
+        let mut transposed = graph.clone();
+        transposed.transpose();
+        assert_eq!(transposed.diff(&reversed), GraphDiff::default());
+
+        assert!(graph.reverse_edge(NodeId(0), NodeId(1)));
+        assert!(graph.has_edge(NodeId(1), NodeId(0)) && !graph.has_edge(NodeId(0), NodeId(1)));
+        assert_eq!(graph.edge_between(NodeId(0), NodeId(1)).unwrap().cost, 2);
+        assert!(!graph.reverse_edge(NodeId(0), NodeId(1)));
+        assert!(!graph.reverse_edge(NodeId(1), NodeId(3))); // two-way edges have no direction to flip
+    }
+
+    #[test]
+    fn test_astar() {
+        // a row A - B - C - D, plus a spur A - E pointing away from D
+        let positions = [0.0, 1.0, 2.0, 3.0, -1.0];
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 0, 1],
+            vec![1, 0, 1, 0, 0],
+            vec![0, 1, 0, 1, 0],
+            vec![0, 0, 1, 0, 0],
+            vec![1, 0, 0, 0, 0],
+        ]);
+        let goal = 3;
+        let straight = |n: NodeId| (positions[goal] - positions[n.index()]) as Cost;
+
+        assert_eq!(
+            graph.astar(NodeId(0), NodeId(goal), |_| 0),
+            graph.shortest_path(NodeId(0), NodeId(goal))
+        );
+        assert_eq!(
+            graph.astar(NodeId(0), NodeId(goal), straight),
+            Some((3, ids(vec![0, 1, 2, 3])))
+        );
+
+        // the heuristic keeps A* off the spur that Dijkstra settles
+        let settled = |trace: Trace| {
+            return AlgoStats::from_events(trace.events.iter().map(|e| &e.event)).settled;
+        };
+        assert_eq!(
+            settled(graph.shortest_path_trace(NodeId(0), NodeId(goal))),
+            5
+        );
+        assert_eq!(
+            settled(graph.astar_trace(NodeId(0), NodeId(goal), straight)),
+            4
+        );
+
+        let unreachable = Graph::from_adjacency_matrix(vec![vec![0, 0], vec![0, 0]]);
+        assert_eq!(unreachable.astar(NodeId(0), NodeId(1), |_| 0), None);
+    }
+
+    #[test]
+    fn test_greedy_best_first() {
+        // A - B - D is two hops of 10, A - C - E - D three of 1, but B looks closer to D
+        let heuristic = [2, 1, 3, 0, 2];
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 10, 1, 0, 0],
+            vec![10, 0, 0, 10, 0],
+            vec![1, 0, 0, 0, 1],
+            vec![0, 10, 0, 0, 1],
+            vec![0, 0, 1, 1, 0],
+        ]);
+        let h = |n: NodeId| heuristic[n.index()];
+        assert_eq!(
+            graph.greedy_best_first(NodeId(0), NodeId(3), h),
+            Some((20, ids(vec![0, 1, 3])))
+        );
+        assert_eq!(
+            graph.astar(NodeId(0), NodeId(3), |_| 0),
+            Some((3, ids(vec![0, 2, 4, 3])))
+        );
+
+        // it settles only what it walks through
+        let trace = graph.greedy_best_first_trace(NodeId(0), NodeId(3), h);
+        assert_eq!(
+            AlgoStats::from_events(trace.events.iter().map(|e| &e.event)).settled,
+            3
+        );
+        let unreachable = Graph::from_adjacency_matrix(vec![vec![0, 0], vec![0, 0]]);
+        assert_eq!(
+            unreachable.greedy_best_first(NodeId(0), NodeId(1), |_| 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bellman_ford() {
+        // one-way A -> B -> C costs 4, but the detour A -> D -> B uses a negative edge
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 3, 0, 1],
+            vec![0, 0, 1, 0],
+            vec![0, 0, 0, 0],
+            vec![0, -2, 0, 0],
+        ]);
+        assert!(graph.has_negative_edges());
+        let paths = graph.bellman_ford(NodeId(0)).unwrap();
+        assert_eq!(paths.dist, vec![Some(0), Some(-1), Some(0), Some(1)]);
+        assert_eq!(
+            paths.path_to(NodeId(0), NodeId(2)),
+            Some((0, ids(vec![0, 3, 1, 2])))
+        );
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((0, ids(vec![0, 3, 1, 2])))
+        );
+        assert_eq!(graph.bellman_ford(NodeId(2)).unwrap().dist[0], None);
+
+        // C -> D closes the cycle B -> C -> D -> B, which costs 1 + 0 - 2 = -1
+        graph.nodes[2].push(Edge {
+            node: NodeId(3),
+            cost: 0,
+            capacity: None,
+            label: None,
+        });
+        let cycle = graph.bellman_ford(NodeId(0)).unwrap_err();
+        let mut rotated = cycle.clone();
+        rotated.rotate_left(cycle.iter().position(|&n| n == NodeId(1)).unwrap());
+        assert_eq!(rotated, ids(vec![1, 2, 3]));
+        assert_eq!(graph.shortest_path(NodeId(0), NodeId(2)), None);
+        assert!(graph.bellman_ford(NodeId(2)).is_err());
+    }
+
+    #[test]
+    fn test_bfs() {
+        // A - B - C costs 10 + 10; A - D - E - C costs 3 in total over one more hop
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 10, 0, 1, 0],
+            vec![10, 0, 10, 0, 0],
+            vec![0, 10, 0, 0, 1],
+            vec![1, 0, 0, 0, 1],
+            vec![0, 0, 1, 1, 0],
+        ]);
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((3, ids(vec![0, 3, 4, 2])))
+        );
+        assert_eq!(
+            graph.bfs_shortest_path(NodeId(0), NodeId(2)),
+            Some((20, ids(vec![0, 1, 2])))
+        );
+        assert_eq!(
+            graph.bfs_shortest_path(NodeId(0), NodeId(0)),
+            Some((0, ids(vec![0])))
+        );
+
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        assert_eq!(
+            graph.bfs_shortest_path(NodeId(0), NodeId(2)),
+            Some((3, ids(vec![0, 3, 4, 2])))
+        );
+
+        graph.nodes[2].clear();
+        graph.nodes[1].retain(|e| e.node != NodeId(2));
+        graph.nodes[4].retain(|e| e.node != NodeId(2));
+        assert_eq!(graph.bfs_shortest_path(NodeId(0), NodeId(2)), None);
+    }
+
+    #[test]
+    fn test_k_shortest_paths() {
+        // A - B - D costs 2, A - C - D 3, A - B - C - D 4, A - D and A - C - B - D 5
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 2, 5],
+            vec![1, 0, 2, 1],
+            vec![2, 2, 0, 1],
+            vec![5, 1, 1, 0],
+        ]);
+        let paths = graph.k_shortest_paths(NodeId(0), NodeId(3), 4);
+        let costs: Vec<Cost> = paths.iter().map(|(c, _)| *c).collect();
+        assert_eq!(costs, vec![2, 3, 4, 5]);
+        assert_eq!(paths[0].1, ids(vec![0, 1, 3]));
+        assert_eq!(paths[1].1, ids(vec![0, 2, 3]));
+        assert_eq!(paths[2].1, ids(vec![0, 1, 2, 3]));
+        // ties go to fewer hops
+        assert_eq!(paths[3].1, ids(vec![0, 3]));
+        assert!(paths.iter().all(|(_, p)| {
+            let mut seen = p.clone();
+            seen.sort();
+            seen.dedup();
+            seen.len() == p.len()
+        }));
+
+        // only so many loopless routes exist
+        assert_eq!(graph.k_shortest_paths(NodeId(0), NodeId(3), 50).len(), 5);
+        assert!(graph.k_shortest_paths(NodeId(0), NodeId(3), 0).is_empty());
+
+        // a banned turn rules out every route making it
+        let mut restricted = graph.clone();
+        restricted.forbidden.push((NodeId(0), NodeId(1), NodeId(3)));
+        let paths = restricted.k_shortest_paths(NodeId(0), NodeId(3), 3);
+        assert_eq!(paths[0].1, ids(vec![0, 2, 3]));
+        assert!(paths.iter().all(|(_, p)| *p != ids(vec![0, 1, 3])));
+    }
+
+    #[test]
+    fn test_batch_queries() {
+        assert_eq!(Graph::parse_label("c"), Some(NodeId(2)));
+        assert_eq!(Graph::parse_label("C"), Some(NodeId(2)));
+        assert_eq!(Graph::parse_label("3"), None);
+        assert_eq!(Graph::parse_label(""), None);
+
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 4, 1, 0],
+            vec![4, 0, 2, 0],
+            vec![1, 2, 0, 0],
+            vec![0, 0, 0, 0],
+        ]);
+        let queries = id_pairs(&[(0, 1), (1, 0), (0, 3), (2, 2), (1, 2)]);
+        let sequential = graph.shortest_paths_for(&queries, 1);
+        assert_eq!(sequential[0], Some((3, ids(vec![0, 2, 1]))));
+        assert_eq!(sequential[2], None);
+        assert_eq!(sequential[3], Some((0, ids(vec![2]))));
+        // threads keep the query order
+        for threads in [2, 3, 8] {
+            assert_eq!(graph.shortest_paths_for(&queries, threads), sequential);
+        }
+    }
+
+    #[test]
+    fn test_one_way() {
+        let mut graph =
+            Graph::from_adjacency_matrix(vec![vec![0, 1, 9], vec![1, 0, 1], vec![9, 1, 0]]);
+        graph.forbidden.push((NodeId(2), NodeId(1), NodeId(0)));
+        assert_eq!(graph.toggle_one_way(NodeId(0), NodeId(1)), Some(true));
+        assert!(graph.has_edge(NodeId(0), NodeId(1)) && !graph.has_edge(NodeId(1), NodeId(0)));
+        assert!(graph.forbidden.is_empty());
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((2, ids(vec![0, 1, 2])))
+        );
+        assert_eq!(
+            graph.shortest_path(NodeId(2), NodeId(0)),
+            Some((9, ids(vec![2, 0])))
+        );
+
+        // toggled from either end, the reverse comes back at the same cost
+        graph.nodes[0][0].cost = 3;
+        assert_eq!(graph.toggle_one_way(NodeId(1), NodeId(0)), Some(false));
+        assert_eq!(
+            graph.shortest_path(NodeId(1), NodeId(0)),
+            Some((3, ids(vec![1, 0])))
+        );
+        graph.nodes[0].clear();
+        graph.nodes[1].clear();
+        graph.nodes[2].clear();
+        assert_eq!(graph.toggle_one_way(NodeId(0), NodeId(2)), None);
+
+        // parallel lanes are left alone
+        graph.add_lane(NodeId(0), NodeId(1), 5, true);
+        graph.add_lane(NodeId(0), NodeId(1), 6, true);
+        assert_eq!(graph.lane_count(NodeId(0), NodeId(1)), 2);
+        assert_eq!(graph.toggle_one_way(NodeId(0), NodeId(1)), None);
+        assert_eq!(graph.count_edges(NodeId(1), NodeId(0)), 2);
+    }
+
+    #[test]
+    fn test_split_edge() {
+        let mut graph =
+            Graph::from_adjacency_matrix(vec![vec![0, 10, 0], vec![10, 0, 4], vec![0, 4, 0]]);
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        assert_eq!(graph.split_edge(NodeId(0), NodeId(1), 0.3), Some(NodeId(3)));
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(1)),
+            Some((10, ids(vec![0, 3, 1])))
+        );
+        assert_eq!(graph.edge_between(NodeId(0), NodeId(3)).unwrap().cost, 3);
+        assert_eq!(graph.edge_between(NodeId(3), NodeId(1)).unwrap().cost, 7);
+        assert_eq!(graph.nodes[1][0].cost, 7); // B -> A became B -> new node, the longer part
+        // the ban on A -> B -> C now arrives at B from the new node
+        assert_eq!(graph.forbidden, vec![(NodeId(3), NodeId(1), NodeId(2))]);
+
+        // one-way edges only get the direction they had; tiny costs cannot be split
+        graph.nodes[2].clear();
+        assert_eq!(graph.split_edge(NodeId(1), NodeId(2), 0.5), Some(NodeId(4)));
+        assert_eq!(graph.nodes[4].len(), 1);
+        assert!(!graph.has_edge(NodeId(2), NodeId(4)));
+        graph.nodes[0].push(Edge {
+            node: NodeId(2),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        assert_eq!(graph.split_edge(NodeId(0), NodeId(2), 0.5), None);
+        assert_eq!(graph.nodes.len(), 5);
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        // D -> B -> A, D -> C -> A, E on its own
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0; 5]; 5]);
+        for (from, to) in [(3, 1), (1, 0), (3, 2), (2, 0)] {
+            graph.nodes[from].push(Edge {
+                node: NodeId(to),
+                cost: 1,
+                capacity: None,
+                label: None,
+            });
+        }
+        assert_eq!(graph.topological_sort(), Ok(ids(vec![3, 1, 2, 0, 4])));
+
+        // A -> D closes D -> B -> A
+        graph.nodes[0].push(Edge {
+            node: NodeId(3),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        let cycle = graph.topological_sort().unwrap_err();
+        assert_eq!(cycle.first(), cycle.last());
+        assert_eq!(cycle.len(), 4);
+        assert!(cycle.windows(2).all(|w| graph.has_edge(w[0], w[1])));
+
+        // one two-way edge is enough
+        let pair = Graph::from_adjacency_matrix(vec![vec![0, 1], vec![1, 0]]);
+        assert_eq!(pair.topological_sort().unwrap_err().len(), 3);
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        // A - B - C - A, with D hanging off C
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 1, 0],
+            vec![1, 0, 1, 0],
+            vec![1, 1, 0, 1],
+            vec![0, 0, 1, 0],
+        ]);
+        let cycle = graph.find_cycle(false).unwrap();
+        assert_eq!(cycle.first(), cycle.last());
+        assert_eq!(cycle.len(), 4);
+        assert!(!cycle.contains(&NodeId(3)));
+        assert!(cycle.windows(2).all(|w| graph.has_edge(w[0], w[1])));
+        // every two-way edge is a directed cycle
+        assert_eq!(graph.find_cycle(true).unwrap().len(), 3);
+
+        // a tree has no undirected cycle, and one-way edges A -> B -> C, A -> C no directed one
+        graph.remove_edges(|from, e| {
+            (from, e.node) == (NodeId(0), NodeId(2)) || (from, e.node) == (NodeId(2), NodeId(0))
+        });
+        assert_eq!(graph.find_cycle(false), None);
+        let mut dag = Graph::from_adjacency_matrix(vec![vec![0; 3]; 3]);
+        for (from, to) in [(0, 1), (1, 2), (0, 2)] {
+            dag.nodes[from].push(Edge {
+                node: NodeId(to),
+                cost: 1,
+                capacity: None,
+                label: None,
+            });
+        }
+        assert_eq!(dag.find_cycle(true), None);
+        assert_eq!(dag.find_cycle(false).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_max_flow() {
+        // A -> B (3), A -> C (2), B -> C (1), B -> D (2), C -> D (3)
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0; 4]; 4]);
+        for (from, to, cost) in [(0, 1, 3), (0, 2, 2), (1, 2, 1), (1, 3, 2), (2, 3, 3)] {
+            graph.nodes[from].push(Edge {
+                node: NodeId(to),
+                cost,
+                capacity: None,
+                label: None,
+            });
+        }
+        let flow = graph.max_flow(NodeId(0), NodeId(3), usize::MAX);
+        assert_eq!(flow.value, 5);
+        assert!(!flow.capped);
+        assert_eq!(flow.augmenting.iter().map(|(a, _)| a).sum::<Cost>(), 5);
+        assert!(
+            flow.augmenting
+                .iter()
+                .all(|(_, p)| p[0] == NodeId(0) && p[p.len() - 1] == NodeId(3))
+        );
+        let cut: Cost = flow
+            .cut
+            .iter()
+            .map(|&(a, b)| graph.edge_between(a, b).unwrap().flow_capacity())
+            .sum();
+        assert_eq!(cut, 5);
+
+        // stopping early keeps the paths found so far, and claims no cut
+        let capped = graph.max_flow(NodeId(0), NodeId(3), 1);
+        assert!(capped.capped && capped.cut.is_empty());
+        assert_eq!(capped.augmenting[..], flow.augmenting[..1]);
+        assert_eq!(capped.value, flow.augmenting[0].0);
+
+        // a capacity overrides the cost, which stays the same for paths
+        graph.nodes[0][0].capacity = Some(1);
+        let flow = graph.max_flow(NodeId(0), NodeId(3), usize::MAX);
+        assert_eq!(flow.value, 3);
+        assert_eq!(flow.cut, id_pairs(&[(0, 1), (0, 2)]));
+        assert_eq!(graph.path_cost(&[NodeId(0), NodeId(1), NodeId(3)]), Some(5));
+
+        // nothing flows back against one-way edges
+        assert_eq!(
+            graph.max_flow(NodeId(3), NodeId(0), usize::MAX),
+            MaxFlow::default()
+        );
+    }
+
+    #[test]
+    fn test_weight_buckets() {
+        // A - B (1), B - C (2), C -> D (3), one-way, and D - A (10)
+        let graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 10],
+            vec![1, 0, 2, 0],
+            vec![0, 2, 0, 3],
+            vec![10, 0, 0, 0],
+        ]);
+        assert_eq!(
+            graph.weight_buckets(3),
+            vec![
+                (1, 3, id_pairs(&[(0, 1), (1, 2), (2, 3)])),
+                (4, 6, vec![]),
+                (7, 10, id_pairs(&[(0, 3)])),
+            ]
+        );
+        // never narrower than one weight
+        let buckets = graph.weight_buckets(50);
+        assert_eq!(buckets.len(), 10);
+        assert!(buckets.iter().all(|&(low, high, _)| low == high));
+        assert!(Graph::default().weight_buckets(5).is_empty());
+    }
+
+    #[test]
+    fn test_eccentricity() {
+        // path A - B - C - D with weights 1, 2, 3 and a shortcut B - D of 4
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 0],
+            vec![1, 0, 2, 4],
+            vec![0, 2, 0, 3],
+            vec![0, 4, 3, 0],
+        ]);
+        assert_eq!(
+            graph.eccentricities(),
+            [(5, 3), (4, 3), (3, 0), (5, 0)].map(|(d, v)| Some((d, NodeId(v))))
+        );
+        assert_eq!(graph.diameter(), Some((5, NodeId(0), NodeId(3))));
+
+        // an isolated node makes every eccentricity infinite
+        graph.nodes.push(vec![]);
+        assert!(graph.eccentricities().iter().all(|e| e.is_none()));
+        assert_eq!(graph.diameter(), None);
+        assert_eq!(Graph::default().diameter(), None);
+    }
+
+    #[test]
+    fn test_connected_components() {
+        // a one-way edge still joins its ends: C -> A, B on its own, D - E
+        let mut graph = Graph::from_adjacency_matrix(vec![vec![0; 5]; 5]);
+        graph.nodes[2].push(Edge {
+            node: NodeId(0),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        graph.nodes[3].push(Edge {
+            node: NodeId(4),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        graph.nodes[4].push(Edge {
+            node: NodeId(3),
+            cost: 1,
+            capacity: None,
+            label: None,
+        });
+        assert_eq!(graph.connected_components(), vec![0, 1, 0, 2, 2]);
+        assert!(Graph::default().connected_components().is_empty());
+    }
+
+    #[test]
+    fn test_pruning() {
+        // A - B - C and D - E, F on its own
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 2, 0, 0, 0, 0],
+            vec![2, 0, 9, 0, 0, 0],
+            vec![0, 9, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 5, 0],
+            vec![0, 0, 0, 5, 0, 0],
+            vec![0, 0, 0, 0, 0, 0],
+        ]);
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        assert_eq!(
+            graph.connected_nodes(),
+            vec![true, true, true, true, true, false]
+        );
+        assert_eq!(graph.connected_components(), vec![0, 0, 0, 1, 1, 2]);
+        assert_eq!(
+            graph.largest_component(),
+            vec![true, true, true, false, false, false]
+        );
+
+        // the ban over B - C goes with it
+        assert_eq!(graph.connection_count(), 3);
+        graph.remove_edges(|_, e| e.cost > 6);
+        assert_eq!(graph.connection_count(), 2);
+        assert!(!graph.has_edge(NodeId(1), NodeId(2)));
+        assert!(graph.forbidden.is_empty());
+        // A - B and D - E tie, the one holding A wins
+        assert_eq!(
+            graph.largest_component(),
+            vec![true, true, false, false, false, false]
+        );
+
+        graph.forbidden.push((NodeId(3), NodeId(4), NodeId(3)));
+        let keep = graph.connected_nodes();
+        let remap = graph.retain_nodes(&keep);
+        assert_eq!(
+            remap,
+            [Some(0), Some(1), None, Some(2), Some(3), None].map(|v| v.map(NodeId))
+        );
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(
+            graph.shortest_path(NodeId(2), NodeId(3)),
+            Some((5, ids(vec![2, 3])))
+        );
+        assert_eq!(graph.forbidden, vec![(NodeId(2), NodeId(3), NodeId(2))]);
+    }
+
+    #[test]
+    fn test_turn_restrictions() {
+        // a square A-B-C-D with a diagonal B-D; A -> B -> C is the short way round
+        let mut graph = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 5],
+            vec![1, 0, 1, 1],
+            vec![0, 1, 0, 1],
+            vec![5, 1, 1, 0],
+        ]);
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((2, ids(vec![0, 1, 2])))
+        );
+
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((3, ids(vec![0, 1, 3, 2])))
+        );
+
+        // with B -> D banned too, the only legal way is round through D first
+        graph.forbidden.push((NodeId(0), NodeId(1), NodeId(3)));
+        assert_eq!(
+            graph.shortest_path(NodeId(0), NodeId(2)),
+            Some((6, ids(vec![0, 3, 2])))
+        );
+
+        // a detour that revisits a node: A -> B, turn round at D, back through B to C
+        let mut detour = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 0],
+            vec![1, 0, 1, 1],
+            vec![0, 1, 0, 0],
+            vec![0, 1, 0, 0],
+        ]);
+        detour.forbidden.push((NodeId(0), NodeId(1), NodeId(2)));
+        assert_eq!(
+            detour.shortest_path(NodeId(0), NodeId(2)),
+            Some((4, ids(vec![0, 1, 3, 1, 2])))
+        );
+
+        let reversed = detour.reverse();
+        assert_eq!(reversed.forbidden, vec![(NodeId(2), NodeId(1), NodeId(0))]);
+        assert_eq!(
+            reversed.shortest_path(NodeId(2), NodeId(0)),
+            Some((4, ids(vec![2, 1, 3, 1, 0])))
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let before =
+            Graph::from_adjacency_matrix(vec![vec![0, 2, 0], vec![2, 0, 5], vec![0, 5, 0]]);
+        let after = Graph::from_adjacency_matrix(vec![
+            vec![0, 3, 0, 1],
+            vec![3, 0, 0, 0],
+            vec![0, 0, 0, 0],
+            vec![1, 0, 0, 0],
+        ]);
+
+        let diff = before.diff(&after);
+        let (a, b, c, d) = (NodeId(0), NodeId(1), NodeId(2), NodeId(3));
+        assert_eq!(diff.added_nodes, vec![d]);
+        assert_eq!(diff.removed, vec![(b, c, 5), (c, b, 5)]);
+        assert_eq!(diff.changed, vec![(a, b, 2, 3), (b, a, 2, 3)]);
+        assert_eq!(diff.added, vec![(a, d, 1), (d, a, 1)]);
+        assert_eq!(after.diff(&after), GraphDiff::default());
+    }
+
+    #[test]
+    fn test_isomorphism() {
+        // triangle A-B-C with a tail C-D
+        let reference = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 2, 0],
+            vec![1, 0, 3, 0],
+            vec![2, 3, 0, 4],
+            vec![0, 0, 4, 0],
+        ]);
+        // the same shape drawn with the tail on A: D-B-A triangle, A-C tail
+        let drawn = Graph::from_adjacency_matrix(vec![
+            vec![0, 3, 4, 2],
+            vec![3, 0, 0, 1],
+            vec![4, 0, 0, 0],
+            vec![2, 1, 0, 0],
+        ]);
+        let mapping = reference.isomorphism(&drawn, true).unwrap();
+        assert_eq!(mapping, ids(vec![3, 1, 0, 2]));
+
+        let mut reweighted = drawn.clone();
+        reweighted.nodes[2][0].cost = 5;
+        reweighted.nodes[0][1].cost = 5;
+        assert!(reference.is_isomorphic(&reweighted));
+        assert!(!reference.is_weighted_isomorphic(&reweighted));
 
-        assert_eq!(graph.shortest_path(0, 1), Some((5, vec![0, 3, 2, 1])));
+        let path = Graph::from_adjacency_matrix(vec![
+            vec![0, 1, 0, 0],
+            vec![1, 0, 1, 0],
+            vec![0, 1, 0, 1],
+            vec![0, 0, 1, 0],
+        ]);
+        assert!(!reference.is_isomorphic(&path));
     }
 }
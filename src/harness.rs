@@ -0,0 +1,278 @@
+use std::time::Duration;
+
+use bevy::app::Plugins;
+use bevy::asset::AssetPlugin;
+use bevy::camera::{RenderTarget, RenderTargetInfo};
+use bevy::gizmos::GizmoPlugin;
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey, NativeKeyCode};
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::{ButtonState, InputPlugin};
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::{ExitCondition, PrimaryWindow, WindowRef, WindowResolution};
+
+pub const WINDOW_SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+// every frame advances the clock by this much, however long it really took
+pub const FRAME: Duration = Duration::from_nanos(16_666_667);
+
+// a bevy app without a window or renderer, stepped one frame at a time from tests. keys, mouse
+// buttons and the cursor arrive as the same messages the window would send, so systems that read
+// clicks and keys, through ButtonInput or the messages, see what a user would do
+pub struct Harness {
+    pub app: App,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        return Harness::new();
+    }
+}
+
+impl Harness {
+    // a primary window of WINDOW_SIZE with the asset stores and gizmos that drawing systems
+    // expect; add the plugins under test on top
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            WindowPlugin {
+                primary_window: Some(Window {
+                    resolution: WindowResolution::new(WINDOW_SIZE.x as u32, WINDOW_SIZE.y as u32),
+                    ..default()
+                }),
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+                ..default()
+            },
+            InputPlugin,
+            GizmoPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<Image>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+        .add_systems(First, fit_cameras);
+        return Harness { app };
+    }
+
+    pub fn add_plugins<M>(&mut self, plugins: impl Plugins<M>) -> &mut Self {
+        self.app.add_plugins(plugins);
+        return self;
+    }
+
+    pub fn world(&self) -> &World {
+        return self.app.world();
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        return self.app.world_mut();
+    }
+
+    pub fn resource<R: Resource>(&self) -> &R {
+        return self.app.world().resource::<R>();
+    }
+
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    pub fn steps(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    // held from the next frame until released, the way the window would report it
+    pub fn press(&mut self, key: KeyCode) {
+        self.key(key, key_text(key), ButtonState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.key(key, key_text(key), ButtonState::Released);
+    }
+
+    // down for one frame and up in the next
+    pub fn tap(&mut self, key: KeyCode) {
+        self.press(key);
+        self.step();
+        self.release(key);
+        self.step();
+    }
+
+    // e.g. chord(&[KeyCode::ControlLeft], KeyCode::KeyS); the modifiers stay down over the tap
+    pub fn chord(&mut self, modifiers: &[KeyCode], key: KeyCode) {
+        for &modifier in modifiers {
+            self.press(modifier);
+        }
+        self.tap(key);
+        for &modifier in modifiers {
+            self.release(modifier);
+        }
+        self.step();
+    }
+
+    // text as one key press per character, for text inputs, all in one frame
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            let (key, logical) = match c {
+                ' ' => (KeyCode::Space, Key::Space),
+                c => (
+                    KeyCode::Unidentified(NativeKeyCode::Unidentified),
+                    Key::Character(c.to_string().into()),
+                ),
+            };
+            self.key(key, logical.clone(), ButtonState::Pressed);
+            self.key(key, logical, ButtonState::Released);
+        }
+        self.step();
+    }
+
+    // puts the cursor over `world` as the lowest-order camera on the primary window sees it
+    pub fn move_cursor(&mut self, world: Vec2) {
+        let window = self.primary_window();
+        let world_mut = self.app.world_mut();
+        let mut cams = world_mut.query::<(&Camera, &GlobalTransform)>();
+        let mut cams: Vec<(&Camera, &GlobalTransform)> = cams
+            .iter(world_mut)
+            .filter(|(c, _)| c.is_active && on_primary(&c.target))
+            .collect();
+        cams.sort_by_key(|(c, _)| c.order);
+        let Some(position) = cams
+            .first()
+            .and_then(|(camera, at)| camera.world_to_viewport(at, world.extend(0.0)).ok())
+        else {
+            return;
+        };
+        world_mut
+            .get_mut::<Window>(window)
+            .unwrap()
+            .set_cursor_position(Some(position));
+        world_mut.write_message(CursorMoved {
+            window,
+            position,
+            delta: None,
+        });
+    }
+
+    // a press and release of `button` over `world`, a frame each
+    pub fn click_with(&mut self, button: MouseButton, world: Vec2) {
+        self.move_cursor(world);
+        self.button(button, ButtonState::Pressed);
+        self.step();
+        self.button(button, ButtonState::Released);
+        self.step();
+    }
+
+    pub fn click(&mut self, world: Vec2) {
+        self.click_with(MouseButton::Left, world);
+    }
+
+    fn key(&mut self, key_code: KeyCode, logical_key: Key, state: ButtonState) {
+        let window = self.primary_window();
+        let text = match (&logical_key, state) {
+            (Key::Character(c), ButtonState::Pressed) => Some(c.clone()),
+            _ => None,
+        };
+        self.world_mut().write_message(KeyboardInput {
+            key_code,
+            logical_key,
+            state,
+            text,
+            repeat: false,
+            window,
+        });
+    }
+
+    fn button(&mut self, button: MouseButton, state: ButtonState) {
+        let window = self.primary_window();
+        self.world_mut().write_message(MouseButtonInput {
+            button,
+            state,
+            window,
+        });
+    }
+
+    fn primary_window(&mut self) -> Entity {
+        let world = self.app.world_mut();
+        let mut windows = world.query_filtered::<Entity, With<PrimaryWindow>>();
+        return windows.single(world).expect("primary window");
+    }
+}
+
+// the logical key a physical one gives on a plain us layout, near enough for key handlers
+fn key_text(key: KeyCode) -> Key {
+    return match key {
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Escape => Key::Escape,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Space => Key::Space,
+        KeyCode::Tab => Key::Tab,
+        _ => {
+            let name = format!("{:?}", key);
+            match name
+                .strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+            {
+                Some(c) if c.len() == 1 => Key::Character(c.to_lowercase().into()),
+                _ => Key::Unidentified(NativeKey::Unidentified),
+            }
+        }
+    };
+}
+
+fn on_primary(target: &RenderTarget) -> bool {
+    return matches!(target, RenderTarget::Window(WindowRef::Primary));
+}
+
+// what the renderer would work out for each camera from the window it draws to, so converting
+// between the cursor and the world works without one
+fn fit_cameras(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cams: Query<(&mut Camera, &mut Projection)>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    for (mut camera, mut projection) in &mut cams {
+        if !on_primary(&camera.target) {
+            continue;
+        }
+        projection.update(window.width(), window.height());
+        camera.computed.clip_from_view = projection.get_clip_from_view();
+        camera.computed.target_info = Some(RenderTargetInfo {
+            physical_size: window.physical_size(),
+            scale_factor: window.scale_factor(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+    use crate::viewer::{GraphView, GraphViewerPlugin};
+
+    #[test]
+    fn test_viewer_runs_headless() {
+        let mut harness = Harness::new();
+        harness.add_plugins(GraphViewerPlugin);
+        let graph = Graph::from_adjacency_matrix(vec![vec![0, 3], vec![3, 0]]);
+        let layout = vec![Vec2::new(-100.0, 0.0), Vec2::new(100.0, 0.0)];
+        harness
+            .world_mut()
+            .insert_resource(GraphView::new(graph, layout));
+        harness.world_mut().spawn(Camera2d);
+        harness.steps(2);
+        let world = harness.world_mut();
+        assert_eq!(world.query::<&Text2d>().iter(world).count(), 2);
+
+        // the cursor lands where the camera sees the world point
+        harness.move_cursor(Vec2::new(100.0, 50.0));
+        let world = harness.world_mut();
+        let mut windows = world.query_filtered::<&Window, With<PrimaryWindow>>();
+        let cursor = windows.single(world).unwrap().cursor_position();
+        assert_eq!(cursor, Some(WINDOW_SIZE / 2.0 + Vec2::new(100.0, -50.0)));
+    }
+}
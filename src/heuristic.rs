@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::ui::{Node as UiNode, RelativeCursorPosition};
+
+use crate::graph::{Cost, NodeId};
+use crate::{HighlightedEdges, Layer, Node, ResultsHud};
+
+const UNDER: Color = Color::srgb(0.2, 0.4, 0.95);
+const EXACT: Color = Color::srgb(0.85, 0.95, 0.85);
+const OVER: Color = Color::srgb(0.95, 0.2, 0.2);
+const MAX_SCALE: f32 = 4.0;
+const SLIDER_WIDTH: f32 = 160.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeuristicKind {
+    Euclidean,
+    Manhattan,
+    Scaled,
+    Zero,
+}
+
+impl HeuristicKind {
+    const ALL: [HeuristicKind; 4] = [
+        HeuristicKind::Euclidean,
+        HeuristicKind::Manhattan,
+        HeuristicKind::Scaled,
+        HeuristicKind::Zero,
+    ];
+
+    fn name(self) -> &'static str {
+        return match self {
+            HeuristicKind::Euclidean => "Euclidean",
+            HeuristicKind::Manhattan => "Manhattan",
+            HeuristicKind::Scaled => "Scaled Euclidean",
+            HeuristicKind::Zero => "Zero (Dijkstra)",
+        };
+    }
+
+    fn next(self) -> HeuristicKind {
+        let i = HeuristicKind::ALL.iter().position(|&k| k == self).unwrap();
+        return HeuristicKind::ALL[(i + 1) % HeuristicKind::ALL.len()];
+    }
+}
+
+// the heuristic A* runs with, and how it compared with the true remaining distance at each node
+// the last run settled: blue underestimates, pale is exact, red overestimates
+#[derive(Resource)]
+pub struct HeuristicView {
+    kind: HeuristicKind,
+    // for the scaled kind, set with the slider
+    scale: f32,
+    quality: HashMap<NodeId, (Cost, Cost)>,
+    // set when the heuristic changed under an A* result, so it gets recomputed
+    pub rerun: bool,
+}
+
+impl Default for HeuristicView {
+    fn default() -> Self {
+        return HeuristicView {
+            kind: HeuristicKind::Euclidean,
+            scale: 1.5,
+            quality: HashMap::new(),
+            rerun: false,
+        };
+    }
+}
+
+impl HeuristicView {
+    // the estimate between two positions, in lengths; the caller converts it to costs
+    pub fn estimate(&self, from: Vec2, to: Vec2) -> f32 {
+        let d = to - from;
+        return match self.kind {
+            HeuristicKind::Euclidean => d.length(),
+            // never shorter than the straight line, so it can overestimate
+            HeuristicKind::Manhattan => d.x.abs() + d.y.abs(),
+            HeuristicKind::Scaled => d.length() * self.scale,
+            HeuristicKind::Zero => 0.0,
+        };
+    }
+
+    pub fn describe(&self) -> String {
+        if self.kind == HeuristicKind::Scaled {
+            return format!("{} x{:.1}", self.kind.name(), self.scale);
+        }
+        return self.kind.name().to_string();
+    }
+
+    pub fn record(
+        &mut self,
+        settled: impl IntoIterator<Item = NodeId>,
+        heuristic: impl Fn(NodeId) -> Cost,
+        remaining: &[Option<Cost>],
+    ) {
+        self.quality = settled
+            .into_iter()
+            .filter_map(|node| Some((node, (heuristic(node), remaining[node.index()]?))))
+            .collect();
+    }
+
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        let &(h, d) = self.quality.get(&id)?;
+        if h <= d {
+            return Some(UNDER.mix(&EXACT, h as f32 / d.max(1) as f32));
+        }
+        return Some(EXACT.mix(&OVER, ((h - d) as f32 / d.max(1) as f32).min(1.0)));
+    }
+
+    pub fn summary(&self) -> String {
+        let over = self.quality.values().filter(|(h, d)| h > d).count();
+        let verdict = if over == 0 {
+            "never overestimated".to_string()
+        } else {
+            format!("overestimated at {} (inadmissible)", over)
+        };
+        return format!(
+            "{} heuristic over {} settled nodes: {}",
+            self.describe(),
+            self.quality.len(),
+            verdict
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.quality.clear();
+    }
+}
+
+#[derive(Component)]
+struct HeuristicLabel;
+
+#[derive(Component)]
+struct HeuristicText;
+
+#[derive(Component)]
+struct ScaleSlider;
+
+#[derive(Component)]
+struct ScaleFill;
+
+pub struct HeuristicPlugin;
+
+impl Plugin for HeuristicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HeuristicView>()
+            .add_systems(Startup, spawn_heuristic_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_heuristic_keys.run_if(crate::prompt::prompt_closed),
+                    drag_scale_slider,
+                    drop_stale_view,
+                    update_heuristic_panel,
+                    update_heuristic_labels,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// below the layer panel on the right
+fn spawn_heuristic_panel(mut commands: Commands) {
+    commands
+        .spawn(UiNode {
+            position_type: PositionType::Absolute,
+            top: Val::Px(290.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                HeuristicText,
+            ));
+            panel
+                .spawn((
+                    Button,
+                    UiNode {
+                        width: Val::Px(SLIDER_WIDTH),
+                        height: Val::Px(10.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.25, 0.25, 0.25)),
+                    RelativeCursorPosition::default(),
+                    ScaleSlider,
+                ))
+                .with_child((
+                    UiNode {
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.95, 0.6, 0.2)),
+                    ScaleFill,
+                ));
+        });
+}
+
+// shift+U cycles the heuristic for the next A* run, and redoes one on screen
+fn handle_heuristic_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    highlights: Res<HighlightedEdges>,
+    mut view: ResMut<HeuristicView>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyU) {
+        return;
+    }
+    view.kind = view.kind.next();
+    view.rerun = !highlights.layer(Layer::AStar).is_empty();
+    hud.0 = format!("A* heuristic: {}", view.describe());
+}
+
+// only the scaled heuristic has a scale, from 0 to MAX_SCALE in steps of 0.1
+fn drag_scale_slider(
+    q_slider: Query<(&Interaction, &RelativeCursorPosition), With<ScaleSlider>>,
+    highlights: Res<HighlightedEdges>,
+    mut view: ResMut<HeuristicView>,
+) -> Result<()> {
+    let (interaction, cursor) = q_slider.single()?;
+    if *interaction != Interaction::Pressed || view.kind != HeuristicKind::Scaled {
+        return Ok(());
+    }
+    let Some(at) = cursor.normalized else {
+        return Ok(());
+    };
+    let scale = ((at.x + 0.5).clamp(0.0, 1.0) * MAX_SCALE * 10.0).round() / 10.0;
+    if scale != view.scale {
+        view.scale = scale;
+        view.rerun = !highlights.layer(Layer::AStar).is_empty();
+    }
+
+    return Ok(());
+}
+
+// the view belongs to the A* path on screen, and goes when edits clear it
+fn drop_stale_view(highlights: Res<HighlightedEdges>, mut view: ResMut<HeuristicView>) {
+    if highlights.is_changed()
+        && highlights.layer(Layer::AStar).is_empty()
+        && !view.quality.is_empty()
+    {
+        view.clear();
+    }
+}
+
+fn update_heuristic_panel(
+    view: Res<HeuristicView>,
+    mut q_text: Query<&mut Text, With<HeuristicText>>,
+    mut q_slider: Query<&mut Visibility, With<ScaleSlider>>,
+    mut q_fill: Query<&mut UiNode, With<ScaleFill>>,
+) -> Result<()> {
+    if !view.is_changed() {
+        return Ok(());
+    }
+    q_text.single_mut()?.0 = format!("A* heuristic (Shift+U): {}", view.describe());
+    q_slider
+        .single_mut()?
+        .set_if_neq(if view.kind == HeuristicKind::Scaled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    q_fill.single_mut()?.width = Val::Percent(view.scale / MAX_SCALE * 100.0);
+
+    return Ok(());
+}
+
+// "h / true" under every node in the view
+fn update_heuristic_labels(
+    mut commands: Commands,
+    view: Res<HeuristicView>,
+    nodes: Query<&Node>,
+    q_labels: Query<Entity, With<HeuristicLabel>>,
+) {
+    if !view.is_changed() {
+        return;
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    for node in &nodes {
+        let Some((h, d)) = view.quality.get(&node.id) else {
+            continue;
+        };
+        commands.spawn((
+            Text2d::new(format!("h {} / {}", h, d)),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            Transform::from_translation(node.position.extend(5.0) - Vec3::Y * (node.r + 10.0)),
+            HeuristicLabel,
+        ));
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::graph::{Cost, NodeId};
+use crate::{HighlightedEdges, Layer, MainCamera, ResultsHud, WeightUnits, WrapperGraph};
+
+const BUCKETS: usize = 12;
+const BAR_WIDTH: f32 = 14.0;
+const BAR_HEIGHT: f32 = 60.0;
+const BAR: Color = Color::srgb(0.45, 0.6, 0.9);
+const BAR_HOVERED: Color = Color::srgb(0.7, 0.8, 1.0);
+
+// the weight distribution of the drawn edges while shown
+#[derive(Resource, Default)]
+struct WeightHistogram {
+    shown: bool,
+    buckets: Vec<(Cost, Cost, Vec<(NodeId, NodeId)>)>,
+}
+
+#[derive(Component)]
+struct HistogramPanel;
+
+#[derive(Component)]
+struct HistogramBar(usize);
+
+pub struct HistogramPlugin;
+
+impl Plugin for HistogramPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeightHistogram>()
+            .add_systems(Startup, spawn_histogram_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_histogram_keys.run_if(crate::prompt::prompt_closed),
+                    refresh_histogram,
+                    update_histogram_panel,
+                    handle_bar_clicks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// above the degree legend in the bottom-right corner
+fn spawn_histogram_panel(mut commands: Commands) {
+    commands.spawn((
+        UiNode {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(100.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+        HistogramPanel,
+    ));
+}
+
+// F3 shows or hides the histogram; shift+F3 is the path profile
+fn handle_histogram_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut histogram: ResMut<WeightHistogram>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if shift || !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    histogram.shown = !histogram.shown;
+    if !histogram.shown {
+        histogram.buckets.clear();
+        hud.0 = "Weight histogram hidden".to_string();
+    }
+}
+
+// follows edits while shown, without touching change detection when no weight moved
+fn refresh_histogram(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut histogram: ResMut<WeightHistogram>,
+) -> Result<()> {
+    if !histogram.shown {
+        return Ok(());
+    }
+    let buckets = q_graph.single()?.0.read().unwrap().weight_buckets(BUCKETS);
+    if histogram.buckets != buckets {
+        histogram.buckets = buckets;
+    }
+
+    return Ok(());
+}
+
+fn update_histogram_panel(
+    mut commands: Commands,
+    histogram: Res<WeightHistogram>,
+    units: Res<WeightUnits>,
+    q_panel: Query<Entity, With<HistogramPanel>>,
+) -> Result<()> {
+    if !histogram.is_changed() && !units.is_changed() {
+        return Ok(());
+    }
+    let panel = q_panel.single()?;
+    commands.entity(panel).despawn_related::<Children>();
+    if !histogram.shown {
+        return Ok(());
+    }
+
+    let text = |content: String| {
+        (
+            Text::new(content),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        )
+    };
+    let (Some(first), Some(last)) = (histogram.buckets.first(), histogram.buckets.last()) else {
+        commands
+            .entity(panel)
+            .with_child(text("Edge weights: no edges yet (F3 hides)".to_string()));
+        return Ok(());
+    };
+    let (min, max) = (units.format(first.0), units.format(last.1));
+    let tallest = histogram
+        .buckets
+        .iter()
+        .map(|(_, _, edges)| edges.len())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn(text(
+            "Edge weights (F3 hides, click a bar to mark its edges)".to_string(),
+        ));
+        parent
+            .spawn(UiNode {
+                align_items: AlignItems::End,
+                column_gap: Val::Px(2.0),
+                height: Val::Px(BAR_HEIGHT),
+                ..default()
+            })
+            .with_children(|row| {
+                for (i, (_, _, edges)) in histogram.buckets.iter().enumerate() {
+                    // empty buckets keep a sliver so they can still be told apart
+                    let height = (BAR_HEIGHT * edges.len() as f32 / tallest as f32).max(2.0);
+                    row.spawn((
+                        Button,
+                        UiNode {
+                            width: Val::Px(BAR_WIDTH),
+                            height: Val::Px(height),
+                            ..default()
+                        },
+                        BackgroundColor(BAR),
+                        HistogramBar(i),
+                    ));
+                }
+            });
+        parent
+            .spawn(UiNode {
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(text(min));
+                row.spawn(text(max));
+            });
+    });
+
+    return Ok(());
+}
+
+// clicking a bar marks every edge in its range, or unmarks them if all are marked already
+fn handle_bar_clicks(
+    mut q_bars: Query<(&Interaction, &HistogramBar, &mut BackgroundColor), Changed<Interaction>>,
+    histogram: Res<WeightHistogram>,
+    units: Res<WeightUnits>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for (interaction, bar, mut background) in &mut q_bars {
+        let Some((low, high, edges)) = histogram.buckets.get(bar.0) else {
+            continue;
+        };
+        let range = if low == high {
+            units.format(*low)
+        } else {
+            format!("{} to {}", units.format(*low), units.format(*high))
+        };
+        background.0 = match interaction {
+            Interaction::None => BAR,
+            _ => BAR_HOVERED,
+        };
+        match interaction {
+            Interaction::Hovered => {
+                hud.0 = format!("{} edges weigh {}", edges.len(), range);
+            }
+            Interaction::Pressed => {
+                let marked = highlights.layer_mut(Layer::Marked);
+                if !edges.is_empty() && edges.iter().all(|e| marked.contains(e)) {
+                    for e in edges {
+                        marked.remove(e);
+                    }
+                    hud.0 = format!("Unmarked the {} edges weighing {}", edges.len(), range);
+                } else {
+                    marked.extend(edges.iter().copied());
+                    hud.0 = format!("Marked the {} edges weighing {}", edges.len(), range);
+                }
+            }
+            Interaction::None => {}
+        }
+    }
+}
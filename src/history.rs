@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::graph::NodeId;
+use crate::{
+    GoalNode, HighlightedEdges, MainCamera, PathMode, ResultsHud, StartNode, WeightUnits,
+    WrapperGraph, show_shortest_path,
+};
+
+const HISTORY_LEN: usize = 10;
+
+// recent (start, goal) pairs, newest first
+#[derive(Resource, Default)]
+pub struct QueryHistory {
+    pairs: Vec<(NodeId, NodeId)>,
+    cursor: usize,
+}
+
+impl QueryHistory {
+    pub fn clear(&mut self) {
+        self.pairs.clear();
+        self.cursor = 0;
+    }
+}
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QueryHistory>().add_systems(
+            Update,
+            (
+                handle_history_keys.run_if(crate::prompt::prompt_closed),
+                record_queries,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn record_queries(
+    q_flags: Query<
+        (&StartNode, &GoalNode),
+        (
+            With<MainCamera>,
+            Or<(Changed<StartNode>, Changed<GoalNode>)>,
+        ),
+    >,
+    mut history: ResMut<QueryHistory>,
+) {
+    let Ok((start, goal)) = q_flags.single() else {
+        return;
+    };
+    let (Some(start), Some(goal)) = (start.id, goal.id) else {
+        return;
+    };
+    // pairs already in the list keep their place, so cycling through them doesn't reshuffle it
+    if history.pairs.contains(&(start, goal)) {
+        return;
+    }
+    history.pairs.insert(0, (start, goal));
+    history.pairs.truncate(HISTORY_LEN);
+    history.cursor = 0;
+}
+
+fn handle_history_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut q_camera: Query<(&WrapperGraph, &mut StartNode, &mut GoalNode), With<MainCamera>>,
+    mode: Res<PathMode>,
+    units: Res<WeightUnits>,
+    mut history: ResMut<QueryHistory>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // ctrl+W is wilson's spanning tree
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if ctrl || !keys.just_pressed(KeyCode::KeyW) {
+        return Ok(());
+    }
+    let (wg, mut start, mut goal) = q_camera.single_mut()?;
+    let graph = wg.0.read().unwrap();
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let (from, to) = if shift {
+        if history.pairs.is_empty() {
+            hud.0 = "No earlier queries yet".to_string();
+            return Ok(());
+        }
+        history.cursor = (history.cursor + 1) % history.pairs.len();
+        history.pairs[history.cursor]
+    } else {
+        let (Some(s), Some(g)) = (start.id, goal.id) else {
+            hud.0 = "Missing starting or goal node!".to_string();
+            return Ok(());
+        };
+        (g, s)
+    };
+    if from.index() >= graph.nodes.len() || to.index() >= graph.nodes.len() {
+        return Ok(());
+    }
+    start.id = Some(from);
+    goal.id = Some(to);
+
+    show_shortest_path(&graph, from, to, &mode, &units, &mut highlights, &mut hud);
+    if shift {
+        hud.0 = format!(
+            "Query {}/{}: {} -> {}\n{}",
+            history.cursor + 1,
+            history.pairs.len(),
+            from,
+            to,
+            hud.0
+        );
+    }
+
+    return Ok(());
+}
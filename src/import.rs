@@ -0,0 +1,418 @@
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::Graph;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::scatter::connect_nearest;
+use crate::{GraphSpawner, MainCamera, ResultsHud, WrapperGraph};
+
+const MAX_POINTS: usize = 5000;
+// kept clear around a fitted import, in pixels of the canvas
+const MARGIN: f32 = 60.0;
+// where web maps cut mercator off, so the poles don't run to infinity
+const MAX_LATITUDE: f64 = 85.051_129;
+
+// points as the file gives them, x then y, with longitude and latitude when `geographic`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Points {
+    at: Vec<DVec2>,
+    names: Vec<Option<String>>,
+    geographic: bool,
+}
+
+// how the points are put on the canvas: fitted to what the camera shows, or at a fixed number of
+// pixels per unit around its centre, and with mercator for latitudes and longitudes; geographic
+// points are otherwise drawn equirectangular, squeezed east-west to their middle latitude
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    scale: Option<f32>,
+    mercator: bool,
+    nearest: usize,
+}
+
+// `<file> [fit | scale <pixels per unit>] [mercator] [k <nearest>]`
+fn parse_request(text: &str) -> Option<(&str, Placement)> {
+    let mut words = text.split_whitespace();
+    let file = words.next()?;
+    let mut placement = Placement {
+        scale: None,
+        mercator: false,
+        nearest: 0,
+    };
+    while let Some(word) = words.next() {
+        match word.to_lowercase().as_str() {
+            "fit" => placement.scale = None,
+            "scale" => {
+                placement.scale = Some(
+                    words
+                        .next()?
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|s| *s > 0.0 && s.is_finite())?,
+                )
+            }
+            "mercator" => placement.mercator = true,
+            "k" => placement.nearest = words.next()?.parse().ok()?,
+            _ => return None,
+        }
+    }
+    return Some((file, placement));
+}
+
+fn column(header: &[String], names: &[&str]) -> Option<usize> {
+    return header.iter().position(|h| names.contains(&h.as_str()));
+}
+
+// comma separated, with an optional header naming lat/lon (or x/y) and a name column; without one
+// the columns are x, y and a name. fields may be quoted but not hold commas
+fn parse_csv(text: &str) -> Result<Points, String> {
+    let rows: Vec<Vec<String>> = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            l.split(',')
+                .map(|f| f.trim().trim_matches('"').to_string())
+                .collect()
+        })
+        .collect();
+    let Some(first) = rows.first() else {
+        return Err("the file is empty".to_string());
+    };
+    let headed = first.iter().take(2).any(|f| f.parse::<f64>().is_err());
+    let (x, y, name, geographic) = if headed {
+        let header: Vec<String> = first.iter().map(|h| h.to_lowercase()).collect();
+        let lon = column(&header, &["lon", "lng", "long", "longitude"]);
+        let lat = column(&header, &["lat", "latitude"]);
+        let name = column(&header, &["name", "label", "id"]);
+        match (lon, lat) {
+            (Some(lon), Some(lat)) => (lon, lat, name, true),
+            _ => match (column(&header, &["x"]), column(&header, &["y"])) {
+                (Some(x), Some(y)) => (x, y, name, false),
+                _ => return Err("the header names no lat and lon, or x and y, columns".to_string()),
+            },
+        }
+    } else {
+        (0, 1, Some(2), false)
+    };
+
+    let mut points = Points {
+        geographic,
+        ..default()
+    };
+    for (i, row) in rows.iter().enumerate().skip(headed as usize) {
+        let number = |c: usize| row.get(c).and_then(|f| f.parse::<f64>().ok());
+        let (Some(px), Some(py)) = (number(x), number(y)) else {
+            return Err(format!("line {} has no coordinates", i + 1));
+        };
+        points.at.push(DVec2::new(px, py));
+        points.names.push(
+            name.and_then(|c| row.get(c))
+                .filter(|n| !n.is_empty())
+                .cloned(),
+        );
+    }
+    return Ok(points);
+}
+
+// the Point and MultiPoint geometries of a FeatureCollection, a Feature or a bare geometry, named
+// by a `name` or `label` property
+fn parse_geojson(text: &str) -> Result<Points, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let features = match value["type"].as_str() {
+        Some("FeatureCollection") => value["features"].as_array().cloned().unwrap_or_default(),
+        Some("Feature") => vec![value],
+        Some(_) => vec![serde_json::json!({ "geometry": value })],
+        None => return Err("not a GeoJSON object".to_string()),
+    };
+    let position = |p: &serde_json::Value| Some(DVec2::new(p[0].as_f64()?, p[1].as_f64()?));
+
+    let mut points = Points {
+        geographic: true,
+        ..default()
+    };
+    for feature in &features {
+        let geometry = &feature["geometry"];
+        let props = &feature["properties"];
+        let name = props["name"]
+            .as_str()
+            .or_else(|| props["label"].as_str())
+            .map(|n| n.to_string());
+        let at: Vec<DVec2> = match geometry["type"].as_str() {
+            Some("Point") => position(&geometry["coordinates"]).into_iter().collect(),
+            Some("MultiPoint") => geometry["coordinates"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(position)
+                .collect(),
+            _ => continue,
+        };
+        for p in at {
+            points.at.push(p);
+            points.names.push(name.clone());
+        }
+    }
+    return Ok(points);
+}
+
+// longitude and latitude in degrees to the plane, still in degrees
+fn project(points: &Points, mercator: bool) -> Vec<DVec2> {
+    if !points.geographic {
+        return points.at.clone();
+    }
+    if mercator {
+        return points
+            .at
+            .iter()
+            .map(|p| {
+                let lat = p.y.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+                let y = (std::f64::consts::FRAC_PI_4 + lat / 2.0).tan().ln();
+                DVec2::new(p.x, y.to_degrees())
+            })
+            .collect();
+    }
+    let (lo, hi) = points.at.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| {
+        (lo.min(p.y), hi.max(p.y))
+    });
+    let squeeze = ((lo + hi) / 2.0).to_radians().cos();
+    return points
+        .at
+        .iter()
+        .map(|p| DVec2::new(p.x * squeeze, p.y))
+        .collect();
+}
+
+// the projected points around the canvas centre: scaled to fill it inside MARGIN while keeping
+// their shape, or by a fixed scale. a spread of zero in one direction is stretched by the other,
+// and all points in one place land on the centre
+fn place(projected: &[DVec2], canvas: Rect, scale: Option<f32>) -> Vec<Vec2> {
+    let (min, max) = projected.iter().fold(
+        (DVec2::splat(f64::MAX), DVec2::splat(f64::MIN)),
+        |(min, max), &p| (min.min(p), max.max(p)),
+    );
+    let middle = (min + max) / 2.0;
+    let spread = max - min;
+    let room = (canvas.size() - Vec2::splat(2.0 * MARGIN))
+        .max(Vec2::ONE)
+        .as_dvec2();
+    let scale = match scale {
+        Some(scale) => scale as f64,
+        None => {
+            let fits = [room.x / spread.x, room.y / spread.y];
+            fits.into_iter()
+                .filter(|s| s.is_finite())
+                .fold(None, |best: Option<f64>, s| {
+                    Some(best.map_or(s, |b| b.min(s)))
+                })
+                .unwrap_or(0.0)
+        }
+    };
+    return projected
+        .iter()
+        .map(|&p| canvas.center() + ((p - middle) * scale).as_vec2())
+        .collect();
+}
+
+pub struct ImportPlugin;
+
+impl Plugin for ImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                handle_import_keys.run_if(crate::prompt::prompt_closed),
+                run_import,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+U asks for a csv or geojson file of points to add as nodes
+fn handle_import_keys(keys: Res<ButtonInput<KeyCode>>, mut prompt: ResMut<Prompt>) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if ctrl && keys.just_pressed(KeyCode::KeyU) {
+        prompt.open(
+            PromptKind::Import,
+            "Import points: `<file.csv|file.geojson>`, then `fit` (default) or `scale <px per unit>`, `mercator`, `k <nearest>`",
+        );
+    }
+}
+
+// what the main camera shows of the world
+fn canvas(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cams: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Rect> {
+    let window = windows.single().ok()?;
+    let (camera, at) = cams.single().ok()?;
+    let a = camera.viewport_to_world_2d(at, Vec2::ZERO).ok()?;
+    let b = camera.viewport_to_world_2d(at, window.size()).ok()?;
+    return Some(Rect::from_corners(a, b));
+}
+
+fn run_import(
+    mut submitted: MessageReader<PromptSubmitted>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut spawner: GraphSpawner,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Import {
+            continue;
+        }
+        let Some((file, placement)) = parse_request(&event.text) else {
+            hud.0 = format!(
+                "Could not read `{}`: try `cities.csv`, `stops.geojson scale 2000 mercator` or `points.csv k 3`",
+                event.text
+            );
+            continue;
+        };
+        let geojson = file.ends_with(".geojson") || file.ends_with(".json");
+        let points = std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|text| match geojson {
+                true => parse_geojson(&text),
+                false => parse_csv(&text),
+            });
+        let points = match points {
+            Ok(points) if points.at.is_empty() => {
+                hud.0 = format!("{} has no points", file);
+                continue;
+            }
+            Ok(points) if points.at.len() > MAX_POINTS => {
+                hud.0 = format!(
+                    "{} has {} points, more than the {} an import takes",
+                    file,
+                    points.at.len(),
+                    MAX_POINTS
+                );
+                continue;
+            }
+            Ok(points) => points,
+            Err(e) => {
+                hud.0 = format!("Could not import {}: {}", file, e);
+                continue;
+            }
+        };
+        let Some(canvas) = canvas(&windows, &cams) else {
+            continue;
+        };
+
+        let positions = place(
+            &project(&points, placement.mercator),
+            canvas,
+            placement.scale,
+        );
+        let wg = wrapper_graph.single()?;
+        let mut graph = wg.0.write().unwrap();
+        let placed: Vec<_> = positions
+            .iter()
+            .map(|&p| spawner.spawn_node(&mut graph, p))
+            .collect();
+        for (node, name) in placed.iter().zip(points.names) {
+            if let Some(name) = name {
+                graph.node_labels.insert(node.id, name);
+            }
+        }
+        let edges = connect_nearest(&mut spawner, &mut graph, &placed, placement.nearest);
+
+        let how = match (points.geographic, placement.mercator) {
+            (true, true) => "latitudes and longitudes in mercator",
+            (true, false) => "latitudes and longitudes, equirectangular",
+            (false, true) => "x and y as given; mercator needs latitudes and longitudes",
+            (false, false) => "x and y as given",
+        };
+        hud.0 = format!(
+            "Imported {} nodes ({} to {}) and {} edges from {}: {}, {}",
+            placed.len(),
+            Graph::node_label(placed[0].id.index()),
+            Graph::node_label(placed[placed.len() - 1].id.index()),
+            edges,
+            file,
+            how,
+            match placement.scale {
+                Some(scale) => format!("{} px per unit", scale),
+                None => "fitted to the view".to_string(),
+            }
+        );
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_points() {
+        let csv = "name,lat,lon\nLisbon,38.72,-9.14\n\"Porto\",41.15,-8.61\n";
+        let points = parse_csv(csv).unwrap();
+        assert!(points.geographic);
+        assert_eq!(
+            points.at,
+            vec![DVec2::new(-9.14, 38.72), DVec2::new(-8.61, 41.15)]
+        );
+        assert_eq!(points.names[1].as_deref(), Some("Porto"));
+
+        let plain = parse_csv("1,2\n3,4,C\n").unwrap();
+        assert!(!plain.geographic);
+        assert_eq!(plain.names, vec![None, Some("C".to_string())]);
+        assert!(parse_csv("a,b\n1,2\n").is_err());
+        assert!(parse_csv("x,y\n1,\n").is_err());
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "A"},
+             "geometry": {"type": "Point", "coordinates": [2.35, 48.86]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "MultiPoint", "coordinates": [[0, 0], [1, 1]]}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}}
+        ]}"#;
+        let points = parse_geojson(geojson).unwrap();
+        assert_eq!(points.at.len(), 3);
+        assert_eq!(points.names, vec![Some("A".to_string()), None, None]);
+        assert_eq!(parse_request("a.csv scale 2 k 3").unwrap().1.nearest, 3);
+        assert!(parse_request("a.csv scale -1").is_none());
+    }
+
+    #[test]
+    fn test_place_fits_the_canvas() {
+        let canvas = Rect::new(-640.0, -360.0, 640.0, 360.0);
+        // a thousandth of a degree apart still fills the view, and keeps its shape
+        let close = [DVec2::new(10.0, 50.0), DVec2::new(10.001, 50.0005)];
+        let placed = place(&close, canvas, None);
+        assert!((placed[1].x - placed[0].x - (1280.0 - 2.0 * MARGIN)).abs() < 0.01);
+        assert!((placed[1].y - placed[0].y - (1280.0 - 2.0 * MARGIN) / 2.0).abs() < 0.01);
+        // far-apart projected metres don't overflow it
+        let far = [
+            DVec2::new(500_000.0, 4_000_000.0),
+            DVec2::new(700_000.0, 4_900_000.0),
+        ];
+        for p in place(&far, canvas, None) {
+            assert!(canvas.contains(p));
+        }
+        // one row of points spreads along it, and a single point sits in the middle
+        let row = place(&[DVec2::ZERO, DVec2::X], canvas, None);
+        assert_eq!(row[0].y, 0.0);
+        assert_eq!(place(&[DVec2::ONE], canvas, None), vec![Vec2::ZERO]);
+        assert_eq!(
+            place(&[DVec2::ZERO, DVec2::X], canvas, Some(10.0)),
+            vec![Vec2::new(-5.0, 0.0), Vec2::new(5.0, 0.0)]
+        );
+
+        // mercator stretches the north apart, equirectangular squeezes longitude
+        let points = Points {
+            at: vec![DVec2::new(0.0, 60.0), DVec2::new(10.0, 70.0)],
+            names: vec![None, None],
+            geographic: true,
+        };
+        let mercator = project(&points, true);
+        assert!(mercator[1].y - mercator[0].y > 10.0);
+        let flat = project(&points, false);
+        assert!((flat[1].x - 10.0 * 65f64.to_radians().cos()).abs() < 1e-9);
+    }
+}
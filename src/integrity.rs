@@ -0,0 +1,81 @@
+use bevy::color::palettes::css::DARK_ORANGE;
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::graph::Validation;
+use crate::{MainCamera, WrapperGraph};
+
+const SHOWN: usize = 3;
+
+#[derive(Component)]
+struct IntegrityBanner;
+
+pub struct IntegrityPlugin;
+
+impl Plugin for IntegrityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_integrity_banner)
+            .add_systems(Update, update_integrity_banner);
+    }
+}
+
+// centred above the morph slider, clear of the banners and parameters at the top
+fn spawn_integrity_banner(mut commands: Commands) {
+    commands
+        .spawn((
+            UiNode {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(130.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+            IntegrityBanner,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BackgroundColor(Color::from(DARK_ORANGE).with_alpha(0.85)),
+                UiNode {
+                    padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+// a loaded file or a bulk edit can leave the graph in a state the editor never makes itself;
+// the banner stays up until the errors are gone. exact copies of an edge are left alone
+fn update_integrity_banner(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut q_banner: Query<(&mut Visibility, &Children), With<IntegrityBanner>>,
+    mut q_text: Query<&mut Text>,
+) -> Result<()> {
+    let graph = q_graph.single()?.0.read().unwrap();
+    let (mut visibility, children) = q_banner.single_mut()?;
+    let errors = Validation {
+        defects: graph.validate().errors().copied().collect(),
+    };
+    if errors.is_valid() {
+        visibility.set_if_neq(Visibility::Hidden);
+        return Ok(());
+    }
+
+    let message = format!("Graph check: {}", errors.summary(SHOWN));
+    for &child in children {
+        if let Ok(mut text) = q_text.get_mut(child)
+            && text.0 != message
+        {
+            text.0 = message.clone();
+        }
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+
+    return Ok(());
+}
@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::graph::{Cost, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, node_positions};
+
+const NEAR: Color = Color::srgb(0.3, 0.95, 0.85);
+const FAR: Color = Color::srgb(0.15, 0.35, 0.75);
+
+// nodes within `budget` of the start node, tinted from NEAR to FAR by how much budget they use
+#[derive(Resource, Default)]
+pub struct Isochrone {
+    budget: Option<Cost>,
+    dist: Vec<Option<Cost>>,
+}
+
+impl Isochrone {
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        let budget = self.budget?;
+        let d = (*self.dist.get(id.index())?)?;
+        return Some(tint(d, budget));
+    }
+
+    pub fn clear(&mut self) {
+        self.budget = None;
+        self.dist.clear();
+    }
+}
+
+fn tint(dist: Cost, budget: Cost) -> Color {
+    // negative weights can bring a node in below zero
+    return NEAR.mix(&FAR, (dist as f32 / budget.max(1) as f32).clamp(0.0, 1.0));
+}
+
+pub struct IsochronePlugin;
+
+impl Plugin for IsochronePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Isochrone>().add_systems(
+            Update,
+            (handle_budget_prompt, refresh_isochrone, draw_isochrone).chain(),
+        );
+    }
+}
+
+fn handle_budget_prompt(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: MessageReader<PromptSubmitted>,
+    units: Res<WeightUnits>,
+    mut isochrone: ResMut<Isochrone>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    // shift+B locks a baseline path, ctrl+B filters by tag
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if keys.just_pressed(KeyCode::KeyB) && !shift && !ctrl && !prompt.is_open() {
+        prompt.open(
+            PromptKind::Budget,
+            "Budget from the start node (empty clears)",
+        );
+    }
+
+    for event in submitted.read() {
+        if event.kind != PromptKind::Budget {
+            continue;
+        }
+        // typed in display units, so `2.5` means 2.5 km when the units are `0.01 km`
+        match event.text.parse::<f32>() {
+            Ok(value) if value >= 0.0 => {
+                isochrone.budget = Some((value / units.scale).round() as Cost);
+                isochrone.dist.clear();
+            }
+            _ => {
+                isochrone.clear();
+                hud.0 = "Budget view off".to_string();
+            }
+        }
+    }
+}
+
+fn refresh_isochrone(
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut isochrone: ResMut<Isochrone>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some(budget) = isochrone.budget else {
+        return Ok(());
+    };
+    let (wg, start) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let dist = match start.id {
+        Some(start) if start.index() < graph.nodes.len() => graph.reachable_within(start, budget),
+        _ => vec![None; graph.nodes.len()],
+    };
+    if isochrone.dist == dist {
+        return Ok(());
+    }
+
+    // the HUD is only rewritten when the reachable set actually changes
+    let reached = dist.iter().filter(|d| d.is_some()).count();
+    hud.0 = match start.id {
+        Some(start) => format!(
+            "{} of {} nodes reachable from {} within {}",
+            reached,
+            graph.nodes.len(),
+            start,
+            units.format(budget)
+        ),
+        None => "Set a start node (S) to see what is reachable within the budget".to_string(),
+    };
+    isochrone.dist = dist;
+
+    return Ok(());
+}
+
+// the part of each edge that can still be walked before the budget runs out
+fn draw_isochrone(
+    isochrone: Res<Isochrone>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    mut gizmos: Gizmos,
+) -> Result<()> {
+    let Some(budget) = isochrone.budget else {
+        return Ok(());
+    };
+    let wg = wrapper_graph.single()?;
+    let graph = wg.0.read().unwrap();
+    let positions = node_positions(&nodes, graph.nodes.len());
+
+    for (from, edges) in graph.nodes.iter().enumerate() {
+        let Some(Some(d)) = isochrone.dist.get(from) else {
+            continue;
+        };
+        for edge in edges {
+            let reach = ((budget - d) as f32 / edge.cost as f32).min(1.0);
+            let (a, b) = (positions[from], positions[edge.node.index()]);
+            let end = a.lerp(b, reach);
+            gizmos.line_2d(a, end, tint(*d, budget));
+        }
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::graph::{Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::rng::Rng;
+use crate::{GoalNode, MainCamera, ResultsHud, StartNode, WeightUnits, WrapperGraph};
+
+const MAX_TRIALS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Jitter {
+    spread: f32,
+    seed: Option<u64>,
+    trials: usize,
+}
+
+impl Jitter {
+    // `<percent>[%]`, then optionally `seed <n>` and `trials <n>` in either order
+    fn parse(text: &str) -> Option<Jitter> {
+        let mut words = text.split_whitespace();
+        let percent: f32 = words.next()?.trim_end_matches('%').parse().ok()?;
+        if !(percent > 0.0 && percent <= 100.0) {
+            return None;
+        }
+        let mut jitter = Jitter {
+            spread: percent / 100.0,
+            seed: None,
+            trials: 1,
+        };
+        while let Some(word) = words.next() {
+            match word.to_ascii_lowercase().as_str() {
+                "seed" => jitter.seed = Some(words.next()?.parse().ok()?),
+                "trials" => {
+                    jitter.trials = words
+                        .next()?
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=MAX_TRIALS).contains(n))?;
+                }
+                _ => return None,
+            }
+        }
+        return Some(jitter);
+    }
+}
+
+pub struct JitterPlugin;
+
+impl Plugin for JitterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                handle_jitter_keys.run_if(crate::prompt::prompt_closed),
+                run_jitter,
+            )
+                .chain(),
+        );
+    }
+}
+
+// F10 asks how hard to shake the weights
+fn handle_jitter_keys(keys: Res<ButtonInput<KeyCode>>, mut prompt: ResMut<Prompt>) {
+    if keys.just_pressed(KeyCode::F10) {
+        prompt.open(
+            PromptKind::Jitter,
+            "Jitter every weight by up to this percent, e.g. `10`, `10 seed 7` or `5 trials 100`",
+        );
+    }
+}
+
+// the real weights are never touched; each trial reruns the query on a jittered copy, so the
+// answer is whether the route on screen would survive that much error in the weights
+fn run_jitter(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut app_rng: ResMut<Rng>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Jitter {
+            continue;
+        }
+        let Some(jitter) = Jitter::parse(&event.text) else {
+            hud.0 = format!(
+                "Could not read `{}`: give a percent up to 100, then `seed <n>` or `trials <n>` (up to {})",
+                event.text, MAX_TRIALS
+            );
+            continue;
+        };
+        let (wg, start, goal) = q_camera.single()?;
+        let (Some(start), Some(goal)) = (start.id, goal.id) else {
+            hud.0 = "Set a start and goal to test the route against".to_string();
+            continue;
+        };
+        let graph = wg.0.read().unwrap();
+        let Some((cost, route)) = graph.shortest_path(start, goal) else {
+            hud.0 = "No path found!".to_string();
+            continue;
+        };
+
+        // an unseeded run still gets a seed, reported so it can be repeated
+        let seed = jitter.seed.unwrap_or_else(|| app_rng.next_u64() >> 32);
+        let mut rng = Rng::new(seed);
+        let mut held = 0;
+        let mut alternatives: HashMap<Vec<NodeId>, usize> = HashMap::new();
+        let mut last = None;
+        for _ in 0..jitter.trials {
+            let jittered = graph.jittered(jitter.spread, &mut rng);
+            let found = jittered.shortest_path(start, goal);
+            match &found {
+                Some((_, path)) if *path == route => held += 1,
+                Some((_, path)) => *alternatives.entry(path.clone()).or_insert(0) += 1,
+                None => {}
+            }
+            last = found;
+        }
+
+        let shaken = format!("±{}% (seed {})", jitter.spread * 100.0, seed);
+        if jitter.trials == 1 {
+            hud.0 = match last {
+                Some((_, path)) if path == route => format!(
+                    "Under {} the route {} still wins",
+                    shaken,
+                    Graph::fmt_path(&route)
+                ),
+                Some((jittered_cost, path)) => format!(
+                    "Under {} the route changes to {} ({} jittered, {} with the real weights against {})",
+                    shaken,
+                    Graph::fmt_path(&path),
+                    units.format(jittered_cost),
+                    graph
+                        .path_cost(&path)
+                        .map_or("-".to_string(), |c| units.format(c)),
+                    units.format(cost)
+                ),
+                None => format!("Under {} no route is left", shaken),
+            };
+            continue;
+        }
+        let mut lines = vec![format!(
+            "Under {} over {} trials, {} held {} times ({:.0}%)",
+            shaken,
+            jitter.trials,
+            Graph::fmt_path(&route),
+            held,
+            held as f32 / jitter.trials as f32 * 100.0
+        )];
+        if let Some((path, count)) = alternatives
+            .iter()
+            .max_by_key(|(path, count)| (**count, std::cmp::Reverse((*path).clone())))
+        {
+            lines.push(format!(
+                "Most common alternative: {} ({} times, {} distinct in all)",
+                Graph::fmt_path(path),
+                count,
+                alternatives.len()
+            ));
+        }
+        hud.0 = lines.join("\n");
+    }
+
+    return Ok(());
+}
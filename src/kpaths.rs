@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::params::AlgorithmParams;
+use crate::{
+    GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, position_lookup,
+};
+
+// reused in turn when more routes than colours are ranked
+const COLORS: [Color; 5] = [
+    Color::srgb(0.2, 0.8, 1.0),
+    Color::srgb(1.0, 0.55, 0.1),
+    Color::srgb(0.6, 0.9, 0.2),
+    Color::srgb(0.9, 0.35, 0.8),
+    Color::srgb(1.0, 0.9, 0.3),
+];
+
+// the cheapest routes between one start and goal, as many as `k` asked for; `current` is drawn
+// thick, the rest thin
+#[derive(Resource, Default)]
+struct KPaths {
+    query: Option<(NodeId, NodeId)>,
+    k: usize,
+    paths: Vec<(Cost, Vec<NodeId>)>,
+    current: usize,
+}
+
+#[derive(Component)]
+struct KPathLabel;
+
+pub struct KPathsPlugin;
+
+impl Plugin for KPathsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KPaths>().add_systems(
+            Update,
+            (
+                handle_kpath_keys.run_if(crate::prompt::prompt_closed),
+                drop_stale_paths,
+                update_kpath_labels,
+                draw_kpaths,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ] finds the K cheapest routes, K set in the parameter panel, and steps to the next one, [ steps back; past either end turns
+// the view off
+fn handle_kpath_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    params: Res<AlgorithmParams>,
+    mut kpaths: ResMut<KPaths>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let step: isize = if keys.just_pressed(KeyCode::BracketRight) {
+        1
+    } else if keys.just_pressed(KeyCode::BracketLeft) {
+        -1
+    } else {
+        return Ok(());
+    };
+
+    if kpaths.paths.is_empty() {
+        let (wg, start, goal) = q_camera.single()?;
+        let (Some(start), Some(goal)) = (start.id, goal.id) else {
+            hud.0 = "Set a start (S) and goal (G) to rank routes".to_string();
+            return Ok(());
+        };
+        let graph = wg.0.read().unwrap();
+        kpaths.paths = graph.k_shortest_paths(start, goal, params.k_paths);
+        if kpaths.paths.is_empty() {
+            hud.0 = "No path found!".to_string();
+            return Ok(());
+        }
+        kpaths.query = Some((start, goal));
+        kpaths.k = params.k_paths;
+        kpaths.current = if step > 0 { 0 } else { kpaths.paths.len() - 1 };
+    } else {
+        let next = kpaths.current as isize + step;
+        if next < 0 || next as usize >= kpaths.paths.len() {
+            *kpaths = KPaths::default();
+            hud.0 = "Route ranking off".to_string();
+            return Ok(());
+        }
+        kpaths.current = next as usize;
+    }
+
+    let mut lines = vec![format!(
+        "Route {} of the {} cheapest ([ / ] to step)",
+        kpaths.current + 1,
+        kpaths.paths.len()
+    )];
+    for (i, (cost, path)) in kpaths.paths.iter().enumerate() {
+        let marker = if i == kpaths.current { ">" } else { " " };
+        lines.push(format!(
+            "{} #{} {}: {}",
+            marker,
+            i + 1,
+            units.format(*cost),
+            Graph::fmt_path(path)
+        ));
+    }
+    hud.0 = lines.join("\n");
+
+    return Ok(());
+}
+
+// the ranking belongs to one query under the weights and K it was made with
+fn drop_stale_paths(
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    params: Res<AlgorithmParams>,
+    mut kpaths: ResMut<KPaths>,
+) -> Result<()> {
+    let Some(query) = kpaths.query else {
+        return Ok(());
+    };
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let stale = (start.id, goal.id) != (Some(query.0), Some(query.1))
+        || kpaths.k != params.k_paths
+        || kpaths.paths.iter().any(|(cost, path)| {
+            path.iter().any(|&n| n.index() >= graph.nodes.len())
+                || graph.path_cost(path) != Some(*cost)
+        });
+    if stale {
+        *kpaths = KPaths::default();
+    }
+
+    return Ok(());
+}
+
+// each route's rank and cost, next to the middle of its middle edge
+fn update_kpath_labels(
+    mut commands: Commands,
+    kpaths: Res<KPaths>,
+    units: Res<WeightUnits>,
+    nodes: Query<&Node>,
+    q_labels: Query<Entity, With<KPathLabel>>,
+) {
+    if !kpaths.is_changed() && !units.is_changed() {
+        return;
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    let position = position_lookup(&nodes);
+    for (i, (cost, path)) in kpaths.paths.iter().enumerate() {
+        let middle = (path.len() - 1) / 2;
+        let (Some(a), Some(b)) = (position(path[middle]), position(path[middle + 1])) else {
+            continue;
+        };
+        let normal = (b - a).perp().normalize_or_zero();
+        let at = (a + b) * 0.5 + normal * (16.0 + 14.0 * i as f32);
+        let size = if i == kpaths.current { 16.0 } else { 12.0 };
+        commands.spawn((
+            Text2d::new(format!("#{} {}", i + 1, units.format(*cost))),
+            TextFont {
+                font_size: size,
+                ..default()
+            },
+            TextColor(COLORS[i % COLORS.len()]),
+            Transform::from_translation(at.extend(6.0)),
+            KPathLabel,
+        ));
+    }
+}
+
+// every route is offset a little to its own side, so shared edges show all their routes
+fn draw_kpaths(kpaths: Res<KPaths>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let position = position_lookup(&nodes);
+    for (i, (_, path)) in kpaths.paths.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let shift = (i as f32 - (kpaths.paths.len() - 1) as f32 * 0.5) * 3.0;
+        for w in path.windows(2) {
+            let (Some(a), Some(b)) = (position(w[0]), position(w[1])) else {
+                continue;
+            };
+            let normal = (b - a).perp().normalize_or_zero();
+            if i == kpaths.current {
+                for offset in [-1.5, 0.0, 1.5] {
+                    let d = normal * (shift + offset);
+                    gizmos.line_2d(a + d, b + d, color);
+                }
+            } else {
+                let d = normal * shift;
+                gizmos.line_2d(a + d, b + d, color.with_alpha(0.5));
+            }
+        }
+    }
+}
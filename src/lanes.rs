@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::NodeId;
+use crate::{
+    EdgeVisual, GraphSpawner, HighlightedEdges, MainCamera, Node, ResultsHud, WeightUnits,
+    WrapperGraph, cursor_world, edge_at,
+};
+
+pub struct LanesPlugin;
+
+impl Plugin for LanesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            handle_lane_keys.run_if(crate::prompt::prompt_closed),
+        );
+    }
+}
+
+// ctrl+Y over an edge adds another between the same two nodes, drawn beside it, e.g. a back road
+// next to a highway; in directed mode it points the way the hovered one was drawn
+fn handle_lane_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_edges: Query<(Entity, &EdgeVisual)>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut spawner: GraphSpawner,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // shift+Y is the one-way toggle
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !ctrl || shift || !keys.just_pressed(KeyCode::KeyY) {
+        return Ok(());
+    }
+    let Some((a, b)) = cursor_world(&windows, &cams).and_then(|w| edge_at(&nodes, &edges, w))
+    else {
+        hud.0 = "Hover an edge and press Ctrl+Y to add another beside it".to_string();
+        return Ok(());
+    };
+
+    let wg = wrapper_graph.single()?;
+    let mut graph = wg.0.write().unwrap();
+    let node = |id: NodeId| nodes.iter().find(|n| n.id == id).unwrap();
+    let cost = spawner.add_lane(&mut graph, &q_edges, node(a), node(b));
+    highlights.clear_results();
+    hud.0 = format!(
+        "Added a {} edge beside {} - {}, {} between them now; Shift+E over it sets its weight",
+        units.format(cost),
+        a,
+        b,
+        graph.lane_count(a, b)
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+
+use bevy::color::palettes::css::{
+    AQUA, CORNFLOWER_BLUE, CRIMSON, DARK_ORANGE, FUCHSIA, GOLD, LIGHT_SALMON, SPRING_GREEN,
+};
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::{EdgeVisual, MainCamera, Node, ResultsHud, cursor_world, edge_at, ord};
+
+const LAYERS: usize = 8;
+
+const TOGGLE_KEYS: [KeyCode; LAYERS] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Path,
+    AStar,
+    Mst,
+    MinCut,
+    Marked,
+    Baseline,
+    Cycle,
+    Tree,
+}
+
+impl Layer {
+    pub const ALL: [Layer; LAYERS] = [
+        Layer::Path,
+        Layer::AStar,
+        Layer::Mst,
+        Layer::MinCut,
+        Layer::Marked,
+        Layer::Baseline,
+        Layer::Cycle,
+        Layer::Tree,
+    ];
+
+    fn index(self) -> usize {
+        return Layer::ALL.iter().position(|&l| l == self).unwrap();
+    }
+
+    fn name(self) -> &'static str {
+        return match self {
+            Layer::Path => "Shortest path",
+            Layer::AStar => "A* path",
+            Layer::Mst => "Spanning tree",
+            Layer::MinCut => "Minimum cut",
+            Layer::Marked => "Marked edges",
+            Layer::Baseline => "Locked baseline",
+            Layer::Cycle => "Cycle",
+            Layer::Tree => "Shortest path tree",
+        };
+    }
+
+    fn color(self) -> Color {
+        return Color::from(match self {
+            Layer::Path => AQUA,
+            Layer::AStar => FUCHSIA,
+            Layer::Mst => GOLD,
+            Layer::MinCut => CRIMSON,
+            Layer::Marked => SPRING_GREEN,
+            Layer::Baseline => LIGHT_SALMON,
+            Layer::Cycle => DARK_ORANGE,
+            Layer::Tree => CORNFLOWER_BLUE,
+        });
+    }
+
+    // algorithm output goes stale when the graph changes; marks are the user's own, and the
+    // baseline is kept on purpose to compare later runs against
+    fn is_result(self) -> bool {
+        return !matches!(self, Layer::Marked | Layer::Baseline);
+    }
+}
+
+// one edge set per layer, keyed by ord pairs; each layer can be hidden without losing its edges
+#[derive(Resource)]
+pub struct HighlightedEdges {
+    edges: [HashSet<(NodeId, NodeId)>; LAYERS],
+    visible: [bool; LAYERS],
+    // the path drawn on the baseline layer and what it cost when it was locked
+    baseline: Option<(Cost, Vec<NodeId>)>,
+}
+
+impl Default for HighlightedEdges {
+    fn default() -> Self {
+        return HighlightedEdges {
+            edges: Default::default(),
+            visible: [true; LAYERS],
+            baseline: None,
+        };
+    }
+}
+
+impl HighlightedEdges {
+    pub fn layer(&self, layer: Layer) -> &HashSet<(NodeId, NodeId)> {
+        return &self.edges[layer.index()];
+    }
+
+    pub fn is_visible(&self, layer: Layer) -> bool {
+        return self.visible[layer.index()];
+    }
+
+    pub fn layer_mut(&mut self, layer: Layer) -> &mut HashSet<(NodeId, NodeId)> {
+        return &mut self.edges[layer.index()];
+    }
+
+    pub fn set_path(&mut self, layer: Layer, path: &[NodeId]) {
+        let edges = self.layer_mut(layer);
+        edges.clear();
+        for w in path.windows(2) {
+            edges.insert(ord(w[0], w[1]));
+        }
+    }
+
+    pub fn clear_results(&mut self) {
+        for layer in Layer::ALL.into_iter().filter(|l| l.is_result()) {
+            self.layer_mut(layer).clear();
+        }
+    }
+
+    pub fn clear_all(&mut self) {
+        for edges in &mut self.edges {
+            edges.clear();
+        }
+        self.baseline = None;
+    }
+
+    pub fn baseline(&self) -> Option<&(Cost, Vec<NodeId>)> {
+        return self.baseline.as_ref();
+    }
+
+    pub fn lock_baseline(&mut self, cost: Cost, path: &[NodeId]) {
+        self.set_path(Layer::Baseline, path);
+        self.baseline = Some((cost, path.to_vec()));
+    }
+
+    pub fn unlock_baseline(&mut self) {
+        self.layer_mut(Layer::Baseline).clear();
+        self.baseline = None;
+    }
+
+    // how a new path's cost compares with the locked baseline, for the HUD
+    pub fn baseline_delta(
+        &self,
+        graph: &Graph,
+        cost: Cost,
+        format: impl Fn(Cost) -> String,
+    ) -> String {
+        let Some((locked, path)) = &self.baseline else {
+            return String::new();
+        };
+        let delta = cost - locked;
+        let sign = if delta > 0 { "+" } else { "" };
+        // re-walking the baseline shows how much of the delta is down to edited weights
+        let now = match graph.path_cost(path) {
+            Some(now) if now != *locked => format!(", it now costs {}", format(now)),
+            Some(_) => String::new(),
+            None => ", it no longer exists".to_string(),
+        };
+        return format!(
+            "\nvs baseline {} ({}{}): {}{}",
+            Graph::fmt_path(path),
+            format(*locked),
+            now,
+            sign,
+            format(delta)
+        );
+    }
+
+    // blend of every visible layer the edge belongs to
+    pub fn edge_color(&self, a: NodeId, b: NodeId) -> Option<Color> {
+        let key = ord(a, b);
+        let mut colors = Layer::ALL
+            .into_iter()
+            .filter(|&l| self.visible[l.index()] && self.layer(l).contains(&key))
+            .map(|l| l.color());
+        let first = colors.next()?;
+        return Some(colors.fold(first, |acc, c| acc.mix(&c, 0.5)));
+    }
+}
+
+#[derive(Component)]
+struct LayerRow(Layer);
+
+#[derive(Component)]
+struct LayerSwatch(Layer);
+
+pub struct LayersPlugin;
+
+impl Plugin for LayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HighlightedEdges>()
+            .add_systems(Startup, spawn_layer_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_layer_keys.run_if(crate::prompt::prompt_closed),
+                    update_layer_panel,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// sits below the routing panel in the top-right corner
+fn spawn_layer_panel(mut commands: Commands) {
+    commands
+        .spawn(UiNode {
+            position_type: PositionType::Absolute,
+            top: Val::Px(120.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new("Highlight layers (1-8 to toggle, Z marks an edge)"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+            for layer in Layer::ALL {
+                panel
+                    .spawn(UiNode {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(6.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            UiNode {
+                                width: Val::Px(12.0),
+                                height: Val::Px(12.0),
+                                ..default()
+                            },
+                            BackgroundColor(layer.color()),
+                            LayerSwatch(layer),
+                        ));
+                        row.spawn((
+                            Text::new(""),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                            LayerRow(layer),
+                        ));
+                    });
+            }
+        });
+}
+
+fn handle_layer_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    // ctrl and alt with a number are camera views
+    for (i, key) in TOGGLE_KEYS.iter().enumerate() {
+        if keys.just_pressed(*key) && !crate::views::slot_chord_held(&keys) {
+            highlights.visible[i] = !highlights.visible[i];
+        }
+    }
+
+    // shift+Z reshapes the layout
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if shift || !keys.just_pressed(KeyCode::KeyZ) {
+        return;
+    }
+    let Some((a, b)) = cursor_world(&windows, &cams).and_then(|w| edge_at(&nodes, &edges, w))
+    else {
+        hud.0 = "Hover an edge and press Z to mark it".to_string();
+        return;
+    };
+    let marked = highlights.layer_mut(Layer::Marked);
+    let edge = format!("{} - {}", a, b);
+    hud.0 = if marked.remove(&ord(a, b)) {
+        format!("Unmarked {}", edge)
+    } else {
+        marked.insert(ord(a, b));
+        format!("Marked {}", edge)
+    };
+}
+
+fn update_layer_panel(
+    highlights: Res<HighlightedEdges>,
+    mut q_rows: Query<(&LayerRow, &mut Text)>,
+    mut q_swatches: Query<(&LayerSwatch, &mut BackgroundColor)>,
+) {
+    if !highlights.is_changed() {
+        return;
+    }
+    for (LayerRow(layer), mut text) in &mut q_rows {
+        let i = layer.index();
+        let state = if highlights.visible[i] { "on" } else { "off" };
+        text.0 = format!(
+            "{} {} ({} edges, {})",
+            i + 1,
+            layer.name(),
+            highlights.layer(*layer).len(),
+            state
+        );
+    }
+    for (LayerSwatch(layer), mut background) in &mut q_swatches {
+        let color = layer.color();
+        background.0 = if highlights.visible[layer.index()] {
+            color
+        } else {
+            color.with_alpha(0.2)
+        };
+    }
+}
@@ -0,0 +1,18 @@
+#![allow(
+    clippy::needless_return,
+    clippy::too_many_arguments,
+    clippy::type_complexity
+)]
+
+// the graph and its algorithms, and a read-only viewer for other bevy apps to draw results with;
+// the editor in main.rs is built on the same graph module, and autograders can grade paths
+// against it with the checker behind `--check`
+pub mod check;
+// graphs and their node positions as versioned json
+pub mod document;
+pub mod graph;
+// a headless app for driving plugins frame by frame from tests
+pub mod harness;
+pub mod rng;
+mod union_find;
+pub mod viewer;
@@ -1,12 +1,185 @@
-use std::collections::HashSet;
+#![allow(
+    clippy::needless_return,
+    clippy::too_many_arguments,
+    clippy::type_complexity
+)]
+
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 
-use bevy::color::palettes::css::{GREEN, RED, WHITE, YELLOW};
+use bevy::color::palettes::css::{GREEN, ORANGE, RED, WHITE, YELLOW};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
+use bevy::ui::Node as UiNode;
+use bevy::window::{ExitCondition, PrimaryWindow};
+
+use djikstra::{check, document, graph, rng};
+use graph::{AlgoStats, Cost, Edge, Graph, NodeId, TieBreak, TraceEvent};
+
+mod export;
+
+mod prompt;
+use prompt::{Prompt, PromptKind, PromptPlugin, PromptSubmitted};
+
+mod a11y;
+use a11y::A11yPlugin;
+
+mod diff_view;
+use diff_view::{DiffView, DiffViewPlugin};
+
+mod bulk;
+use bulk::BulkEdgesPlugin;
+
+mod scatter;
+use scatter::ScatterPlugin;
+
+mod ruler;
+use ruler::RulerPlugin;
+
+mod regions;
+use regions::RegionsPlugin;
+
+mod annotations;
+use annotations::AnnotationsPlugin;
+
+mod persist;
+use persist::PersistPlugin;
+
+mod sources;
+use sources::{Sources, SourcesPlugin};
+
+mod directed;
+use directed::{DirectedPlugin, EdgeMode};
+
+mod isochrone;
+use isochrone::{Isochrone, IsochronePlugin};
+
+mod routing;
+use routing::{Routing, RoutingPlugin};
+
+mod turns;
+use turns::TurnsPlugin;
+
+mod animation;
+use animation::{Animation, AnimationPlugin};
+
+mod history;
+use history::HistoryPlugin;
+
+mod degree;
+use degree::{DegreePlugin, DegreeView};
+
+mod layers;
+use layers::{HighlightedEdges, Layer, LayersPlugin};
+
+mod notes;
+use notes::{NodeNotes, NotesPlugin};
+
+mod weights;
+use weights::WeightsPlugin;
+
+mod demo;
+use demo::DemoPlugin;
+
+mod traversal;
+use traversal::{Traversal, TraversalPlugin};
 
-mod graph;
-use graph::{Edge, Graph};
+mod matrix;
+use matrix::MatrixPlugin;
+
+mod mst;
+use mst::MstPlugin;
+
+mod heuristic;
+use heuristic::{HeuristicPlugin, HeuristicView};
+
+mod prune;
+use prune::PrunePlugin;
+
+mod kpaths;
+use kpaths::KPathsPlugin;
+
+mod batch;
+use batch::BatchPlugin;
+
+mod components;
+use components::{Components, ComponentsPlugin};
+
+mod topo;
+use topo::TopoPlugin;
+
+mod cycles;
+use cycles::CyclesPlugin;
+
+mod flow;
+use flow::FlowPlugin;
+
+mod histogram;
+use histogram::HistogramPlugin;
+
+mod stats;
+use stats::StatsPlugin;
+mod bookmarks;
+use bookmarks::BookmarksPlugin;
+mod params;
+use params::{AlgorithmParams, ParamsPlugin};
+mod spt;
+use spt::SptPlugin;
+mod quiz;
+use quiz::QuizPlugin;
+mod jitter;
+use jitter::JitterPlugin;
+mod ties;
+use ties::TiesPlugin;
+mod corridor;
+use corridor::{Corridor, CorridorPlugin};
+mod views;
+use views::ViewsPlugin;
+mod walk;
+use walk::{RandomWalk, WalkPlugin};
+mod presenter;
+use presenter::PresenterPlugin;
+mod stamp;
+use stamp::StampPlugin;
+mod suggest;
+use suggest::SuggestPlugin;
+mod euler;
+use euler::EulerPlugin;
+mod failures;
+use failures::FailuresPlugin;
+mod tsp;
+use tsp::TspPlugin;
+mod coloring;
+use coloring::{Coloring, ColoringPlugin};
+mod traffic;
+use traffic::TrafficPlugin;
+mod wilson;
+use wilson::WilsonPlugin;
+mod alt;
+use alt::AltPlugin;
+mod ch;
+use ch::ChPlugin;
+mod dstar;
+use dstar::DStarPlugin;
+mod tags;
+use tags::{Tags, TagsPlugin};
+mod profile;
+use profile::ProfilePlugin;
+mod lanes;
+use lanes::LanesPlugin;
+mod morph;
+use morph::MorphPlugin;
+mod patterns;
+use patterns::PatternsPlugin;
+mod node_info;
+use node_info::NodeInfoPlugin;
+
+mod reshape;
+use reshape::ReshapePlugin;
+mod integrity;
+use integrity::IntegrityPlugin;
+mod import;
+use import::ImportPlugin;
 
 #[derive(Component)]
 struct MainCamera;
@@ -15,7 +188,7 @@ struct MainCamera;
 struct Node {
     position: Vec2,
     r: f32,
-    id: usize,
+    id: NodeId,
 }
 
 #[derive(Component)]
@@ -23,17 +196,22 @@ struct WrapperGraph(Arc<RwLock<Graph>>);
 
 #[derive(Component)]
 struct SelectedNode {
-    id: Option<usize>,
+    id: Option<NodeId>,
 }
 
 #[derive(Component, Debug)]
 struct StartNode {
-    id: Option<usize>,
+    id: Option<NodeId>,
 }
 
 #[derive(Component, Debug)]
 struct GoalNode {
-    id: Option<usize>,
+    id: Option<NodeId>,
+}
+
+#[derive(Component, Default)]
+struct MultiSelection {
+    ids: Vec<NodeId>,
 }
 
 #[derive(Component)]
@@ -48,34 +226,247 @@ struct EdgeMat(Handle<ColorMaterial>);
 #[derive(Component)]
 struct Background;
 
+// one lane of the a - b pair; lane 0 unless the pair has parallel edges
 #[derive(Component)]
 struct EdgeVisual {
-    a: usize,
-    b: usize,
+    a: NodeId,
+    b: NodeId,
+    lane: usize,
+}
+
+#[derive(Component)]
+struct EdgeLabel {
+    a: NodeId,
+    b: NodeId,
+    lane: usize,
+}
+
+// the edge's name, if it has one, written along it on the side away from the weight
+#[derive(Component)]
+struct EdgeName {
+    a: NodeId,
+    b: NodeId,
+    lane: usize,
+}
+
+// title for the caption block on exports; None leaves exports uncaptioned
+#[derive(Resource, Default)]
+struct ExportCaption {
+    title: Option<String>,
+}
+
+#[derive(Resource)]
+struct WeightUnits {
+    scale: f32,
+    suffix: String,
+}
+
+impl Default for WeightUnits {
+    fn default() -> Self {
+        return WeightUnits {
+            scale: 1.0,
+            suffix: String::new(),
+        };
+    }
+}
+
+impl WeightUnits {
+    fn format(&self, cost: Cost) -> String {
+        let value = if self.scale == 1.0 {
+            cost.to_string()
+        } else {
+            format!("{:.2}", cost as f32 * self.scale)
+        };
+        return self.with_suffix(value);
+    }
+
+    fn format_distance(&self, distance: f32) -> String {
+        return self.with_suffix(format!("{:.2}", distance * self.scale));
+    }
+
+    fn with_suffix(&self, value: String) -> String {
+        if self.suffix.is_empty() {
+            return value;
+        }
+        return format!("{} {}", value, self.suffix);
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut words = text.split_whitespace().peekable();
+        let scale = match words.peek().and_then(|w| w.parse::<f32>().ok()) {
+            Some(scale) if scale > 0.0 => {
+                words.next();
+                scale
+            }
+            _ => 1.0,
+        };
+        return WeightUnits {
+            scale,
+            suffix: words.collect::<Vec<_>>().join(" "),
+        };
+    }
+}
+
+// P and everything that reruns it find the minimum-hop route instead when min_hops is set, and
+// pick among equally cheap routes by tie_break, copied from the algorithm parameters
+#[derive(Resource, Default)]
+struct PathMode {
+    min_hops: bool,
+    tie_break: TieBreak,
+}
+
+impl PathMode {
+    fn path(&self, graph: &Graph, start: NodeId, goal: NodeId) -> Option<(Cost, Vec<NodeId>)> {
+        if self.min_hops {
+            return graph.bfs_shortest_path(start, goal);
+        }
+        return graph.shortest_path_tie_break(start, goal, self.tie_break);
+    }
 }
 
 #[derive(Resource, Default)]
-struct HighlightedEdges(HashSet<(usize, usize)>);
+struct ResultsHud(String);
+
+#[derive(Component)]
+struct ResultsText;
+
+const MIN_EDGE_LENGTH: f32 = 1.0;
+const NODE_RADIUS: f32 = 20.0;
+const EDGE_HIT_DISTANCE: f32 = 6.0;
+const EDGE_THICKNESS: f32 = 2.0;
+const LANE_GAP: f32 = 12.0;
 
 fn main() {
+    // `--check <graph.json> <from> <to> <path file>` grades a path and exits without a window
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|a| a == "--check") {
+        let (verdict, code) = check::run(&args[2..]);
+        println!("{}", verdict);
+        std::process::exit(code);
+    }
+
     App::new()
-        .add_plugins(DefaultPlugins)
-        .add_systems(Startup, setup)
-        .init_resource::<HighlightedEdges>()
-        .add_systems(Update, (handle_click, handle_keyboard_input))
-        .add_systems(
-            Update,
-            (
-                add_node_visuals,
-                update_selected_ring,
-                update_node_colors,
-                update_edge_colors,
-            ),
-        )
+        .add_plugins((
+            // the presenter window never keeps the app open on its own
+            DefaultPlugins.set(WindowPlugin {
+                exit_condition: ExitCondition::OnPrimaryClosed,
+                ..default()
+            }),
+            EditorPlugin,
+        ))
         .run();
 }
 
-fn ord(a: usize, b: usize) -> (usize, usize) {
+// the whole editor but the window and renderer, which DefaultPlugins brings in main and
+// djikstra::harness::Harness stands in for in tests
+struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((PromptPlugin, A11yPlugin, PersistPlugin, LayersPlugin))
+            // editing tools
+            .add_plugins((
+                BulkEdgesPlugin,
+                ScatterPlugin,
+                RulerPlugin,
+                RegionsPlugin,
+                AnnotationsPlugin,
+                DirectedPlugin,
+                RoutingPlugin,
+                TurnsPlugin,
+                NotesPlugin,
+                WeightsPlugin,
+                PrunePlugin,
+                ReshapePlugin,
+            ))
+            // algorithm views
+            .add_plugins((
+                DiffViewPlugin,
+                SourcesPlugin,
+                IsochronePlugin,
+                AnimationPlugin,
+                HistoryPlugin,
+                DegreePlugin,
+                DemoPlugin,
+                TraversalPlugin,
+                MatrixPlugin,
+                MstPlugin,
+                HeuristicPlugin,
+                KPathsPlugin,
+                BatchPlugin,
+                ComponentsPlugin,
+                TopoPlugin,
+            ))
+            .add_plugins((
+                CyclesPlugin,
+                FlowPlugin,
+                HistogramPlugin,
+                StatsPlugin,
+                BookmarksPlugin,
+                ParamsPlugin,
+                SptPlugin,
+                QuizPlugin,
+                JitterPlugin,
+                TiesPlugin,
+                CorridorPlugin,
+                ViewsPlugin,
+                WalkPlugin,
+                PresenterPlugin,
+                StampPlugin,
+            ))
+            .add_plugins((
+                SuggestPlugin,
+                EulerPlugin,
+                FailuresPlugin,
+                TspPlugin,
+                ColoringPlugin,
+                TrafficPlugin,
+                WilsonPlugin,
+                AltPlugin,
+                ChPlugin,
+                DStarPlugin,
+                TagsPlugin,
+                ProfilePlugin,
+                LanesPlugin,
+                MorphPlugin,
+                PatternsPlugin,
+            ))
+            .add_plugins((NodeInfoPlugin, IntegrityPlugin, ImportPlugin))
+            .add_systems(Startup, setup)
+            .init_resource::<ResultsHud>()
+            .init_resource::<WeightUnits>()
+            .init_resource::<ExportCaption>()
+            .init_resource::<PathMode>()
+            .init_resource::<rng::Rng>()
+            .add_systems(
+                Update,
+                // the relaxation drill takes over plain clicks while it runs
+                (
+                    handle_click.run_if(quiz::quiz_inactive),
+                    remove_clicked_edge.run_if(quiz::quiz_inactive),
+                    handle_keyboard_input,
+                    handle_exports,
+                )
+                    .run_if(prompt::prompt_closed),
+            )
+            .add_systems(Update, (handle_units, handle_export_caption))
+            .add_systems(
+                Update,
+                (
+                    add_node_visuals,
+                    sync_node_transforms,
+                    sync_edge_transforms,
+                    update_selected_ring,
+                    update_node_colors,
+                    update_edge_colors,
+                    update_results_hud,
+                    update_edge_labels,
+                ),
+            );
+    }
+}
+
+fn ord(a: NodeId, b: NodeId) -> (NodeId, NodeId) {
     if a < b { (a, b) } else { (b, a) }
 }
 
@@ -89,9 +480,10 @@ fn setup(
         Camera2d,
         MainCamera,
         SelectedNode { id: None },
+        MultiSelection::default(),
         StartNode { id: None },
         GoalNode { id: None },
-        WrapperGraph(Arc::new(RwLock::new(Graph { nodes: vec![] }))),
+        WrapperGraph(Arc::new(RwLock::new(Graph::default()))),
     ));
 
     let win = windows.single().expect("primary window");
@@ -102,6 +494,22 @@ fn setup(
         Transform::from_translation(Vec3::new(0.0, 0.0, -100.0)),
         Background,
     ));
+
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        UiNode {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        ResultsText,
+    ));
 }
 
 fn add_node_visuals(
@@ -118,69 +526,168 @@ fn add_node_visuals(
             MeshMaterial2d(mat.clone()),
             NodeMat(mat),
             Transform::from_translation(Vec3::new(node.position.x, node.position.y, 0.0)),
-            Text2d::new((b'A' + node.id as u8) as char),
+            Text2d::new(node.id.to_string()),
             TextColor(Color::BLACK),
         ));
     }
 }
 
+fn sync_node_transforms(mut q_nodes: Query<(&Node, &mut Transform), Changed<Node>>) {
+    for (node, mut transform) in &mut q_nodes {
+        transform.translation.x = node.position.x;
+        transform.translation.y = node.position.y;
+    }
+}
+
+// edges are rebuilt between their nodes whenever one of those moves, e.g. in the topological layout
+fn sync_edge_transforms(
+    q_moved: Query<&Node, Changed<Node>>,
+    nodes: Query<&Node>,
+    mut q_edges: Query<
+        (&EdgeVisual, &Mesh2d, &mut Transform, &Children),
+        (Without<EdgeLabel>, Without<EdgeName>),
+    >,
+    mut q_labels: Query<&mut Transform, With<EdgeLabel>>,
+    mut q_names: Query<&mut Transform, (With<EdgeName>, Without<EdgeLabel>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let moved: HashSet<NodeId> = q_moved.iter().map(|n| n.id).collect();
+    if moved.is_empty() {
+        return;
+    }
+    let position = position_lookup(&nodes);
+    for (ev, mesh, mut transform, children) in &mut q_edges {
+        if !moved.contains(&ev.a) && !moved.contains(&ev.b) {
+            continue;
+        }
+        let (Some(a), Some(b)) = (position(ev.a), position(ev.b)) else {
+            continue;
+        };
+        let d = b - a;
+        let angle = d.y.atan2(d.x);
+        let mid = (a + b) * 0.5 + lane_offset(ev, a, b);
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            *mesh = Rectangle::new(d.length(), EDGE_THICKNESS).into();
+        }
+        transform.translation = mid.extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(angle);
+        for child in children.iter() {
+            if let Ok(mut label) = q_labels.get_mut(child) {
+                label.rotation = Quat::from_rotation_z(-angle);
+            }
+            if let Ok(mut name) = q_names.get_mut(child) {
+                name.rotation = along_edge(angle);
+            }
+        }
+    }
+}
+
 fn update_selected_ring(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    q_sel: Query<&SelectedNode, (With<MainCamera>, Changed<SelectedNode>)>,
-    q_nodes: Query<&Node>,
+    q_sel: Query<
+        (&SelectedNode, &MultiSelection),
+        (
+            With<MainCamera>,
+            Or<(Changed<SelectedNode>, Changed<MultiSelection>)>,
+        ),
+    >,
+    q_nodes: Query<(Entity, &Node)>,
     q_old: Query<Entity, With<SelectedRing>>,
 ) {
-    if q_sel.is_empty() {
+    let Ok((selected, multi)) = q_sel.single() else {
         return;
-    }
+    };
 
     for e in q_old.iter() {
         commands.entity(e).despawn();
     }
 
-    let selected_id = q_sel.single().unwrap().id;
-    let Some(id) = selected_id else {
-        return;
-    };
-    let Some(node) = q_nodes.iter().find(|n| n.id == id) else {
-        return;
-    };
+    let rings = selected
+        .id
+        .map(|id| (id, 5.0, Color::from(RED)))
+        .into_iter()
+        .chain(multi.ids.iter().map(|&id| (id, 10.0, Color::from(ORANGE))));
+    for (id, inset, color) in rings {
+        let Some((node_entity, node)) = q_nodes.iter().find(|(_, n)| n.id == id) else {
+            continue;
+        };
 
-    commands.spawn((
-        Mesh2d(meshes.add(Annulus::new(node.r + 5.0, node.r + 8.0))),
-        MeshMaterial2d(materials.add(Color::from(RED))),
-        Transform::from_translation(Vec3::new(node.position.x, node.position.y, 1.0)),
-        SelectedRing,
-    ));
+        // parented to the node so the ring tracks it when the node moves
+        commands.spawn((
+            Mesh2d(meshes.add(Annulus::new(node.r + inset, node.r + inset + 3.0))),
+            MeshMaterial2d(materials.add(color)),
+            Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
+            SelectedRing,
+            ChildOf(node_entity),
+        ));
+    }
 }
 
 fn update_node_colors(
-    q_flags: Query<
-        (&StartNode, &GoalNode),
-        (
-            With<MainCamera>,
-            Or<(Changed<StartNode>, Changed<GoalNode>)>,
-        ),
-    >,
+    q_flags: Query<(Ref<StartNode>, Ref<GoalNode>), With<MainCamera>>,
+    animation: Res<Animation>,
+    heuristic: Res<HeuristicView>,
+    traversal: Res<Traversal>,
+    components: Res<Components>,
+    sources: Res<Sources>,
+    isochrone: Res<Isochrone>,
+    degree: Res<DegreeView>,
+    corridor: Res<Corridor>,
+    walk: Res<RandomWalk>,
+    coloring: Res<Coloring>,
+    tags: Res<Tags>,
     q_nodes: Query<(&Node, &NodeMat)>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) -> Result<()> {
-    if q_flags.is_empty() {
+    let (start, goal) = q_flags.single()?;
+    let overlays = animation.is_changed()
+        || heuristic.is_changed()
+        || traversal.is_changed()
+        || components.is_changed()
+        || sources.is_changed()
+        || isochrone.is_changed()
+        || degree.is_changed()
+        || corridor.is_changed()
+        || walk.is_changed()
+        || coloring.is_changed()
+        || tags.is_changed();
+    if !start.is_changed() && !goal.is_changed() && !overlays {
         return Ok(());
     }
-    let (start, goal) = q_flags.single()?;
 
     for (node, NodeMat(handle)) in q_nodes.iter() {
         if let Some(m) = materials.get_mut(handle) {
-            m.color = if start.id == Some(node.id) {
+            let color = if start.id == Some(node.id) {
                 Color::from(GREEN)
             } else if goal.id == Some(node.id) {
                 Color::from(YELLOW)
+            } else if let Some(color) = animation.node_color(node.id) {
+                color
+            } else if let Some(color) = corridor.node_color(node.id) {
+                color
+            } else if let Some(color) = walk.node_color(node.id) {
+                color
+            } else if let Some(color) = coloring.node_color(node.id) {
+                color
+            } else if let Some(color) = heuristic.node_color(node.id) {
+                color
+            } else if let Some(color) = traversal.node_color(node.id) {
+                color
+            } else if let Some(color) = components.node_color(node.id) {
+                color
+            } else if let Some(color) = sources.node_color(node.id) {
+                color
+            } else if let Some(color) = isochrone.node_color(node.id) {
+                color
+            } else if let Some(color) = degree.node_color(node.id) {
+                color
             } else {
                 Color::from(WHITE)
             };
+            // nodes outside the tag filter are dimmed whatever overlay colours them
+            m.color = tags.dim_node(node.id, color);
         }
     }
 
@@ -189,17 +696,115 @@ fn update_node_colors(
 
 fn update_edge_colors(
     highlights: Res<HighlightedEdges>,
+    diff: Res<DiffView>,
+    tags: Res<Tags>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut edges: Query<(&EdgeVisual, &EdgeMat)>,
 ) {
-    let on = Color::from(bevy::color::palettes::css::AQUA);
     let off = Color::from(bevy::color::palettes::css::WHITE);
 
     for (ev, EdgeMat(h)) in &mut edges {
-        let target = if highlights.0.contains(&ord(ev.a, ev.b)) { on } else { off };
+        let target = if let Some(color) = diff.edge_color(ev.a, ev.b) {
+            color
+        } else if let Some(color) = highlights.edge_color(ev.a, ev.b) {
+            color
+        } else {
+            off
+        };
         if let Some(m) = materials.get_mut(h) {
-            m.color = target;
+            m.color = tags.dim_edge(ev.a, ev.b, target);
+        }
+    }
+}
+
+fn update_results_hud(hud: Res<ResultsHud>, mut q_text: Query<&mut Text, With<ResultsText>>) {
+    if !hud.is_changed() {
+        return;
+    }
+    for mut text in &mut q_text {
+        text.0 = hud.0.clone();
+    }
+}
+
+fn update_edge_labels(
+    units: Res<WeightUnits>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut q_labels: Query<(&EdgeLabel, &mut Text2d)>,
+    mut q_names: Query<(&EdgeName, &mut Text2d), Without<EdgeLabel>>,
+) -> Result<()> {
+    let wg = wrapper_graph.single()?;
+    let graph = wg.0.read().unwrap();
+
+    // costs change under units, routing profiles and reversals, and names when edited; only touch
+    // labels that differ
+    for (label, mut text) in &mut q_labels {
+        if let Some(edge) = graph.lane(label.a, label.b, label.lane) {
+            let formatted = units.format(edge.cost);
+            if text.0 != formatted {
+                text.0 = formatted;
+            }
+        }
+    }
+    for (name, mut text) in &mut q_names {
+        let named = graph
+            .lane_label(name.a, name.b, name.lane)
+            .unwrap_or_default();
+        if text.0 != named {
+            text.0 = named.to_string();
+        }
+    }
+
+    return Ok(());
+}
+
+fn handle_units(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut units: ResMut<WeightUnits>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    // shift+U is the A* heuristic, ctrl+U imports points
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if keys.just_pressed(KeyCode::KeyU) && !shift && !ctrl && !prompt.is_open() {
+        prompt.open(PromptKind::Units, "Weight units (e.g. `0.01 km`)");
+    }
+
+    for event in submitted.read() {
+        if event.kind != PromptKind::Units {
+            continue;
         }
+        *units = WeightUnits::parse(&event.text);
+        hud.0 = format!("1 weight unit = {}", units.format(1));
+    }
+}
+
+// F4 asks for a caption title; an empty one turns captions off again
+fn handle_export_caption(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut caption: ResMut<ExportCaption>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    if keys.just_pressed(KeyCode::F4) && !prompt.is_open() {
+        prompt.open(
+            PromptKind::Caption,
+            "Caption title for exports (empty turns captions off)",
+        );
+    }
+
+    for event in submitted.read() {
+        if event.kind != PromptKind::Caption {
+            continue;
+        }
+        let title = event.text.trim();
+        caption.title = (!title.is_empty()).then(|| title.to_string());
+        hud.0 = match &caption.title {
+            Some(title) => format!("Exports are captioned \"{}\" with a legend", title),
+            None => "Exports are uncaptioned".to_string(),
+        };
     }
 }
 
@@ -225,29 +830,203 @@ fn handle_keyboard_input(
     mut selected_node: Query<&mut SelectedNode, With<MainCamera>>,
     mut start_node: Query<&mut StartNode, With<MainCamera>>,
     mut goal_node: Query<&mut GoalNode, With<MainCamera>>,
+    nodes: Query<&Node>,
     mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+    mut mode: ResMut<PathMode>,
+    mut heuristic_view: ResMut<HeuristicView>,
+    params: Res<AlgorithmParams>,
+    units: Res<WeightUnits>,
+    tags: Res<Tags>,
 ) -> Result<()> {
-    if keys.just_pressed(KeyCode::KeyP) {
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return Ok(()); // ctrl chords (save/load) are not node commands
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    // a tie rule picked in the parameters redoes the path on screen
+    if mode.tie_break != params.tie_break {
+        mode.tie_break = params.tie_break;
+        let (Some(start), Some(goal)) = (start_node.single()?.id, goal_node.single()?.id) else {
+            return Ok(());
+        };
+        if !highlights.layer(Layer::Path).is_empty() {
+            let whole = wrapper_graph.single()?.0.read().unwrap();
+            let graph = tags.restricted(&whole);
+            show_shortest_path(
+                &graph,
+                start,
+                goal,
+                &mode,
+                &units,
+                &mut highlights,
+                &mut hud,
+            );
+        }
+        return Ok(());
+    }
+    if shift && keys.just_pressed(KeyCode::KeyH) {
+        mode.min_hops = !mode.min_hops;
+        hud.0 = if mode.min_hops {
+            "Minimum-hop mode: P ignores weights and finds the route with the fewest edges"
+        } else {
+            "Weighted mode: P finds the cheapest route"
+        }
+        .to_string();
+        // a path already on screen is redone in the new mode
+        let (Some(start), Some(goal)) = (start_node.single()?.id, goal_node.single()?.id) else {
+            return Ok(());
+        };
+        if !highlights.layer(Layer::Path).is_empty() {
+            let whole = wrapper_graph.single()?.0.read().unwrap();
+            let graph = tags.restricted(&whole);
+            let message = hud.0.clone();
+            show_shortest_path(
+                &graph,
+                start,
+                goal,
+                &mode,
+                &units,
+                &mut highlights,
+                &mut hud,
+            );
+            hud.0 = format!("{}\n{}", message, hud.0);
+        }
+        return Ok(());
+    }
+    // shift+B locks the shown path as a baseline for later runs to be compared with
+    if shift && keys.just_pressed(KeyCode::KeyB) {
+        if highlights.baseline().is_some() {
+            highlights.unlock_baseline();
+            hud.0 = "Baseline unlocked".to_string();
+            return Ok(());
+        }
+        // the path P shows, so on the tag-restricted graph when there is one
+        let whole = wrapper_graph.single()?.0.read().unwrap();
+        let graph = tags.restricted(&whole);
+        let shown = match (start_node.single()?.id, goal_node.single()?.id) {
+            (Some(start), Some(goal)) if !highlights.layer(Layer::Path).is_empty() => {
+                mode.path(&graph, start, goal)
+            }
+            _ => None,
+        };
+        let Some((cost, path)) = shown else {
+            hud.0 = "Show a path with P, then press Shift+B to lock it as the baseline".to_string();
+            return Ok(());
+        };
+        highlights.lock_baseline(cost, &path);
+        hud.0 = format!(
+            "Locked {} ({}) as the baseline",
+            Graph::fmt_path(&path),
+            units.format(cost)
+        );
+        return Ok(());
+    }
+    if keys.just_pressed(KeyCode::KeyP) && !shift {
         let wg = wrapper_graph.single()?;
-        let graph = wg.0.read().unwrap();
-        highlights.0.clear();
+        let whole = wg.0.read().unwrap();
+        let graph = tags.restricted(&whole);
 
         let (Some(start_node_id), Some(goal_node_id)) =
             (start_node.single()?.id, goal_node.single()?.id)
         else {
             println!("Missing starting or goal node!");
+            hud.0 = "Missing starting or goal node!".to_string();
             return Ok(());
         };
-        let Some((length, path)) = graph.shortest_path(start_node_id, goal_node_id) else {
-            println!("No current available path");
+        show_shortest_path(
+            &graph,
+            start_node_id,
+            goal_node_id,
+            &mode,
+            &units,
+            &mut highlights,
+            &mut hud,
+        );
+        return Ok(());
+    }
+    // a heuristic picked while an A* path is on screen reruns it
+    let rerun = heuristic_view.rerun;
+    if (keys.just_pressed(KeyCode::KeyA) && !shift) || rerun {
+        heuristic_view.rerun = false;
+        let wg = wrapper_graph.single()?;
+        let whole = wg.0.read().unwrap();
+        let graph = tags.restricted(&whole);
+        let (Some(start), Some(goal)) = (start_node.single()?.id, goal_node.single()?.id) else {
+            println!("Missing starting or goal node!");
+            hud.0 = "Missing starting or goal node!".to_string();
             return Ok(());
         };
 
-        println!("Path length: {}, Path: {}", length, Graph::fmt_path(&path));
-
-        for w in path.windows(2) {
-            highlights.0.insert(ord(w[0], w[1]));
+        // dijkstra first, so the two paths and their counters can be compared; A* is weighted, so
+        // the comparison is too whatever the path mode
+        let weighted = PathMode::default();
+        show_shortest_path(
+            &graph,
+            start,
+            goal,
+            &weighted,
+            &units,
+            &mut highlights,
+            &mut hud,
+        );
+        highlights.layer_mut(Layer::AStar).clear();
+        heuristic_view.clear();
+        if graph.has_negative_edges() {
+            hud.0 = format!("{}\nA* needs non-negative weights", hud.0);
+            return Ok(());
         }
+        let dijkstra = graph.shortest_path_with_stats(start, goal).1;
+        let positions = node_positions(&nodes, graph.nodes.len());
+        let heuristic = position_heuristic(
+            &graph,
+            &positions,
+            goal,
+            &heuristic_view,
+            params.astar_weight,
+        );
+        let trace = graph.astar_trace(start, goal, &heuristic);
+        let stats = AlgoStats::from_events(trace.events.iter().map(|e| &e.event));
+        // true distances to the goal, to grade the heuristic at every node A* settled
+        let remaining: Vec<Option<Cost>> = graph
+            .reverse()
+            .multi_source_shortest_paths(&[goal])
+            .into_iter()
+            .map(|d| d.map(|(d, _)| d))
+            .collect();
+        heuristic_view.record(
+            trace.events.iter().filter_map(|e| match e.event {
+                TraceEvent::Pop { node, stale, .. } if !stale => Some(node),
+                _ => None,
+            }),
+            &heuristic,
+            &remaining,
+        );
+
+        let Some((length, path)) = trace.result else {
+            return Ok(());
+        };
+        println!(
+            "A* path length: {}, Path: {}",
+            length,
+            Graph::fmt_path(&path)
+        );
+        highlights.set_path(Layer::AStar, &path);
+        let weighted = if params.astar_weight == 1.0 {
+            String::new()
+        } else {
+            format!(" (heuristic weight x{})", params.astar_weight)
+        };
+        hud.0 = format!(
+            "{}\nA* path length{}: {}, Path: {}\n{}\nA* settled {} of Dijkstra's {}\n{}",
+            hud.0,
+            weighted,
+            units.format(length),
+            Graph::fmt_path(&path),
+            stats,
+            stats.settled,
+            dijkstra.settled,
+            heuristic_view.summary()
+        );
         return Ok(());
     }
 
@@ -261,13 +1040,15 @@ fn handle_keyboard_input(
 
     for key in keys.get_just_pressed() {
         match key {
-            KeyCode::KeyS => {
+            // shift+S is the topological layout
+            KeyCode::KeyS if !shift => {
                 start_node.single_mut()?.id = Some(id);
                 if goal_node.single()?.id == Some(id) {
                     goal_node.single_mut()?.id = None;
                 }
             }
-            KeyCode::KeyG => {
+            // shift+G is the max flow
+            KeyCode::KeyG if !shift => {
                 goal_node.single_mut()?.id = Some(id);
                 if start_node.single()?.id == Some(id) {
                     start_node.single_mut()?.id = None;
@@ -280,7 +1061,213 @@ fn handle_keyboard_input(
     return Ok(());
 }
 
-fn clicked_node_id(nodes: &Query<&Node>, world: Vec2) -> Option<usize> {
+// runs Dijkstra for start -> goal, highlighting the path and reporting it on the HUD
+fn show_shortest_path(
+    graph: &Graph,
+    start: NodeId,
+    goal: NodeId,
+    mode: &PathMode,
+    units: &WeightUnits,
+    highlights: &mut HighlightedEdges,
+    hud: &mut ResultsHud,
+) {
+    highlights.layer_mut(Layer::Path).clear();
+    if mode.min_hops {
+        let Some((length, path)) = graph.bfs_shortest_path(start, goal) else {
+            println!("No current available path");
+            hud.0 = format!(
+                "No current available path{}",
+                no_path_reason(graph, start, goal)
+            );
+            return;
+        };
+        let hops = path.len() - 1;
+        println!("Hops: {}, Path: {}", hops, Graph::fmt_path(&path));
+        // the weighted optimum alongside, so the two notions of distance can be compared
+        let cheapest = graph.shortest_path(start, goal).map(|(cost, _)| cost);
+        hud.0 = format!(
+            "Fewest hops: {}, Path: {} (weight {}{})",
+            hops,
+            Graph::fmt_path(&path),
+            units.format(length),
+            match cheapest {
+                Some(cost) if cost < length =>
+                    format!(", cheapest route weighs {}", units.format(cost)),
+                _ => String::new(),
+            }
+        );
+        highlights.set_path(Layer::Path, &path);
+        hud.0 += &highlights.baseline_delta(graph, length, |c| units.format(c));
+        return;
+    }
+    let (mut result, stats) = graph.shortest_path_with_stats(start, goal);
+    // the counters are the search's own; only the route it reports changes
+    if mode.tie_break != TieBreak::Search {
+        result = graph.shortest_path_tie_break(start, goal, mode.tie_break);
+    }
+    let Some((length, path)) = result else {
+        println!("No current available path");
+        hud.0 = format!(
+            "No current available path{}\n{}",
+            no_path_reason(graph, start, goal),
+            stats
+        );
+        return;
+    };
+
+    println!("Path length: {}, Path: {}", length, Graph::fmt_path(&path));
+    hud.0 = format!(
+        "Path length: {}, Path: {}\n{}",
+        units.format(length),
+        Graph::fmt_path(&path),
+        stats
+    );
+    highlights.set_path(Layer::Path, &path);
+    hud.0 += &highlights.baseline_delta(graph, length, |c| units.format(c));
+}
+
+fn no_path_reason(graph: &Graph, start: NodeId, goal: NodeId) -> String {
+    if components::separated(graph, start, goal) {
+        return ": start and goal are in different components (Shift+I shows them)".to_string();
+    }
+    return String::new();
+}
+
+// the picked estimate of the distance to the goal, scaled down by the cheapest cost per unit of
+// length in the graph so the straight line never overestimates, even after weights were edited
+// or re-derived by a profile. manhattan and a scale or weight above 1 give that guarantee away
+fn position_heuristic(
+    graph: &Graph,
+    positions: &[Vec2],
+    goal: NodeId,
+    view: &HeuristicView,
+    weight: f32,
+) -> impl Fn(NodeId) -> Cost + use<> {
+    let mut per_unit = f32::INFINITY;
+    for (from, edge) in graph.arcs() {
+        let len = positions[from.index()].distance(positions[edge.node.index()]);
+        if len > 0.0 {
+            per_unit = per_unit.min(edge.cost as f32 / len);
+        }
+    }
+    if !per_unit.is_finite() {
+        per_unit = 0.0;
+    }
+    let remaining: Vec<Cost> = positions
+        .iter()
+        .map(|&p| (view.estimate(p, positions[goal.index()]) * per_unit * weight) as Cost)
+        .collect();
+    return move |node: NodeId| remaining[node.index()];
+}
+
+fn node_positions(nodes: &Query<&Node>, count: usize) -> Vec<Vec2> {
+    let mut positions = vec![Vec2::ZERO; count];
+    for node in nodes {
+        positions[node.id.index()] = node.position;
+    }
+    return positions;
+}
+
+// each node's position by id, collected once so a system drawing many edges doesn't search the
+// query for every end
+fn position_lookup(nodes: &Query<&Node>) -> impl Fn(NodeId) -> Option<Vec2> {
+    let positions: HashMap<NodeId, Vec2> = nodes.iter().map(|n| (n.id, n.position)).collect();
+    return move |id| positions.get(&id).copied();
+}
+
+fn handle_exports(
+    keys: Res<ButtonInput<KeyCode>>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    start_node: Query<&StartNode, With<MainCamera>>,
+    goal_node: Query<&GoalNode, With<MainCamera>>,
+    nodes: Query<&Node>,
+    notes: Res<NodeNotes>,
+    caption: Res<ExportCaption>,
+    units: Res<WeightUnits>,
+    tags: Res<Tags>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if ctrl && keys.just_pressed(KeyCode::KeyC) {
+        // sorted and free of positions, so two sessions' files diff line by line
+        let graph = wrapper_graph.single()?.0.read().unwrap();
+        let text = graph.canonical_text();
+        hud.0 = match std::fs::write("graph.txt", text) {
+            Ok(()) => "Wrote graph.txt, the graph's canonical form to diff against".to_string(),
+            Err(e) => format!("Canonical export failed: {}", e),
+        };
+        return Ok(());
+    }
+    let (exercise, trace, mermaid) = (
+        // ctrl+E traces an euler path
+        keys.just_pressed(KeyCode::KeyE) && !shift && !ctrl,
+        // shift+J is the batch runner
+        keys.just_pressed(KeyCode::KeyJ) && !shift && !ctrl,
+        keys.just_pressed(KeyCode::KeyJ) && ctrl,
+    );
+    if mermaid {
+        // the graph P searches, so without the edges a tag restriction hides, with the start,
+        // goal and shortest path styled when they are set
+        let whole = wrapper_graph.single()?.0.read().unwrap();
+        let graph = tags.restricted(&whole);
+        let query = start_node.single()?.id.zip(goal_node.single()?.id);
+        let path = query
+            .and_then(|(start, goal)| graph.shortest_path(start, goal))
+            .map(|(_, path)| path)
+            .unwrap_or_default();
+        let text = export::mermaid(&graph, query, &path, |c| units.format(c));
+        hud.0 = match std::fs::write("graph.mmd", text) {
+            Ok(()) => "Exported graph.mmd, a Mermaid flowchart to paste into Markdown".to_string(),
+            Err(e) => format!("Mermaid export failed: {}", e),
+        };
+        return Ok(());
+    }
+    if !exercise && !trace {
+        return Ok(());
+    }
+    let (Some(start), Some(goal)) = (start_node.single()?.id, goal_node.single()?.id) else {
+        hud.0 = "Set a start and goal node before exporting".to_string();
+        return Ok(());
+    };
+
+    let wg = wrapper_graph.single()?;
+    let graph = wg.0.read().unwrap();
+
+    if trace {
+        let run = graph.shortest_path_trace(start, goal);
+        let json = export::trace_json(&graph, &run, start, goal, &notes);
+        hud.0 = match std::fs::write("trace.json", json) {
+            Ok(()) => "Exported trace.json".to_string(),
+            Err(e) => format!("Trace export failed: {}", e),
+        };
+        return Ok(());
+    }
+    let positions = node_positions(&nodes, graph.nodes.len());
+    let caption = caption.title.as_ref().map(|title| export::Caption {
+        title: title.clone(),
+        date: export::today(),
+    });
+
+    let written = std::fs::write(
+        "exercise.svg",
+        export::exercise_svg(&graph, &positions, start, goal, caption.as_ref()),
+    )
+    .and_then(|_| {
+        std::fs::write(
+            "exercise_answers.svg",
+            export::answer_key_svg(&graph, &positions, start, goal, &notes, caption.as_ref()),
+        )
+    });
+    hud.0 = match written {
+        Ok(()) => "Exported exercise.svg and exercise_answers.svg".to_string(),
+        Err(e) => format!("Exercise export failed: {}", e),
+    };
+
+    return Ok(());
+}
+
+fn clicked_node_id(nodes: &Query<&Node>, world: Vec2) -> Option<NodeId> {
     for node in nodes {
         if (world - node.position).length() < node.r {
             return Some(node.id);
@@ -289,92 +1276,598 @@ fn clicked_node_id(nodes: &Query<&Node>, world: Vec2) -> Option<usize> {
     None
 }
 
+// lane 0 runs between the node centres and the others alternate either side of it, on the same
+// side whichever end the pair was drawn from
+fn lane_offset(ev: &EdgeVisual, a: Vec2, b: Vec2) -> Vec2 {
+    let (low, high) = if ev.a < ev.b { (a, b) } else { (b, a) };
+    let side = (high - low).normalize_or_zero().perp();
+    let step = ev.lane.div_ceil(2) as f32 * LANE_GAP;
+    return if ev.lane % 2 == 1 {
+        side * step
+    } else {
+        -side * step
+    };
+}
+
+// text rotated with its edge, turned half around where it would read upside down
+fn along_edge(angle: f32) -> Quat {
+    if angle.abs() > std::f32::consts::FRAC_PI_2 {
+        return Quat::from_rotation_z(std::f32::consts::PI);
+    }
+    return Quat::IDENTITY;
+}
+
+// the drawn edge passing within a few pixels of `world`, as its (a, b) node pair
+fn edge_at(
+    nodes: &Query<&Node>,
+    edges: &Query<&EdgeVisual>,
+    world: Vec2,
+) -> Option<(NodeId, NodeId)> {
+    return lane_at(nodes, edges, world).map(|(a, b, _)| (a, b));
+}
+
+// the same with the lane it was in, for edits that touch one of parallel edges only
+fn lane_at(
+    nodes: &Query<&Node>,
+    edges: &Query<&EdgeVisual>,
+    world: Vec2,
+) -> Option<(NodeId, NodeId, usize)> {
+    let position = position_lookup(nodes);
+    for ev in edges {
+        let (Some(a), Some(b)) = (position(ev.a), position(ev.b)) else {
+            continue;
+        };
+        let offset = lane_offset(ev, a, b);
+        let (a, b) = (a + offset, b + offset);
+        let ab = b - a;
+        let t = ((world - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0);
+        if world.distance(a + ab * t) < EDGE_HIT_DISTANCE {
+            return Some((ev.a, ev.b, ev.lane));
+        }
+    }
+    return None;
+}
+
+#[derive(SystemParam)]
+struct GraphSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+    units: Res<'w, WeightUnits>,
+    mode: Res<'w, EdgeMode>,
+}
+
+impl GraphSpawner<'_, '_> {
+    fn spawn_node(&mut self, graph: &mut Graph, position: Vec2) -> Node {
+        graph.nodes.push(Vec::new());
+        let node = Node {
+            position,
+            r: NODE_RADIUS,
+            id: NodeId(graph.nodes.len() - 1),
+        };
+        self.commands.spawn(node.clone());
+        return node;
+    }
+
+    // adds a distance-weighted edge a -> b (both ways unless in directed mode) and its visual;
+    // false if the nodes are too close
+    fn connect(&mut self, graph: &mut Graph, a: &Node, b: &Node) -> bool {
+        let len = a.position.distance(b.position);
+        if len < MIN_EDGE_LENGTH {
+            println!("Nodes are too close to connect!");
+            return false;
+        }
+
+        let cost = (len as Cost).max(1); // a 0-cost edge would make distinct nodes coincide
+        let drawn = graph.has_edge(b.id, a.id); // b -> a already has a visual for this pair
+        if !self.mode.directed {
+            graph.nodes[b.id.index()].push(Edge {
+                node: a.id,
+                cost,
+                capacity: None,
+                label: None,
+            });
+        }
+        graph.nodes[a.id.index()].push(Edge {
+            node: b.id,
+            cost,
+            capacity: None,
+            label: None,
+        });
+        if !drawn {
+            self.spawn_edge_visual(a, b, 0, cost);
+        }
+
+        return true;
+    }
+
+    // puts a new node on the a - b edge where `at` projects onto it and draws the two halves; the
+    // caller despawns the old edge visual
+    fn split_edge(&mut self, graph: &mut Graph, a: &Node, b: &Node, at: Vec2) -> Option<Node> {
+        let ab = b.position - a.position;
+        let t = ((at - a.position).dot(ab) / ab.length_squared()).clamp(0.0, 1.0);
+        let id = graph.split_edge(a.id, b.id, t)?;
+        let node = Node {
+            position: a.position + ab * t,
+            r: NODE_RADIUS,
+            id,
+        };
+        self.commands.spawn(node.clone());
+        for (from, to) in [(a, &node), (&node, b)] {
+            let cost = graph.edge_between(from.id, to.id).unwrap().cost;
+            self.spawn_edge_visual(from, to, 0, cost);
+        }
+        return Some(node);
+    }
+
+    // another edge alongside a - b, e.g. a back road next to a highway, weighted by the distance
+    // like any new edge; the pair's lanes are drawn again
+    fn add_lane(
+        &mut self,
+        graph: &mut Graph,
+        q_edges: &Query<(Entity, &EdgeVisual)>,
+        a: &Node,
+        b: &Node,
+    ) -> Cost {
+        let cost = (a.position.distance(b.position) as Cost).max(1);
+        graph.add_lane(a.id, b.id, cost, !self.mode.directed);
+        self.respawn_lanes(graph, q_edges, a, b);
+        return cost;
+    }
+
+    // lanes renumber when one is added or removed, so every visual of the pair is replaced
+    fn respawn_lanes(
+        &mut self,
+        graph: &Graph,
+        q_edges: &Query<(Entity, &EdgeVisual)>,
+        a: &Node,
+        b: &Node,
+    ) {
+        for (entity, ev) in q_edges {
+            if ord(ev.a, ev.b) == ord(a.id, b.id) {
+                self.commands.entity(entity).despawn();
+            }
+        }
+        for (from, to, lane) in graph.lanes() {
+            if ord(from, to) != ord(a.id, b.id) {
+                continue;
+            }
+            let (from, to) = if from == a.id { (a, b) } else { (b, a) };
+            let cost = graph.lane(from.id, to.id, lane).unwrap().cost;
+            self.spawn_edge_visual(from, to, lane, cost);
+        }
+    }
+
+    fn spawn_edge_visual(&mut self, a: &Node, b: &Node, lane: usize, cost: Cost) {
+        let d = b.position - a.position;
+        let len = d.length();
+        let angle = d.y.atan2(d.x);
+        let visual = EdgeVisual {
+            a: a.id,
+            b: b.id,
+            lane,
+        };
+        let mid = (b.position + a.position) * 0.5 + lane_offset(&visual, a.position, b.position);
+
+        let mat = self.materials.add(ColorMaterial::from(Color::WHITE));
+        let label = self.units.format(cost);
+
+        self.commands
+            .spawn((
+                Mesh2d(self.meshes.add(Rectangle::new(len, EDGE_THICKNESS))),
+                MeshMaterial2d(mat.clone()),
+                EdgeMat(mat),
+                Transform {
+                    translation: Vec3::new(mid.x, mid.y, -10.0),
+                    rotation: Quat::from_rotation_z(angle),
+                    ..Default::default()
+                },
+                visual,
+            ))
+            .with_children(|parent| {
+                // offset off the edge, counter-rotated so the label stays upright
+                parent.spawn((
+                    Text2d::new(label),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                    Transform {
+                        translation: Vec3::new(0.0, 12.0, 1.0),
+                        rotation: Quat::from_rotation_z(-angle),
+                        ..Default::default()
+                    },
+                    EdgeLabel {
+                        a: a.id,
+                        b: b.id,
+                        lane,
+                    },
+                ));
+                parent.spawn((
+                    Text2d::new(""),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.6, 0.75, 0.9)),
+                    Transform {
+                        translation: Vec3::new(0.0, -12.0, 1.0),
+                        rotation: along_edge(angle),
+                        ..Default::default()
+                    },
+                    EdgeName {
+                        a: a.id,
+                        b: b.id,
+                        lane,
+                    },
+                ));
+            });
+    }
+}
+
 fn handle_click(
     buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
     windows: Query<&Window, With<PrimaryWindow>>,
     cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_edges: Query<(Entity, &EdgeVisual)>,
     mut wrapper_graph: Query<&mut WrapperGraph, With<MainCamera>>,
     mut selected_node: Query<&mut SelectedNode, With<MainCamera>>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut multi_selection: Query<&mut MultiSelection, With<MainCamera>>,
+    mut spawner: GraphSpawner,
+    mut routing: ResMut<Routing>,
+    mut tags: ResMut<Tags>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+    ui: Query<&Interaction>,
 ) -> Result<()> {
     if !buttons.just_pressed(MouseButton::Left) {
         return Ok(());
     }
+    // clicks on UI buttons such as the distance matrix are not meant for the canvas
+    if ui.iter().any(|i| *i != Interaction::None) {
+        return Ok(());
+    }
     let Some(world) = cursor_world(&windows, &cams) else {
         return Ok(());
     };
     let clicked = clicked_node_id(&nodes, world);
 
+    if keys.any_pressed([
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+    ]) {
+        return Ok(()); // alt+drag is the scatter tool, ctrl+click the ruler
+    }
+    let node_with = |id: NodeId| nodes.iter().find(|n| n.id == id);
+    // holding ` chains: each click adds a node, or picks one, joined to the last and selected
+    if keys.pressed(KeyCode::Backquote) {
+        let wg = wrapper_graph.single_mut()?;
+        let mut graph = wg.0.write().unwrap();
+        let mut selected = selected_node.single_mut()?;
+        let next = match clicked {
+            Some(id) => node_with(id).unwrap().clone(),
+            None => spawner.spawn_node(&mut graph, world),
+        };
+        if let Some(last) = selected.id.and_then(node_with)
+            && last.id != next.id
+            && !graph.has_edge(last.id, next.id)
+        {
+            spawner.connect(&mut graph, last, &next);
+        }
+        selected.id = Some(next.id);
+        hud.0 = format!("Chain at {} (keep ` held and click to go on)", next.id);
+        return Ok(());
+    }
+    if keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
+        // with a hub selected, shift+clicks on empty space add spokes to it
+        if clicked.is_none()
+            && let Some(hub) = selected_node.single()?.id.and_then(node_with)
+        {
+            let wg = wrapper_graph.single_mut()?;
+            let mut graph = wg.0.write().unwrap();
+            let spoke = spawner.spawn_node(&mut graph, world);
+            spawner.connect(&mut graph, hub, &spoke);
+            hud.0 = format!("Spoke {} off hub {}", spoke.id, hub.id);
+            return Ok(());
+        }
+        let mut multi = multi_selection.single_mut()?;
+        match clicked {
+            Some(id) => match multi.ids.iter().position(|&i| i == id) {
+                Some(pos) => {
+                    multi.ids.remove(pos);
+                }
+                None => multi.ids.push(id),
+            },
+            None => multi.ids.clear(),
+        }
+        return Ok(());
+    }
+
     let wg = wrapper_graph.single_mut()?;
     let mut graph = wg.0.write().unwrap();
 
     if let Some(clicked_node_id) = clicked {
         if let Some(prev_selected_node_id) = selected_node.single_mut()?.id.take() {
-            let clicked_node = nodes.iter().find(|n| n.id == clicked_node_id).unwrap();
+            let clicked_node = node_with(clicked_node_id).unwrap();
 
-            if graph.nodes[clicked_node_id]
-                .iter()
-                .any(|n| n.node == prev_selected_node_id)
-            {
+            if graph.has_edge(prev_selected_node_id, clicked_node_id) {
                 selected_node.single_mut()?.id = Some(clicked_node_id);
                 return Ok(());
             }
 
-            let prev_selected_node = nodes
-                .iter()
-                .find(|n| n.id == prev_selected_node_id)
-                .unwrap();
-            let d = clicked_node.position - prev_selected_node.position;
-            let len = d.length();
-            let angle = d.y.atan2(d.x);
-            let mid = (clicked_node.position + prev_selected_node.position) * 0.5;
-            let thickness = 2.0;
-
-            let cost = len as usize;
-            graph.nodes[clicked_node_id].push(Edge {
-                node: prev_selected_node_id,
-                cost,
-            });
-            graph.nodes[prev_selected_node_id].push(Edge {
-                node: clicked_node_id,
-                cost,
-            });
-
-            let mat = materials.add(ColorMaterial::from(Color::WHITE));
-
-            commands.spawn((
-                Mesh2d(meshes.add(Rectangle::new(len, thickness))),
-                MeshMaterial2d(mat.clone()),
-                EdgeMat(mat),
-                Transform {
-                    translation: Vec3::new(mid.x, mid.y, -10.0),
-                    rotation: Quat::from_rotation_z(angle),
-                    ..Default::default()
-                },
-                EdgeVisual {
-                    a: prev_selected_node_id,
-                    b: clicked_node_id,
-                },
-            ));
+            let prev_selected_node = node_with(prev_selected_node_id).unwrap();
+            spawner.connect(&mut graph, prev_selected_node, clicked_node);
 
             selected_node.single_mut()?.id = None;
             return Ok(());
         }
         selected_node.single_mut()?.id = Some(clicked_node_id);
+    } else if let Some((a, b)) = edge_at(&nodes, &edges, world) {
+        // clicking an edge splits it at that point
+        selected_node.single_mut()?.id = None;
+        if graph.lane_count(a, b) > 1 {
+            hud.0 = format!(
+                "{} - {} has parallel edges; right-click all but one away to split it",
+                a, b
+            );
+            return Ok(());
+        }
+        let Some(mid) = spawner.split_edge(
+            &mut graph,
+            node_with(a).unwrap(),
+            node_with(b).unwrap(),
+            world,
+        ) else {
+            hud.0 = format!("{} - {} is too light to split", a, b);
+            return Ok(());
+        };
+        for (entity, ev) in &q_edges {
+            if ord(ev.a, ev.b) == ord(a, b) {
+                spawner.commands.entity(entity).despawn();
+            }
+        }
+        // the halves keep the old edge's category, tags and mark; other results are stale
+        highlights.clear_results();
+        if highlights.layer_mut(Layer::Marked).remove(&ord(a, b)) {
+            let marked = highlights.layer_mut(Layer::Marked);
+            marked.insert(ord(a, mid.id));
+            marked.insert(ord(mid.id, b));
+        }
+        if let Some(category) = routing.categories.remove(&ord(a, b)) {
+            routing.categories.insert(ord(a, mid.id), category);
+            routing.categories.insert(ord(mid.id, b), category);
+        }
+        if let Some(edge_tags) = tags.edges.remove(&ord(a, b)) {
+            tags.edges.insert(ord(a, mid.id), edge_tags.clone());
+            tags.edges.insert(ord(mid.id, b), edge_tags);
+        }
+        hud.0 = format!("Split {} - {} at new node {}", a, b, mid.id);
     } else {
         selected_node.single_mut()?.id = None;
-        graph.nodes.push(Vec::new());
-        let new_id = graph.nodes.len() - 1;
+        spawner.spawn_node(&mut graph, world);
+    }
 
-        commands.spawn(Node {
-            position: world,
-            r: 20.0,
-            id: new_id,
-        });
+    return Ok(());
+}
+
+// a right-click on an edge deletes it, both directions at once; a right-drag is still a stroke
+fn remove_clicked_edge(
+    mut spawner: GraphSpawner,
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_edges: Query<(Entity, &EdgeVisual)>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut pressed_at: Local<Option<Vec2>>,
+    mut routing: ResMut<Routing>,
+    mut tags: ResMut<Tags>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some(world) = cursor_world(&windows, &cams) else {
+        return Ok(());
+    };
+    if buttons.just_pressed(MouseButton::Right) {
+        *pressed_at = Some(world);
     }
+    if !buttons.just_released(MouseButton::Right) {
+        return Ok(());
+    }
+    let Some(pressed_at) = pressed_at
+        .take()
+        .filter(|p| p.distance(world) < EDGE_HIT_DISTANCE)
+    else {
+        return Ok(());
+    };
+    if clicked_node_id(&nodes, pressed_at).is_some() {
+        return Ok(());
+    }
+    let Some((a, b, lane)) = lane_at(&nodes, &edges, pressed_at) else {
+        return Ok(());
+    };
+
+    let wg = wrapper_graph.single()?;
+    let mut graph = wg.0.write().unwrap();
+    // results drawn over the old graph are stale
+    highlights.clear_results();
+    // one of parallel edges goes on its own, and the pair keeps its mark, category and tags
+    if graph.lane_count(a, b) > 1 {
+        graph.remove_lane(a, b, lane);
+        let node = |id: NodeId| nodes.iter().find(|n| n.id == id).unwrap();
+        spawner.respawn_lanes(&graph, &q_edges, node(a), node(b));
+        hud.0 = format!(
+            "Removed one of the edges between {} and {}; {} left",
+            a,
+            b,
+            graph.lane_count(a, b)
+        );
+        return Ok(());
+    }
+    graph.remove_edge(a, b);
+    for (entity, ev) in &q_edges {
+        if ord(ev.a, ev.b) == ord(a, b) {
+            spawner.commands.entity(entity).despawn();
+        }
+    }
+    // the edge's own mark, category and tags go with it
+    highlights.layer_mut(Layer::Marked).remove(&ord(a, b));
+    routing.categories.remove(&ord(a, b));
+    tags.edges.remove(&ord(a, b));
+    hud.0 = format!("Removed {} - {}", a, b);
 
     return Ok(());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use djikstra::harness::Harness;
+
+    const A: Vec2 = Vec2::new(-300.0, 0.0);
+    const B: Vec2 = Vec2::new(0.0, 200.0);
+    const C: Vec2 = Vec2::new(300.0, 0.0);
+
+    fn editor() -> Harness {
+        let mut harness = Harness::new();
+        harness.add_plugins(EditorPlugin);
+        harness.world_mut().insert_resource(rng::Rng::new(7));
+        harness.step();
+        return harness;
+    }
+
+    // A - B - C, each edge drawn by clicking its two ends
+    fn path_of_three() -> Harness {
+        let mut harness = editor();
+        for at in [A, B, C, A, B, B, C] {
+            harness.click(at);
+        }
+        return harness;
+    }
+
+    fn graph_of(harness: &mut Harness) -> Graph {
+        let world = harness.world_mut();
+        let mut q = world.query_filtered::<&WrapperGraph, With<MainCamera>>();
+        return q.single(world).unwrap().0.read().unwrap().clone();
+    }
+
+    fn count<C: Component>(harness: &mut Harness) -> usize {
+        let world = harness.world_mut();
+        return world.query::<&C>().iter(world).count();
+    }
+
+    #[test]
+    fn test_clicks_build_the_graph() {
+        let mut harness = path_of_three();
+        assert_eq!(count::<Node>(&mut harness), 3);
+        assert_eq!(count::<EdgeVisual>(&mut harness), 2);
+        let graph = graph_of(&mut harness);
+        assert!(graph.has_edge(NodeId(0), NodeId(1)) && graph.has_edge(NodeId(1), NodeId(0)));
+        assert!(graph.has_edge(NodeId(1), NodeId(2)) && !graph.has_edge(NodeId(0), NodeId(2)));
+
+        // a click on an edge splits it with a new node, and both halves keep its tags
+        harness
+            .world_mut()
+            .resource_mut::<Tags>()
+            .edges
+            .insert((NodeId(0), NodeId(1)), ["bridge".to_string()].into());
+        harness.click((A + B) / 2.0);
+        assert_eq!(count::<Node>(&mut harness), 4);
+        assert_eq!(count::<EdgeVisual>(&mut harness), 3);
+        assert_eq!(
+            harness.resource::<ResultsHud>().0,
+            "Split A - B at new node D"
+        );
+        let graph = graph_of(&mut harness);
+        assert!(
+            graph.has_edge(NodeId(0), NodeId(3))
+                && graph.has_edge(NodeId(3), NodeId(1))
+                && !graph.has_edge(NodeId(0), NodeId(1))
+        );
+        let tagged: std::collections::BTreeSet<(NodeId, NodeId)> =
+            harness.resource::<Tags>().edges.keys().copied().collect();
+        assert_eq!(
+            tagged,
+            [(NodeId(0), NodeId(3)), (NodeId(1), NodeId(3))].into()
+        );
+    }
+
+    #[test]
+    fn test_keys_show_the_path() {
+        let mut harness = path_of_three();
+        // clicking a selected node again lets go of it
+        harness.click(A);
+        harness.tap(KeyCode::KeyS);
+        harness.click(A);
+        harness.click(C);
+        harness.tap(KeyCode::KeyG);
+        harness.tap(KeyCode::KeyP);
+        let highlights = harness.resource::<HighlightedEdges>();
+        let path: HashSet<(NodeId, NodeId)> =
+            [(NodeId(0), NodeId(1)), (NodeId(1), NodeId(2))].into();
+        assert_eq!(highlights.layer(Layer::Path), &path);
+    }
+
+    #[test]
+    fn test_chain_and_star_gestures() {
+        let mut harness = editor();
+        harness.press(KeyCode::Backquote);
+        for at in [A, B, C, A] {
+            harness.click(at);
+        }
+        harness.release(KeyCode::Backquote);
+        let graph = graph_of(&mut harness);
+        assert_eq!(graph.nodes.len(), 3);
+        assert!(
+            graph.has_edge(NodeId(0), NodeId(1))
+                && graph.has_edge(NodeId(1), NodeId(2))
+                && graph.has_edge(NodeId(2), NodeId(0))
+        );
+
+        // A was picked last, so it stays selected as the hub
+        harness.press(KeyCode::ShiftLeft);
+        harness.click(A + Vec2::new(0.0, -200.0));
+        harness.click(A + Vec2::new(-200.0, 0.0));
+        harness.release(KeyCode::ShiftLeft);
+        assert_eq!(harness.resource::<ResultsHud>().0, "Spoke E off hub A");
+        let graph = graph_of(&mut harness);
+        assert!(
+            graph.has_edge(NodeId(0), NodeId(3))
+                && graph.has_edge(NodeId(0), NodeId(4))
+                && !graph.has_edge(NodeId(3), NodeId(4))
+        );
+        assert_eq!(count::<EdgeVisual>(&mut harness), 5);
+    }
+
+    #[test]
+    fn test_prompt_names_a_node() {
+        let mut harness = path_of_three();
+        harness.click(B);
+        harness.chord(&[KeyCode::ControlLeft], KeyCode::KeyI);
+        assert!(harness.resource::<Prompt>().is_open());
+        harness.type_text("Home pop=5000");
+        harness.tap(KeyCode::Enter);
+        let graph = graph_of(&mut harness);
+        assert_eq!(
+            graph.describe_node(NodeId(1)).as_deref(),
+            Some("Home; pop = 5000")
+        );
+        // the canvas shows the label in place of the letter
+        harness.step();
+        let world = harness.world_mut();
+        let texts: Vec<String> = world
+            .query::<(&Node, &Text2d)>()
+            .iter(world)
+            .filter(|(node, _)| node.id == NodeId(1))
+            .map(|(_, text)| text.0.clone())
+            .collect();
+        assert_eq!(texts, vec!["Home".to_string()]);
+    }
+}
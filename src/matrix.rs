@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::{
+    GoalNode, HighlightedEdges, MainCamera, PathMode, ResultsHud, StartNode, WeightUnits,
+    WrapperGraph, show_shortest_path,
+};
+
+// larger graphs only show their first nodes, the matrix would not fit on screen otherwise
+const MAX_NODES: usize = 12;
+const CELL_WIDTH: f32 = 48.0;
+const CELL: Color = Color::srgba(0.15, 0.15, 0.15, 0.9);
+const CELL_HOVERED: Color = Color::srgb(0.35, 0.35, 0.35);
+const HEADER: Color = Color::srgb(0.7, 0.7, 0.7);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum MatrixMode {
+    #[default]
+    Hidden,
+    FloydWarshall,
+    Johnson,
+}
+
+// the all-pairs distances on screen, kept to tell when the panel has to be rebuilt
+#[derive(Resource, Default)]
+struct MatrixView {
+    mode: MatrixMode,
+    dist: Vec<Vec<Option<Cost>>>,
+    title: String,
+}
+
+#[derive(Component)]
+struct MatrixPanel;
+
+#[derive(Component)]
+struct MatrixCell {
+    from: usize,
+    to: usize,
+}
+
+pub struct MatrixPlugin;
+
+impl Plugin for MatrixPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatrixView>()
+            .add_systems(Startup, spawn_matrix_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_matrix_keys.run_if(crate::prompt::prompt_closed),
+                    refresh_matrix,
+                    handle_cell_clicks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// above the prompt in the bottom-left corner
+fn spawn_matrix_panel(mut commands: Commands) {
+    commands.spawn((
+        UiNode {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(50.0),
+            left: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        Visibility::Hidden,
+        MatrixPanel,
+    ));
+}
+
+// shift+A shows the all-pairs matrix by floyd-warshall, then by johnson, then hides it
+fn handle_matrix_keys(keys: Res<ButtonInput<KeyCode>>, mut view: ResMut<MatrixView>) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if shift && keys.just_pressed(KeyCode::KeyA) {
+        view.mode = match view.mode {
+            MatrixMode::Hidden => MatrixMode::FloydWarshall,
+            MatrixMode::FloydWarshall => MatrixMode::Johnson,
+            MatrixMode::Johnson => MatrixMode::Hidden,
+        };
+        view.dist.clear();
+        view.title.clear();
+    }
+}
+
+// recomputed every frame while shown; the panel is only rebuilt when a distance changes
+fn refresh_matrix(
+    mut commands: Commands,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut view: ResMut<MatrixView>,
+    mut q_panel: Query<(Entity, &mut Visibility), With<MatrixPanel>>,
+) -> Result<()> {
+    let (panel, mut visibility) = q_panel.single_mut()?;
+    if view.mode == MatrixMode::Hidden {
+        visibility.set_if_neq(Visibility::Hidden);
+        return Ok(());
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+
+    let graph = wrapper_graph.single()?.0.read().unwrap();
+    let (dist, mut title) = match view.mode {
+        MatrixMode::Johnson => match graph.johnson() {
+            Ok(dist) => (
+                dist,
+                "All-pairs distances by Johnson (Shift+A hides, click a cell for its path)"
+                    .to_string(),
+            ),
+            // johnson has no matrix to give, only the cycle that breaks it
+            Err(cycle) => (
+                Vec::new(),
+                format!(
+                    "Johnson (Shift+A hides): negative cycle {} -> {}",
+                    Graph::fmt_path(&cycle),
+                    cycle[0]
+                ),
+            ),
+        },
+        _ => {
+            let dist = graph.all_pairs_shortest_paths();
+            let mut title =
+                "All-pairs distances by Floyd-Warshall (Shift+A for Johnson, click a cell for its path)"
+                    .to_string();
+            if dist
+                .iter()
+                .enumerate()
+                .any(|(i, row)| row[i].is_some_and(|d| d < 0))
+            {
+                title.push_str("\nNegative cycle: distances through it are undefined");
+            }
+            (dist, title)
+        }
+    };
+    if !graph.forbidden.is_empty() {
+        title.push_str("\nTurn restrictions are not considered");
+    }
+    if dist.len() > MAX_NODES {
+        title.push_str(&format!("\nFirst {} of {} nodes", MAX_NODES, dist.len()));
+    }
+    if view.dist == dist && view.title == title && !units.is_changed() {
+        return Ok(());
+    }
+
+    let shown = dist.len().min(MAX_NODES);
+    let text = |content: String, color: Color| {
+        (
+            Text::new(content),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(color),
+        )
+    };
+    let cell = UiNode {
+        width: Val::Px(CELL_WIDTH),
+        justify_content: JustifyContent::Center,
+        ..default()
+    };
+    commands.entity(panel).despawn_related::<Children>();
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn(text(title.clone(), HEADER));
+        parent
+            .spawn(UiNode {
+                column_gap: Val::Px(2.0),
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(cell.clone());
+                for to in 0..shown {
+                    row.spawn(cell.clone())
+                        .with_child(text(Graph::node_label(to), HEADER));
+                }
+            });
+        for (from, distances) in dist.iter().enumerate().take(shown) {
+            parent
+                .spawn(UiNode {
+                    column_gap: Val::Px(2.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(cell.clone())
+                        .with_child(text(Graph::node_label(from), HEADER));
+                    for (to, d) in distances.iter().enumerate().take(shown) {
+                        let label = d.map_or("-".to_string(), |d| units.format(d));
+                        row.spawn((
+                            Button,
+                            cell.clone(),
+                            BackgroundColor(CELL),
+                            MatrixCell { from, to },
+                        ))
+                        .with_child(text(label, Color::WHITE));
+                    }
+                });
+        }
+    });
+    view.dist = dist;
+    view.title = title;
+
+    return Ok(());
+}
+
+// hovering a cell lights it up; clicking it makes its row the start, its column the goal, and
+// shows the path between them
+fn handle_cell_clicks(
+    mut q_cells: Query<(&Interaction, &MatrixCell, &mut BackgroundColor), Changed<Interaction>>,
+    mut q_camera: Query<(&WrapperGraph, &mut StartNode, &mut GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for (interaction, cell, mut background) in &mut q_cells {
+        background.0 = match interaction {
+            Interaction::None => CELL,
+            _ => CELL_HOVERED,
+        };
+        if *interaction != Interaction::Pressed || cell.from == cell.to {
+            continue;
+        }
+        let (wg, mut start, mut goal) = q_camera.single_mut()?;
+        let graph = wg.0.read().unwrap();
+        let (from, to) = (NodeId(cell.from), NodeId(cell.to));
+        start.id = Some(from);
+        goal.id = Some(to);
+        // the matrix holds weighted distances, so its paths are weighted too
+        show_shortest_path(
+            &graph,
+            from,
+            to,
+            &PathMode::default(),
+            &units,
+            &mut highlights,
+            &mut hud,
+        );
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,270 @@
+use bevy::prelude::*;
+use bevy::ui::{Node as UiNode, RelativeCursorPosition};
+
+use crate::graph::NodeId;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{Node, ResultsHud};
+
+const MORPH_SECS: f32 = 1.5;
+const SLIDER_WIDTH: f32 = 240.0;
+
+// two stored layouts of the same nodes and how far between them the canvas is, 0 at A and 1 at
+// B; `playing` is where a running morph is headed
+#[derive(Resource, Default)]
+struct Morph {
+    a: Option<Vec<Vec2>>,
+    b: Option<Vec<Vec2>>,
+    t: f32,
+    playing: Option<f32>,
+}
+
+impl Morph {
+    fn describe(&self) -> String {
+        let stored = |layout: &Option<Vec<Vec2>>| match layout {
+            Some(positions) => format!("{} nodes", positions.len()),
+            None => "not stored".to_string(),
+        };
+        let at = if self.a.is_some() && self.b.is_some() {
+            format!(", {:.0}% of the way to B", self.t * 100.0)
+        } else {
+            String::new()
+        };
+        return format!(
+            "Layout morph (Ctrl+K): A {}, B {}{}",
+            stored(&self.a),
+            stored(&self.b),
+            at
+        );
+    }
+
+    // every node between its two positions; None until both layouts are stored
+    fn positions(&self) -> Option<Vec<Vec2>> {
+        let (Some(a), Some(b)) = (&self.a, &self.b) else {
+            return None;
+        };
+        // eased, so a played morph starts and lands gently
+        let t = self.t * self.t * (3.0 - 2.0 * self.t);
+        return Some(a.iter().zip(b).map(|(a, b)| a.lerp(*b, t)).collect());
+    }
+}
+
+#[derive(Component)]
+struct MorphPanel;
+
+#[derive(Component)]
+struct MorphText;
+
+#[derive(Component)]
+struct MorphSlider;
+
+#[derive(Component)]
+struct MorphFill;
+
+pub struct MorphPlugin;
+
+impl Plugin for MorphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Morph>()
+            .add_systems(Startup, spawn_morph_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_morph_keys.run_if(crate::prompt::prompt_closed),
+                    handle_morph_prompt,
+                    drop_stale_layouts,
+                    drag_morph_slider,
+                    play_morph,
+                    apply_morph,
+                    update_morph_panel,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// centred above the tag filter bar, hidden until a layout is stored
+fn spawn_morph_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            UiNode {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(80.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            Visibility::Hidden,
+            MorphPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                MorphText,
+            ));
+            panel
+                .spawn((
+                    Button,
+                    UiNode {
+                        width: Val::Px(SLIDER_WIDTH),
+                        height: Val::Px(10.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.25, 0.25, 0.25)),
+                    RelativeCursorPosition::default(),
+                    MorphSlider,
+                ))
+                .with_child((
+                    UiNode {
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.4, 0.8, 0.9)),
+                    MorphFill,
+                ));
+        });
+}
+
+fn handle_morph_keys(keys: Res<ButtonInput<KeyCode>>, mut prompt: ResMut<Prompt>) {
+    // shift+K finds a cycle
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if ctrl && !shift && keys.just_pressed(KeyCode::KeyK) {
+        prompt.open(
+            PromptKind::Morph,
+            "Layout morph: `a` or `b` stores the layout on screen, `play` morphs to the other end, `clear` forgets both",
+        );
+    }
+}
+
+fn handle_morph_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    nodes: Query<&Node>,
+    mut morph: ResMut<Morph>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Morph {
+            continue;
+        }
+        let mut layout: Vec<(NodeId, Vec2)> = nodes.iter().map(|n| (n.id, n.position)).collect();
+        layout.sort_by_key(|&(id, _)| id);
+        let layout: Vec<Vec2> = layout.into_iter().map(|(_, p)| p).collect();
+        match event.text.trim().to_lowercase().as_str() {
+            "a" => {
+                morph.a = Some(layout);
+                morph.t = 0.0;
+                hud.0 = "Stored the layout on screen as A".to_string();
+            }
+            "b" => {
+                morph.b = Some(layout);
+                morph.t = 1.0;
+                hud.0 = "Stored the layout on screen as B".to_string();
+            }
+            "play" => {
+                if morph.a.is_none() || morph.b.is_none() {
+                    hud.0 = "Store both layouts with `a` and `b` first".to_string();
+                    continue;
+                }
+                morph.playing = Some(if morph.t < 0.5 { 1.0 } else { 0.0 });
+            }
+            "clear" => {
+                *morph = Morph::default();
+                hud.0 = "Forgot both layouts".to_string();
+            }
+            text => {
+                hud.0 = format!("Could not read `{}`: type a, b, play or clear", text);
+            }
+        }
+    }
+}
+
+// layouts of another set of nodes cannot be morphed between
+fn drop_stale_layouts(nodes: Query<&Node>, mut morph: ResMut<Morph>, mut hud: ResMut<ResultsHud>) {
+    let count = nodes.iter().count();
+    let stale = |layout: &Option<Vec<Vec2>>| layout.as_ref().is_some_and(|l| l.len() != count);
+    if stale(&morph.a) || stale(&morph.b) {
+        *morph = Morph::default();
+        hud.0 = "The nodes changed, so the stored layouts were dropped".to_string();
+    }
+}
+
+fn drag_morph_slider(
+    q_slider: Query<(&Interaction, &RelativeCursorPosition), With<MorphSlider>>,
+    mut morph: ResMut<Morph>,
+) -> Result<()> {
+    let (interaction, cursor) = q_slider.single()?;
+    if *interaction != Interaction::Pressed || morph.a.is_none() || morph.b.is_none() {
+        return Ok(());
+    }
+    let Some(at) = cursor.normalized else {
+        return Ok(());
+    };
+    let t = (at.x + 0.5).clamp(0.0, 1.0);
+    if t != morph.t {
+        morph.t = t;
+        morph.playing = None;
+    }
+
+    return Ok(());
+}
+
+fn play_morph(time: Res<Time>, mut morph: ResMut<Morph>) {
+    let Some(target) = morph.playing else {
+        return;
+    };
+    let step = time.delta_secs() / MORPH_SECS;
+    morph.t = if target > morph.t {
+        (morph.t + step).min(target)
+    } else {
+        (morph.t - step).max(target)
+    };
+    if morph.t == target {
+        morph.playing = None;
+    }
+}
+
+// nodes only move when t does, so dragging one by hand between morphs sticks
+fn apply_morph(morph: Res<Morph>, mut nodes: Query<&mut Node>) {
+    if !morph.is_changed() {
+        return;
+    }
+    let Some(positions) = morph.positions() else {
+        return;
+    };
+    for mut node in &mut nodes {
+        if let Some(&at) = positions.get(node.id.index())
+            && node.position != at
+        {
+            node.position = at;
+        }
+    }
+}
+
+fn update_morph_panel(
+    morph: Res<Morph>,
+    mut q_panel: Query<&mut Visibility, With<MorphPanel>>,
+    mut q_text: Query<&mut Text, With<MorphText>>,
+    mut q_fill: Query<&mut UiNode, With<MorphFill>>,
+) -> Result<()> {
+    if !morph.is_changed() {
+        return Ok(());
+    }
+    q_panel
+        .single_mut()?
+        .set_if_neq(if morph.a.is_some() || morph.b.is_some() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        });
+    q_text.single_mut()?.0 = morph.describe();
+    q_fill.single_mut()?.width = Val::Percent(morph.t * 100.0);
+
+    return Ok(());
+}
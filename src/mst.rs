@@ -0,0 +1,179 @@
+use bevy::color::palettes::css::{GOLD, ORANGE_RED};
+use bevy::prelude::*;
+
+use crate::graph::{Graph, KruskalStep};
+use crate::{
+    HighlightedEdges, Layer, MainCamera, Node, ResultsHud, WeightUnits, WrapperGraph, ord,
+    position_lookup,
+};
+
+const STEP_SECS: f32 = 0.8;
+
+// kruskal's steps played back one edge at a time; accepted edges land on the spanning tree
+// layer, rejected ones stay drawn in red
+#[derive(Resource, Default)]
+struct KruskalAnimation {
+    steps: Vec<KruskalStep>,
+    shown: usize,
+    timer: f32,
+}
+
+pub struct MstPlugin;
+
+impl Plugin for MstPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KruskalAnimation>().add_systems(
+            Update,
+            (
+                handle_mst_keys.run_if(crate::prompt::prompt_closed),
+                step_kruskal,
+                draw_kruskal,
+            )
+                .chain(),
+        );
+    }
+}
+
+// shift+M cycles Prim's tree -> Kruskal played step by step -> off
+fn handle_mst_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut kruskal: ResMut<KruskalAnimation>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyM) {
+        return Ok(());
+    }
+    if !kruskal.steps.is_empty() {
+        *kruskal = KruskalAnimation::default();
+        highlights.layer_mut(Layer::Mst).clear();
+        hud.0 = "Spanning tree off".to_string();
+        return Ok(());
+    }
+
+    let graph = wrapper_graph.single()?.0.read().unwrap();
+    if !highlights.layer(Layer::Mst).is_empty() {
+        highlights.layer_mut(Layer::Mst).clear();
+        kruskal.steps = graph.mst_kruskal();
+        hud.0 = "Kruskal: edges cheapest first, skipping any that would close a cycle".to_string();
+        return Ok(());
+    }
+
+    let (total, tree) = graph.mst_prim();
+    if tree.is_empty() {
+        hud.0 = "No edges to span".to_string();
+        return Ok(());
+    }
+    let mst = highlights.layer_mut(Layer::Mst);
+    mst.extend(tree.iter().map(|&(a, b)| ord(a, b)));
+    hud.0 = format!(
+        "{} (Prim): {} edges, total weight {}",
+        spanning(&graph, tree.len()),
+        tree.len(),
+        units.format(total)
+    );
+
+    return Ok(());
+}
+
+// one tree per component, n - components edges in all
+fn spanning(graph: &Graph, edges: usize) -> String {
+    let components = graph.nodes.len() - edges;
+    if components > 1 {
+        return format!("Minimum spanning forest of {} trees", components);
+    }
+    return "Minimum spanning tree".to_string();
+}
+
+fn step_kruskal(
+    time: Res<Time>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut kruskal: ResMut<KruskalAnimation>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // an edit cleared the tree under the playback, so its steps are stale
+    let accepted = kruskal.steps[..kruskal.shown]
+        .iter()
+        .filter(|s| s.accepted)
+        .count();
+    if highlights.layer(Layer::Mst).len() < accepted {
+        *kruskal = KruskalAnimation::default();
+        return Ok(());
+    }
+    if kruskal.shown == kruskal.steps.len() {
+        return Ok(());
+    }
+    kruskal.timer -= time.delta_secs();
+    if kruskal.timer > 0.0 {
+        return Ok(());
+    }
+    kruskal.timer = STEP_SECS;
+
+    let step = kruskal.steps[kruskal.shown];
+    let tie = kruskal.shown > 0 && kruskal.steps[kruskal.shown - 1].cost == step.cost;
+    kruskal.shown += 1;
+    let edge = format!(
+        "{} - {} ({}{})",
+        step.a,
+        step.b,
+        units.format(step.cost),
+        if tie { ", tied with the last edge" } else { "" }
+    );
+    hud.0 = if step.accepted {
+        highlights.layer_mut(Layer::Mst).insert(ord(step.a, step.b));
+        format!(
+            "Kruskal step {} of {}: {} joins two trees",
+            kruskal.shown,
+            kruskal.steps.len(),
+            edge
+        )
+    } else {
+        format!(
+            "Kruskal step {} of {}: {} rejected, both ends are already connected",
+            kruskal.shown,
+            kruskal.steps.len(),
+            edge
+        )
+    };
+
+    if kruskal.shown == kruskal.steps.len() {
+        let graph = wrapper_graph.single()?.0.read().unwrap();
+        let tree: Vec<&KruskalStep> = kruskal.steps.iter().filter(|s| s.accepted).collect();
+        hud.0 = format!(
+            "{} (Kruskal): {} edges, total weight {}, {} rejected",
+            spanning(&graph, tree.len()),
+            tree.len(),
+            units.format(tree.iter().map(|s| s.cost).sum()),
+            kruskal.steps.len() - tree.len()
+        );
+    }
+
+    return Ok(());
+}
+
+// the edge under consideration is drawn thick, rejected edges stay red
+fn draw_kruskal(kruskal: Res<KruskalAnimation>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    if kruskal.steps.is_empty() {
+        return;
+    }
+    let position = position_lookup(&nodes);
+    for (i, step) in kruskal.steps[..kruskal.shown].iter().enumerate() {
+        let (Some(a), Some(b)) = (position(step.a), position(step.b)) else {
+            continue;
+        };
+        let color = if step.accepted { GOLD } else { ORANGE_RED };
+        if i + 1 == kruskal.shown {
+            let normal = (b - a).perp().normalize_or_zero() * 2.0;
+            for offset in [-normal, Vec2::ZERO, normal] {
+                gizmos.line_2d(a + offset, b + offset, color);
+            }
+        } else if !step.accepted {
+            gizmos.line_2d(a, b, color.with_alpha(0.6));
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{Cost, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    EdgeVisual, MainCamera, Node, ResultsHud, SelectedNode, WeightUnits, WrapperGraph,
+    clicked_node_id, cursor_world, lane_at,
+};
+
+// what caps the flow through each node; a max flow may pass no more than its capacity. names
+// and details live in the graph itself, beside the edges' names
+#[derive(Resource, Default)]
+pub struct NodeInfo {
+    pub capacities: HashMap<NodeId, Cost>,
+}
+
+impl NodeInfo {
+    // follows renumbered nodes; a merged node keeps the tighter capacity
+    pub fn remap(&mut self, remap: &[Option<NodeId>]) {
+        for (old, cap) in std::mem::take(&mut self.capacities) {
+            if let Some(new) = remap[old.index()] {
+                let kept = self.capacities.entry(new).or_insert(cap);
+                *kept = (*kept).min(cap);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = NodeInfo::default();
+    }
+}
+
+// node, or lane (a, b, lane) of an edge, the open info prompt will write to
+#[derive(Resource, Default)]
+struct InfoDraft {
+    node: Option<NodeId>,
+    lane: Option<(NodeId, NodeId, usize)>,
+}
+
+pub struct NodeInfoPlugin;
+
+impl Plugin for NodeInfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NodeInfo>()
+            .init_resource::<InfoDraft>()
+            .add_systems(
+                Update,
+                (
+                    handle_info_keys.run_if(crate::prompt::prompt_closed),
+                    handle_info_prompt,
+                    sync_node_texts,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// ctrl+I over an edge asks for its name, or else with a node selected for the node's label and
+// details
+fn handle_info_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_camera: Query<(&WrapperGraph, &SelectedNode), With<MainCamera>>,
+    mut draft: ResMut<InfoDraft>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyI) {
+        return Ok(());
+    }
+    let (wg, selected) = q_camera.single()?;
+    // edges run into their nodes, so a node under the cursor wins over them
+    let hovered = cursor_world(&windows, &cams)
+        .filter(|&w| clicked_node_id(&nodes, w).is_none())
+        .and_then(|w| lane_at(&nodes, &edges, w));
+    if let Some((a, b, lane)) = hovered {
+        *draft = InfoDraft {
+            lane: Some((a, b, lane)),
+            ..default()
+        };
+        let now = match wg.0.read().unwrap().lane_label(a, b, lane) {
+            Some(now) => format!(" (now `{}`, empty clears it)", now),
+            None => String::new(),
+        };
+        prompt.open(
+            PromptKind::EdgeName,
+            format!("Name for the edge {} - {}, e.g. `I-95`{}", a, b, now),
+        );
+        return Ok(());
+    }
+    let Some(id) = selected.id else {
+        hud.0 = "Hover an edge or select a node, then press Ctrl+I to name it".to_string();
+        return Ok(());
+    };
+    *draft = InfoDraft {
+        node: Some(id),
+        ..default()
+    };
+    let now = match wg.0.read().unwrap().describe_node(id) {
+        Some(now) => format!(" (now {}; empty clears)", now),
+        None => String::new(),
+    };
+    prompt.open(
+        PromptKind::NodeInfo,
+        format!(
+            "Label for {}, then any `key=value` details; `key=` drops one, `cap=5` limits the flow through it{}",
+            id,
+            now
+        ),
+    );
+
+    return Ok(());
+}
+
+// words with an `=` are details and the rest the label; typing only details keeps the label.
+// `cap` is the node's flow capacity rather than a detail, typed in display units like weights
+fn handle_info_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut draft: ResMut<InfoDraft>,
+    mut info: ResMut<NodeInfo>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind == PromptKind::EdgeName {
+            let Some((a, b, lane)) = draft.lane.take() else {
+                continue;
+            };
+            let name = event.text.trim();
+            let edge = format!("{} - {}", a, b);
+            let mut graph = q_graph.single()?.0.write().unwrap();
+            graph.set_lane_label(a, b, lane, (!name.is_empty()).then(|| name.to_string()));
+            hud.0 = if name.is_empty() {
+                format!("Cleared the name of {}", edge)
+            } else {
+                format!("Named {} `{}`", edge, name)
+            };
+            continue;
+        }
+        if event.kind != PromptKind::NodeInfo {
+            continue;
+        }
+        let Some(id) = draft.node.take() else {
+            continue;
+        };
+        let mut graph = q_graph.single()?.0.write().unwrap();
+        if event.text.trim().is_empty() {
+            graph.node_labels.remove(&id);
+            graph.node_metadata.remove(&id);
+            info.capacities.remove(&id);
+            hud.0 = format!("Cleared the label and details of {}", id);
+            continue;
+        }
+        let (details, words): (Vec<&str>, Vec<&str>) =
+            event.text.split_whitespace().partition(|w| w.contains('='));
+        let (caps, details): (Vec<&str>, Vec<&str>) =
+            details.into_iter().partition(|w| w.starts_with("cap="));
+        let cap = match caps.last().map(|w| &w["cap=".len()..]) {
+            None => None,
+            Some("") => Some(None),
+            Some(text) => match text.parse::<f32>() {
+                Ok(value) if value >= 0.0 => Some(Some((value / units.scale).round() as Cost)),
+                _ => {
+                    hud.0 = "Capacities cannot be negative, e.g. `cap=5`".to_string();
+                    continue;
+                }
+            },
+        };
+        match cap {
+            Some(Some(cap)) => {
+                info.capacities.insert(id, cap);
+            }
+            Some(None) => {
+                info.capacities.remove(&id);
+            }
+            None => {}
+        }
+        if !words.is_empty() {
+            graph.node_labels.insert(id, words.join(" "));
+        }
+        let metadata = graph.node_metadata.entry(id).or_default();
+        for detail in details {
+            let (key, value) = detail.split_once('=').unwrap();
+            if value.is_empty() {
+                metadata.remove(key);
+            } else if !key.is_empty() {
+                metadata.insert(key.to_string(), value.to_string());
+            }
+        }
+        if metadata.is_empty() {
+            graph.node_metadata.remove(&id);
+        }
+        hud.0 = format!(
+            "{}: {}",
+            id,
+            graph
+                .describe_node(id)
+                .unwrap_or_else(|| "no label or details".to_string())
+        );
+        if let Some(&cap) = info.capacities.get(&id) {
+            hud.0 += &format!(", carries up to {}", units.format(cap));
+        }
+    }
+
+    return Ok(());
+}
+
+// the text on every node: its label, or its letter. labels change inside the graph, which says
+// nothing when they do, so every node is checked each frame
+fn sync_node_texts(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut q_nodes: Query<(&Node, &mut Text2d)>,
+) -> Result<()> {
+    let graph = q_graph.single()?.0.read().unwrap();
+    for (node, mut text) in &mut q_nodes {
+        let shown = graph.node_name(node.id);
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+
+    return Ok(());
+}
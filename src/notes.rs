@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use bevy::color::palettes::css::GOLD;
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+use bevy::window::PrimaryWindow;
+
+use crate::animation::Animation;
+use crate::graph::{Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    MainCamera, Node, ResultsHud, SelectedNode, WeightUnits, WrapperGraph, clicked_node_id,
+    cursor_world,
+};
+
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(16.0, 16.0);
+
+// free-text notes keyed by node id, shown on hover and carried into path exports
+#[derive(Resource, Default)]
+pub struct NodeNotes(pub HashMap<usize, String>);
+
+impl NodeNotes {
+    // the notes of the nodes a path passes through, in path order
+    pub fn along<'a>(&'a self, path: &[NodeId]) -> Vec<(NodeId, &'a str)> {
+        return path
+            .iter()
+            .filter_map(|&id| Some((id, self.0.get(&id.index())?.as_str())))
+            .collect();
+    }
+}
+
+// node the open note prompt will write to
+#[derive(Resource, Default)]
+struct NoteDraft(Option<usize>);
+
+#[derive(Component)]
+struct NoteTooltip;
+
+pub struct NotesPlugin;
+
+impl Plugin for NotesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NodeNotes>()
+            .init_resource::<NoteDraft>()
+            .add_systems(Startup, spawn_tooltip)
+            .add_systems(
+                Update,
+                (
+                    handle_note_keys.run_if(crate::prompt::prompt_closed),
+                    handle_note_prompt,
+                    update_tooltip,
+                    draw_note_markers,
+                ),
+            );
+    }
+}
+
+fn spawn_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+        UiNode {
+            position_type: PositionType::Absolute,
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        Visibility::Hidden,
+        NoteTooltip,
+    ));
+}
+
+// O with a node selected asks for its note; submitting an empty note removes it
+fn handle_note_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_selected: Query<&SelectedNode, With<MainCamera>>,
+    notes: Res<NodeNotes>,
+    mut draft: ResMut<NoteDraft>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // ctrl+O loads and shift+O traverses
+    if !keys.just_pressed(KeyCode::KeyO)
+        || keys.any_pressed([
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+        ])
+    {
+        return Ok(());
+    }
+    let Some(id) = q_selected.single()?.id else {
+        hud.0 = "Select a node, then press O to write a note on it".to_string();
+        return Ok(());
+    };
+    draft.0 = Some(id.index());
+    let label = match notes.0.get(&id.index()) {
+        Some(note) => format!("Note for {} (now \"{}\", empty removes it)", id, note),
+        None => format!("Note for {}", id),
+    };
+    prompt.open(PromptKind::NodeNote, label);
+
+    return Ok(());
+}
+
+fn handle_note_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut draft: ResMut<NoteDraft>,
+    mut notes: ResMut<NodeNotes>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for event in submitted.read() {
+        if event.kind != PromptKind::NodeNote {
+            continue;
+        }
+        let Some(id) = draft.0.take() else {
+            continue;
+        };
+        if event.text.is_empty() {
+            if notes.0.remove(&id).is_some() {
+                hud.0 = format!("Removed the note on {}", Graph::node_label(id));
+            }
+            continue;
+        }
+        notes.0.insert(id, event.text.clone());
+        hud.0 = format!("Noted {}: {}", Graph::node_label(id), event.text);
+    }
+}
+
+fn update_tooltip(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    notes: Res<NodeNotes>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    animation: Res<Animation>,
+    units: Res<WeightUnits>,
+    mut q_tooltip: Query<(&mut Text, &mut UiNode, &mut Visibility), With<NoteTooltip>>,
+) -> Result<()> {
+    let (mut text, mut ui, mut visibility) = q_tooltip.single_mut()?;
+    let graph = wrapper_graph.single()?.0.read().unwrap();
+    // the node's label and details on one line, its note on the next, then what a paused
+    // animation knows about it
+    let hovered = cursor_world(&windows, &cams)
+        .and_then(|world| clicked_node_id(&nodes, world))
+        .and_then(|id| {
+            let lines: Vec<String> = [
+                graph.describe_node(id),
+                notes.0.get(&id.index()).cloned(),
+                animation.inspect(id, &units),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            (!lines.is_empty()).then_some((id, lines))
+        });
+    let cursor = windows.single().ok().and_then(|w| w.cursor_position());
+    let (Some((id, lines)), Some(cursor)) = (hovered, cursor) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return Ok(());
+    };
+
+    let content = format!("{}: {}", id, lines.join("\n"));
+    if text.0 != content {
+        text.0 = content;
+    }
+    ui.left = Val::Px(cursor.x + TOOLTIP_OFFSET.x);
+    ui.top = Val::Px(cursor.y + TOOLTIP_OFFSET.y);
+    visibility.set_if_neq(Visibility::Visible);
+
+    return Ok(());
+}
+
+// a small dot on the rim of every node that has a note
+fn draw_note_markers(notes: Res<NodeNotes>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    for node in &nodes {
+        if notes.0.contains_key(&node.id.index()) {
+            let rim = node.position + Vec2::new(1.0, 1.0).normalize() * node.r;
+            gizmos.circle_2d(rim, 3.0, GOLD);
+        }
+    }
+}
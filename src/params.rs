@@ -0,0 +1,335 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::graph::TieBreak;
+use crate::heuristic::HeuristicView;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::rng::Rng;
+use crate::{HighlightedEdges, Layer, ResultsHud};
+
+const ROW: Color = Color::srgba(0.15, 0.15, 0.15, 0.9);
+const ROW_HOVERED: Color = Color::srgb(0.35, 0.35, 0.35);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Param {
+    AStarWeight,
+    KPaths,
+    FlowPaths,
+    Damping,
+    TieBreak,
+    Patterns,
+    Seed,
+}
+
+impl Param {
+    const ALL: [Param; 7] = [
+        Param::AStarWeight,
+        Param::KPaths,
+        Param::FlowPaths,
+        Param::Damping,
+        Param::TieBreak,
+        Param::Patterns,
+        Param::Seed,
+    ];
+
+    fn name(self) -> &'static str {
+        return match self {
+            Param::AStarWeight => "A* heuristic weight",
+            Param::KPaths => "Routes ranked by [ and ]",
+            Param::FlowPaths => "Max flow augmenting path cap",
+            Param::Damping => "PageRank damping",
+            Param::TieBreak => "Equally cheap paths broken by",
+            Param::Patterns => "State patterns besides colours",
+            Param::Seed => "Random seed",
+        };
+    }
+
+    fn range(self) -> &'static str {
+        return match self {
+            Param::AStarWeight => "0 to 10; 0 is Dijkstra, above 1 trades optimality for speed",
+            Param::KPaths => "a whole number from 1 to 10",
+            Param::FlowPaths => "a whole number from 1 to 10000",
+            Param::Damping => "0 to 1; the chance of following a link rather than jumping anywhere",
+            Param::TieBreak => {
+                "`search`, `hops` for the fewest edges or `labels` for the lowest labels"
+            }
+            Param::Patterns => "`on` or `off`",
+            Param::Seed => {
+                "a whole number, or `clock` for a new one; setting it restarts the sequence"
+            }
+        };
+    }
+}
+
+// tunables the algorithms, and the views of them, read instead of constants; kept for the
+// session, through loads and regenerated demos. the seed mirrors the app's Rng, which demos,
+// scatters, walks and every randomized run draw from
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct AlgorithmParams {
+    pub astar_weight: f32,
+    pub k_paths: usize,
+    pub flow_paths: usize,
+    pub pagerank_damping: f32,
+    pub tie_break: TieBreak,
+    pub state_patterns: bool,
+    pub seed: u64,
+}
+
+impl Default for AlgorithmParams {
+    fn default() -> Self {
+        return AlgorithmParams {
+            astar_weight: 1.0,
+            k_paths: 5,
+            flow_paths: 1000,
+            pagerank_damping: 0.85,
+            tie_break: TieBreak::Search,
+            state_patterns: false,
+            seed: 0,
+        };
+    }
+}
+
+impl AlgorithmParams {
+    fn value(&self, param: Param) -> String {
+        return match param {
+            Param::AStarWeight => format!("{}", self.astar_weight),
+            Param::KPaths => self.k_paths.to_string(),
+            Param::FlowPaths => self.flow_paths.to_string(),
+            Param::Damping => format!("{}", self.pagerank_damping),
+            Param::TieBreak => match self.tie_break {
+                TieBreak::Search => "search order",
+                TieBreak::FewestHops => "fewest hops",
+                TieBreak::Labels => "lowest labels",
+            }
+            .to_string(),
+            Param::Patterns => if self.state_patterns { "on" } else { "off" }.to_string(),
+            Param::Seed => self.seed.to_string(),
+        };
+    }
+
+    fn set(&mut self, param: Param, text: &str) -> Option<()> {
+        let text = text.trim();
+        match param {
+            Param::AStarWeight => {
+                self.astar_weight = text
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|w| (0.0..=10.0).contains(w))?;
+            }
+            Param::KPaths => {
+                self.k_paths = text.parse().ok().filter(|k| (1..=10).contains(k))?;
+            }
+            Param::FlowPaths => {
+                self.flow_paths = text.parse().ok().filter(|n| (1..=10_000).contains(n))?;
+            }
+            Param::Damping => {
+                self.pagerank_damping = text
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|d| (0.0..=1.0).contains(d))?;
+            }
+            Param::TieBreak => {
+                self.tie_break = match text.to_ascii_lowercase().as_str() {
+                    "search" => TieBreak::Search,
+                    "hops" => TieBreak::FewestHops,
+                    "labels" => TieBreak::Labels,
+                    _ => return None,
+                };
+            }
+            Param::Patterns => {
+                self.state_patterns = match text.to_ascii_lowercase().as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return None,
+                };
+            }
+            Param::Seed => {
+                self.seed = match text.to_ascii_lowercase().as_str() {
+                    "clock" => Rng::default().seed(),
+                    text => text.parse().ok()?,
+                };
+            }
+        }
+        return Some(());
+    }
+}
+
+// what F7 shows, and the parameter the open prompt will set
+#[derive(Resource, Default)]
+struct ParamsPanel {
+    shown: bool,
+    editing: Option<Param>,
+}
+
+#[derive(Component)]
+struct ParamsRoot;
+
+#[derive(Component)]
+struct ParamButton(Param);
+
+pub struct ParamsPlugin;
+
+impl Plugin for ParamsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AlgorithmParams>()
+            .init_resource::<ParamsPanel>()
+            .add_systems(Startup, spawn_params_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_params_keys.run_if(crate::prompt::prompt_closed),
+                    mirror_seed,
+                    handle_param_prompt,
+                    update_params_panel,
+                    handle_param_clicks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// centred under the negative cycle banner
+fn spawn_params_panel(mut commands: Commands) {
+    commands.spawn((
+        UiNode {
+            position_type: PositionType::Absolute,
+            top: Val::Px(50.0),
+            width: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        ParamsRoot,
+    ));
+}
+
+// F7 shows or hides the parameters
+fn handle_params_keys(keys: Res<ButtonInput<KeyCode>>, mut panel: ResMut<ParamsPanel>) {
+    if keys.just_pressed(KeyCode::F7) {
+        panel.shown = !panel.shown;
+    }
+}
+
+// the Rng is also reseeded by loads, so the panel follows it rather than the other way round
+fn mirror_seed(rng: Res<Rng>, mut params: ResMut<AlgorithmParams>) {
+    if params.seed != rng.seed() {
+        params.seed = rng.seed();
+    }
+}
+
+// out of range input keeps the old value; a new A* weight redoes the A* path on screen, and a
+// seed restarts the random sequence even when it is the one already set, to replay a run
+fn handle_param_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut panel: ResMut<ParamsPanel>,
+    mut params: ResMut<AlgorithmParams>,
+    mut rng: ResMut<Rng>,
+    highlights: Res<HighlightedEdges>,
+    mut heuristic_view: ResMut<HeuristicView>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Param {
+            continue;
+        }
+        let Some(param) = panel.editing.take() else {
+            continue;
+        };
+        let mut next = params.clone();
+        if next.set(param, &event.text).is_none() {
+            hud.0 = format!(
+                "{} must be {}, kept {}",
+                param.name(),
+                param.range(),
+                params.value(param)
+            );
+            continue;
+        }
+        if param == Param::Seed {
+            *rng = Rng::new(next.seed);
+        } else if next == *params {
+            continue;
+        }
+        *params = next;
+        if param == Param::AStarWeight {
+            heuristic_view.rerun = !highlights.layer(Layer::AStar).is_empty();
+        }
+        hud.0 = format!("{} set to {}", param.name(), params.value(param));
+    }
+}
+
+fn update_params_panel(
+    mut commands: Commands,
+    panel: Res<ParamsPanel>,
+    params: Res<AlgorithmParams>,
+    q_root: Query<Entity, With<ParamsRoot>>,
+) -> Result<()> {
+    if !panel.is_changed() && !params.is_changed() {
+        return Ok(());
+    }
+    let root = q_root.single()?;
+    commands.entity(root).despawn_related::<Children>();
+    if !panel.shown {
+        return Ok(());
+    }
+
+    let text = |content: String| {
+        (
+            Text::new(content),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )
+    };
+    commands.entity(root).with_children(|parent| {
+        parent.spawn(text(
+            "Algorithm parameters (F7 hides, click one to change it)".to_string(),
+        ));
+        for param in Param::ALL {
+            parent
+                .spawn((
+                    Button,
+                    UiNode {
+                        padding: UiRect::horizontal(Val::Px(4.0)),
+                        ..default()
+                    },
+                    BackgroundColor(ROW),
+                    ParamButton(param),
+                ))
+                .with_child(text(format!("{}: {}", param.name(), params.value(param))));
+        }
+    });
+
+    return Ok(());
+}
+
+fn handle_param_clicks(
+    mut q_buttons: Query<(&Interaction, &ParamButton, &mut BackgroundColor), Changed<Interaction>>,
+    params: Res<AlgorithmParams>,
+    mut panel: ResMut<ParamsPanel>,
+    mut prompt: ResMut<Prompt>,
+) {
+    for (interaction, button, mut background) in &mut q_buttons {
+        background.0 = match interaction {
+            Interaction::None => ROW,
+            _ => ROW_HOVERED,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let param = button.0;
+        panel.editing = Some(param);
+        prompt.open(
+            PromptKind::Param,
+            format!(
+                "{} ({}, now {})",
+                param.name(),
+                param.range(),
+                params.value(param)
+            ),
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::animation::{Animation, Seen};
+use crate::graph::NodeId;
+use crate::params::AlgorithmParams;
+use crate::{HighlightedEdges, Layer, Node};
+
+const STRIPES: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+const DOTS: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+const RING: Color = Color::WHITE;
+
+// shapes on top of the state colours, so the animation and the path on screen read without colour
+// vision: stripes on settled nodes, dots on the frontier and a bold ring on path nodes
+pub struct PatternsPlugin;
+
+impl Plugin for PatternsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_state_patterns);
+    }
+}
+
+// three diagonal chords across the node
+fn stripes(gizmos: &mut Gizmos, at: Vec2, r: f32) {
+    let along = Vec2::new(1.0, 1.0).normalize();
+    let across = along.perp();
+    for d in [-0.5 * r, 0.0, 0.5 * r] {
+        let half = (r * r - d * d).sqrt();
+        let mid = at + across * d;
+        gizmos.line_2d(mid - along * half, mid + along * half, STRIPES);
+    }
+}
+
+// a small grid of dots inside the node
+fn dots(gizmos: &mut Gizmos, at: Vec2, r: f32) {
+    for x in [-0.45, 0.0, 0.45] {
+        for y in [-0.45, 0.0, 0.45] {
+            let dot = at + Vec2::new(x, y) * r;
+            gizmos.circle_2d(Isometry2d::from_translation(dot), 2.0, DOTS);
+        }
+    }
+}
+
+fn draw_state_patterns(
+    params: Res<AlgorithmParams>,
+    animation: Res<Animation>,
+    highlights: Res<HighlightedEdges>,
+    nodes: Query<&Node>,
+    mut gizmos: Gizmos,
+) {
+    if !params.state_patterns {
+        return;
+    }
+    let on_path: HashSet<NodeId> = [Layer::Path, Layer::AStar]
+        .into_iter()
+        .filter(|&layer| highlights.is_visible(layer))
+        .flat_map(|layer| highlights.layer(layer).iter())
+        .flat_map(|&(a, b)| [a, b])
+        .collect();
+    for node in &nodes {
+        match animation.seen(node.id) {
+            Some(Seen::Settled) => stripes(&mut gizmos, node.position, node.r),
+            Some(Seen::Frontier) => dots(&mut gizmos, node.position, node.r),
+            None => {}
+        }
+        if on_path.contains(&node.id) {
+            let at = Isometry2d::from_translation(node.position);
+            for grow in [2.0, 3.5, 5.0] {
+                gizmos.circle_2d(at, node.r + grow, RING);
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Animation;
+use crate::annotations::{Annotation, spawn_annotation};
+use crate::bookmarks::Bookmarks;
+use crate::directed::EdgeMode;
+use crate::document::{self, GraphDocument};
+use crate::graph::{Cost, NodeId};
+use crate::history::QueryHistory;
+use crate::isochrone::Isochrone;
+use crate::node_info::NodeInfo;
+use crate::notes::NodeNotes;
+use crate::regions::{Region, spawn_region};
+use crate::rng::Rng;
+use crate::routing::{Category, Profile, Routing};
+use crate::sources::Sources;
+use crate::tags::Tags;
+use crate::traversal::Traversal;
+use crate::views::{CameraView, CameraViews};
+use crate::{
+    EdgeVisual, GoalNode, GraphSpawner, HighlightedEdges, Layer, MainCamera, MultiSelection,
+    NODE_RADIUS, Node, ResultsHud, SelectedNode, StartNode, WrapperGraph, node_positions, ord,
+};
+
+const GRAPH_FILE: &str = "graph.json";
+
+#[derive(Serialize, Deserialize)]
+struct RegionFile {
+    name: String,
+    members: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RoutingFile {
+    profiles: Vec<Profile>,
+    active: usize,
+    categories: Vec<(NodeId, NodeId, Category)>,
+}
+
+// the versioned graph document with the editor's own state alongside it
+#[derive(Serialize, Deserialize)]
+struct GraphFile {
+    #[serde(flatten)]
+    document: GraphDocument,
+    start: Option<NodeId>,
+    goal: Option<NodeId>,
+    #[serde(default)]
+    directed: bool,
+    #[serde(default)]
+    regions: Vec<RegionFile>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    #[serde(default)]
+    routing: Option<RoutingFile>,
+    #[serde(default)]
+    marked: Vec<(NodeId, NodeId)>,
+    #[serde(default)]
+    notes: Vec<(usize, String)>,
+    #[serde(default)]
+    bookmarks: Vec<(NodeId, String)>,
+    #[serde(default)]
+    views: Vec<CameraView>,
+    #[serde(default)]
+    node_tags: Vec<(NodeId, Vec<String>)>,
+    #[serde(default)]
+    edge_tags: Vec<(NodeId, NodeId, Vec<String>)>,
+    #[serde(default)]
+    node_capacities: Vec<(NodeId, Cost)>,
+    // the seed the random sequence started from; loading restarts it there
+    #[serde(default)]
+    seed: Option<u64>,
+}
+
+impl GraphFile {
+    fn validate(&self) -> Result<(), String> {
+        self.document.validate()?;
+        let n = self.document.positions.len();
+        let ids = self
+            .start
+            .into_iter()
+            .chain(self.goal)
+            .chain(
+                self.routing
+                    .iter()
+                    .flat_map(|r| r.categories.iter().flat_map(|&(a, b, _)| [a, b])),
+            )
+            .chain(self.marked.iter().flat_map(|&(a, b)| [a, b]))
+            .chain(self.node_tags.iter().map(|(id, _)| *id))
+            .chain(self.edge_tags.iter().flat_map(|&(a, b, _)| [a, b]))
+            .chain(self.node_capacities.iter().map(|(id, _)| *id))
+            .chain(self.bookmarks.iter().map(|(id, _)| *id))
+            .map(NodeId::index)
+            .chain(self.regions.iter().flat_map(|r| r.members.iter().copied()))
+            .chain(self.notes.iter().map(|(id, _)| *id));
+        for id in ids {
+            if id >= n {
+                return Err(format!("node {} does not exist", id));
+            }
+        }
+        for view in &self.views {
+            let valid = (1..=9).contains(&view.slot) && view.zoom > 0.0 && view.zoom.is_finite();
+            if !valid {
+                return Err(format!("camera view \"{}\" is not valid", view.name));
+            }
+        }
+        return Ok(());
+    }
+}
+
+pub struct PersistPlugin;
+
+impl Plugin for PersistPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (save_graph, load_graph).run_if(crate::prompt::prompt_closed),
+        );
+    }
+}
+
+fn ctrl_just_pressed(keys: &ButtonInput<KeyCode>, key: KeyCode) -> bool {
+    return keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        && keys.just_pressed(key);
+}
+
+fn save_graph(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    nodes: Query<&Node>,
+    q_regions: Query<&Region>,
+    q_annotations: Query<&Annotation>,
+    mode: Res<EdgeMode>,
+    routing: Res<Routing>,
+    highlights: Res<HighlightedEdges>,
+    notes: Res<NodeNotes>,
+    bookmarks: Res<Bookmarks>,
+    views: Res<CameraViews>,
+    tags: Res<Tags>,
+    info: Res<NodeInfo>,
+    rng: Res<Rng>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !ctrl_just_pressed(&keys, KeyCode::KeyS) {
+        return Ok(());
+    }
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+
+    let file = GraphFile {
+        document: GraphDocument::new(graph.clone(), node_positions(&nodes, graph.nodes.len())),
+        start: start.id,
+        goal: goal.id,
+        directed: mode.directed,
+        regions: q_regions
+            .iter()
+            .map(|r| RegionFile {
+                name: r.name.clone(),
+                members: r.members.clone(),
+            })
+            .collect(),
+        annotations: q_annotations.iter().cloned().collect(),
+        routing: Some(RoutingFile {
+            profiles: routing.profiles.clone(),
+            active: routing.active,
+            categories: routing
+                .categories
+                .iter()
+                .map(|(&(a, b), &c)| (a, b, c))
+                .collect(),
+        }),
+        marked: highlights.layer(Layer::Marked).iter().copied().collect(),
+        notes: notes
+            .0
+            .iter()
+            .map(|(&id, note)| (id, note.clone()))
+            .collect(),
+        bookmarks: bookmarks.0.clone(),
+        views: views.0.clone(),
+        node_tags: tags
+            .nodes
+            .iter()
+            .map(|(&id, t)| (id, t.iter().cloned().collect()))
+            .collect(),
+        edge_tags: tags
+            .edges
+            .iter()
+            .map(|(&(a, b), t)| (a, b, t.iter().cloned().collect()))
+            .collect(),
+        node_capacities: info.capacities.iter().map(|(&id, &c)| (id, c)).collect(),
+        seed: Some(rng.seed()),
+    };
+    let written = serde_json::to_string_pretty(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(GRAPH_FILE, json).map_err(|e| e.to_string()));
+    hud.0 = match written {
+        Ok(()) => format!("Saved {}", GRAPH_FILE),
+        Err(e) => format!("Save failed: {}", e),
+    };
+
+    return Ok(());
+}
+
+fn load_graph(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut q_camera: Query<
+        (
+            &WrapperGraph,
+            &mut SelectedNode,
+            &mut MultiSelection,
+            &mut StartNode,
+            &mut GoalNode,
+        ),
+        With<MainCamera>,
+    >,
+    q_old: Query<Entity, Or<(With<Node>, With<EdgeVisual>, With<Region>, With<Annotation>)>>,
+    mut spawner: GraphSpawner,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut sources: ResMut<Sources>,
+    mut isochrone: ResMut<Isochrone>,
+    mut traversal: ResMut<Traversal>,
+    mut routing: ResMut<Routing>,
+    mut animation: ResMut<Animation>,
+    mut history: ResMut<QueryHistory>,
+    mut notes: ResMut<NodeNotes>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut views: ResMut<CameraViews>,
+    (mut tags, mut info, mut rng): (ResMut<Tags>, ResMut<NodeInfo>, ResMut<Rng>),
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !ctrl_just_pressed(&keys, KeyCode::KeyO) {
+        return Ok(());
+    }
+    let file = std::fs::read_to_string(GRAPH_FILE)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            document::read_version(&json)?;
+            return serde_json::from_str::<GraphFile>(&json).map_err(|e| e.to_string());
+        })
+        .and_then(|file| file.validate().map(|_| file));
+    let file = match file {
+        Ok(file) => file,
+        Err(e) => {
+            println!("Could not load {}: {}", GRAPH_FILE, e);
+            hud.0 = format!("Could not load {}: {}", GRAPH_FILE, e);
+            return Ok(());
+        }
+    };
+
+    for e in &q_old {
+        spawner.commands.entity(e).despawn();
+    }
+
+    let (wg, mut selected, mut multi, mut start, mut goal) = q_camera.single_mut()?;
+    let mut graph = wg.0.write().unwrap();
+    *graph = file.document.graph;
+    selected.id = None;
+    multi.ids.clear();
+    start.id = file.start;
+    goal.id = file.goal;
+    highlights.clear_all();
+    highlights
+        .layer_mut(Layer::Marked)
+        .extend(file.marked.into_iter().map(|(a, b)| ord(a, b)));
+    sources.clear();
+    isochrone.clear();
+    traversal.clear();
+    animation.clear();
+    history.clear();
+    notes.0 = file.notes.into_iter().collect();
+    bookmarks.0 = file.bookmarks;
+    views.0 = file.views;
+    tags.clear();
+    tags.nodes = file
+        .node_tags
+        .into_iter()
+        .map(|(id, t)| (id, t.into_iter().collect()))
+        .collect();
+    tags.edges = file
+        .edge_tags
+        .into_iter()
+        .map(|(a, b, t)| (ord(a, b), t.into_iter().collect()))
+        .collect();
+    info.capacities = file.node_capacities.into_iter().collect();
+    if let Some(seed) = file.seed {
+        *rng = Rng::new(seed);
+    }
+    *routing = match file.routing {
+        Some(r) => Routing::restore(
+            r.profiles,
+            r.active,
+            r.categories
+                .into_iter()
+                .map(|(a, b, c)| ((a, b), c))
+                .collect(),
+        ),
+        None => Routing::default(),
+    };
+    // the spawner already borrows EdgeMode, so the new mode is applied through commands
+    spawner.commands.insert_resource(EdgeMode {
+        directed: file.directed,
+    });
+
+    let nodes: Vec<Node> = file
+        .document
+        .positions
+        .iter()
+        .enumerate()
+        .map(|(id, &position)| Node {
+            position,
+            r: NODE_RADIUS,
+            id: NodeId(id),
+        })
+        .collect();
+    for node in &nodes {
+        spawner.commands.spawn(node.clone());
+    }
+    // one visual per lane of each connected pair, whichever directions it has
+    for (a, b, lane) in graph.lanes() {
+        let cost = graph.lane(a, b, lane).unwrap().cost;
+        spawner.spawn_edge_visual(&nodes[a.index()], &nodes[b.index()], lane, cost);
+    }
+
+    for (index, region) in file.regions.into_iter().enumerate() {
+        spawn_region(
+            &mut spawner.commands,
+            &mut spawner.meshes,
+            &mut spawner.materials,
+            region.name,
+            region.members,
+            index,
+        );
+    }
+    for annotation in file.annotations {
+        spawn_annotation(&mut spawner.commands, annotation);
+    }
+
+    hud.0 = format!(
+        "Loaded {}: {} nodes, start {}, goal {}",
+        GRAPH_FILE,
+        nodes.len(),
+        file.start.map_or("-".to_string(), |id| id.to_string()),
+        file.goal.map_or("-".to_string(), |id| id.to_string())
+    );
+    // loaded anyway, so the problems can be fixed in the editor
+    let report = graph.validate();
+    if !report.defects.is_empty() {
+        hud.0
+            .push_str(&format!("\nGraph check: {}", report.summary(5)));
+    }
+
+    return Ok(());
+}
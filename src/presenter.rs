@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+
+use bevy::camera::RenderTarget;
+use bevy::camera::visibility::RenderLayers;
+use bevy::prelude::*;
+use bevy::window::WindowRef;
+
+use crate::graph::NodeId;
+use crate::{MainCamera, NODE_RADIUS, Node, ResultsHud, WrapperGraph, position_lookup};
+
+// what only the projector camera renders; everything else is on layer 0, seen by both
+const PROJECTOR_LAYER: usize = 1;
+const LABEL_SIZE: f32 = 28.0;
+
+// the second window and its camera while presenting
+#[derive(Resource, Default)]
+struct Presenter {
+    window: Option<Entity>,
+    camera: Option<Entity>,
+}
+
+#[derive(Component)]
+struct ProjectorCamera;
+
+#[derive(Component)]
+struct ProjectorLabel(NodeId);
+
+pub struct PresenterPlugin;
+
+impl Plugin for PresenterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Presenter>().add_systems(
+            Update,
+            (
+                toggle_presenter.run_if(crate::prompt::prompt_closed),
+                close_presenter,
+                mirror_main_camera,
+                sync_projector_labels,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+P opens a second window for the projector, or closes it
+fn toggle_presenter(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut presenter: ResMut<Presenter>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+    if let Some(window) = presenter.window {
+        commands.entity(window).despawn();
+        return;
+    }
+    let window = commands
+        .spawn(Window {
+            title: "Dijkstra UI - presenter".to_string(),
+            ..default()
+        })
+        .id();
+    // the UI only goes to the primary window's camera, so the projector gets no HUD or panels
+    let camera = commands
+        .spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Window(WindowRef::Entity(window)),
+                ..default()
+            },
+            RenderLayers::from_layers(&[0, PROJECTOR_LAYER]),
+            ProjectorCamera,
+        ))
+        .id();
+    *presenter = Presenter {
+        window: Some(window),
+        camera: Some(camera),
+    };
+    hud.0 = "Presenting in a second window (Ctrl+P closes it)".to_string();
+}
+
+// closing the window by hand ends the presentation too
+fn close_presenter(
+    mut commands: Commands,
+    windows: Query<(), With<Window>>,
+    mut presenter: ResMut<Presenter>,
+    q_labels: Query<Entity, With<ProjectorLabel>>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let Some(window) = presenter.window else {
+        return;
+    };
+    if windows.contains(window) {
+        return;
+    }
+    if let Some(camera) = presenter.camera {
+        commands.entity(camera).despawn();
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    *presenter = Presenter::default();
+    hud.0 = "Presenter window closed".to_string();
+}
+
+// the projector looks wherever the editor does, at the same zoom
+fn mirror_main_camera(
+    q_main: Query<(&Transform, &Projection), (With<MainCamera>, Without<ProjectorCamera>)>,
+    mut q_projector: Query<(&mut Transform, &mut Projection), With<ProjectorCamera>>,
+) -> Result<()> {
+    let (transform, projection) = q_main.single()?;
+    for (mut mirrored, mut mirrored_projection) in &mut q_projector {
+        if *mirrored != *transform {
+            *mirrored = *transform;
+        }
+        if let (Projection::Orthographic(main), Projection::Orthographic(ortho)) =
+            (projection, &mut *mirrored_projection)
+            && ortho.scale != main.scale
+        {
+            ortho.scale = main.scale;
+        }
+    }
+
+    return Ok(());
+}
+
+// a big label over each node that only the projector draws, showing its name, which changes inside
+// the graph like the node's own text
+fn sync_projector_labels(
+    mut commands: Commands,
+    presenter: Res<Presenter>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    mut q_labels: Query<(Entity, &ProjectorLabel, &mut Transform, &mut Text2d)>,
+) -> Result<()> {
+    if presenter.window.is_none() {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    let position = position_lookup(&nodes);
+    let above = |at: Vec2| at.extend(6.0) + Vec3::Y * (NODE_RADIUS + LABEL_SIZE * 0.6);
+    let mut labelled = HashSet::new();
+    for (e, label, mut transform, mut text) in &mut q_labels {
+        let Some(at) = position(label.0) else {
+            commands.entity(e).despawn();
+            continue;
+        };
+        transform.translation = above(at);
+        let shown = graph.node_name(label.0);
+        if text.0 != shown {
+            text.0 = shown;
+        }
+        labelled.insert(label.0);
+    }
+    for node in nodes.iter().filter(|n| !labelled.contains(&n.id)) {
+        commands.spawn((
+            Text2d::new(graph.node_name(node.id)),
+            TextFont {
+                font_size: LABEL_SIZE,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Transform::from_translation(above(node.position)),
+            RenderLayers::layer(PROJECTOR_LAYER),
+            ProjectorLabel(node.id),
+        ));
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::graph::{Cost, NodeId};
+use crate::{
+    HighlightedEdges, Layer, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph,
+    position_lookup,
+};
+
+const COLUMN_WIDTH: f32 = 14.0;
+const CHART_HEIGHT: f32 = 70.0;
+const MAX_COLUMNS: usize = 40;
+const CUMULATIVE: Color = Color::srgb(0.45, 0.45, 0.5);
+const HOP: Color = Color::srgb(1.0, 0.6, 0.25);
+const HOVERED: Color = Color::srgb(1.0, 0.85, 0.5);
+
+// the path on the path layer, hop by hop from the start, with what each hop costs
+#[derive(Resource, Default)]
+struct PathProfile {
+    shown: bool,
+    hops: Vec<(NodeId, NodeId, Cost)>,
+    hovered: Option<usize>,
+}
+
+impl PathProfile {
+    fn total(&self) -> Cost {
+        return self.hops.iter().map(|&(_, _, c)| c).sum();
+    }
+
+    // how far along the route each hop ends
+    fn cumulative(&self) -> Vec<Cost> {
+        return self
+            .hops
+            .iter()
+            .scan(0, |sum, &(_, _, c)| {
+                *sum += c;
+                Some(*sum)
+            })
+            .collect();
+    }
+}
+
+#[derive(Component)]
+struct ProfilePanel;
+
+#[derive(Component)]
+struct ProfileColumn(usize);
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PathProfile>()
+            .add_systems(Startup, spawn_profile_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_profile_keys.run_if(crate::prompt::prompt_closed),
+                    refresh_profile,
+                    update_profile_panel,
+                    handle_column_hovers,
+                    draw_hovered_hop,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// above the weight histogram in the bottom-right corner
+fn spawn_profile_panel(mut commands: Commands) {
+    commands.spawn((
+        UiNode {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(200.0),
+            right: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+        ProfilePanel,
+    ));
+}
+
+// shift+F3 shows or hides the profile
+fn handle_profile_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut profile: ResMut<PathProfile>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    profile.shown = !profile.shown;
+    if !profile.shown {
+        *profile = PathProfile::default();
+        hud.0 = "Path profile hidden".to_string();
+    }
+}
+
+// the path layer is a set of edges, so the route is walked out of it from the start node
+fn ordered_path(edges: &HashSet<(NodeId, NodeId)>, start: NodeId) -> Option<Vec<NodeId>> {
+    let mut path = vec![start];
+    while let Some(&at) = path.last() {
+        let next = edges
+            .iter()
+            .filter_map(|&(a, b)| match (a == at, b == at) {
+                (true, _) => Some(b),
+                (_, true) => Some(a),
+                _ => None,
+            })
+            .find(|n| !path.contains(n));
+        match next {
+            Some(next) => path.push(next),
+            None => break,
+        }
+    }
+    return (path.len() == edges.len() + 1).then_some(path);
+}
+
+// follows the path layer and the weights while shown, without touching change detection when
+// neither moved
+fn refresh_profile(
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    highlights: Res<HighlightedEdges>,
+    mut profile: ResMut<PathProfile>,
+) -> Result<()> {
+    if !profile.shown {
+        return Ok(());
+    }
+    let (wg, start) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let layer = highlights.layer(Layer::Path);
+    let path = start
+        .id
+        .filter(|_| !layer.is_empty())
+        .and_then(|start| ordered_path(layer, start))
+        .unwrap_or_default();
+    let hops: Vec<(NodeId, NodeId, Cost)> = path
+        .windows(2)
+        .filter_map(|w| Some((w[0], w[1], graph.edge_between(w[0], w[1])?.cost)))
+        .collect();
+    if profile.hops != hops {
+        profile.hops = hops;
+        profile.hovered = None;
+    }
+
+    return Ok(());
+}
+
+fn update_profile_panel(
+    mut commands: Commands,
+    profile: Res<PathProfile>,
+    units: Res<WeightUnits>,
+    q_panel: Query<Entity, With<ProfilePanel>>,
+) -> Result<()> {
+    if !profile.is_changed() && !units.is_changed() {
+        return Ok(());
+    }
+    let panel = q_panel.single()?;
+    commands.entity(panel).despawn_related::<Children>();
+    if !profile.shown {
+        return Ok(());
+    }
+
+    let text = |content: String| {
+        (
+            Text::new(content),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        )
+    };
+    if profile.hops.is_empty() {
+        commands.entity(panel).with_child(text(
+            "Path profile: show a path from the start with P (Shift+F3 hides)".to_string(),
+        ));
+        return Ok(());
+    }
+    let total = profile.total();
+    let cumulative = profile.cumulative();
+    let &(a, b, most) = profile.hops.iter().max_by_key(|&&(_, _, c)| c).unwrap();
+    // one scale for both bars, so a hop's bar is the height it adds to the running total
+    let scale = cumulative
+        .iter()
+        .chain(profile.hops.iter().map(|(_, _, c)| c))
+        .map(|c| c.abs())
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+    let height = |cost: Cost| (CHART_HEIGHT * cost.max(0) as f32 / scale).max(1.0);
+    let first = profile.hops[0].0;
+    let last = profile.hops[profile.hops.len() - 1].1;
+
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn(text(format!(
+            "Path profile {} to {}: {} over {} hops (Shift+F3 hides, hover a hop)",
+            first,
+            last,
+            units.format(total),
+            profile.hops.len()
+        )));
+        parent.spawn(text(format!(
+            "Costliest hop {} - {}: {}{}",
+            a,
+            b,
+            units.format(most),
+            if total > 0 {
+                format!(", {:.0}% of the route", most as f32 / total as f32 * 100.0)
+            } else {
+                String::new()
+            }
+        )));
+        parent
+            .spawn(UiNode {
+                align_items: AlignItems::End,
+                column_gap: Val::Px(3.0),
+                height: Val::Px(CHART_HEIGHT),
+                ..default()
+            })
+            .with_children(|row| {
+                let columns = profile.hops.iter().zip(&cumulative).take(MAX_COLUMNS);
+                for (i, (&(_, _, cost), &so_far)) in columns.enumerate() {
+                    let bar = |cost: Cost, color: Color| {
+                        (
+                            UiNode {
+                                width: Val::Px(COLUMN_WIDTH / 2.0),
+                                height: Val::Px(height(cost)),
+                                ..default()
+                            },
+                            BackgroundColor(color),
+                        )
+                    };
+                    row.spawn((
+                        Button,
+                        UiNode {
+                            align_items: AlignItems::End,
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        ProfileColumn(i),
+                    ))
+                    .with_children(|column| {
+                        column.spawn(bar(so_far, CUMULATIVE));
+                        column.spawn(bar(cost, HOP));
+                    });
+                }
+            });
+        if profile.hops.len() > MAX_COLUMNS {
+            parent.spawn(text(format!(
+                "First {} of {} hops drawn",
+                MAX_COLUMNS,
+                profile.hops.len()
+            )));
+        }
+        parent.spawn(text(
+            "Grey: cost so far; orange: what each hop adds".to_string(),
+        ));
+    });
+
+    return Ok(());
+}
+
+fn handle_column_hovers(
+    q_columns: Query<(&Interaction, &ProfileColumn), Changed<Interaction>>,
+    units: Res<WeightUnits>,
+    mut profile: ResMut<PathProfile>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for (interaction, column) in &q_columns {
+        if *interaction == Interaction::None {
+            if profile.hovered == Some(column.0) {
+                profile.bypass_change_detection().hovered = None;
+            }
+            continue;
+        }
+        let Some(&(a, b, cost)) = profile.hops.get(column.0) else {
+            continue;
+        };
+        let total = profile.total();
+        let so_far = profile.cumulative()[column.0];
+        hud.0 = format!(
+            "Hop {}: {} - {} costs {}, {} of {} so far{}",
+            column.0 + 1,
+            a,
+            b,
+            units.format(cost),
+            units.format(so_far),
+            units.format(total),
+            if total > 0 {
+                format!(" ({:.0}%)", so_far as f32 / total as f32 * 100.0)
+            } else {
+                String::new()
+            }
+        );
+        // hovering only moves the highlight on the canvas; the panel stays as it is
+        profile.bypass_change_detection().hovered = Some(column.0);
+    }
+}
+
+// the hovered hop drawn thick over the path
+fn draw_hovered_hop(profile: Res<PathProfile>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let Some(&(a, b, _)) = profile.hovered.and_then(|i| profile.hops.get(i)) else {
+        return;
+    };
+    let position = position_lookup(&nodes);
+    let (Some(a), Some(b)) = (position(a), position(b)) else {
+        return;
+    };
+    let side = (b - a).normalize_or_zero().perp() * 2.0;
+    for offset in [-side, Vec2::ZERO, side] {
+        gizmos.line_2d(a + offset, b + offset, HOVERED);
+    }
+}
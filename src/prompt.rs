@@ -0,0 +1,143 @@
+use bevy::input::ButtonState;
+use bevy::input::InputSystems;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Units,
+    Scatter,
+    RegionName,
+    AnnotationText,
+    Budget,
+    Multipliers,
+    Race,
+    NodeNote,
+    EdgeWeight,
+    Prune,
+    Batch,
+    Reshape,
+    Caption,
+    Bookmark,
+    Param,
+    Jitter,
+    CameraView,
+    Suggest,
+    Failures,
+    Traffic,
+    Ch,
+    Tags,
+    TagFilter,
+    Morph,
+    NodeInfo,
+    EdgeName,
+    Import,
+}
+
+#[derive(Resource, Default)]
+pub struct Prompt {
+    active: Option<(PromptKind, String)>,
+    buffer: String,
+}
+
+impl Prompt {
+    pub fn open(&mut self, kind: PromptKind, label: impl Into<String>) {
+        self.active = Some((kind, label.into()));
+        self.buffer.clear();
+    }
+
+    pub fn is_open(&self) -> bool {
+        return self.active.is_some();
+    }
+}
+
+#[derive(Message, Debug, Clone)]
+pub struct PromptSubmitted {
+    pub kind: PromptKind,
+    pub text: String,
+}
+
+#[derive(Component)]
+struct PromptText;
+
+pub struct PromptPlugin;
+
+impl Plugin for PromptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Prompt>()
+            .add_message::<PromptSubmitted>()
+            .add_systems(Startup, spawn_prompt_text)
+            // runs before Update so the key that opens a prompt is never typed into it
+            .add_systems(PreUpdate, read_prompt_input.after(InputSystems))
+            .add_systems(Update, update_prompt_text);
+    }
+}
+
+pub fn prompt_closed(prompt: Res<Prompt>) -> bool {
+    return !prompt.is_open();
+}
+
+fn spawn_prompt_text(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        UiNode {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        PromptText,
+    ));
+}
+
+fn read_prompt_input(
+    mut events: MessageReader<KeyboardInput>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: MessageWriter<PromptSubmitted>,
+) {
+    for event in events.read() {
+        if event.state != ButtonState::Pressed || !prompt.is_open() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let (kind, _) = prompt.active.take().unwrap();
+                submitted.write(PromptSubmitted {
+                    kind,
+                    text: prompt.buffer.trim().to_string(),
+                });
+            }
+            Key::Escape => {
+                prompt.active = None;
+            }
+            Key::Backspace => {
+                prompt.buffer.pop();
+            }
+            Key::Space => {
+                prompt.buffer.push(' ');
+            }
+            Key::Character(s) => {
+                prompt.buffer.push_str(s);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_prompt_text(prompt: Res<Prompt>, mut q_text: Query<&mut Text, With<PromptText>>) {
+    if !prompt.is_changed() {
+        return;
+    }
+    for mut text in &mut q_text {
+        text.0 = match &prompt.active {
+            Some((_, label)) => format!("{}: {}_", label, prompt.buffer),
+            None => String::new(),
+        };
+    }
+}
@@ -0,0 +1,356 @@
+use std::sync::{Arc, RwLock};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::animation::Animation;
+use crate::bookmarks::Bookmarks;
+use crate::graph::{Cost, Graph, NodeId};
+use crate::history::QueryHistory;
+use crate::isochrone::Isochrone;
+use crate::node_info::NodeInfo;
+use crate::notes::NodeNotes;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::regions::Region;
+use crate::routing::Routing;
+use crate::sources::Sources;
+use crate::tags::Tags;
+use crate::traversal::Traversal;
+use crate::{
+    EdgeVisual, GoalNode, GraphSpawner, HighlightedEdges, Layer, MainCamera, MultiSelection, Node,
+    ResultsHud, SelectedNode, StartNode, WrapperGraph,
+};
+
+enum Prune {
+    Isolated,
+    Above(Cost),
+    Below(Cost),
+    Largest,
+}
+
+impl Prune {
+    // thresholds are typed in display units, like the budget
+    fn parse(text: &str, scale: f32) -> Option<Prune> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let threshold = |word: &str| -> Option<Cost> {
+            return Some((word.parse::<f32>().ok()? / scale).round() as Cost);
+        };
+        return match words.as_slice() {
+            ["isolated"] => Some(Prune::Isolated),
+            ["largest"] => Some(Prune::Largest),
+            ["above", value] => Some(Prune::Above(threshold(value)?)),
+            ["below", value] => Some(Prune::Below(threshold(value)?)),
+            _ => None,
+        };
+    }
+}
+
+pub struct PrunePlugin;
+
+impl Plugin for PrunePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (handle_prune_keys, merge_nodes).run_if(crate::prompt::prompt_closed),
+                apply_prune,
+            )
+                .chain(),
+        );
+    }
+}
+
+// Delete asks which cleanup to run
+fn handle_prune_keys(keys: Res<ButtonInput<KeyCode>>, mut prompt: ResMut<Prompt>) {
+    if keys.just_pressed(KeyCode::Delete) {
+        prompt.open(
+            PromptKind::Prune,
+            "Prune: `isolated` nodes, edges `above 10` or `below 2`, or keep the `largest` component",
+        );
+    }
+}
+
+// what the user made (notes, bookmarks, regions, marks, categories, start and goal) follows the
+// surviving nodes to their new ids; computed views are cleared, as after loading a graph. nodes
+// can share a new id when they were merged, and the lowest old id keeps its entity
+#[derive(SystemParam)]
+pub struct Renumber<'w, 's> {
+    q_camera: Query<
+        'w,
+        's,
+        (
+            &'static WrapperGraph,
+            &'static mut SelectedNode,
+            &'static mut MultiSelection,
+            &'static mut StartNode,
+            &'static mut GoalNode,
+        ),
+        With<MainCamera>,
+    >,
+    q_nodes: Query<'w, 's, (Entity, &'static mut Node, Option<&'static mut Text2d>)>,
+    q_edges: Query<'w, 's, Entity, With<EdgeVisual>>,
+    q_regions: Query<'w, 's, (Entity, &'static mut Region)>,
+    pub spawner: GraphSpawner<'w, 's>,
+    highlights: ResMut<'w, HighlightedEdges>,
+    routing: ResMut<'w, Routing>,
+    notes: ResMut<'w, NodeNotes>,
+    bookmarks: ResMut<'w, Bookmarks>,
+    tags: ResMut<'w, Tags>,
+    info: ResMut<'w, NodeInfo>,
+    overlays: (
+        ResMut<'w, Sources>,
+        ResMut<'w, Isochrone>,
+        ResMut<'w, Traversal>,
+        ResMut<'w, Animation>,
+        ResMut<'w, QueryHistory>,
+    ),
+}
+
+impl Renumber<'_, '_> {
+    pub fn graph(&self) -> Result<Arc<RwLock<Graph>>> {
+        let (wg, ..) = self.q_camera.single()?;
+        return Ok(wg.0.clone());
+    }
+
+    pub fn multi_selection(&self) -> Result<Vec<NodeId>> {
+        let (_, _, multi, ..) = self.q_camera.single()?;
+        return Ok(multi.ids.clone());
+    }
+
+    pub fn position(&self, id: NodeId) -> Option<Vec2> {
+        return self
+            .q_nodes
+            .iter()
+            .find(|(_, node, _)| node.id == id)
+            .map(|(_, node, _)| node.position);
+    }
+
+    // before apply, so the edges are rebuilt against the new position
+    pub fn move_node(&mut self, id: NodeId, position: Vec2) {
+        for (_, mut node, _) in &mut self.q_nodes {
+            if node.id == id {
+                node.position = position;
+            }
+        }
+    }
+
+    pub fn apply(&mut self, graph: &Graph, remap: &[Option<NodeId>]) -> Result<()> {
+        let (_, mut selected, mut multi, mut start, mut goal) = self.q_camera.single_mut()?;
+        let id = |old: Option<NodeId>| old.and_then(|old| remap[old.index()]);
+        selected.id = id(selected.id);
+        let mut ids: Vec<NodeId> = Vec::new();
+        for new in multi.ids.iter().filter_map(|&old| remap[old.index()]) {
+            if !ids.contains(&new) {
+                ids.push(new);
+            }
+        }
+        multi.ids = ids;
+        start.id = id(start.id);
+        goal.id = id(goal.id);
+        if start.id.is_some() && start.id == goal.id {
+            goal.id = None;
+        }
+        // a merged node keeps the note that sorts first
+        let mut notes: Vec<(usize, String)> = self.notes.0.drain().collect();
+        notes.sort();
+        for (old, note) in notes {
+            if let Some(new) = remap[old] {
+                self.notes.0.entry(new.index()).or_insert(note);
+            }
+        }
+        for (entity, mut region) in &mut self.q_regions {
+            let mut members: Vec<usize> = Vec::new();
+            for new in region.members.iter().filter_map(|&old| remap[old]) {
+                if !members.contains(&new.index()) {
+                    members.push(new.index());
+                }
+            }
+            region.members = members;
+            if region.members.is_empty() {
+                self.spawner.commands.entity(entity).despawn();
+            }
+        }
+        // renumbering keeps the order, so (low, high) pairs stay ordered
+        let connected = |&(a, b): &(NodeId, NodeId)| graph.has_edge(a, b) || graph.has_edge(b, a);
+        self.routing.remap(remap);
+        self.bookmarks.remap(remap);
+        self.routing.categories.retain(|pair, _| connected(pair));
+        self.tags.remap(remap);
+        self.info.remap(remap);
+        self.tags.edges.retain(|pair, _| connected(pair));
+        let marked: Vec<(NodeId, NodeId)> = self
+            .highlights
+            .layer(Layer::Marked)
+            .iter()
+            .filter_map(|&(a, b)| Some((remap[a.index()]?, remap[b.index()]?)))
+            .filter(connected)
+            .collect();
+        let baseline = self.highlights.baseline().and_then(|(cost, path)| {
+            let mut path: Vec<NodeId> = path
+                .iter()
+                .map(|&old| remap[old.index()])
+                .collect::<Option<_>>()?;
+            path.dedup();
+            Some((*cost, path))
+        });
+        self.highlights.clear_all();
+        self.highlights.layer_mut(Layer::Marked).extend(marked);
+        if let Some((cost, path)) = baseline {
+            self.highlights.lock_baseline(cost, &path);
+        }
+        let (sources, isochrone, traversal, animation, history) = &mut self.overlays;
+        sources.clear();
+        isochrone.clear();
+        traversal.clear();
+        animation.clear();
+        history.clear();
+
+        let mut kept: Vec<Option<NodeId>> = vec![None; graph.nodes.len()];
+        for (old, new) in remap.iter().enumerate() {
+            if let Some(new) = *new
+                && kept[new.index()].is_none()
+            {
+                kept[new.index()] = Some(NodeId(old));
+            }
+        }
+        let mut survivors: Vec<Option<Node>> = vec![None; graph.nodes.len()];
+        for (entity, mut node, label) in &mut self.q_nodes {
+            match remap[node.id.index()] {
+                Some(id) if kept[id.index()] == Some(node.id) => {
+                    if node.id != id {
+                        node.id = id;
+                        if let Some(mut label) = label {
+                            label.0 = id.to_string();
+                        }
+                    }
+                    survivors[id.index()] = Some(node.clone());
+                }
+                _ => self.spawner.commands.entity(entity).despawn(),
+            }
+        }
+        for e in &self.q_edges {
+            self.spawner.commands.entity(e).despawn();
+        }
+        for (a, b, lane) in graph.lanes() {
+            let (Some(from), Some(to)) = (&survivors[a.index()], &survivors[b.index()]) else {
+                continue;
+            };
+            let cost = graph.lane(a, b, lane).unwrap().cost;
+            self.spawner.spawn_edge_visual(from, to, lane, cost);
+        }
+
+        return Ok(());
+    }
+}
+
+fn apply_prune(
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut renumber: Renumber,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Prune {
+            continue;
+        }
+        let Some(prune) = Prune::parse(&event.text, renumber.spawner.units.scale) else {
+            hud.0 = "Type `isolated`, `above 10`, `below 2`, or `largest`".to_string();
+            continue;
+        };
+
+        let wg = renumber.graph()?;
+        let mut graph = wg.write().unwrap();
+        let (nodes, connections) = (graph.nodes.len(), graph.connection_count());
+        let remap = match prune {
+            Prune::Isolated => {
+                let keep = graph.connected_nodes();
+                graph.retain_nodes(&keep)
+            }
+            Prune::Largest => {
+                let keep = graph.largest_component();
+                graph.retain_nodes(&keep)
+            }
+            Prune::Above(threshold) => {
+                graph.remove_edges(|_, e| e.cost > threshold);
+                (0..nodes).map(|i| Some(NodeId(i))).collect()
+            }
+            Prune::Below(threshold) => {
+                graph.remove_edges(|_, e| e.cost < threshold);
+                (0..nodes).map(|i| Some(NodeId(i))).collect()
+            }
+        };
+        let removed = (
+            nodes - graph.nodes.len(),
+            connections - graph.connection_count(),
+        );
+        if removed == (0, 0) {
+            hud.0 = "Nothing to prune".to_string();
+            continue;
+        }
+        renumber.apply(&graph, &remap)?;
+
+        hud.0 = match prune {
+            Prune::Isolated => format!("Deleted {} isolated nodes", removed.0),
+            Prune::Largest => format!(
+                "Kept the largest component: deleted {} nodes and {} edges",
+                removed.0, removed.1
+            ),
+            Prune::Above(threshold) => format!(
+                "Deleted {} edges heavier than {}",
+                removed.1,
+                renumber.spawner.units.format(threshold)
+            ),
+            Prune::Below(threshold) => format!(
+                "Deleted {} edges lighter than {}",
+                removed.1,
+                renumber.spawner.units.format(threshold)
+            ),
+        };
+        hud.0 += &format!(
+            "\n{} nodes and {} edges left",
+            graph.nodes.len(),
+            graph.connection_count()
+        );
+    }
+
+    return Ok(());
+}
+
+// ctrl+M merges the two shift+clicked nodes into one halfway between them, for intersections an
+// import duplicated; where both connect to the same node the cheaper edge stays
+fn merge_nodes(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut renumber: Renumber,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyM) {
+        return Ok(());
+    }
+    let ids = renumber.multi_selection()?;
+    let [a, b] = ids[..] else {
+        hud.0 = "Shift+click two nodes, then Ctrl+M to merge them".to_string();
+        return Ok(());
+    };
+    let (keep, gone) = (a.min(b), a.max(b));
+    let (Some(from), Some(to)) = (renumber.position(keep), renumber.position(gone)) else {
+        return Ok(());
+    };
+
+    let wg = renumber.graph()?;
+    let mut graph = wg.write().unwrap();
+    let connections = graph.connection_count();
+    let remap = graph.merge_nodes(keep, gone);
+    renumber.move_node(keep, from.midpoint(to));
+    renumber.apply(&graph, &remap)?;
+
+    hud.0 = format!(
+        "Merged {} into {}, {} fewer edges\n{} nodes and {} edges left",
+        gone,
+        keep,
+        connections - graph.connection_count(),
+        graph.nodes.len(),
+        graph.connection_count()
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,379 @@
+use bevy::color::palettes::css::{GOLD, LIME};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::{
+    EdgeVisual, GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph,
+    clicked_node_id, cursor_world, edge_at,
+};
+
+const LABEL: Color = Color::srgb(0.9, 0.9, 0.6);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Phase {
+    // the user picks the unsettled node with the smallest tentative distance
+    Settle,
+    // the user relaxes every edge out of `node` that shortens a tentative distance
+    Relax { node: NodeId, pending: Vec<NodeId> },
+}
+
+// a dijkstra run the user drives by hand, checked against what dijkstra would do at every click
+#[derive(Resource, Default)]
+pub struct Quiz {
+    active: Option<Phase>,
+    goal: Option<NodeId>,
+    dist: Vec<Option<Cost>>,
+    settled: Vec<bool>,
+    correct: usize,
+    mistakes: usize,
+    // the weights the run started with
+    costs: Vec<(NodeId, NodeId, Cost)>,
+}
+
+impl Quiz {
+    // every tentative distance dijkstra could settle next; ties are all fine
+    fn next_candidates(&self) -> (Option<Cost>, Vec<NodeId>) {
+        let best = (0..self.dist.len())
+            .filter(|&n| !self.settled[n])
+            .filter_map(|n| self.dist[n])
+            .min();
+        let nodes = (0..self.dist.len())
+            .filter(|&n| !self.settled[n] && best.is_some() && self.dist[n] == best)
+            .map(NodeId)
+            .collect();
+        return (best, nodes);
+    }
+
+    // the neighbours whose distance goes down through `node`, cheapest edge per neighbour
+    fn improvements(&self, graph: &Graph, node: NodeId) -> Vec<NodeId> {
+        let Some(d) = self.dist[node.index()] else {
+            return Vec::new();
+        };
+        let mut pending: Vec<NodeId> = graph
+            .edges_from(node)
+            .iter()
+            .filter(|e| !self.settled[e.node.index()])
+            .filter(|e| self.dist[e.node.index()].is_none_or(|old| d + e.cost < old))
+            .map(|e| e.node)
+            .collect();
+        pending.sort_unstable();
+        pending.dedup();
+        return pending;
+    }
+
+    fn score(&self) -> String {
+        return format!("{} right, {} mistakes", self.correct, self.mistakes);
+    }
+}
+
+pub fn quiz_inactive(quiz: Res<Quiz>) -> bool {
+    return quiz.active.is_none();
+}
+
+#[derive(Component)]
+struct QuizLabel;
+
+pub struct QuizPlugin;
+
+impl Plugin for QuizPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Quiz>().add_systems(
+            Update,
+            (
+                handle_quiz_keys.run_if(crate::prompt::prompt_closed),
+                drop_stale_quiz,
+                handle_quiz_click,
+                update_quiz_labels,
+                draw_quiz,
+            )
+                .chain(),
+        );
+    }
+}
+
+// F9 starts a hand-driven dijkstra from the start node, or gives it up
+fn handle_quiz_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    mut quiz: ResMut<Quiz>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::F9) {
+        return Ok(());
+    }
+    if quiz.active.is_some() {
+        hud.0 = format!("Stopped the drill: {}", quiz.score());
+        *quiz = Quiz::default();
+        return Ok(());
+    }
+    let (wg, start, goal) = q_camera.single()?;
+    let Some(start) = start.id else {
+        hud.0 = "Set a start node (S) to drive Dijkstra from".to_string();
+        return Ok(());
+    };
+    let graph = wg.0.read().unwrap();
+    if graph.has_negative_edges() {
+        hud.0 = "Dijkstra needs non-negative weights".to_string();
+        return Ok(());
+    }
+
+    let n = graph.nodes.len();
+    let mut dist = vec![None; n];
+    dist[start.index()] = Some(0);
+    *quiz = Quiz {
+        active: Some(Phase::Settle),
+        goal: goal.id,
+        dist,
+        settled: vec![false; n],
+        costs: graph.arc_costs(),
+        ..default()
+    };
+    hud.0 = format!(
+        "You drive Dijkstra from {}: click the node to settle next (F9 stops)",
+        start
+    );
+
+    return Ok(());
+}
+
+// the checks only hold for the weights the run began with
+fn drop_stale_quiz(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut quiz: ResMut<Quiz>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if quiz.active.is_none() {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    if graph.arc_costs() != quiz.costs {
+        hud.0 = format!("The graph changed, so the drill stopped: {}", quiz.score());
+        *quiz = Quiz::default();
+    }
+
+    return Ok(());
+}
+
+// a node click settles it; while relaxing, an edge click, or a click on the node at its far end,
+// relaxes that edge
+fn handle_quiz_click(
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    ui: Query<&Interaction>,
+    units: Res<WeightUnits>,
+    mut quiz: ResMut<Quiz>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some(phase) = quiz.active.clone() else {
+        return Ok(());
+    };
+    if !buttons.just_pressed(MouseButton::Left) || ui.iter().any(|i| *i != Interaction::None) {
+        return Ok(());
+    }
+    let Some(world) = cursor_world(&windows, &cams) else {
+        return Ok(());
+    };
+    let graph = q_graph.single()?.0.read().unwrap();
+    let clicked = clicked_node_id(&nodes, world);
+    let dist = |d: Option<Cost>| d.map_or("infinity".to_string(), |d| units.format(d));
+
+    match phase {
+        Phase::Settle => {
+            let Some(id) = clicked else {
+                return Ok(());
+            };
+            let (best, candidates) = quiz.next_candidates();
+            if !candidates.contains(&id) {
+                quiz.mistakes += 1;
+                hud.0 = if quiz.settled[id.index()] {
+                    format!("{} is already settled ({})", id, quiz.score())
+                } else {
+                    format!(
+                        "Not {}: its tentative distance is {}, but {} has {} ({})",
+                        id,
+                        dist(quiz.dist[id.index()]),
+                        candidates[0],
+                        dist(best),
+                        quiz.score()
+                    )
+                };
+                return Ok(());
+            }
+            quiz.correct += 1;
+            quiz.settled[id.index()] = true;
+            if quiz.goal == Some(id) {
+                hud.0 = format!(
+                    "The goal {} is settled at {}, which is final. Done: {}",
+                    id,
+                    dist(quiz.dist[id.index()]),
+                    quiz.score()
+                );
+                quiz.active = None;
+                return Ok(());
+            }
+            let pending = quiz.improvements(&graph, id);
+            hud.0 = if pending.is_empty() {
+                format!(
+                    "Settled {} at {}; no edge out of it improves anything, so settle the next node",
+                    id,
+                    dist(quiz.dist[id.index()])
+                )
+            } else {
+                format!(
+                    "Settled {} at {}; now relax the edges out of it that shorten a distance",
+                    id,
+                    dist(quiz.dist[id.index()])
+                )
+            };
+            quiz.active = Some(if pending.is_empty() {
+                Phase::Settle
+            } else {
+                Phase::Relax { node: id, pending }
+            });
+        }
+        Phase::Relax { node, pending } => {
+            let target = match (clicked, edge_at(&nodes, &edges, world)) {
+                (Some(id), _) => id,
+                (None, Some((a, b))) if a == node => b,
+                (None, Some((a, b))) if b == node => a,
+                (None, Some(_)) => {
+                    quiz.mistakes += 1;
+                    hud.0 = format!("That edge does not leave {} ({})", node, quiz.score());
+                    return Ok(());
+                }
+                (None, None) => return Ok(()),
+            };
+            if !pending.contains(&target) {
+                quiz.mistakes += 1;
+                let through = graph
+                    .edges_from(node)
+                    .iter()
+                    .filter(|e| e.node == target)
+                    .map(|e| quiz.dist[node.index()].unwrap() + e.cost)
+                    .min();
+                hud.0 = match through {
+                    _ if target == node => {
+                        format!("Pick an edge out of {} ({})", node, quiz.score())
+                    }
+                    None => format!(
+                        "There is no edge from {} to {} ({})",
+                        node,
+                        target,
+                        quiz.score()
+                    ),
+                    Some(_) if quiz.settled[target.index()] => format!(
+                        "{} is settled, its distance can't change ({})",
+                        target,
+                        quiz.score()
+                    ),
+                    Some(through) => format!(
+                        "Going through {} costs {}, no better than {} already has ({})",
+                        node,
+                        units.format(through),
+                        dist(quiz.dist[target.index()]),
+                        quiz.score()
+                    ),
+                };
+                return Ok(());
+            }
+
+            let d = quiz.dist[node.index()].unwrap();
+            let cheapest = graph
+                .edges_from(node)
+                .iter()
+                .filter(|e| e.node == target)
+                .map(|e| d + e.cost)
+                .min();
+            let old = quiz.dist[target.index()];
+            quiz.dist[target.index()] = cheapest;
+            quiz.correct += 1;
+            let pending: Vec<NodeId> = pending.into_iter().filter(|&p| p != target).collect();
+            hud.0 = format!(
+                "{}: {} -> {} via {}{}",
+                target,
+                dist(old),
+                dist(cheapest),
+                node,
+                if pending.is_empty() {
+                    "; that was the last one, settle the next node"
+                } else {
+                    ""
+                }
+            );
+            quiz.active = Some(if pending.is_empty() {
+                Phase::Settle
+            } else {
+                Phase::Relax { node, pending }
+            });
+        }
+    }
+
+    if quiz.active == Some(Phase::Settle) && quiz.next_candidates().1.is_empty() {
+        hud.0 = format!(
+            "{}\nEvery reachable node is settled. Done: {}",
+            hud.0,
+            quiz.score()
+        );
+        quiz.active = None;
+    }
+
+    return Ok(());
+}
+
+// tentative distances above the nodes while the drill runs
+fn update_quiz_labels(
+    mut commands: Commands,
+    quiz: Res<Quiz>,
+    units: Res<WeightUnits>,
+    nodes: Query<&Node>,
+    q_labels: Query<Entity, With<QuizLabel>>,
+) {
+    if !quiz.is_changed() && !units.is_changed() {
+        return;
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    if quiz.active.is_none() {
+        return;
+    }
+    for node in &nodes {
+        let Some(d) = quiz.dist.get(node.id.index()) else {
+            continue;
+        };
+        commands.spawn((
+            Text2d::new(d.map_or("inf".to_string(), |d| units.format(d))),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(LABEL),
+            Transform::from_translation(node.position.extend(5.0) + Vec3::Y * (node.r + 10.0)),
+            QuizLabel,
+        ));
+    }
+}
+
+// settled nodes get a green ring, the node being relaxed a gold one
+fn draw_quiz(quiz: Res<Quiz>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let Some(phase) = &quiz.active else {
+        return;
+    };
+    let relaxing = match phase {
+        Phase::Relax { node, .. } => Some(*node),
+        Phase::Settle => None,
+    };
+    for node in &nodes {
+        let at = Isometry2d::from_translation(node.position);
+        if relaxing == Some(node.id) {
+            gizmos.circle_2d(at, node.r + 6.0, GOLD);
+        } else if quiz.settled.get(node.id.index()) == Some(&true) {
+            gizmos.circle_2d(at, node.r + 4.0, LIME);
+        }
+    }
+}
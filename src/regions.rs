@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+
+use crate::graph::NodeId;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{MainCamera, MultiSelection, Node, ResultsHud};
+
+const PADDING: f32 = 20.0;
+const PALETTE: [(f32, f32, f32); 5] = [
+    (0.2, 0.5, 1.0),
+    (1.0, 0.6, 0.2),
+    (0.4, 0.9, 0.4),
+    (0.9, 0.3, 0.7),
+    (0.9, 0.9, 0.3),
+];
+
+#[derive(Component)]
+pub struct Region {
+    pub name: String,
+    color: Color,
+    pub members: Vec<usize>,
+}
+
+#[derive(Component)]
+struct RegionFill;
+
+pub struct RegionsPlugin;
+
+impl Plugin for RegionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (handle_region_keys, layout_regions).chain());
+    }
+}
+
+fn handle_region_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut multi_selection: Query<&mut MultiSelection, With<MainCamera>>,
+    q_regions: Query<&Region>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    // ctrl+R sends traffic
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if keys.just_pressed(KeyCode::KeyR) && !shift && !ctrl && !prompt.is_open() {
+        if multi_selection.single()?.ids.is_empty() {
+            hud.0 = "Shift+click the nodes to group before creating a region".to_string();
+        } else {
+            prompt.open(PromptKind::RegionName, "Region name");
+        }
+    }
+
+    for event in submitted.read() {
+        if event.kind != PromptKind::RegionName {
+            continue;
+        }
+        let mut multi = multi_selection.single_mut()?;
+        spawn_region(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            event.text.clone(),
+            std::mem::take(&mut multi.ids)
+                .into_iter()
+                .map(NodeId::index)
+                .collect(),
+            q_regions.iter().count(),
+        );
+        hud.0 = format!("Created region \"{}\"", event.text);
+    }
+
+    return Ok(());
+}
+
+// `index` picks the palette colour, so regions created in a row get distinct colours
+pub fn spawn_region(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    name: String,
+    members: Vec<usize>,
+    index: usize,
+) {
+    let (r, g, b) = PALETTE[index % PALETTE.len()];
+    let color = Color::srgb(r, g, b);
+
+    commands
+        .spawn((
+            Transform::default(),
+            Visibility::default(),
+            Region {
+                name: name.clone(),
+                color,
+                members,
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
+                MeshMaterial2d(materials.add(color.with_alpha(0.15))),
+                Transform::default(),
+                RegionFill,
+            ));
+            parent.spawn((
+                Text2d::new(name),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(color),
+                Transform::default(),
+            ));
+        });
+}
+
+// regions are re-fitted every frame so they follow their members around
+fn layout_regions(
+    nodes: Query<&Node>,
+    mut q_regions: Query<(&Region, &Children, &mut Transform)>,
+    mut q_parts: Query<(&mut Transform, Option<&mut Text2d>, Has<RegionFill>), Without<Region>>,
+    mut gizmos: Gizmos,
+) {
+    for (region, children, mut transform) in &mut q_regions {
+        let members: Vec<&Node> = nodes
+            .iter()
+            .filter(|n| region.members.contains(&n.id.index()))
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        let (min, max) = members.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(lo, hi), n| {
+                (
+                    lo.min(n.position - n.r - PADDING),
+                    hi.max(n.position + n.r + PADDING),
+                )
+            },
+        );
+        let (center, size) = ((min + max) * 0.5, max - min);
+        transform.translation = center.extend(-50.0);
+
+        for child in children.iter() {
+            let Ok((mut part, label, is_fill)) = q_parts.get_mut(child) else {
+                continue;
+            };
+            if is_fill {
+                part.scale = size.extend(1.0);
+            }
+            if let Some(mut label) = label {
+                part.translation = Vec3::new(0.0, size.y * 0.5 + 10.0, 1.0);
+                if label.0 != region.name {
+                    label.0 = region.name.clone();
+                }
+            }
+        }
+
+        gizmos.rounded_rect_2d(Isometry2d::from_translation(center), size, region.color);
+    }
+}
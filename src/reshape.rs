@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{MainCamera, MultiSelection, Node, ResultsHud};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reshape {
+    MirrorX,
+    MirrorY,
+    Rotate(f32),
+    Scale(f32),
+}
+
+impl Reshape {
+    // `mirror x|y`, `rotate <degrees>` (counterclockwise) or `scale <factor>`
+    fn parse(text: &str) -> Option<Reshape> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let number = || words.get(1)?.parse::<f32>().ok().filter(|v| v.is_finite());
+        return match words.first()?.to_ascii_lowercase().as_str() {
+            "mirror" | "flip" => match words.get(1)?.to_ascii_lowercase().as_str() {
+                "x" | "h" | "horizontal" => Some(Reshape::MirrorX),
+                "y" | "v" | "vertical" => Some(Reshape::MirrorY),
+                _ => None,
+            },
+            "rotate" => number().map(Reshape::Rotate),
+            "scale" => number().filter(|&f| f > 0.0).map(Reshape::Scale),
+            _ => None,
+        };
+    }
+
+    // a point relative to the centroid
+    fn apply(self, p: Vec2) -> Vec2 {
+        return match self {
+            Reshape::MirrorX => Vec2::new(-p.x, p.y),
+            Reshape::MirrorY => Vec2::new(p.x, -p.y),
+            Reshape::Rotate(degrees) => Vec2::from_angle(degrees.to_radians()).rotate(p),
+            Reshape::Scale(factor) => p * factor,
+        };
+    }
+
+    fn describe(self) -> String {
+        return match self {
+            Reshape::MirrorX => "mirrored left to right".to_string(),
+            Reshape::MirrorY => "mirrored top to bottom".to_string(),
+            Reshape::Rotate(degrees) => format!("rotated by {} degrees", degrees),
+            Reshape::Scale(factor) => format!("scaled by x{}", factor),
+        };
+    }
+}
+
+pub struct ReshapePlugin;
+
+impl Plugin for ReshapePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                handle_reshape_keys.run_if(crate::prompt::prompt_closed),
+                apply_reshape,
+            )
+                .chain(),
+        );
+    }
+}
+
+// shift+Z reshapes the shift-click selection, or the whole graph when nothing is selected
+fn handle_reshape_keys(keys: Res<ButtonInput<KeyCode>>, mut prompt: ResMut<Prompt>) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if shift && keys.just_pressed(KeyCode::KeyZ) {
+        prompt.open(
+            PromptKind::Reshape,
+            "Reshape: mirror x|y, rotate <degrees> or scale <factor>",
+        );
+    }
+}
+
+// about the centroid of the nodes it moves; edges follow their nodes
+fn apply_reshape(
+    mut submitted: MessageReader<PromptSubmitted>,
+    multi_selection: Query<&MultiSelection, With<MainCamera>>,
+    mut nodes: Query<&mut Node>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Reshape {
+            continue;
+        }
+        let Some(reshape) = Reshape::parse(&event.text) else {
+            hud.0 = format!("Could not read `{}` as a transform", event.text);
+            continue;
+        };
+        let selected = &multi_selection.single()?.ids;
+        let moved = |node: &Node| selected.is_empty() || selected.contains(&node.id);
+
+        let points: Vec<Vec2> = nodes
+            .iter()
+            .filter(|n| moved(n))
+            .map(|n| n.position)
+            .collect();
+        if points.is_empty() {
+            hud.0 = "No nodes to reshape".to_string();
+            continue;
+        }
+        let center = points.iter().sum::<Vec2>() / points.len() as f32;
+        for mut node in &mut nodes {
+            if moved(&node) {
+                node.position = center + reshape.apply(node.position - center);
+            }
+        }
+        hud.0 = format!(
+            "{}: {}",
+            if selected.is_empty() {
+                format!("Whole graph ({} nodes)", points.len())
+            } else {
+                format!("{} selected nodes", points.len())
+            },
+            reshape.describe()
+        );
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,67 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::Resource;
+
+// SplitMix64: tiny, seedable, and identical on every platform, so seeded runs reproduce exactly.
+// the editor keeps one for every generator and randomized run, and remembers the seed it started
+// from so a session can be replayed
+#[derive(Resource, Debug, Clone)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        return Rng::new(seed);
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        return Rng { seed, state: seed };
+    }
+
+    pub fn seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        return (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+    }
+
+    pub fn range_f32(&mut self, lo: f32, hi: f32) -> f32 {
+        return lo + (hi - lo) * self.next_f32();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_sequences_repeat() {
+        let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+            let x = a.next_f32();
+            assert!((0.0..1.0).contains(&x));
+            assert_eq!(x, b.next_f32());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+        assert_eq!(a.seed(), 42);
+    }
+}
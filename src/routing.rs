@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    EdgeVisual, GoalNode, HighlightedEdges, Layer, MainCamera, Node, PathMode, ResultsHud,
+    StartNode, WeightUnits, WrapperGraph, cursor_world, edge_at, ord, show_shortest_path,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Highway,
+    #[default]
+    Street,
+    Footpath,
+}
+
+impl Category {
+    const ALL: [Category; 3] = [Category::Highway, Category::Street, Category::Footpath];
+
+    fn index(self) -> usize {
+        return Category::ALL.iter().position(|&c| c == self).unwrap();
+    }
+
+    fn next(self) -> Category {
+        return Category::ALL[(self.index() + 1) % Category::ALL.len()];
+    }
+
+    fn name(self) -> &'static str {
+        return match self {
+            Category::Highway => "highway",
+            Category::Street => "street",
+            Category::Footpath => "footpath",
+        };
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    // indexed like Category::ALL
+    pub multipliers: [f32; 3],
+}
+
+// edge costs are base cost x the active profile's multiplier for the edge's category
+#[derive(Resource)]
+pub struct Routing {
+    pub profiles: Vec<Profile>,
+    pub active: usize,
+    pub categories: HashMap<(NodeId, NodeId), Category>,
+    // multipliers currently baked into the graph's costs
+    applied: [f32; 3],
+    // (from, to) -> (base cost, effective cost it produced); stale entries are re-derived
+    bases: HashMap<(NodeId, NodeId), (f32, Cost)>,
+}
+
+impl Default for Routing {
+    fn default() -> Self {
+        let profile = |name: &str, multipliers| Profile {
+            name: name.to_string(),
+            multipliers,
+        };
+        return Routing {
+            profiles: vec![
+                profile("Default", [1.0, 1.0, 1.0]),
+                profile("Car", [0.5, 1.0, 5.0]),
+                profile("Bike", [3.0, 1.0, 1.2]),
+                profile("Walk", [10.0, 1.0, 0.8]),
+            ],
+            active: 0,
+            categories: HashMap::new(),
+            applied: [1.0; 3],
+            bases: HashMap::new(),
+        };
+    }
+}
+
+impl Routing {
+    pub fn category(&self, a: NodeId, b: NodeId) -> Category {
+        return self.categories.get(&ord(a, b)).copied().unwrap_or_default();
+    }
+
+    // for loaded graphs, whose costs already include the active profile's multipliers
+    pub fn restore(
+        profiles: Vec<Profile>,
+        active: usize,
+        categories: HashMap<(NodeId, NodeId), Category>,
+    ) -> Routing {
+        if profiles.is_empty() {
+            return Routing::default();
+        }
+        let active = active.min(profiles.len() - 1);
+        return Routing {
+            applied: profiles[active].multipliers,
+            profiles,
+            active,
+            categories,
+            bases: HashMap::new(),
+        };
+    }
+
+    // re-derives every edge cost from its base cost under the active profile
+    pub fn apply(&mut self, graph: &mut Graph) {
+        let target = self.profiles[self.active].multipliers;
+        for from in graph.ids() {
+            for i in 0..graph.edges_from(from).len() {
+                let (to, was) = (
+                    graph.edges_from(from)[i].node,
+                    graph.edges_from(from)[i].cost,
+                );
+                let c = self.category(from, to).index();
+                let base = match self.bases.get(&(from, to)) {
+                    Some(&(base, effective)) if effective == was => base,
+                    _ => was as f32 / self.applied[c],
+                };
+                // positive weights stay at least 1, negative ones keep their sign
+                let cost = (base * target[c]).round() as Cost;
+                let cost = if base < 0.0 {
+                    cost.min(-1)
+                } else {
+                    cost.max(1)
+                };
+                graph.nodes[from.index()][i].cost = cost;
+                self.bases.insert((from, to), (base, cost));
+            }
+        }
+        self.applied = target;
+    }
+
+    // after nodes were deleted and the rest renumbered in order, so (low, high) keys stay ordered;
+    // categories of the deleted nodes' edges go
+    pub fn remap(&mut self, remap: &[Option<NodeId>]) {
+        let pair = |(a, b): (NodeId, NodeId)| Some((remap[a.index()]?, remap[b.index()]?));
+        self.categories = self
+            .categories
+            .iter()
+            .filter_map(|(&key, &c)| Some((pair(key)?, c)))
+            .collect();
+        self.bases = self
+            .bases
+            .iter()
+            .filter_map(|(&key, &base)| Some((pair(key)?, base)))
+            .collect();
+    }
+
+    fn set_category(&mut self, graph: &mut Graph, a: NodeId, b: NodeId, category: Category) {
+        self.apply(graph); // pins the base costs under the old category
+        self.categories.insert(ord(a, b), category);
+        self.apply(graph);
+    }
+
+    fn describe(&self) -> String {
+        let mut out = "Routing profile (F to switch, Shift+F to edit)".to_string();
+        for (i, profile) in self.profiles.iter().enumerate() {
+            let multipliers: Vec<String> = Category::ALL
+                .iter()
+                .map(|c| format!("{} x{}", c.name(), profile.multipliers[c.index()]))
+                .collect();
+            let marker = if i == self.active { ">" } else { " " };
+            out.push_str(&format!(
+                "\n{} {}: {}",
+                marker,
+                profile.name,
+                multipliers.join(", ")
+            ));
+        }
+        return out;
+    }
+}
+
+#[derive(Component)]
+struct RoutingPanel;
+
+pub struct RoutingPlugin;
+
+impl Plugin for RoutingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Routing>()
+            .add_systems(Startup, spawn_routing_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_routing_keys.run_if(crate::prompt::prompt_closed),
+                    handle_multiplier_prompt,
+                    update_routing_panel,
+                    draw_categories,
+                ),
+            );
+    }
+}
+
+fn spawn_routing_panel(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        UiNode {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        RoutingPanel,
+    ));
+}
+
+// the highlighted path is recomputed so it always reflects the current weights
+fn rerun_path(
+    q_camera: &Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    graph: &Graph,
+    mode: &PathMode,
+    units: &WeightUnits,
+    highlights: &mut HighlightedEdges,
+    hud: &mut ResultsHud,
+) -> Result<()> {
+    let (_, start, goal) = q_camera.single()?;
+    let shown = !highlights.layer(Layer::Path).is_empty();
+    // other results were computed under the old weights
+    highlights.clear_results();
+    if let (true, Some(start), Some(goal)) = (shown, start.id, goal.id) {
+        show_shortest_path(graph, start, goal, mode, units, highlights, hud);
+    }
+    return Ok(());
+}
+
+fn handle_routing_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    mode: Res<PathMode>,
+    units: Res<WeightUnits>,
+    mut prompt: ResMut<Prompt>,
+    mut routing: ResMut<Routing>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // ctrl+F simulates edge failures
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !keys.any_just_pressed([KeyCode::KeyF, KeyCode::KeyY]) || ctrl {
+        return Ok(());
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keys.just_pressed(KeyCode::KeyF) && shift {
+        let name = routing.profiles[routing.active].name.clone();
+        prompt.open(
+            PromptKind::Multipliers,
+            format!("{} multipliers: highway street footpath", name),
+        );
+        return Ok(());
+    }
+
+    let (wg, _, _) = q_camera.single()?;
+    let mut graph = wg.0.write().unwrap();
+
+    if keys.just_pressed(KeyCode::KeyF) {
+        routing.active = (routing.active + 1) % routing.profiles.len();
+        routing.apply(&mut graph);
+        hud.0 = format!("Routing profile: {}", routing.profiles[routing.active].name);
+        rerun_path(&q_camera, &graph, &mode, &units, &mut highlights, &mut hud)?;
+    }
+
+    // shift+Y is the one-way toggle
+    if keys.just_pressed(KeyCode::KeyY) && !shift {
+        let Some((a, b)) = cursor_world(&windows, &cams).and_then(|w| edge_at(&nodes, &edges, w))
+        else {
+            hud.0 = "Hover an edge and press Y to change its category".to_string();
+            return Ok(());
+        };
+        let category = routing.category(a, b).next();
+        routing.set_category(&mut graph, a, b, category);
+        hud.0 = format!("{} - {} is now a {}", a, b, category.name());
+        rerun_path(&q_camera, &graph, &mode, &units, &mut highlights, &mut hud)?;
+    }
+
+    return Ok(());
+}
+
+fn handle_multiplier_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    mode: Res<PathMode>,
+    units: Res<WeightUnits>,
+    mut routing: ResMut<Routing>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Multipliers {
+            continue;
+        }
+        let values: Vec<f32> = event
+            .text
+            .split_whitespace()
+            .filter_map(|w| w.parse::<f32>().ok())
+            .filter(|&m| m > 0.0)
+            .collect();
+        let Ok(multipliers) = <[f32; 3]>::try_from(values) else {
+            hud.0 = "Type three positive multipliers, e.g. `0.5 1 4`".to_string();
+            continue;
+        };
+
+        let (wg, _, _) = q_camera.single()?;
+        let mut graph = wg.0.write().unwrap();
+        let active = routing.active;
+        routing.profiles[active].multipliers = multipliers;
+        routing.apply(&mut graph);
+        hud.0 = format!("Updated the {} profile", routing.profiles[active].name);
+        rerun_path(&q_camera, &graph, &mode, &units, &mut highlights, &mut hud)?;
+    }
+
+    return Ok(());
+}
+
+fn update_routing_panel(routing: Res<Routing>, mut q_text: Query<&mut Text, With<RoutingPanel>>) {
+    if !routing.is_changed() {
+        return;
+    }
+    for mut text in &mut q_text {
+        text.0 = routing.describe();
+    }
+}
+
+// highways are drawn as a double line and footpaths dashed; streets keep the plain edge
+fn draw_categories(
+    routing: Res<Routing>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    mut gizmos: Gizmos,
+) {
+    let color = Color::srgb(0.6, 0.6, 0.6);
+    for ev in &edges {
+        let category = routing.category(ev.a, ev.b);
+        let (Some(a), Some(b)) = (
+            nodes.iter().find(|n| n.id == ev.a),
+            nodes.iter().find(|n| n.id == ev.b),
+        ) else {
+            continue;
+        };
+        let (a, b) = (a.position, b.position);
+        let dir = (b - a).normalize_or_zero();
+        let side = dir.perp() * 4.0;
+
+        match category {
+            Category::Highway => {
+                gizmos.line_2d(a + side, b + side, color);
+                gizmos.line_2d(a - side, b - side, color);
+            }
+            Category::Footpath => {
+                let len = a.distance(b);
+                let mut t = 0.0;
+                while t < len {
+                    let end = (t + 4.0).min(len);
+                    gizmos.line_2d(a + dir * t + side, a + dir * end + side, color);
+                    t += 10.0;
+                }
+            }
+            Category::Street => {}
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::color::palettes::css::FUCHSIA;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::NodeId;
+use crate::{
+    MainCamera, Node, ResultsHud, WeightUnits, WrapperGraph, clicked_node_id, cursor_world,
+};
+
+#[derive(Debug, Clone, Copy)]
+struct RulerPoint {
+    position: Vec2,
+    node: Option<NodeId>,
+}
+
+#[derive(Resource, Default)]
+struct Ruler {
+    a: Option<RulerPoint>,
+    b: Option<RulerPoint>,
+}
+
+pub struct RulerPlugin;
+
+impl Plugin for RulerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ruler>()
+            .add_systems(Update, (handle_ruler_click, draw_ruler));
+    }
+}
+
+fn handle_ruler_click(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut ruler: ResMut<Ruler>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !buttons.just_pressed(MouseButton::Left) {
+        return Ok(());
+    }
+    let Some(world) = cursor_world(&windows, &cams) else {
+        return Ok(());
+    };
+
+    // points that land on a node snap to its centre
+    let node = clicked_node_id(&nodes, world);
+    let position = node
+        .and_then(|id| nodes.iter().find(|n| n.id == id))
+        .map_or(world, |n| n.position);
+    let point = RulerPoint { position, node };
+
+    let (Some(a), None) = (ruler.a, ruler.b) else {
+        ruler.a = Some(point);
+        ruler.b = None;
+        hud.0 = "Ruler: Ctrl+click a second point".to_string();
+        return Ok(());
+    };
+    ruler.b = Some(point);
+
+    let straight = a.position.distance(point.position);
+    hud.0 = format!("Straight line: {}", units.format_distance(straight));
+
+    if let (Some(from), Some(to)) = (a.node, point.node) {
+        let wg = wrapper_graph.single()?;
+        let graph = wg.0.read().unwrap();
+        let path = match graph.shortest_path(from, to) {
+            Some((cost, _)) if straight > 0.0 => format!(
+                "{} (x{:.2} the straight line)",
+                units.format(cost),
+                cost as f32 / straight
+            ),
+            Some((cost, _)) => units.format(cost),
+            None => "unreachable".to_string(),
+        };
+        hud.0
+            .push_str(&format!("\nShortest path {} -> {}: {}", from, to, path));
+    }
+
+    return Ok(());
+}
+
+fn draw_ruler(ruler: Res<Ruler>, mut gizmos: Gizmos) {
+    let color = Color::from(FUCHSIA);
+    for point in [ruler.a, ruler.b].into_iter().flatten() {
+        gizmos.circle_2d(Isometry2d::from_translation(point.position), 4.0, color);
+    }
+    if let (Some(a), Some(b)) = (ruler.a, ruler.b) {
+        gizmos.line_2d(a.position, b.position, color);
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::color::palettes::css::AQUA;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::Graph;
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::rng::Rng;
+use crate::{GraphSpawner, MainCamera, Node, ResultsHud, WrapperGraph, cursor_world};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    Uniform,
+    PoissonDisk,
+}
+
+#[derive(Resource)]
+struct ScatterSettings {
+    count: usize,
+    k: usize,
+    placement: Placement,
+}
+
+impl Default for ScatterSettings {
+    fn default() -> Self {
+        return ScatterSettings {
+            count: 10,
+            k: 2,
+            placement: Placement::PoissonDisk,
+        };
+    }
+}
+
+#[derive(Resource, Default)]
+struct ScatterDrag {
+    start: Option<Vec2>,
+}
+
+pub struct ScatterPlugin;
+
+impl Plugin for ScatterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScatterSettings>()
+            .init_resource::<ScatterDrag>()
+            .add_systems(Update, (handle_scatter_settings, handle_scatter_drag));
+    }
+}
+
+fn uniform(rng: &mut Rng, min: Vec2, max: Vec2, count: usize) -> Vec<Vec2> {
+    return (0..count)
+        .map(|_| Vec2::new(rng.range_f32(min.x, max.x), rng.range_f32(min.y, max.y)))
+        .collect();
+}
+
+// dart throwing: keeps points at least `spacing` apart, giving up after a fixed number of misses
+pub fn poisson_disk(rng: &mut Rng, min: Vec2, max: Vec2, count: usize) -> Vec<Vec2> {
+    let area = (max - min).x * (max - min).y;
+    let spacing = (area / count.max(1) as f32).sqrt() * 0.7;
+    let mut points: Vec<Vec2> = Vec::with_capacity(count);
+    let mut attempts = 0;
+    while points.len() < count && attempts < count * 30 {
+        attempts += 1;
+        let p = Vec2::new(rng.range_f32(min.x, max.x), rng.range_f32(min.y, max.y));
+        if points.iter().all(|q| q.distance(p) >= spacing) {
+            points.push(p);
+        }
+    }
+    return points;
+}
+
+// connects every placed node to its k nearest placed neighbours, returning the edges added
+pub fn connect_nearest(
+    spawner: &mut GraphSpawner,
+    graph: &mut Graph,
+    placed: &[Node],
+    k: usize,
+) -> usize {
+    let mut edges = 0;
+    for a in placed {
+        let mut nearest: Vec<_> = placed.iter().filter(|b| b.id != a.id).collect();
+        nearest.sort_by(|b, c| {
+            a.position
+                .distance(b.position)
+                .total_cmp(&a.position.distance(c.position))
+        });
+        for b in nearest.into_iter().take(k) {
+            if graph.nodes[a.id.index()].iter().any(|e| e.node == b.id) {
+                continue;
+            }
+            if spawner.connect(graph, a, b) {
+                edges += 1;
+            }
+        }
+    }
+    return edges;
+}
+
+fn handle_scatter_settings(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut settings: ResMut<ScatterSettings>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    if keys.just_pressed(KeyCode::KeyX) && !prompt.is_open() {
+        prompt.open(
+            PromptKind::Scatter,
+            "Scatter: count [k nearest] [uniform|poisson]",
+        );
+    }
+
+    for event in submitted.read() {
+        if event.kind != PromptKind::Scatter {
+            continue;
+        }
+        let mut numbers = Vec::new();
+        for word in event.text.split_whitespace() {
+            match word {
+                "uniform" => settings.placement = Placement::Uniform,
+                "poisson" => settings.placement = Placement::PoissonDisk,
+                _ => numbers.extend(word.parse::<usize>().ok()),
+            }
+        }
+        if let Some(&count) = numbers.first() {
+            settings.count = count.max(1);
+        }
+        if let Some(&k) = numbers.get(1) {
+            settings.k = k;
+        }
+        hud.0 = format!(
+            "Scatter: {} nodes, {:?}, connect to {} nearest (Alt+drag to place)",
+            settings.count, settings.placement, settings.k
+        );
+    }
+}
+
+fn handle_scatter_drag(
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    settings: Res<ScatterSettings>,
+    mut drag: ResMut<ScatterDrag>,
+    mut rng: ResMut<Rng>,
+    mut spawner: GraphSpawner,
+    mut gizmos: Gizmos,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some(world) = cursor_world(&windows, &cams) else {
+        return Ok(());
+    };
+    let alt = keys.pressed(KeyCode::AltLeft) || keys.pressed(KeyCode::AltRight);
+    if alt && buttons.just_pressed(MouseButton::Left) {
+        drag.start = Some(world);
+    }
+    let Some(start) = drag.start else {
+        return Ok(());
+    };
+    let (min, max) = (start.min(world), start.max(world));
+
+    if buttons.pressed(MouseButton::Left) {
+        gizmos.rect_2d(
+            Isometry2d::from_translation((min + max) * 0.5),
+            max - min,
+            Color::from(AQUA),
+        );
+        return Ok(());
+    }
+    drag.start = None;
+    if (max - min).min_element() < 1.0 {
+        return Ok(());
+    }
+
+    let points = match settings.placement {
+        Placement::Uniform => uniform(&mut rng, min, max, settings.count),
+        Placement::PoissonDisk => poisson_disk(&mut rng, min, max, settings.count),
+    };
+
+    let wg = wrapper_graph.single()?;
+    let mut graph = wg.0.write().unwrap();
+    let placed: Vec<_> = points
+        .iter()
+        .map(|&p| spawner.spawn_node(&mut graph, p))
+        .collect();
+
+    let edges = connect_nearest(&mut spawner, &mut graph, &placed, settings.k);
+    hud.0 = format!("Scattered {} nodes and {} edges", placed.len(), edges);
+
+    return Ok(());
+}
@@ -0,0 +1,128 @@
+use bevy::color::Srgba;
+use bevy::color::palettes::css::{DEEP_SKY_BLUE, HOT_PINK, LIME, ORANGE_RED, TURQUOISE, VIOLET};
+use bevy::prelude::*;
+
+use crate::graph::{Graph, NodeId};
+use crate::{MainCamera, ResultsHud, SelectedNode, WrapperGraph};
+
+const PALETTE: [Srgba; 6] = [DEEP_SKY_BLUE, ORANGE_RED, LIME, VIOLET, TURQUOISE, HOT_PINK];
+
+// extra start nodes for the multi-source view; each node is tinted by the source nearest to it
+#[derive(Resource, Default)]
+pub struct Sources {
+    ids: Vec<NodeId>,
+    owners: Vec<Option<NodeId>>,
+}
+
+impl Sources {
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        let owner = (*self.owners.get(id.index())?)?;
+        let index = self.ids.iter().position(|&s| s == owner)?;
+        let color = Color::from(PALETTE[index % PALETTE.len()]);
+        if owner == id {
+            return Some(color);
+        }
+        return Some(color.mix(&Color::WHITE, 0.55));
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.owners.clear();
+    }
+
+    fn owners_in(&self, graph: &Graph) -> Vec<Option<NodeId>> {
+        let starts: Vec<NodeId> = self
+            .ids
+            .iter()
+            .copied()
+            .filter(|id| id.index() < graph.nodes.len())
+            .collect();
+        return graph
+            .multi_source_shortest_paths(&starts)
+            .into_iter()
+            .map(|best| best.map(|(_, owner)| owner))
+            .collect();
+    }
+}
+
+pub struct SourcesPlugin;
+
+impl Plugin for SourcesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sources>().add_systems(
+            Update,
+            (
+                toggle_source.run_if(crate::prompt::prompt_closed),
+                refresh_owners,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn toggle_source(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &SelectedNode), With<MainCamera>>,
+    mut sources: ResMut<Sources>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    // shift+M is the spanning tree, ctrl+M merges nodes
+    if !keys.just_pressed(KeyCode::KeyM)
+        || keys.any_pressed([
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+        ])
+    {
+        return Ok(());
+    }
+    let (wg, selected) = q_camera.single()?;
+    let Some(id) = selected.id else {
+        hud.0 = "Select a node, then press M to toggle it as a source".to_string();
+        return Ok(());
+    };
+    match sources.ids.iter().position(|&s| s == id) {
+        Some(pos) => {
+            sources.ids.remove(pos);
+        }
+        None => sources.ids.push(id),
+    }
+
+    let graph = wg.0.read().unwrap();
+    sources.owners = sources.owners_in(&graph);
+    if sources.ids.is_empty() {
+        hud.0 = "No sources".to_string();
+        return Ok(());
+    }
+    let summary: Vec<String> = sources
+        .ids
+        .iter()
+        .map(|&s| {
+            let owned = sources.owners.iter().filter(|&&o| o == Some(s)).count();
+            format!("{} owns {}", s, owned)
+        })
+        .collect();
+    let unreached = sources.owners.iter().filter(|o| o.is_none()).count();
+    hud.0 = format!(
+        "Sources: {} ({} unreachable)",
+        summary.join(", "),
+        unreached
+    );
+
+    return Ok(());
+}
+
+// keeps ownership current while the graph is edited, without touching change detection when nothing moved
+fn refresh_owners(q_graph: Query<&WrapperGraph, With<MainCamera>>, mut sources: ResMut<Sources>) {
+    if sources.ids.is_empty() {
+        return;
+    }
+    let Ok(wg) = q_graph.single() else {
+        return;
+    };
+    let owners = sources.owners_in(&wg.0.read().unwrap());
+    if sources.owners != owners {
+        sources.owners = owners;
+    }
+}
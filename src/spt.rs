@@ -0,0 +1,184 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId, ShortestPaths};
+use crate::{
+    HighlightedEdges, Layer, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph,
+    ord,
+};
+
+const LABEL: Color = Color::srgb(0.6, 0.75, 1.0);
+
+// the shortest path tree from the start while shown, redone whenever the start moves and patched
+// in place whenever weights or edges change
+#[derive(Resource, Default)]
+struct TreeView {
+    shown: bool,
+    start: Option<NodeId>,
+    costs: Vec<(NodeId, NodeId, Cost)>,
+    tree: Option<ShortestPaths>,
+}
+
+#[derive(Component)]
+struct TreeLabel;
+
+pub struct SptPlugin;
+
+impl Plugin for SptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TreeView>().add_systems(
+            Update,
+            (
+                handle_tree_keys.run_if(crate::prompt::prompt_closed),
+                refresh_tree,
+                update_tree_labels,
+            )
+                .chain(),
+        );
+    }
+}
+
+// F8 shows or hides the tree of shortest paths from the start to every node
+fn handle_tree_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut view: ResMut<TreeView>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    if !keys.just_pressed(KeyCode::F8) {
+        return;
+    }
+    let shown = !view.shown;
+    *view = TreeView { shown, ..default() };
+    if !shown {
+        highlights.layer_mut(Layer::Tree).clear();
+        hud.0 = "Shortest path tree hidden".to_string();
+    }
+}
+
+// edits clear the result layers, so a tree missing from its layer is put back too
+fn refresh_tree(
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut view: ResMut<TreeView>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !view.shown {
+        return Ok(());
+    }
+    let (wg, start) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let costs = graph.arc_costs();
+    let edges = view.tree.as_ref().map_or(Vec::new(), |t| t.tree_edges());
+    let kept = highlights.layer(Layer::Tree).len() == edges.len();
+    if view.start == start.id && view.costs == costs && kept {
+        return Ok(());
+    }
+    // with the same start, only the edges that differ are worked through again
+    let changed: Vec<(NodeId, NodeId)> = if view.start == start.id {
+        let (before, after): (BTreeSet<_>, BTreeSet<_>) = (
+            view.costs.iter().copied().collect(),
+            costs.iter().copied().collect(),
+        );
+        before
+            .symmetric_difference(&after)
+            .map(|&(from, to, _)| (from, to))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    } else {
+        Vec::new()
+    };
+    view.start = start.id;
+    view.costs = costs;
+    highlights.layer_mut(Layer::Tree).clear();
+
+    let Some(start) = start.id else {
+        view.tree = None;
+        hud.0 = "Set a start node (S) to grow its shortest path tree".to_string();
+        return Ok(());
+    };
+    let mut patched = None;
+    if let Some(tree) = view.tree.as_mut()
+        && !changed.is_empty()
+    {
+        patched = graph
+            .repair_shortest_path_tree(start, tree, &changed)
+            .map(|settled| {
+                format!(
+                    "\nPatched after {} changed edges: {} nodes settled again, a fresh tree settles {}",
+                    changed.len(),
+                    settled,
+                    tree.dist.iter().flatten().count()
+                )
+            });
+    }
+    let grown = match patched {
+        Some(_) => Ok(view.tree.take().unwrap()),
+        None => graph.shortest_path_tree(start),
+    };
+    match grown {
+        Ok(tree) => {
+            let edges = tree.tree_edges();
+            highlights
+                .layer_mut(Layer::Tree)
+                .extend(edges.iter().map(|&(a, b)| ord(a, b)));
+            let farthest = tree.dist.iter().flatten().max().copied().unwrap_or(0);
+            hud.0 = format!(
+                "Shortest path tree from {}: reaches {} of {} nodes, farthest {} (F8 hides){}",
+                start,
+                edges.len() + 1,
+                graph.nodes.len(),
+                units.format(farthest),
+                patched.unwrap_or_default()
+            );
+            view.tree = Some(tree);
+        }
+        Err(cycle) => {
+            view.tree = None;
+            hud.0 = format!(
+                "No shortest path tree from {}: negative cycle {}",
+                start,
+                Graph::fmt_path(&cycle)
+            );
+        }
+    }
+
+    return Ok(());
+}
+
+// each reached node's distance from the start, above it
+fn update_tree_labels(
+    mut commands: Commands,
+    view: Res<TreeView>,
+    units: Res<WeightUnits>,
+    nodes: Query<&Node>,
+    q_labels: Query<Entity, With<TreeLabel>>,
+) {
+    if !view.is_changed() && !units.is_changed() {
+        return;
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    let Some(tree) = &view.tree else {
+        return;
+    };
+    for node in &nodes {
+        let Some(Some(d)) = tree.dist.get(node.id.index()) else {
+            continue;
+        };
+        commands.spawn((
+            Text2d::new(units.format(*d)),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(LABEL),
+            Transform::from_translation(node.position.extend(5.0) + Vec3::Y * (node.r + 10.0)),
+            TreeLabel,
+        ));
+    }
+}
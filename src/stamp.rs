@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::{MainCamera, WrapperGraph};
+
+#[derive(Component)]
+struct StampText;
+
+pub struct StampPlugin;
+
+impl Plugin for StampPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_stamp)
+            .add_systems(Update, update_stamp);
+    }
+}
+
+// centred just above the stats readout
+fn spawn_stamp(mut commands: Commands) {
+    commands
+        .spawn(UiNode {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(30.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_child((
+            Text::new(""),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.55, 0.55, 0.55)),
+            StampText,
+        ));
+}
+
+// two people with the same stamp are looking at the same nodes, edges, weights and turn bans
+fn update_stamp(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut q_text: Query<&mut Text, With<StampText>>,
+) -> Result<()> {
+    let graph = q_graph.single()?.0.read().unwrap();
+    let stamp = if graph.nodes.is_empty() {
+        String::new()
+    } else {
+        format!("graph {}", graph.fmt_hash())
+    };
+    let mut text = q_text.single_mut()?;
+    if text.0 != stamp {
+        text.0 = stamp;
+    }
+
+    return Ok(());
+}
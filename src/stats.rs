@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::{
+    HighlightedEdges, Layer, MainCamera, ResultsHud, SelectedNode, WeightUnits, WrapperGraph,
+};
+
+// eccentricities and the diameter while the readout is shown, recomputed only when a weight or
+// edge changes
+#[derive(Resource, Default)]
+struct StatsView {
+    shown: bool,
+    costs: Vec<(NodeId, NodeId, Cost)>,
+    eccentricities: Vec<Option<(Cost, NodeId)>>,
+    diameter: Option<(Cost, NodeId, NodeId)>,
+}
+
+#[derive(Component)]
+struct StatsText;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StatsView>()
+            .add_systems(Startup, spawn_stats_readout)
+            .add_systems(
+                Update,
+                (
+                    handle_stats_keys.run_if(crate::prompt::prompt_closed),
+                    refresh_stats,
+                    update_stats_readout,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// centred along the bottom edge
+fn spawn_stats_readout(mut commands: Commands) {
+    commands
+        .spawn(UiNode {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_child((
+            Text::new(""),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            StatsText,
+        ));
+}
+
+// F5 shows or hides the readout; shift+F5 highlights the path behind the selected node's
+// eccentricity, or behind the diameter when nothing is selected
+fn handle_stats_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &SelectedNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut view: ResMut<StatsView>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::F5) {
+        return Ok(());
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift {
+        view.shown = !view.shown;
+        if !view.shown {
+            view.costs.clear();
+        }
+        return Ok(());
+    }
+
+    let (wg, selected) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let ends = match selected.id {
+        Some(from) => graph
+            .eccentricities()
+            .get(from.index())
+            .copied()
+            .flatten()
+            .map(|(_, to)| (from, to)),
+        None => graph.diameter().map(|(_, from, to)| (from, to)),
+    };
+    let Some((from, to)) = ends.filter(|(from, to)| from != to) else {
+        hud.0 = "No farthest node to show a path to".to_string();
+        return Ok(());
+    };
+    let Some((cost, path)) = graph.shortest_path(from, to) else {
+        return Ok(());
+    };
+    highlights.set_path(Layer::Path, &path);
+    hud.0 = format!(
+        "{} is farthest from {}: {} via {}",
+        to,
+        from,
+        units.format(cost),
+        Graph::fmt_path(&path)
+    );
+
+    return Ok(());
+}
+
+fn refresh_stats(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut view: ResMut<StatsView>,
+) -> Result<()> {
+    if !view.shown {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    let costs = graph.arc_costs();
+    if view.costs == costs && view.eccentricities.len() == graph.nodes.len() {
+        return Ok(());
+    }
+    view.eccentricities = graph.eccentricities();
+    view.diameter = graph.diameter();
+    view.costs = costs;
+
+    return Ok(());
+}
+
+fn update_stats_readout(
+    view: Res<StatsView>,
+    units: Res<WeightUnits>,
+    q_selected: Query<Ref<SelectedNode>, With<MainCamera>>,
+    mut q_text: Query<&mut Text, With<StatsText>>,
+) -> Result<()> {
+    let selected = q_selected.single()?;
+    if !view.is_changed() && !units.is_changed() && !selected.is_changed() {
+        return Ok(());
+    }
+    let mut text = q_text.single_mut()?;
+    if !view.shown {
+        text.0.clear();
+        return Ok(());
+    }
+
+    let radius = view
+        .eccentricities
+        .iter()
+        .map(|e| e.map(|(d, _)| d))
+        .min()
+        .flatten();
+    let mut lines = vec![match (view.diameter, radius) {
+        (Some((d, from, to)), Some(r)) => format!(
+            "Diameter {} ({} to {}), radius {}",
+            units.format(d),
+            from,
+            to,
+            units.format(r)
+        ),
+        _ if view.eccentricities.is_empty() => "No nodes yet".to_string(),
+        _ => "Diameter infinite: some node can't reach another".to_string(),
+    }];
+    if let Some(id) = selected.id
+        && let Some(eccentricity) = view.eccentricities.get(id.index())
+    {
+        lines.push(match eccentricity {
+            Some((d, to)) => format!("{}: eccentricity {}, farthest {}", id, units.format(*d), to),
+            None => format!("{}: eccentricity infinite, some node is out of reach", id),
+        });
+    }
+    lines.push("(F5 hides, Shift+F5 shows the farthest path)".to_string());
+    text.0 = lines.join("\n");
+
+    return Ok(());
+}
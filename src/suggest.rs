@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{Cost, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    EDGE_HIT_DISTANCE, GraphSpawner, MainCamera, Node, ResultsHud, WrapperGraph, cursor_world,
+};
+
+const PREVIEW: Color = Color::srgb(0.4, 0.8, 0.7);
+const HOVERED: Color = Color::srgb(0.6, 1.0, 0.85);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Goal {
+    Connected,
+    MinDegree(usize),
+}
+
+impl Goal {
+    fn parse(text: &str) -> Option<Goal> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        return match words.as_slice() {
+            ["connect"] => Some(Goal::Connected),
+            ["degree", k] => Some(Goal::MinDegree(k.parse().ok()?)),
+            _ => None,
+        };
+    }
+}
+
+// edges that would get the graph to the goal, previewed dashed until they are added one by one
+#[derive(Resource, Default)]
+struct Suggestions {
+    goal: Option<Goal>,
+    pairs: Vec<(NodeId, NodeId)>,
+    // the pair under the cursor, which Insert adds
+    hovered: Option<usize>,
+    // the weights and node positions the pairs were picked from
+    basis: Option<(Vec<(NodeId, NodeId, Cost)>, Vec<Vec2>)>,
+}
+
+pub struct SuggestPlugin;
+
+impl Plugin for SuggestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Suggestions>().add_systems(
+            Update,
+            (
+                hover_suggestion,
+                handle_suggest_keys.run_if(crate::prompt::prompt_closed),
+                apply_suggest_prompt,
+                refresh_suggestions,
+                draw_suggestions,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn hover_suggestion(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut suggestions: ResMut<Suggestions>,
+) {
+    let Some((_, positions)) = &suggestions.basis else {
+        return;
+    };
+    let hovered = cursor_world(&windows, &cams).and_then(|world| {
+        suggestions.pairs.iter().position(|&(a, b)| {
+            let (a, b) = (positions[a.index()], positions[b.index()]);
+            let ab = b - a;
+            let t = ((world - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0);
+            world.distance(a + ab * t) < EDGE_HIT_DISTANCE
+        })
+    });
+    if suggestions.hovered != hovered {
+        suggestions.hovered = hovered;
+    }
+}
+
+// Insert over a suggestion adds it; anywhere else it asks what to suggest
+fn handle_suggest_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    suggestions: Res<Suggestions>,
+    mut spawner: GraphSpawner,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::Insert) {
+        return Ok(());
+    }
+    let Some(i) = suggestions.hovered else {
+        prompt.open(
+            PromptKind::Suggest,
+            "Suggest edges that `connect` the graph, or give every node a `degree 3` (empty clears)",
+        );
+        return Ok(());
+    };
+    let (a, b) = suggestions.pairs[i];
+    let (Some(a), Some(b)) = (
+        nodes.iter().find(|n| n.id == a),
+        nodes.iter().find(|n| n.id == b),
+    ) else {
+        return Ok(());
+    };
+    let mut graph = q_graph.single()?.0.write().unwrap();
+    if !spawner.connect(&mut graph, a, b) {
+        hud.0 = "Those nodes are too close to connect".to_string();
+    }
+
+    return Ok(());
+}
+
+fn apply_suggest_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut suggestions: ResMut<Suggestions>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Suggest {
+            continue;
+        }
+        if event.text.is_empty() {
+            *suggestions = Suggestions::default();
+            hud.0 = "Edge suggestions cleared".to_string();
+            continue;
+        }
+        let Some(goal) = Goal::parse(&event.text) else {
+            hud.0 = "Type `connect` or `degree 3`".to_string();
+            continue;
+        };
+        *suggestions = Suggestions {
+            goal: Some(goal),
+            ..default()
+        };
+    }
+}
+
+// nearest pairs first, redone after every accepted edge, edit or node move
+fn refresh_suggestions(
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    mut suggestions: ResMut<Suggestions>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some(goal) = suggestions.goal else {
+        return Ok(());
+    };
+    let graph = q_graph.single()?.0.read().unwrap();
+    let mut positions = vec![Vec2::ZERO; graph.nodes.len()];
+    for node in &nodes {
+        if let Some(p) = positions.get_mut(node.id.index()) {
+            *p = node.position;
+        }
+    }
+    let basis = Some((graph.arc_costs(), positions));
+    if suggestions.basis == basis {
+        return Ok(());
+    }
+    let (_, positions) = basis.as_ref().unwrap();
+    let distance = |a: NodeId, b: NodeId| positions[a.index()].distance(positions[b.index()]);
+    let pairs = match goal {
+        Goal::Connected => graph.connecting_edges(distance),
+        Goal::MinDegree(k) => graph.degree_edges(k, distance),
+    };
+
+    hud.0 = match (goal, pairs.len()) {
+        (Goal::Connected, 0) => "The graph is connected".to_string(),
+        (Goal::MinDegree(k), 0) => {
+            format!(
+                "Every node has at least {} neighbours, or can't get more",
+                k
+            )
+        }
+        (Goal::Connected, n) => format!(
+            "{} suggested edges join its {} components",
+            n,
+            graph
+                .connected_components()
+                .iter()
+                .max()
+                .map_or(0, |c| c + 1)
+        ),
+        (Goal::MinDegree(k), n) => {
+            format!("{} suggested edges give every node {} neighbours", n, k)
+        }
+    };
+    if !pairs.is_empty() {
+        hud.0 += "\nHover a dashed edge and press Insert to add it";
+    }
+    *suggestions = Suggestions {
+        goal: (!pairs.is_empty()).then_some(goal),
+        pairs,
+        hovered: None,
+        basis,
+    };
+
+    return Ok(());
+}
+
+fn draw_suggestions(suggestions: Res<Suggestions>, mut gizmos: Gizmos) {
+    let Some((_, positions)) = &suggestions.basis else {
+        return;
+    };
+    for (i, &(a, b)) in suggestions.pairs.iter().enumerate() {
+        let (a, b) = (positions[a.index()], positions[b.index()]);
+        if suggestions.hovered == Some(i) {
+            gizmos.line_2d(a, b, HOVERED);
+            continue;
+        }
+        let len = a.distance(b);
+        let dir = (b - a).normalize_or_zero();
+        let mut t = 0.0;
+        while t < len {
+            let end = (t + 6.0).min(len);
+            gizmos.line_2d(a + dir * t, a + dir * end, PREVIEW);
+            t += 12.0;
+        }
+    }
+}
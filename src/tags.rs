@@ -0,0 +1,361 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{EdgeVisual, MainCamera, Node, ResultsHud, SelectedNode, cursor_world, edge_at, ord};
+
+const DIMMED: f32 = 0.15;
+
+// free-form tags on nodes and on edges (keyed by ord pairs), and the tag everything else is
+// dimmed against; with `restrict` the path searches only see what the filter shows
+#[derive(Resource, Default)]
+pub struct Tags {
+    pub nodes: HashMap<NodeId, BTreeSet<String>>,
+    pub edges: HashMap<(NodeId, NodeId), BTreeSet<String>>,
+    pub filter: Option<String>,
+    pub restrict: bool,
+}
+
+impl Tags {
+    // follows renumbered nodes; tags on removed nodes, or on edges touching them, go
+    pub fn remap(&mut self, remap: &[Option<NodeId>]) {
+        let mut nodes: HashMap<NodeId, BTreeSet<String>> = HashMap::new();
+        for (old, tags) in self.nodes.drain() {
+            if let Some(new) = remap[old.index()] {
+                nodes.entry(new).or_default().extend(tags);
+            }
+        }
+        self.nodes = nodes;
+        self.edges = self
+            .edges
+            .drain()
+            .filter_map(|((a, b), tags)| Some(((remap[a.index()]?, remap[b.index()]?), tags)))
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        *self = Tags::default();
+    }
+
+    fn has(&self, id: NodeId, tag: &str) -> bool {
+        return self.nodes.get(&id).is_some_and(|t| t.contains(tag));
+    }
+
+    fn edge_has(&self, a: NodeId, b: NodeId, tag: &str) -> bool {
+        return self.edges.get(&ord(a, b)).is_some_and(|t| t.contains(tag));
+    }
+
+    // a node matches when it has the tag or one of its edges does
+    pub fn shows_node(&self, id: NodeId) -> bool {
+        let Some(tag) = &self.filter else {
+            return true;
+        };
+        return self.has(id, tag)
+            || self
+                .edges
+                .iter()
+                .any(|(&(a, b), tags)| (a == id || b == id) && tags.contains(tag));
+    }
+
+    // an edge matches when it has the tag or both its ends do
+    pub fn shows_edge(&self, a: NodeId, b: NodeId) -> bool {
+        let Some(tag) = &self.filter else {
+            return true;
+        };
+        return self.edge_has(a, b, tag) || (self.has(a, tag) && self.has(b, tag));
+    }
+
+    pub fn dim_node(&self, id: NodeId, color: Color) -> Color {
+        if self.shows_node(id) {
+            return color;
+        }
+        return color.with_alpha(DIMMED);
+    }
+
+    pub fn dim_edge(&self, a: NodeId, b: NodeId, color: Color) -> Color {
+        if self.shows_edge(a, b) {
+            return color;
+        }
+        return color.with_alpha(DIMMED);
+    }
+
+    // the graph the path searches run on: the whole of it, or with `restrict` only the edges the
+    // filter shows. node ids stay the same, so results can be drawn as usual
+    pub fn restricted<'a>(&self, graph: &'a Graph) -> Cow<'a, Graph> {
+        if !self.restrict || self.filter.is_none() {
+            return Cow::Borrowed(graph);
+        }
+        let mut restricted = graph.clone();
+        restricted.remove_edges(|from, e| !self.shows_edge(from, e.node));
+        return Cow::Owned(restricted);
+    }
+
+    // every tag in use and how many nodes and edges carry it
+    fn counts(&self) -> BTreeMap<&str, (usize, usize)> {
+        let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for tag in self.nodes.values().flatten() {
+            counts.entry(tag.as_str()).or_default().0 += 1;
+        }
+        for tag in self.edges.values().flatten() {
+            counts.entry(tag.as_str()).or_default().1 += 1;
+        }
+        return counts;
+    }
+
+    fn describe(&self) -> String {
+        let counts = self.counts();
+        if let Some(tag) = &self.filter {
+            let (nodes, edges) = counts.get(tag.as_str()).copied().unwrap_or_default();
+            return format!(
+                "Tag filter `{}`: {} nodes and {} edges tagged{} (Ctrl+B changes it)",
+                tag,
+                nodes,
+                edges,
+                if self.restrict {
+                    "; P, A, Shift+P and races run on the filtered part only"
+                } else {
+                    ""
+                }
+            );
+        }
+        if counts.is_empty() {
+            return String::new();
+        }
+        let tags: Vec<String> = counts
+            .iter()
+            .map(|(tag, (nodes, edges))| format!("{} ({})", tag, nodes + edges))
+            .collect();
+        return format!("Tags: {} (Ctrl+B filters)", tags.join(", "));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    Node(NodeId),
+    Edge(NodeId, NodeId),
+}
+
+impl Target {
+    fn label(self) -> String {
+        return match self {
+            Target::Node(id) => id.to_string(),
+            Target::Edge(a, b) => format!("{} - {}", a, b),
+        };
+    }
+}
+
+// node or edge the open tag prompt will write to
+#[derive(Resource, Default)]
+struct TagDraft(Option<Target>);
+
+#[derive(Component)]
+struct FilterBar;
+
+pub struct TagsPlugin;
+
+impl Plugin for TagsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tags>()
+            .init_resource::<TagDraft>()
+            .add_systems(Startup, spawn_filter_bar)
+            .add_systems(
+                Update,
+                (
+                    handle_tag_keys.run_if(crate::prompt::prompt_closed),
+                    handle_tag_prompts,
+                    update_filter_bar,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// centred above the graph stamp, clear of the parameter panel at the top
+fn spawn_filter_bar(mut commands: Commands) {
+    commands
+        .spawn(UiNode {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(50.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_child((
+            Text::new(""),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            UiNode {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                ..default()
+            },
+            Visibility::Hidden,
+            FilterBar,
+        ));
+}
+
+// ctrl+N tags the hovered edge, or else the selected node; ctrl+B sets the filter
+fn handle_tag_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    q_selected: Query<&SelectedNode, With<MainCamera>>,
+    tags: Res<Tags>,
+    mut draft: ResMut<TagDraft>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl {
+        return Ok(());
+    }
+    if keys.just_pressed(KeyCode::KeyB) {
+        let now = match &tags.filter {
+            Some(tag) if tags.restrict => format!(", now `{} only`", tag),
+            Some(tag) => format!(", now `{}`", tag),
+            None => String::new(),
+        };
+        prompt.open(
+            PromptKind::TagFilter,
+            format!(
+                "Dim all but a tag: `<tag>`, or `<tag> only` to search just those too{}; empty clears",
+                now
+            ),
+        );
+        return Ok(());
+    }
+    if !keys.just_pressed(KeyCode::KeyN) {
+        return Ok(());
+    }
+
+    let hovered = cursor_world(&windows, &cams).and_then(|w| edge_at(&nodes, &edges, w));
+    let target = match (hovered, q_selected.single()?.id) {
+        (Some((a, b)), _) => Target::Edge(a, b),
+        (None, Some(id)) => Target::Node(id),
+        (None, None) => {
+            hud.0 = "Hover an edge or select a node, then press Ctrl+N to tag it".to_string();
+            return Ok(());
+        }
+    };
+    let current = match target {
+        Target::Node(id) => tags.nodes.get(&id),
+        Target::Edge(a, b) => tags.edges.get(&ord(a, b)),
+    };
+    let current: Vec<&str> = current.into_iter().flatten().map(|t| t.as_str()).collect();
+    draft.0 = Some(target);
+    prompt.open(
+        PromptKind::Tags,
+        if current.is_empty() {
+            format!("Tags for {}, separated by spaces", target.label())
+        } else {
+            format!(
+                "Tags for {} (now `{}`, empty removes them)",
+                target.label(),
+                current.join(" ")
+            )
+        },
+    );
+
+    return Ok(());
+}
+
+fn replace<K: Eq + Hash>(map: &mut HashMap<K, BTreeSet<String>>, key: K, tags: BTreeSet<String>) {
+    if tags.is_empty() {
+        map.remove(&key);
+    } else {
+        map.insert(key, tags);
+    }
+}
+
+fn handle_tag_prompts(
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut draft: ResMut<TagDraft>,
+    mut tags: ResMut<Tags>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for event in submitted.read() {
+        match event.kind {
+            PromptKind::Tags => {
+                let Some(target) = draft.0.take() else {
+                    continue;
+                };
+                let words: BTreeSet<String> = event
+                    .text
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|w| !w.is_empty())
+                    .map(|w| w.to_string())
+                    .collect();
+                hud.0 = if words.is_empty() {
+                    format!("Removed the tags on {}", target.label())
+                } else {
+                    format!(
+                        "Tagged {}: {}",
+                        target.label(),
+                        words.iter().cloned().collect::<Vec<_>>().join(", ")
+                    )
+                };
+                match target {
+                    Target::Node(id) => replace(&mut tags.nodes, id, words),
+                    Target::Edge(a, b) => replace(&mut tags.edges, ord(a, b), words),
+                }
+            }
+            PromptKind::TagFilter => {
+                let words: Vec<&str> = event.text.split_whitespace().collect();
+                let (filter, restrict) = match words.as_slice() {
+                    [] => (None, false),
+                    [tag] => (Some(tag.to_string()), false),
+                    [tag, only] if only.eq_ignore_ascii_case("only") => {
+                        (Some(tag.to_string()), true)
+                    }
+                    _ => {
+                        hud.0 = format!(
+                            "Could not read `{}`: give one tag, optionally followed by `only`",
+                            event.text
+                        );
+                        continue;
+                    }
+                };
+                hud.0 = match &filter {
+                    Some(tag) if restrict => format!(
+                        "Showing `{}` only; P, A, Shift+P and races run on it alone",
+                        tag
+                    ),
+                    Some(tag) => format!("Dimmed everything not tagged `{}`", tag),
+                    None => "Tag filter cleared".to_string(),
+                };
+                tags.filter = filter;
+                tags.restrict = restrict;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_filter_bar(
+    tags: Res<Tags>,
+    mut q_bar: Query<(&mut Text, &mut Visibility), With<FilterBar>>,
+) -> Result<()> {
+    if !tags.is_changed() {
+        return Ok(());
+    }
+    let (mut text, mut visibility) = q_bar.single_mut()?;
+    text.0 = tags.describe();
+    visibility.set_if_neq(if text.0.is_empty() {
+        Visibility::Hidden
+    } else {
+        Visibility::Visible
+    });
+
+    return Ok(());
+}
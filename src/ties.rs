@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::{
+    GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, position_lookup,
+};
+
+// enough to step through by hand; past it the count is a lower bound
+const MAX_TIES: usize = 100;
+const COLOR: Color = Color::srgb(0.95, 0.5, 0.75);
+
+// every route that ties for the cheapest between one start and goal, `current` drawn thick
+#[derive(Resource, Default)]
+struct Ties {
+    query: Option<(NodeId, NodeId)>,
+    cost: Cost,
+    paths: Vec<Vec<NodeId>>,
+    current: usize,
+    // the weights the ties were found under
+    costs: Vec<(NodeId, NodeId, Cost)>,
+}
+
+pub struct TiesPlugin;
+
+impl Plugin for TiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ties>().add_systems(
+            Update,
+            (
+                handle_tie_keys.run_if(crate::prompt::prompt_closed),
+                drop_stale_ties,
+                draw_ties,
+            )
+                .chain(),
+        );
+    }
+}
+
+// , finds every cheapest route and steps to the next one; past the last turns the view off
+fn handle_tie_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut ties: ResMut<Ties>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::Comma) {
+        return Ok(());
+    }
+
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    if ties.paths.is_empty() {
+        let (Some(start), Some(goal)) = (start.id, goal.id) else {
+            hud.0 = "Set a start (S) and goal (G) to list the tied routes".to_string();
+            return Ok(());
+        };
+        let Some((cost, paths)) = graph.all_shortest_paths(start, goal, MAX_TIES) else {
+            hud.0 = "No path found!".to_string();
+            return Ok(());
+        };
+        *ties = Ties {
+            query: Some((start, goal)),
+            cost,
+            paths,
+            current: 0,
+            costs: graph.arc_costs(),
+        };
+    } else if ties.current + 1 < ties.paths.len() {
+        ties.current += 1;
+    } else {
+        *ties = Ties::default();
+        hud.0 = "Tied routes off".to_string();
+        return Ok(());
+    }
+
+    let count = if ties.paths.len() == MAX_TIES {
+        format!("at least {}", MAX_TIES)
+    } else {
+        ties.paths.len().to_string()
+    };
+    let mut lines = vec![format!(
+        "Tied route {} of {} at {} (, for the next): {}",
+        ties.current + 1,
+        count,
+        units.format(ties.cost),
+        Graph::fmt_path(&ties.paths[ties.current])
+    )];
+    if ties.paths.len() == 1 {
+        lines.push("No other route costs the same".to_string());
+    }
+    if !graph.forbidden.is_empty() {
+        lines.push("With turn bans only the one shortest path is listed".to_string());
+    }
+    hud.0 = lines.join("\n");
+
+    return Ok(());
+}
+
+// any weight change can add or break a tie, not only one on the listed routes
+fn drop_stale_ties(
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    mut ties: ResMut<Ties>,
+) -> Result<()> {
+    let Some(query) = ties.query else {
+        return Ok(());
+    };
+    let (wg, start, goal) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    if (start.id, goal.id) != (Some(query.0), Some(query.1)) || graph.arc_costs() != ties.costs {
+        *ties = Ties::default();
+    }
+
+    return Ok(());
+}
+
+// the current tie thick, the others faint underneath
+fn draw_ties(ties: Res<Ties>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let position = position_lookup(&nodes);
+    for (i, path) in ties.paths.iter().enumerate() {
+        for w in path.windows(2) {
+            let (Some(a), Some(b)) = (position(w[0]), position(w[1])) else {
+                continue;
+            };
+            if i == ties.current {
+                let normal = (b - a).perp().normalize_or_zero();
+                for offset in [-1.5, 0.0, 1.5] {
+                    gizmos.line_2d(a + normal * offset, b + normal * offset, COLOR);
+                }
+            } else {
+                gizmos.line_2d(a, b, COLOR.with_alpha(0.25));
+            }
+        }
+    }
+}
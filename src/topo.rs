@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::graph::{Graph, NodeId};
+use crate::{MainCamera, Node, ResultsHud, WrapperGraph};
+
+const MORPH_SECS: f32 = 1.2;
+const COLUMN_SPACING: f32 = 140.0;
+const ROW_SPACING: f32 = 90.0;
+
+// nodes morphing between two layouts; `original` is where they were before the topological one,
+// kept until they are morphed back
+#[derive(Resource)]
+struct TopoLayout {
+    original: Vec<Vec2>,
+    from: Vec<Vec2>,
+    to: Vec<Vec2>,
+    progress: f32,
+}
+
+impl Default for TopoLayout {
+    fn default() -> Self {
+        return TopoLayout {
+            original: Vec::new(),
+            from: Vec::new(),
+            to: Vec::new(),
+            progress: 1.0,
+        };
+    }
+}
+
+pub struct TopoPlugin;
+
+impl Plugin for TopoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TopoLayout>().add_systems(
+            Update,
+            (
+                handle_topo_keys.run_if(crate::prompt::prompt_closed),
+                morph_nodes,
+            )
+                .chain(),
+        );
+    }
+}
+
+// one column per level, where a node's level is the longest chain of edges leading to it, so every
+// edge points right; columns keep the topological order top to bottom
+fn layered_positions(graph: &Graph, order: &[NodeId], center: Vec2) -> Vec<Vec2> {
+    let n = graph.nodes.len();
+    let mut level = vec![0; n];
+    for &v in order {
+        for edge in graph.edges_from(v) {
+            level[edge.node.index()] = level[edge.node.index()].max(level[v.index()] + 1);
+        }
+    }
+    let levels = level.iter().max().map_or(0, |&l| l + 1);
+    let mut columns: Vec<Vec<NodeId>> = vec![Vec::new(); levels];
+    for &v in order {
+        columns[level[v.index()]].push(v);
+    }
+
+    let mut positions = vec![Vec2::ZERO; n];
+    for (x, column) in columns.iter().enumerate() {
+        for (y, &v) in column.iter().enumerate() {
+            positions[v.index()] = center
+                + Vec2::new(
+                    (x as f32 - (levels - 1) as f32 * 0.5) * COLUMN_SPACING,
+                    ((column.len() - 1) as f32 * 0.5 - y as f32) * ROW_SPACING,
+                );
+        }
+    }
+    return positions;
+}
+
+// shift+S lays a DAG out left to right in topological order; pressing it again morphs back
+fn handle_topo_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    mut layout: ResMut<TopoLayout>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyS) {
+        return Ok(());
+    }
+    let graph = wrapper_graph.single()?.0.read().unwrap();
+    let current = crate::node_positions(&nodes, graph.nodes.len());
+
+    if !layout.original.is_empty() && layout.original.len() == current.len() {
+        layout.to = std::mem::take(&mut layout.original);
+        layout.from = current;
+        layout.progress = 0.0;
+        hud.0 = "Restoring the previous layout".to_string();
+        return Ok(());
+    }
+
+    let order = match graph.topological_sort() {
+        Ok(order) if !order.is_empty() => order,
+        Ok(_) => {
+            hud.0 = "No nodes to sort".to_string();
+            return Ok(());
+        }
+        Err(cycle) => {
+            hud.0 = format!(
+                "No topological order: {} is a cycle{}",
+                Graph::fmt_path(&cycle),
+                if cycle.len() == 3 {
+                    " (a two-way edge; build the graph in directed mode, Shift+D)"
+                } else {
+                    ""
+                }
+            );
+            return Ok(());
+        }
+    };
+    let center = current.iter().sum::<Vec2>() / current.len() as f32;
+    layout.to = layered_positions(&graph, &order, center);
+    layout.original = current.clone();
+    layout.from = current;
+    layout.progress = 0.0;
+    hud.0 = format!(
+        "Topological order: {} (Shift+S restores the layout)",
+        Graph::fmt_path(&order)
+    );
+
+    return Ok(());
+}
+
+fn morph_nodes(time: Res<Time>, mut layout: ResMut<TopoLayout>, mut nodes: Query<&mut Node>) {
+    if layout.progress >= 1.0 {
+        return;
+    }
+    // nodes added or removed mid-morph leave nothing sensible to morph between
+    if nodes.iter().len() != layout.to.len() {
+        *layout = TopoLayout::default();
+        return;
+    }
+    layout.progress = (layout.progress + time.delta_secs() / MORPH_SECS).min(1.0);
+    let t = layout.progress * layout.progress * (3.0 - 2.0 * layout.progress);
+    for mut node in &mut nodes {
+        let (from, to) = (layout.from[node.id.index()], layout.to[node.id.index()]);
+        node.position = from.lerp(to, t);
+    }
+}
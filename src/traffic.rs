@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    GoalNode, MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, ord,
+    position_lookup,
+};
+
+const RUN_SECS: f32 = 0.25;
+const MAX_RUNS: usize = 1000;
+const DEFAULT_PER_USE: f32 = 0.1;
+const QUIET: Color = Color::srgb(0.4, 0.8, 0.4);
+const JAMMED: Color = Color::srgb(0.95, 0.15, 0.1);
+const LATEST: Color = Color::WHITE;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Order {
+    Send { runs: usize, per_use: Option<f32> },
+    Reset,
+}
+
+impl Order {
+    // `reset`, or a number of runs, then optionally `congestion <percent>[%]`
+    fn parse(text: &str) -> Option<Order> {
+        let mut words = text.split_whitespace();
+        let first = words.next()?;
+        if first.eq_ignore_ascii_case("reset") {
+            return words.next().is_none().then_some(Order::Reset);
+        }
+        let runs = first.parse().ok().filter(|n| (1..=MAX_RUNS).contains(n))?;
+        let per_use = match (words.next(), words.next()) {
+            (None, _) => None,
+            (Some(word), Some(percent)) if word.eq_ignore_ascii_case("congestion") => {
+                let percent: f32 = percent.trim_end_matches('%').parse().ok()?;
+                Some(percent / 100.0).filter(|p| *p >= 0.0)
+            }
+            _ => return None,
+        };
+        if words.next().is_some() {
+            return None;
+        }
+        return Some(Order::Send { runs, per_use });
+    }
+}
+
+// repeated start-to-goal runs, each routed on weights raised by the traffic so far; the real
+// weights are never touched
+#[derive(Resource, Default)]
+struct Traffic {
+    query: Option<(NodeId, NodeId)>,
+    per_use: f32,
+    pending: usize,
+    runs: usize,
+    timer: f32,
+    // runs over each (from, to) edge, and how often each route was taken
+    usage: HashMap<(NodeId, NodeId), usize>,
+    routes: HashMap<Vec<NodeId>, usize>,
+    latest: Option<(Cost, Vec<NodeId>)>,
+    // the weights the runs started from
+    costs: Vec<(NodeId, NodeId, Cost)>,
+}
+
+impl Traffic {
+    // both directions of a pair together, busiest first
+    fn busiest(&self) -> Vec<((NodeId, NodeId), usize)> {
+        let mut pairs: HashMap<(NodeId, NodeId), usize> = HashMap::new();
+        for (&(a, b), &runs) in &self.usage {
+            *pairs.entry(ord(a, b)).or_insert(0) += runs;
+        }
+        let mut pairs: Vec<((NodeId, NodeId), usize)> = pairs.into_iter().collect();
+        pairs.sort_by_key(|&(pair, runs)| (std::cmp::Reverse(runs), pair));
+        return pairs;
+    }
+
+    fn summary(&self, graph: &Graph, units: &WeightUnits) -> String {
+        let Some((start, goal)) = self.query else {
+            return String::new();
+        };
+        let mut lines = vec![format!(
+            "Traffic {} to {}: {} runs, {} to go, +{}% per run over an edge (Ctrl+R sends more)",
+            start,
+            goal,
+            self.runs,
+            self.pending,
+            self.per_use * 100.0
+        )];
+        if let Some((cost, path)) = &self.latest {
+            lines.push(format!(
+                "Latest route {}: {} congested, {} free-flowing",
+                Graph::fmt_path(path),
+                units.format(*cost),
+                graph
+                    .path_cost(path)
+                    .map_or("-".to_string(), |c| units.format(c))
+            ));
+        }
+        let busiest: Vec<String> = self
+            .busiest()
+            .iter()
+            .take(3)
+            .map(|&((a, b), runs)| format!("{} - {} ({})", a, b, runs))
+            .collect();
+        if !busiest.is_empty() {
+            lines.push(format!(
+                "{} routes taken; busiest edges {}",
+                self.routes.len(),
+                busiest.join(", ")
+            ));
+        }
+        return lines.join("\n");
+    }
+}
+
+pub struct TrafficPlugin;
+
+impl Plugin for TrafficPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Traffic>().add_systems(
+            Update,
+            (
+                handle_traffic_keys.run_if(crate::prompt::prompt_closed),
+                apply_traffic_prompt,
+                run_traffic,
+                draw_traffic,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+R asks how many runs to send from the start to the goal
+fn handle_traffic_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    traffic: Res<Traffic>,
+    mut prompt: ResMut<Prompt>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+    let per_use = if traffic.query.is_some() {
+        traffic.per_use
+    } else {
+        DEFAULT_PER_USE
+    };
+    prompt.open(
+        PromptKind::Traffic,
+        format!(
+            "Send runs from the start to the goal, e.g. `20` or `20 congestion 25%` (now {}%), or `reset`",
+            per_use * 100.0
+        ),
+    );
+}
+
+// more runs for the same query and weights add to the traffic so far; anything else starts over
+fn apply_traffic_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    q_camera: Query<(&WrapperGraph, &StartNode, &GoalNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut traffic: ResMut<Traffic>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::Traffic {
+            continue;
+        }
+        let (runs, per_use) = match Order::parse(&event.text) {
+            Some(Order::Send { runs, per_use }) => (runs, per_use),
+            Some(Order::Reset) => {
+                *traffic = Traffic::default();
+                hud.0 = "Traffic cleared".to_string();
+                continue;
+            }
+            None => {
+                hud.0 = format!(
+                    "Could not read `{}`: give up to {} runs, then `congestion <percent>`, or `reset`",
+                    event.text, MAX_RUNS
+                );
+                continue;
+            }
+        };
+        let (wg, start, goal) = q_camera.single()?;
+        let (Some(start), Some(goal)) = (start.id, goal.id) else {
+            hud.0 = "Set a start and goal to send traffic between".to_string();
+            continue;
+        };
+        let graph = wg.0.read().unwrap();
+        let query = Some((start, goal));
+        let costs = graph.arc_costs();
+        if traffic.query != query || traffic.costs != costs {
+            *traffic = Traffic {
+                query,
+                per_use: DEFAULT_PER_USE,
+                costs,
+                ..default()
+            };
+        }
+        if let Some(per_use) = per_use {
+            traffic.per_use = per_use;
+        }
+        traffic.pending += runs;
+        hud.0 = traffic.summary(&graph, &units);
+    }
+
+    return Ok(());
+}
+
+// one run every RUN_SECS; editing the graph clears the traffic
+fn run_traffic(
+    time: Res<Time>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut traffic: ResMut<Traffic>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some((start, goal)) = traffic.query else {
+        return Ok(());
+    };
+    let graph = q_graph.single()?.0.read().unwrap();
+    if graph.arc_costs() != traffic.costs {
+        *traffic = Traffic::default();
+        hud.0 = "The graph changed, so the traffic was cleared".to_string();
+        return Ok(());
+    }
+    if traffic.pending == 0 {
+        return Ok(());
+    }
+    traffic.timer += time.delta_secs();
+    if traffic.timer < RUN_SECS {
+        return Ok(());
+    }
+    traffic.timer = 0.0;
+
+    let Some((cost, path)) = graph
+        .congested(&traffic.usage, traffic.per_use)
+        .shortest_path(start, goal)
+    else {
+        traffic.pending = 0;
+        hud.0 = format!("No route from {} to {} to send traffic along", start, goal);
+        return Ok(());
+    };
+    for hop in path.windows(2) {
+        *traffic.usage.entry((hop[0], hop[1])).or_insert(0) += 1;
+    }
+    *traffic.routes.entry(path.clone()).or_insert(0) += 1;
+    traffic.latest = Some((cost, path));
+    traffic.runs += 1;
+    traffic.pending -= 1;
+    hud.0 = traffic.summary(&graph, &units);
+
+    return Ok(());
+}
+
+// edges from green to red and thicker by their share of the busiest one's traffic, with the
+// latest run's route on top
+fn draw_traffic(traffic: Res<Traffic>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let busiest = traffic.busiest();
+    let Some(&(_, most)) = busiest.first() else {
+        return;
+    };
+    let position = position_lookup(&nodes);
+    for ((a, b), runs) in busiest {
+        let (Some(a), Some(b)) = (position(a), position(b)) else {
+            continue;
+        };
+        let share = runs as f32 / most as f32;
+        let color = QUIET.mix(&JAMMED, share);
+        let side = (b - a).normalize_or_zero().perp();
+        let lanes = 1 + (share * 3.0).round() as i32;
+        for lane in -lanes..=lanes {
+            let offset = side * lane as f32;
+            gizmos.line_2d(a + offset, b + offset, color);
+        }
+    }
+    if let Some((_, path)) = &traffic.latest {
+        for hop in path.windows(2) {
+            if let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) {
+                gizmos.line_2d(a, b, LATEST);
+            }
+        }
+    }
+}
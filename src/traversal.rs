@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::graph::{Graph, NodeId};
+use crate::{MainCamera, ResultsHud, StartNode, WrapperGraph};
+
+const STEP_SECS: f32 = 0.5;
+const FIRST: Color = Color::srgb(1.0, 0.6, 0.15);
+const LAST: Color = Color::srgb(0.55, 0.25, 0.85);
+
+// a depth-first visit order revealed one node per step, tinted from FIRST to LAST by position
+#[derive(Resource, Default)]
+pub struct Traversal {
+    order: Vec<NodeId>,
+    shown: usize,
+    timer: f32,
+}
+
+impl Traversal {
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        let i = self.order[..self.shown].iter().position(|&n| n == id)?;
+        let t = i as f32 / (self.order.len() - 1).max(1) as f32;
+        return Some(FIRST.mix(&LAST, t));
+    }
+
+    pub fn clear(&mut self) {
+        *self = Traversal::default();
+    }
+}
+
+pub struct TraversalPlugin;
+
+impl Plugin for TraversalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Traversal>().add_systems(
+            Update,
+            (
+                handle_traversal_keys.run_if(crate::prompt::prompt_closed),
+                step_traversal,
+            )
+                .chain(),
+        );
+    }
+}
+
+// shift+O starts a depth-first traversal from the start node; pressing it again clears it
+fn handle_traversal_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    mut traversal: ResMut<Traversal>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyO) {
+        return Ok(());
+    }
+    if !traversal.order.is_empty() {
+        traversal.clear();
+        hud.0 = "Traversal view off".to_string();
+        return Ok(());
+    }
+
+    let (wg, start) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let Some(start) = start.id.filter(|s| s.index() < graph.nodes.len()) else {
+        hud.0 = "Set a start node (S) to traverse from".to_string();
+        return Ok(());
+    };
+    traversal.order = graph.dfs_order(start);
+    traversal.shown = 0;
+    traversal.timer = 0.0;
+
+    return Ok(());
+}
+
+fn step_traversal(time: Res<Time>, mut traversal: ResMut<Traversal>, mut hud: ResMut<ResultsHud>) {
+    // bypass change detection while waiting, so node colours only refresh when a node is revealed
+    let t = traversal.bypass_change_detection();
+    if t.shown == t.order.len() {
+        return;
+    }
+    t.timer -= time.delta_secs();
+    if t.timer > 0.0 {
+        return;
+    }
+    t.timer = STEP_SECS;
+    t.shown += 1;
+
+    let visited = &traversal.order[..traversal.shown];
+    hud.0 = format!(
+        "DFS order ({} of {}): {}",
+        visited.len(),
+        traversal.order.len(),
+        Graph::fmt_path(visited)
+    );
+    traversal.set_changed();
+}
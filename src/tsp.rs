@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::{MainCamera, Node, ResultsHud, StartNode, WeightUnits, WrapperGraph, position_lookup};
+
+const TOUR: Color = Color::srgb(0.75, 0.45, 1.0);
+const GREEDY: Color = Color::srgb(0.5, 0.4, 0.6);
+
+// a travelling salesman tour by nearest neighbour, then shortened by 2-opt; both are drawn as
+// closed loops along the shortest paths between consecutive stops
+#[derive(Resource, Default)]
+struct TspTour {
+    shown: bool,
+    basis: Option<(NodeId, Vec<(NodeId, NodeId, Cost)>)>,
+    greedy: Vec<Vec<NodeId>>,
+    // the stops in visiting order, and each leg walked out
+    stops: Vec<NodeId>,
+    legs: Vec<Vec<NodeId>>,
+}
+
+#[derive(Component)]
+struct TspLabel;
+
+pub struct TspPlugin;
+
+impl Plugin for TspPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TspTour>().add_systems(
+            Update,
+            (
+                handle_tsp_keys.run_if(crate::prompt::prompt_closed),
+                refresh_tsp,
+                draw_tsp,
+                update_tsp_labels,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn legs(graph: &Graph, tour: &[NodeId]) -> Vec<Vec<NodeId>> {
+    return tour
+        .windows(2)
+        .filter_map(|leg| graph.shortest_path(leg[0], leg[1]).map(|(_, path)| path))
+        .collect();
+}
+
+// ctrl+T shows a tour through every node, or hides it
+fn handle_tsp_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut tour: ResMut<TspTour>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyT) {
+        return;
+    }
+    let shown = !tour.shown;
+    *tour = TspTour { shown, ..default() };
+    if !shown {
+        hud.0 = "TSP tour off".to_string();
+    }
+}
+
+// redone whenever the start or a weight moves; without a start the tour begins at A
+fn refresh_tsp(
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut tour: ResMut<TspTour>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !tour.shown {
+        return Ok(());
+    }
+    let (wg, start) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    let start = start.id.unwrap_or(NodeId(0));
+    let basis = Some((start, graph.arc_costs()));
+    if tour.basis == basis {
+        return Ok(());
+    }
+    if graph.nodes.is_empty() {
+        *tour = TspTour {
+            shown: true,
+            basis,
+            ..default()
+        };
+        hud.0 = "Add some nodes to tour".to_string();
+        return Ok(());
+    }
+
+    let (greedy_cost, greedy) = graph.tsp_nearest_neighbor(start);
+    let (cost, stops) = graph.tsp_two_opt(&greedy);
+    *tour = TspTour {
+        shown: true,
+        basis,
+        greedy: legs(&graph, &greedy),
+        legs: legs(&graph, &stops),
+        stops,
+    };
+
+    let visited = tour.stops.len() - 1;
+    let mut lines = vec![format!(
+        "TSP tour from {}: nearest neighbour {}, after 2-opt {} ({:.1}% shorter) (Ctrl+T hides)",
+        start,
+        units.format(greedy_cost),
+        units.format(cost),
+        (greedy_cost - cost) as f32 / greedy_cost.max(1) as f32 * 100.0
+    )];
+    lines.push(format!("Stops: {}", Graph::fmt_path(&tour.stops)));
+    if visited < graph.nodes.len() {
+        lines.push(format!(
+            "{} nodes left out: {} can't reach them or get back from them",
+            graph.nodes.len() - visited,
+            start
+        ));
+    }
+    if !graph.forbidden.is_empty() {
+        lines.push("Turn bans are left out of these distances".to_string());
+    }
+    hud.0 = lines.join("\n");
+
+    return Ok(());
+}
+
+// the 2-opt tour thick over a faint nearest neighbour one
+fn draw_tsp(tour: Res<TspTour>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let position = position_lookup(&nodes);
+    for path in &tour.greedy {
+        for hop in path.windows(2) {
+            if let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) {
+                gizmos.line_2d(a, b, GREEDY);
+            }
+        }
+    }
+    for path in &tour.legs {
+        for hop in path.windows(2) {
+            let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) else {
+                continue;
+            };
+            let side = (b - a).normalize_or_zero().perp() * 2.0;
+            for offset in [-side, Vec2::ZERO, side] {
+                gizmos.line_2d(a + offset, b + offset, TOUR);
+            }
+        }
+    }
+}
+
+// each stop's place in the tour, above the node
+fn update_tsp_labels(
+    mut commands: Commands,
+    tour: Res<TspTour>,
+    nodes: Query<&Node>,
+    q_labels: Query<Entity, With<TspLabel>>,
+) {
+    if !tour.is_changed() {
+        return;
+    }
+    for e in &q_labels {
+        commands.entity(e).despawn();
+    }
+    let stops = tour.stops.len().saturating_sub(1);
+    for (i, &id) in tour.stops.iter().take(stops).enumerate() {
+        let Some(node) = nodes.iter().find(|n| n.id == id) else {
+            continue;
+        };
+        commands.spawn((
+            Text2d::new(format!("#{}", i + 1)),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(TOUR),
+            Transform::from_translation(node.position.extend(5.0) + Vec3::Y * (node.r + 10.0)),
+            TspLabel,
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+use bevy::color::palettes::css::RED;
+use bevy::prelude::*;
+
+use crate::graph::Graph;
+use crate::{
+    HighlightedEdges, MainCamera, MultiSelection, Node, ResultsHud, WrapperGraph, position_lookup,
+};
+
+const MARKER_OFFSET: f32 = 30.0;
+
+pub struct TurnsPlugin;
+
+impl Plugin for TurnsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_turn_ban.run_if(crate::prompt::prompt_closed),
+                draw_turn_bans,
+            ),
+        );
+    }
+}
+
+// shift+click from, via, to in that order, then Q bans (or allows again) that turn
+fn toggle_turn_ban(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &MultiSelection), With<MainCamera>>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::KeyQ) {
+        return Ok(());
+    }
+    let (wg, multi) = q_camera.single()?;
+    let &[from, via, to] = multi.ids.as_slice() else {
+        hud.0 =
+            "Shift+click three nodes (from, via, to), then press Q to ban that turn".to_string();
+        return Ok(());
+    };
+    let turn = Graph::fmt_path(&[from, via, to]);
+    let mut graph = wg.0.write().unwrap();
+    if !graph.has_edge(from, via) || !graph.has_edge(via, to) {
+        hud.0 = format!("{} is not a turn in this graph", turn);
+        return Ok(());
+    }
+
+    match graph.forbidden.iter().position(|&t| t == (from, via, to)) {
+        Some(pos) => {
+            graph.forbidden.remove(pos);
+            hud.0 = format!("Allowed {} again", turn);
+        }
+        None => {
+            graph.forbidden.push((from, via, to));
+            hud.0 = format!("Banned the turn {}", turn);
+        }
+    }
+    // the shown path may use the turn that was just banned
+    highlights.clear_results();
+
+    return Ok(());
+}
+
+// a red link across the corner with a cross on it, between the incoming and outgoing edges
+fn draw_turn_bans(
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    nodes: Query<&Node>,
+    mut gizmos: Gizmos,
+) -> Result<()> {
+    let wg = wrapper_graph.single()?;
+    let graph = wg.0.read().unwrap();
+    let color = Color::from(RED);
+    let position = position_lookup(&nodes);
+
+    for &(from, via, to) in &graph.forbidden {
+        let (Some(a), Some(v), Some(b)) = (position(from), position(via), position(to)) else {
+            continue;
+        };
+        let into = v + (a - v).normalize_or_zero() * MARKER_OFFSET;
+        let out = v + (b - v).normalize_or_zero() * MARKER_OFFSET;
+        gizmos.line_2d(into, out, color);
+
+        let mid = (into + out) * 0.5;
+        let arm = Vec2::splat(4.0);
+        gizmos.line_2d(mid - arm, mid + arm, color);
+        gizmos.line_2d(mid + arm.with_x(-4.0), mid - arm.with_x(-4.0), color);
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,54 @@
+// disjoint sets over 0..n with path halving and union by size
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(n: usize) -> Self {
+        return UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        };
+    }
+
+    pub fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        return x;
+    }
+
+    // false when a and b were already in the same set
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find() {
+        let mut sets = UnionFind::new(5);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert_ne!(sets.find(0), sets.find(3));
+        assert!(sets.union(1, 4));
+        assert_eq!(sets.find(0), sets.find(3));
+        assert!(!sets.union(0, 4));
+        assert_ne!(sets.find(2), sets.find(0));
+    }
+}
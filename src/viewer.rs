@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+
+use crate::graph::{Graph, NodeId};
+
+const NODE: Color = Color::srgb(0.8, 0.8, 0.85);
+const EDGE: Color = Color::srgb(0.45, 0.45, 0.5);
+const LABEL: Color = Color::WHITE;
+
+// edges and nodes drawn over the graph in one color; later layers draw on top of earlier ones
+#[derive(Debug, Clone)]
+pub struct HighlightLayer {
+    pub name: String,
+    pub color: Color,
+    pub edges: Vec<(NodeId, NodeId)>,
+    pub nodes: Vec<NodeId>,
+    pub visible: bool,
+}
+
+impl HighlightLayer {
+    pub fn new(name: impl Into<String>, color: Color) -> Self {
+        return HighlightLayer {
+            name: name.into(),
+            color,
+            edges: Vec::new(),
+            nodes: Vec::new(),
+            visible: true,
+        };
+    }
+
+    // a path's hops and the nodes along it, e.g. the result of Graph::shortest_path
+    pub fn path(name: impl Into<String>, color: Color, path: &[NodeId]) -> Self {
+        return HighlightLayer {
+            edges: path.windows(2).map(|hop| (hop[0], hop[1])).collect(),
+            nodes: path.to_vec(),
+            ..HighlightLayer::new(name, color)
+        };
+    }
+
+    pub fn with_edges(mut self, edges: impl IntoIterator<Item = (NodeId, NodeId)>) -> Self {
+        self.edges.extend(edges);
+        return self;
+    }
+
+    pub fn with_nodes(mut self, nodes: impl IntoIterator<Item = NodeId>) -> Self {
+        self.nodes.extend(nodes);
+        return self;
+    }
+}
+
+// what GraphViewerPlugin draws: a graph, where each node sits in world space and the layers over
+// it. the host app owns it and swaps in new results as it likes; nothing here edits the graph
+#[derive(Resource, Debug, Clone)]
+pub struct GraphView {
+    pub graph: Graph,
+    pub layout: Vec<Vec2>,
+    pub layers: Vec<HighlightLayer>,
+    pub node_radius: f32,
+    pub labels: bool,
+}
+
+impl Default for GraphView {
+    fn default() -> Self {
+        return GraphView {
+            graph: Graph::default(),
+            layout: Vec::new(),
+            layers: Vec::new(),
+            node_radius: 20.0,
+            labels: true,
+        };
+    }
+}
+
+impl GraphView {
+    // nodes without a position in the layout aren't drawn, nor are edges touching them
+    pub fn new(graph: Graph, layout: Vec<Vec2>) -> Self {
+        return GraphView {
+            graph,
+            layout,
+            ..default()
+        };
+    }
+
+    pub fn with_layer(mut self, layer: HighlightLayer) -> Self {
+        self.layers.push(layer);
+        return self;
+    }
+
+    // replaces the layer with the same name, or adds it on top
+    pub fn set_layer(&mut self, layer: HighlightLayer) {
+        match self.layers.iter_mut().find(|l| l.name == layer.name) {
+            Some(existing) => *existing = layer,
+            None => self.layers.push(layer),
+        }
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut HighlightLayer> {
+        return self.layers.iter_mut().find(|l| l.name == name);
+    }
+}
+
+// draws GraphView with gizmos and keeps a text label on each node, under whichever 2d camera the
+// host app has; there are no input systems, so it can sit under a game's own controls
+pub struct GraphViewerPlugin;
+
+impl Plugin for GraphViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GraphView>()
+            .add_systems(Update, (sync_viewer_labels, draw_graph_view).chain());
+    }
+}
+
+#[derive(Component)]
+struct ViewerLabel;
+
+fn sync_viewer_labels(
+    mut commands: Commands,
+    view: Res<GraphView>,
+    labels: Query<Entity, With<ViewerLabel>>,
+) {
+    if !view.is_changed() {
+        return;
+    }
+    for entity in &labels {
+        commands.entity(entity).despawn();
+    }
+    if !view.labels {
+        return;
+    }
+    for (id, &at) in view.layout.iter().enumerate().take(view.graph.nodes.len()) {
+        commands.spawn((
+            ViewerLabel,
+            Text2d::new(Graph::node_label(id)),
+            TextFont::from_font_size(view.node_radius * 0.8),
+            TextColor(LABEL),
+            Transform::from_translation(at.extend(1.0)),
+        ));
+    }
+}
+
+// edges stop at the node rims; one-way edges get an arrowhead, edges both ways a plain line
+fn draw_graph_view(view: Res<GraphView>, mut gizmos: Gizmos) {
+    let graph = &view.graph;
+    let position = |id: NodeId| {
+        view.layout
+            .get(id.index())
+            .filter(|_| id.index() < graph.nodes.len())
+    };
+    let rims = |a: Vec2, b: Vec2| {
+        let along = (b - a).normalize_or_zero() * view.node_radius;
+        return (a + along, b - along);
+    };
+
+    for (from, edge) in graph.arcs() {
+        let to = edge.node;
+        let two_way = graph.has_edge(to, from);
+        if two_way && to < from {
+            continue;
+        }
+        let (Some(&a), Some(&b)) = (position(from), position(to)) else {
+            continue;
+        };
+        let (a, b) = rims(a, b);
+        if two_way {
+            gizmos.line_2d(a, b, EDGE);
+        } else {
+            gizmos.arrow_2d(a, b, EDGE).with_tip_length(10.0);
+        }
+    }
+    for id in graph.ids() {
+        if let Some(&at) = position(id) {
+            gizmos.circle_2d(Isometry2d::from_translation(at), view.node_radius, NODE);
+        }
+    }
+
+    for layer in view.layers.iter().filter(|l| l.visible) {
+        for &(from, to) in &layer.edges {
+            let (Some(&a), Some(&b)) = (position(from), position(to)) else {
+                continue;
+            };
+            let (a, b) = rims(a, b);
+            let side = (b - a).normalize_or_zero().perp() * 2.0;
+            for offset in [-side, Vec2::ZERO, side] {
+                gizmos.line_2d(a + offset, b + offset, layer.color);
+            }
+        }
+        for &id in &layer.nodes {
+            if let Some(&at) = position(id) {
+                let at = Isometry2d::from_translation(at);
+                gizmos.circle_2d(at, view.node_radius + 3.0, layer.color);
+                gizmos.circle_2d(at, view.node_radius + 5.0, layer.color);
+            }
+        }
+    }
+}
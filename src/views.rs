@@ -0,0 +1,209 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{MainCamera, ResultsHud};
+
+const SLOT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+// screen pixels a second, so panning feels the same at any zoom
+const PAN_SPEED: f32 = 600.0;
+
+// where the camera looks and how far out it is; zoom is the orthographic scale, above 1 shows more
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CameraView {
+    pub slot: usize,
+    pub name: String,
+    pub position: Vec2,
+    pub zoom: f32,
+}
+
+// named views on the number keys, saved with the graph
+#[derive(Resource, Default)]
+pub struct CameraViews(pub Vec<CameraView>);
+
+impl CameraViews {
+    fn get(&self, slot: usize) -> Option<&CameraView> {
+        return self.0.iter().find(|v| v.slot == slot);
+    }
+
+    fn describe(&self) -> String {
+        if self.0.is_empty() {
+            return "No camera views yet: Ctrl+1-9 saves one".to_string();
+        }
+        let mut views: Vec<&CameraView> = self.0.iter().collect();
+        views.sort_by_key(|v| v.slot);
+        let list: Vec<String> = views
+            .iter()
+            .map(|v| format!("{} \"{}\"", v.slot, v.name))
+            .collect();
+        return format!("Camera views (Alt+number): {}", list.join(", "));
+    }
+}
+
+// slot and view the open name prompt will save
+#[derive(Resource, Default)]
+struct ViewDraft(Option<(usize, Vec2, f32)>);
+
+pub struct ViewsPlugin;
+
+impl Plugin for ViewsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraViews>()
+            .init_resource::<ViewDraft>()
+            .add_systems(
+                Update,
+                (
+                    (navigate_camera, handle_view_keys).run_if(crate::prompt::prompt_closed),
+                    handle_view_prompt,
+                )
+                    .chain(),
+            );
+    }
+}
+
+pub fn slot_chord_held(keys: &ButtonInput<KeyCode>) -> bool {
+    return keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+    ]);
+}
+
+fn zoom(projection: &Projection) -> f32 {
+    return match projection {
+        Projection::Orthographic(ortho) => ortho.scale,
+        _ => 1.0,
+    };
+}
+
+// the wheel zooms and the arrow keys pan
+fn navigate_camera(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: MessageReader<MouseWheel>,
+    mut q_camera: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
+) -> Result<()> {
+    let (mut transform, mut projection) = q_camera.single_mut()?;
+    let Projection::Orthographic(ortho) = &mut *projection else {
+        return Ok(());
+    };
+    for event in wheel.read() {
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        };
+        ortho.scale = (ortho.scale * 0.9_f32.powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    let mut direction = Vec2::ZERO;
+    for (key, step) in [
+        (KeyCode::ArrowLeft, Vec2::NEG_X),
+        (KeyCode::ArrowRight, Vec2::X),
+        (KeyCode::ArrowDown, Vec2::NEG_Y),
+        (KeyCode::ArrowUp, Vec2::Y),
+    ] {
+        if keys.pressed(key) {
+            direction += step;
+        }
+    }
+    let step = direction * PAN_SPEED * ortho.scale * time.delta_secs();
+    transform.translation += step.extend(0.0);
+
+    return Ok(());
+}
+
+// ctrl+number asks for a name and saves the view there, alt+number jumps back to it
+fn handle_view_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut q_camera: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
+    views: Res<CameraViews>,
+    mut draft: ResMut<ViewDraft>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    let Some(slot) = SLOT_KEYS.iter().position(|k| keys.just_pressed(*k)) else {
+        return Ok(());
+    };
+    let slot = slot + 1;
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let (mut transform, mut projection) = q_camera.single_mut()?;
+
+    if ctrl {
+        draft.0 = Some((slot, transform.translation.truncate(), zoom(&projection)));
+        let label = match views.get(slot) {
+            Some(view) => format!(
+                "Save this view over {} \"{}\" as (empty keeps the name)",
+                slot, view.name
+            ),
+            None => format!(
+                "Save this view on {} as (empty names it View {})",
+                slot, slot
+            ),
+        };
+        prompt.open(PromptKind::CameraView, label);
+    } else if alt {
+        let Some(view) = views.get(slot) else {
+            hud.0 = format!("Nothing on {}. {}", slot, views.describe());
+            return Ok(());
+        };
+        transform.translation.x = view.position.x;
+        transform.translation.y = view.position.y;
+        if let Projection::Orthographic(ortho) = &mut *projection {
+            ortho.scale = view.zoom;
+        }
+        hud.0 = format!("View {} \"{}\"", slot, view.name);
+    }
+
+    return Ok(());
+}
+
+fn handle_view_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    mut draft: ResMut<ViewDraft>,
+    mut views: ResMut<CameraViews>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    for event in submitted.read() {
+        if event.kind != PromptKind::CameraView {
+            continue;
+        }
+        let Some((slot, position, zoom)) = draft.0.take() else {
+            continue;
+        };
+        let old = views.0.iter().position(|v| v.slot == slot);
+        let name = match (event.text.trim(), old) {
+            ("", Some(i)) => views.0[i].name.clone(),
+            ("", None) => format!("View {}", slot),
+            (name, _) => name.to_string(),
+        };
+        let view = CameraView {
+            slot,
+            name,
+            position,
+            zoom,
+        };
+        hud.0 = format!(
+            "Saved view {} \"{}\" (Alt+{} returns)",
+            slot, view.name, slot
+        );
+        match old {
+            Some(i) => views.0[i] = view,
+            None => views.0.push(view),
+        }
+    }
+}
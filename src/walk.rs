@@ -0,0 +1,173 @@
+use bevy::color::palettes::css::ORANGE_RED;
+use bevy::prelude::*;
+
+use crate::graph::{Cost, NodeId};
+use crate::rng::Rng;
+use crate::{MainCamera, Node, ResultsHud, StartNode, WrapperGraph, position_lookup};
+
+const STEP_SECS: f32 = 0.3;
+const COLD: Color = Color::srgb(0.85, 0.9, 1.0);
+const HOT: Color = Color::srgb(0.95, 0.2, 0.1);
+
+// a token hopping from the start along edges picked in proportion to their weights; every
+// arrival counts as a visit and the counts tint the nodes
+#[derive(Resource, Default)]
+pub struct RandomWalk {
+    active: bool,
+    // the hop being animated, and how far along it is in [0, 1)
+    hop: Option<(NodeId, NodeId)>,
+    progress: f32,
+    at: NodeId,
+    start: NodeId,
+    visits: Vec<usize>,
+    steps: usize,
+    restarts: usize,
+    // the weights the walk started with
+    costs: Vec<(NodeId, NodeId, Cost)>,
+}
+
+impl RandomWalk {
+    pub fn node_color(&self, id: NodeId) -> Option<Color> {
+        let max = *self.visits.iter().max()?;
+        if max == 0 {
+            return None;
+        }
+        let visits = *self.visits.get(id.index())?;
+        return Some(COLD.mix(&HOT, visits as f32 / max as f32));
+    }
+
+    fn summary(&self) -> String {
+        let total: usize = self.visits.iter().sum();
+        let (busiest, most) = self
+            .visits
+            .iter()
+            .enumerate()
+            .max_by_key(|&(id, v)| (*v, std::cmp::Reverse(id)))
+            .map_or((self.start, 0), |(id, v)| (NodeId(id), *v));
+        let mut text = format!(
+            "Random walk from {}: {} steps, at {}, most visited {} ({:.0}% of visits) (F12 stops)",
+            self.start,
+            self.steps,
+            self.at,
+            busiest,
+            most as f32 / total.max(1) as f32 * 100.0
+        );
+        if self.restarts > 0 {
+            text.push_str(&format!(
+                "\n{} dead ends sent it back to the start",
+                self.restarts
+            ));
+        }
+        return text;
+    }
+}
+
+pub struct WalkPlugin;
+
+impl Plugin for WalkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RandomWalk>().add_systems(
+            Update,
+            (
+                handle_walk_keys.run_if(crate::prompt::prompt_closed),
+                step_walk,
+                draw_walk,
+            )
+                .chain(),
+        );
+    }
+}
+
+// F12 starts a walk from the start node, or stops it and clears the tint
+fn handle_walk_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    mut walk: ResMut<RandomWalk>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !keys.just_pressed(KeyCode::F12) {
+        return Ok(());
+    }
+    if walk.active {
+        hud.0 = format!("Stopped after {} steps", walk.steps);
+        *walk = RandomWalk::default();
+        return Ok(());
+    }
+    let (wg, start) = q_camera.single()?;
+    let Some(start) = start.id else {
+        hud.0 = "Set a start node (S) to walk from".to_string();
+        return Ok(());
+    };
+    let graph = wg.0.read().unwrap();
+    let mut visits = vec![0; graph.nodes.len()];
+    visits[start.index()] = 1;
+    *walk = RandomWalk {
+        active: true,
+        at: start,
+        start,
+        visits,
+        costs: graph.arc_costs(),
+        ..default()
+    };
+    hud.0 = walk.summary();
+
+    return Ok(());
+}
+
+// one hop every STEP_SECS; a dead end teleports the token back to the start
+fn step_walk(
+    time: Res<Time>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    mut rng: ResMut<Rng>,
+    mut walk: ResMut<RandomWalk>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !walk.active {
+        return Ok(());
+    }
+    let graph = q_graph.single()?.0.read().unwrap();
+    if graph.arc_costs() != walk.costs {
+        hud.0 = format!(
+            "The graph changed, so the walk stopped after {} steps",
+            walk.steps
+        );
+        *walk = RandomWalk::default();
+        return Ok(());
+    }
+
+    walk.progress += time.delta_secs() / STEP_SECS;
+    if walk.hop.is_some() && walk.progress < 1.0 {
+        return Ok(());
+    }
+    if let Some((_, to)) = walk.hop.take() {
+        walk.at = to;
+        walk.visits[to.index()] += 1;
+        walk.steps += 1;
+    }
+    walk.progress = 0.0;
+    let at = walk.at;
+    walk.hop = match graph.random_step(at, &mut rng) {
+        Some(next) => Some((at, next)),
+        None => {
+            walk.restarts += 1;
+            Some((at, walk.start))
+        }
+    };
+    hud.0 = walk.summary();
+
+    return Ok(());
+}
+
+// the token between the two ends of its hop
+fn draw_walk(walk: Res<RandomWalk>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    let Some((from, to)) = walk.hop else {
+        return;
+    };
+    let position = position_lookup(&nodes);
+    let (Some(a), Some(b)) = (position(from), position(to)) else {
+        return;
+    };
+    let at = Isometry2d::from_translation(a.lerp(b, walk.progress));
+    gizmos.circle_2d(at, 7.0, ORANGE_RED);
+    gizmos.circle_2d(at, 4.0, ORANGE_RED);
+}
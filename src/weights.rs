@@ -0,0 +1,234 @@
+use bevy::color::palettes::css::DARK_RED;
+use bevy::prelude::*;
+use bevy::ui::Node as UiNode;
+use bevy::window::PrimaryWindow;
+
+use crate::graph::{Cost, Graph, NodeId};
+use crate::prompt::{Prompt, PromptKind, PromptSubmitted};
+use crate::{
+    EdgeVisual, HighlightedEdges, MainCamera, Node, ResultsHud, StartNode, WeightUnits,
+    WrapperGraph, cursor_world, lane_at,
+};
+
+// edge the open weight prompt will write to, and which of parallel edges it is
+#[derive(Resource, Default)]
+struct WeightDraft(Option<(NodeId, NodeId, usize)>);
+
+#[derive(Component)]
+struct CycleBanner;
+
+// the negative cycle last found from the start, and the start and weights it was looked for
+// with; bellman-ford only runs again once either changes
+#[derive(Resource, Default)]
+struct CycleCheck {
+    basis: Option<(Option<NodeId>, Vec<(NodeId, NodeId, Cost)>)>,
+    cycle: Option<Vec<NodeId>>,
+}
+
+pub struct WeightsPlugin;
+
+impl Plugin for WeightsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeightDraft>()
+            .init_resource::<CycleCheck>()
+            .add_systems(Startup, spawn_cycle_banner)
+            .add_systems(
+                Update,
+                (
+                    handle_weight_keys.run_if(crate::prompt::prompt_closed),
+                    handle_weight_prompt,
+                    update_cycle_banner,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_cycle_banner(mut commands: Commands) {
+    commands
+        .spawn((
+            UiNode {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+            CycleBanner,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BackgroundColor(Color::from(DARK_RED)),
+                UiNode {
+                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+// shift+E over an edge asks for a new weight, which may be negative, and optionally a capacity
+fn handle_weight_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cams: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    nodes: Query<&Node>,
+    edges: Query<&EdgeVisual>,
+    mut draft: ResMut<WeightDraft>,
+    mut prompt: ResMut<Prompt>,
+    mut hud: ResMut<ResultsHud>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keys.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let Some((a, b, lane)) = cursor_world(&windows, &cams).and_then(|w| lane_at(&nodes, &edges, w))
+    else {
+        hud.0 = "Hover an edge and press Shift+E to set its weight".to_string();
+        return;
+    };
+    draft.0 = Some((a, b, lane));
+    prompt.open(
+        PromptKind::EdgeWeight,
+        format!(
+            "Weight for {} - {} (negative allowed; `cap 5` sets a capacity, `cap none` clears it)",
+            a, b
+        ),
+    );
+}
+
+fn handle_weight_prompt(
+    mut submitted: MessageReader<PromptSubmitted>,
+    wrapper_graph: Query<&WrapperGraph, With<MainCamera>>,
+    units: Res<WeightUnits>,
+    mut draft: ResMut<WeightDraft>,
+    mut highlights: ResMut<HighlightedEdges>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    for event in submitted.read() {
+        if event.kind != PromptKind::EdgeWeight {
+            continue;
+        }
+        let Some((a, b, lane)) = draft.0.take() else {
+            continue;
+        };
+        // typed in display units, like the budget
+        let (weight, capacity) = match event.text.split_once("cap") {
+            Some((weight, capacity)) => (weight.trim(), Some(capacity.trim())),
+            None => (event.text.trim(), None),
+        };
+        let capacity = match capacity {
+            None => None,
+            Some("none") => Some(None),
+            Some(text) => match text.parse::<f32>() {
+                Ok(value) if value >= 0.0 => Some(Some((value / units.scale).round() as Cost)),
+                _ => {
+                    hud.0 = "Capacities cannot be negative, e.g. `cap 5`".to_string();
+                    continue;
+                }
+            },
+        };
+        let cost = match weight.parse::<f32>() {
+            _ if weight.is_empty() && capacity.is_some() => None,
+            Ok(value) => Some((value / units.scale).round() as Cost),
+            Err(_) => {
+                hud.0 = "Type a number, e.g. `-3`".to_string();
+                continue;
+            }
+        };
+        if cost == Some(0) {
+            hud.0 = "Weights cannot be zero".to_string();
+            continue;
+        }
+
+        let wg = wrapper_graph.single()?;
+        let mut graph = wg.0.write().unwrap();
+        let mut two_way = true;
+        for (from, to) in [(a, b), (b, a)] {
+            match graph.lane_mut(from, to, lane) {
+                Some(edge) => {
+                    edge.cost = cost.unwrap_or(edge.cost);
+                    edge.capacity = capacity.unwrap_or(edge.capacity);
+                }
+                None => two_way = false,
+            }
+        }
+        highlights.clear_results();
+        let edge = format!("{} - {}", a, b);
+        let Some(cost) = cost else {
+            hud.0 = match capacity.flatten() {
+                Some(capacity) => format!("{} now carries up to {}", edge, units.format(capacity)),
+                None => format!("{} carries as much as it weighs again", edge),
+            };
+            continue;
+        };
+        hud.0 = if cost < 0 && two_way {
+            // A -> B -> A already costs 2 * cost
+            format!(
+                "{} now weighs {}. A negative two-way edge is a negative cycle on its own; \
+                 use directed mode (Shift+D) for negative weights",
+                edge,
+                units.format(cost)
+            )
+        } else {
+            format!("{} now weighs {}", edge, units.format(cost))
+        };
+        if let Some(Some(capacity)) = capacity {
+            hud.0 += &format!(" and carries up to {}", units.format(capacity));
+        }
+    }
+
+    return Ok(());
+}
+
+// a negative cycle reachable from the start leaves no shortest path to report, so say why
+fn update_cycle_banner(
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    mut q_banner: Query<(&mut Visibility, &Children), With<CycleBanner>>,
+    mut q_text: Query<&mut Text>,
+    mut check: ResMut<CycleCheck>,
+) -> Result<()> {
+    let (wg, start) = q_camera.single()?;
+    let (mut visibility, children) = q_banner.single_mut()?;
+    let graph = wg.0.read().unwrap();
+
+    let basis = Some((start.id, graph.arc_costs()));
+    if check.basis != basis {
+        check.cycle = match start.id {
+            Some(start) if start.index() < graph.nodes.len() && graph.has_negative_edges() => {
+                graph.bellman_ford(start).err()
+            }
+            _ => None,
+        };
+        check.basis = basis;
+    }
+    let Some(cycle) = &check.cycle else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return Ok(());
+    };
+
+    let mut around = cycle.clone();
+    around.push(cycle[0]);
+    let message = format!(
+        "Negative cycle {} - shortest paths from {} are undefined",
+        Graph::fmt_path(&around),
+        start.id.unwrap()
+    );
+    for &child in children {
+        if let Ok(mut text) = q_text.get_mut(child)
+            && text.0 != message
+        {
+            text.0 = message.clone();
+        }
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+
+    return Ok(());
+}
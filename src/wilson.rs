@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+
+use crate::animation::{Animation, pace};
+use crate::graph::{Cost, Graph, NodeId, WilsonStep};
+use crate::rng::Rng;
+use crate::{MainCamera, Node, ResultsHud, StartNode, WrapperGraph, position_lookup};
+
+const TREE: Color = Color::srgb(0.35, 0.85, 0.45);
+const WALK: Color = Color::srgb(1.0, 0.65, 0.2);
+const ERASED: Color = Color::srgb(0.95, 0.25, 0.25);
+
+// wilson's algorithm played back a move at a time, at the animation speed and paused along with
+// it; the walk in progress is drawn over the tree grown so far
+#[derive(Resource, Default)]
+struct WilsonDemo {
+    steps: Vec<WilsonStep>,
+    shown: usize,
+    budget: f32,
+    seed: u64,
+    root: NodeId,
+    members: usize,
+    tree: Vec<(NodeId, NodeId)>,
+    walk: Vec<NodeId>,
+    // the loop erased last, until the walk moves on
+    erased: Vec<NodeId>,
+    hops: usize,
+    erasures: usize,
+    // the weights the walks were taken on
+    costs: Vec<(NodeId, NodeId, Cost)>,
+}
+
+impl WilsonDemo {
+    fn done(&self) -> bool {
+        return self.shown == self.steps.len();
+    }
+
+    fn step(&mut self) {
+        let Some(step) = self.steps.get(self.shown).cloned() else {
+            return;
+        };
+        self.shown += 1;
+        match step {
+            WilsonStep::Hop(from, to) => {
+                if self.walk.is_empty() {
+                    self.walk.push(from);
+                }
+                self.walk.push(to);
+                self.erased.clear();
+                self.hops += 1;
+            }
+            WilsonStep::Erase(erased) => {
+                self.walk.truncate(self.walk.len() + 1 - erased.len());
+                self.erased = erased;
+                self.erasures += 1;
+            }
+            WilsonStep::Join(walk) => {
+                self.tree
+                    .extend(walk.windows(2).map(|hop| (hop[0], hop[1])));
+                self.walk.clear();
+            }
+        }
+    }
+
+    fn summary(&self) -> String {
+        let joined = self.tree.len() + 1;
+        let mut lines = vec![format!(
+            "Wilson's spanning tree from {} (seed {}): {} of {} nodes joined, {} hops, {} loops erased (Ctrl+W stops)",
+            self.root, self.seed, joined, self.members, self.hops, self.erasures
+        )];
+        lines.push(match (self.walk.first(), self.erased.len()) {
+            (Some(&first), 0) => format!("Walking from {}: {}", first, Graph::fmt_path(&self.walk)),
+            (Some(_), _) => format!("Loop erased: {}", Graph::fmt_path(&self.erased)),
+            (None, _) if self.done() => "Every reachable node is in the tree".to_string(),
+            (None, _) => "The last walk joined the tree".to_string(),
+        });
+        return lines.join("\n");
+    }
+}
+
+pub struct WilsonPlugin;
+
+impl Plugin for WilsonPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WilsonDemo>().add_systems(
+            Update,
+            (
+                handle_wilson_keys.run_if(crate::prompt::prompt_closed),
+                step_wilson,
+                draw_wilson,
+            )
+                .chain(),
+        );
+    }
+}
+
+// ctrl+W grows a random spanning tree toward the start, or stops; while it plays and no search
+// is animating, space pauses and `.` steps as they do for searches
+fn handle_wilson_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_camera: Query<(&WrapperGraph, &StartNode), With<MainCamera>>,
+    mut app_rng: ResMut<Rng>,
+    mut animation: ResMut<Animation>,
+    mut demo: ResMut<WilsonDemo>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if !demo.steps.is_empty() && animation.runs.is_empty() {
+        if keys.just_pressed(KeyCode::Space) {
+            animation.paused = !animation.paused;
+        }
+        if keys.just_pressed(KeyCode::Period) && animation.paused {
+            demo.step();
+            hud.0 = demo.summary();
+        }
+    }
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !ctrl || !keys.just_pressed(KeyCode::KeyW) {
+        return Ok(());
+    }
+    if !demo.steps.is_empty() {
+        *demo = WilsonDemo::default();
+        hud.0 = "Wilson's spanning tree off".to_string();
+        return Ok(());
+    }
+
+    let (wg, start) = q_camera.single()?;
+    let graph = wg.0.read().unwrap();
+    if graph.connection_count() == 0 {
+        hud.0 = "There are no edges to span".to_string();
+        return Ok(());
+    }
+    let root = start.id.unwrap_or(NodeId(0));
+    let seed = app_rng.next_u64() >> 32;
+    let steps = graph.wilson_spanning_tree(root, &mut Rng::new(seed));
+    let members = 1 + steps
+        .iter()
+        .map(|s| match s {
+            WilsonStep::Join(walk) => walk.len() - 1,
+            _ => 0,
+        })
+        .sum::<usize>();
+    if members == 1 {
+        hud.0 = format!("No other node can reach {} to grow a tree toward", root);
+        return Ok(());
+    }
+    *demo = WilsonDemo {
+        steps,
+        seed,
+        root,
+        members,
+        costs: graph.arc_costs(),
+        ..default()
+    };
+    animation.paused = false;
+    hud.0 = demo.summary();
+
+    return Ok(());
+}
+
+// `speed` moves a second; editing the graph stops the demo
+fn step_wilson(
+    time: Res<Time>,
+    q_graph: Query<&WrapperGraph, With<MainCamera>>,
+    animation: Res<Animation>,
+    mut demo: ResMut<WilsonDemo>,
+    mut hud: ResMut<ResultsHud>,
+) -> Result<()> {
+    if demo.steps.is_empty() {
+        return Ok(());
+    }
+    if q_graph.single()?.0.read().unwrap().arc_costs() != demo.costs {
+        *demo = WilsonDemo::default();
+        hud.0 = "The graph changed, so Wilson's spanning tree stopped".to_string();
+        return Ok(());
+    }
+    if demo.done() || animation.paused {
+        demo.budget = 0.0;
+        return Ok(());
+    }
+    let mut budget = demo.budget;
+    let steps = pace(animation.speed, &mut budget, time.delta_secs(), || {
+        demo.step();
+        return !demo.done();
+    });
+    demo.budget = budget;
+    if steps == 0 {
+        return Ok(());
+    }
+    hud.0 = demo.summary();
+
+    return Ok(());
+}
+
+// the tree thick, the walk in progress over it and the loop just erased in red
+fn draw_wilson(demo: Res<WilsonDemo>, nodes: Query<&Node>, mut gizmos: Gizmos) {
+    if demo.steps.is_empty() {
+        return;
+    }
+    let position = position_lookup(&nodes);
+    for &(a, b) in &demo.tree {
+        let (Some(a), Some(b)) = (position(a), position(b)) else {
+            continue;
+        };
+        let side = (b - a).normalize_or_zero().perp() * 2.0;
+        for offset in [-side, Vec2::ZERO, side] {
+            gizmos.line_2d(a + offset, b + offset, TREE);
+        }
+    }
+    for (path, color) in [(&demo.erased, ERASED.with_alpha(0.6)), (&demo.walk, WALK)] {
+        for hop in path.windows(2) {
+            if let (Some(a), Some(b)) = (position(hop[0]), position(hop[1])) {
+                gizmos.line_2d(a, b, color);
+            }
+        }
+    }
+    if let Some(at) = demo.walk.last().and_then(|&id| position(id)) {
+        gizmos.circle_2d(Isometry2d::from_translation(at), 5.0, WALK);
+    }
+    if let Some(root) = position(demo.root) {
+        gizmos.circle_2d(Isometry2d::from_translation(root), 8.0, TREE);
+    }
+}